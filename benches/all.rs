@@ -4,5 +4,6 @@ mod field;
 mod hash;
 mod fft;
 mod polynom;
+mod prover;
 
-criterion_main!(field::group, hash::group, fft::group, polynom::group);
\ No newline at end of file
+criterion_main!(field::group, hash::group, fft::group, polynom::group, prover::group);
\ No newline at end of file
@@ -0,0 +1,82 @@
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use criterion::{ black_box, criterion_group, criterion_main, BenchmarkId, Criterion };
+use distaff::{ testing::StackEvaluatorFixture, OpCode };
+
+const TRACE_LENGTH: usize = 1024;
+const EXTENSION_FACTORS: [usize; 3] = [8, 16, 32];
+
+/// System allocator which counts the number of allocations made through it; this is used to
+/// make sure that constraint evaluation does not allocate memory on every step.
+struct CountingAllocator;
+
+static NUM_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        NUM_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return System.alloc(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+pub fn stack_evaluation(c: &mut Criterion) {
+
+    let mut group = c.benchmark_group("Stack constraints");
+
+    for &extension_factor in EXTENSION_FACTORS.iter() {
+        let mut fixture = StackEvaluatorFixture::new(TRACE_LENGTH, extension_factor, 42);
+
+        // make sure both evaluation paths agree at a point in the evaluation domain
+        let step = 13;
+        let expected = fixture.evaluate(step).to_vec();
+        let x = fixture.domain_point(step);
+        assert_eq!(expected, fixture.evaluate_at(x), "evaluate() and evaluate_at() disagree at step {}", step);
+
+        group.bench_function(BenchmarkId::new("evaluate", extension_factor), |bench| {
+            bench.iter(|| { fixture.evaluate(black_box(step)); })
+        });
+
+        group.bench_function(BenchmarkId::new("evaluate_at", extension_factor), |bench| {
+            bench.iter(|| { fixture.evaluate_at(black_box(x)); })
+        });
+    }
+
+    group.finish();
+}
+
+pub fn hash_allocations(c: &mut Criterion) {
+
+    let mut group = c.benchmark_group("Stack constraints (allocations)");
+
+    // the hash round is evaluated on every step: first half of the round over the current
+    // state and inverse of the second half over the next state; neither should allocate
+    let mut fixture = StackEvaluatorFixture::with_op_code(TRACE_LENGTH, EXTENSION_FACTORS[0], OpCode::RescR, 42);
+    let num_steps = TRACE_LENGTH * EXTENSION_FACTORS[0];
+
+    let num_allocations = NUM_ALLOCATIONS.load(Ordering::Relaxed);
+    for step in 0..num_steps {
+        fixture.evaluate(black_box(step));
+    }
+    let num_allocations = NUM_ALLOCATIONS.load(Ordering::Relaxed) - num_allocations;
+    assert_eq!(0, num_allocations, "evaluating {} steps made {} allocations", num_steps, num_allocations);
+
+    group.bench_function(BenchmarkId::new("evaluate", num_steps), |bench| {
+        bench.iter(|| {
+            for step in 0..num_steps {
+                fixture.evaluate(black_box(step));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(group, stack_evaluation, hash_allocations);
+criterion_main!(group);
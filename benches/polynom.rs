@@ -1,5 +1,5 @@
-use criterion::{ black_box, criterion_group, Criterion };
-use distaff::math::{ field, polynom, quartic };
+use criterion::{ black_box, criterion_group, Criterion, BenchmarkId };
+use distaff::math::{ field, fft, polynom, parallel, quartic };
 
 pub fn eval(c: &mut Criterion) {
     let p = field::rand_vector(1024);
@@ -28,5 +28,19 @@ pub fn interpolate_quartic_batch(c: &mut Criterion) {
     });
 }
 
+pub fn interpolate_columns(c: &mut Criterion) {
+    let n: usize = 1 << 16;
+    let inv_twiddles = fft::get_inv_twiddles(field::get_root_of_unity(n), n);
+    let mut columns: Vec<Vec<u128>> = (0..32).map(|_| field::rand_vector(n)).collect();
 
-criterion_group!(group, eval, evaluate_quartic_batch, interpolate_quartic_batch);
\ No newline at end of file
+    let mut group = c.benchmark_group("Poly interpolation (32 columns)");
+    group.sample_size(10);
+    for &num_threads in [1, 2, 4, 8].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(num_threads), &num_threads, |bench, &num_threads| {
+            bench.iter(|| parallel::interpolate_fft_twiddles(black_box(&mut columns), black_box(&inv_twiddles), true, num_threads))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(group, eval, evaluate_quartic_batch, interpolate_quartic_batch, interpolate_columns);
\ No newline at end of file
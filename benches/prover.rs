@@ -0,0 +1,50 @@
+use criterion::{ criterion_group, Criterion };
+use distaff::{ assembly, ProgramInputs, ProofOptions, ProverContext };
+
+pub fn execute(c: &mut Criterion) {
+
+    let program = assembly::compile("begin push.3 push.5 add push.7 mul end").unwrap();
+    let inputs = ProgramInputs::none();
+    let options = ProofOptions::new(32, 32, 0, distaff::crypto::hash::blake3);
+
+    let mut group = c.benchmark_group("Prover");
+    group.sample_size(10);
+
+    group.bench_function("execute (no context)", |bench| {
+        bench.iter(|| distaff::execute(&program, &inputs, 1, &options))
+    });
+
+    let mut context = ProverContext::new();
+    group.bench_function("execute (shared context)", |bench| {
+        bench.iter(|| distaff::execute_with_context(&program, &inputs, 1, &options, &mut context))
+    });
+
+    group.finish();
+}
+
+pub fn execute_hash_free(c: &mut Criterion) {
+
+    // both programs have the same trace length, but the second one spends most steps hashing;
+    // hash constraints are evaluated over the entire domain for both programs, and thus, the
+    // two programs are expected to take about the same time to prove
+    let hash_free = assembly::compile("begin repeat.20 dup mul push.3 add end end").unwrap();
+    let hashing = assembly::compile("begin repeat.24 hash.2 end end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2, 3]);
+    let options = ProofOptions::new(32, 32, 0, distaff::crypto::hash::blake3);
+
+    let mut group = c.benchmark_group("Prover (hash operations)");
+    group.sample_size(10);
+
+    let mut context = ProverContext::new();
+    group.bench_function("execute (no hash operations)", |bench| {
+        bench.iter(|| distaff::execute_with_context(&hash_free, &inputs, 1, &options, &mut context))
+    });
+
+    group.bench_function("execute (mostly hash operations)", |bench| {
+        bench.iter(|| distaff::execute_with_context(&hashing, &inputs, 1, &options, &mut context))
+    });
+
+    group.finish();
+}
+
+criterion_group!(group, execute, execute_hash_free);
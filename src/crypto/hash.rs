@@ -10,7 +10,7 @@ const ALPHA: u128 = 3;
 const INV_ALPHA: u128 = 226854911280625642308916371969163307691;
 
 // MDS matrix used by Poseidon and Rescue
-const MDS: [u128; 36] = [
+pub(crate) const MDS: [u128; 36] = [
      34702391375697798808541201166389247321, 292720401120629668097050277338444166479, 252221686506898646925660607780980529565,   1545301432720594930091500405440765270, 249229091188143033873076468277345141138, 220001593723324427188563221285612032538,
     223274184432289781839114239013770504955, 330042960751289206923775620692185805456,  68147806084648525660922442535124284349, 170632587193822854126540173326689266153, 250033902372207462477717017592730125263, 241770281110130121239200125437407593586,
      59697037488579951129595490016876870776, 173037025415440639734730939871096987969, 244520331803890388707106378055030145592,  34432552219210978837375640622811234255, 224883744083395074894597669527169800639, 118987613174044827738657284387435362389,
@@ -20,7 +20,7 @@ const MDS: [u128; 36] = [
 ];
 
 // Round constants for all hash functions; Poseidon uses all constants, Rescue the first 120, GMiMC uses the first 166
-const ARK: [u128; 546] = [
+pub(crate) const ARK: [u128; 546] = [
      73742662193393629993182617210984534396, 158538539401072639862099558319550076686,  82429262549299942290847183493004485261, 310538827479436149892724250590698914519, 190338348930091047298074165559397264378, 289278996656706117461857789813498821934,
      53265540956785335308970867946461681393, 221096166077280180974764042888991644280, 135862987622353414661673448620033990934, 158907965876520949616863328303176330572, 274633988293091071340356635555807179190, 274604860873273636237081114376077113475,
      14395595548581550072136442264588359269,  58496669788416466040038464653643977917, 189653807408664613044858917026657980625, 230609671293877243511889006223284127479, 178527953570703982986577498890483203023, 126000924558481152083098962591383883438,
@@ -5,6 +5,7 @@ use crate::crypto::{ HashFunction };
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Serialize, Deserialize)]
 pub struct MerkleTree {
     nodes   : Vec<[u8; 32]>,
     values  : Vec<[u8; 32]>
@@ -43,6 +44,32 @@ impl MerkleTree {
         return &self.values;
     }
 
+    /// Replaces the leaf at the specified `index` with `value`, and recomputes the nodes on the
+    /// path from the leaf to the root; returns the new root of the tree. `hash` must be the same
+    /// hash function as the one used to build the tree.
+    pub fn update_leaf(&mut self, index: usize, value: [u8; 32], hash: HashFunction) -> &[u8; 32] {
+        assert!(index < self.values.len(), "invalid index {}", index);
+        self.values[index] = value;
+
+        // re-hash the pair of leaves which contains the updated leaf
+        let mut buf = [0u8; 64];
+        let pair = index - (index & 1);
+        buf[..32].copy_from_slice(&self.values[pair]);
+        buf[32..].copy_from_slice(&self.values[pair + 1]);
+        let mut index = (index + self.nodes.len()) >> 1;
+        hash(&buf, &mut self.nodes[index]);
+
+        // re-hash all nodes on the path to the root
+        while index > 1 {
+            index = index >> 1;
+            buf[..32].copy_from_slice(&self.nodes[index << 1]);
+            buf[32..].copy_from_slice(&self.nodes[(index << 1) + 1]);
+            hash(&buf, &mut self.nodes[index]);
+        }
+
+        return self.root();
+    }
+
     /// Computes merkle path the given leaf index.
     pub fn prove(&self, index: usize) -> Vec<[u8; 32]> {
         assert!(index < self.values.len(), "invalid index {}", index);
@@ -150,6 +177,32 @@ impl MerkleTree {
         return v == *root;
     }
 
+    /// Checks whether the path for the specified index is valid. Unlike `verify()`, this function
+    /// does not branch on (or use for memory addressing) bits of the index or contents of the
+    /// path: node ordering at every level is selected using bit masks, and the computed root is
+    /// compared to the expected root without early exit. Thus, execution time depends only on
+    /// the length of the path, assuming `hash` itself runs in constant time.
+    pub fn verify_ct(root: &[u8; 32], index: usize, proof: &[[u8; 32]], hash: HashFunction) -> bool {
+        let mut buf = [0u8; 64];
+        let mut v = [0u8; 32];
+
+        cswap_into(&mut buf, &proof[0], &proof[1], index & 1);
+        hash(&buf, &mut v);
+
+        for i in 2..proof.len() {
+            let bit = (index >> (i - 1)) & 1;
+            let node = v;
+            cswap_into(&mut buf, &node, &proof[i], bit);
+            hash(&buf, &mut v);
+        }
+
+        let mut diff = 0u8;
+        for i in 0..32 {
+            diff |= v[i] ^ root[i];
+        }
+        return diff == 0;
+    }
+
     /// Checks whether the batch proof contains merkle paths for the of the specified indexes.
     pub fn verify_batch(root: &[u8; 32], indexes: &[usize], proof: &BatchMerkleProof, hash: HashFunction) -> bool {
         let mut buf = [0u8; 64];
@@ -293,6 +346,17 @@ pub fn build_merkle_nodes(leaves: &[[u8; 32]], hash: HashFunction) -> Vec<[u8; 3
     return nodes;
 }
 
+/// Copies `a` and `b` into `buf` as `a || b` if `bit` is 0, and as `b || a` if `bit` is 1,
+/// without branching on the value of `bit`.
+fn cswap_into(buf: &mut [u8; 64], a: &[u8; 32], b: &[u8; 32], bit: usize) {
+    let mask = 0u8.wrapping_sub(bit as u8);
+    for i in 0..32 {
+        let t = (a[i] ^ b[i]) & mask;
+        buf[i] = a[i] ^ t;
+        buf[i + 32] = b[i] ^ t;
+    }
+}
+
 fn map_indexes(indexes: &[usize], max_valid: usize) -> HashMap<usize, usize> {
     let mut map = HashMap::new();
     for (i, index) in indexes.iter().cloned().enumerate() {
@@ -423,6 +487,28 @@ mod tests {
         assert_eq!(true, super::MerkleTree::verify(tree.root(), 6, &proof, hash::poseidon));
     }
 
+    #[test]
+    fn verify_ct() {
+        let leaves = (0..32u8).map(|i| [i; 32]).collect::<Vec<[u8; 32]>>();
+        let tree = super::MerkleTree::new(leaves, hash::blake3);
+
+        for index in 0..32 {
+            let proof = tree.prove(index);
+            for &other in [index, (index + 1) % 32, index ^ 16].iter() {
+                let expected = super::MerkleTree::verify(tree.root(), other, &proof, hash::blake3);
+                assert_eq!(expected, super::MerkleTree::verify_ct(tree.root(), other, &proof, hash::blake3));
+                assert_eq!(other == index, expected);
+            }
+
+            // tampered path
+            let mut proof = proof;
+            let i = index % proof.len();
+            proof[i][0] ^= 1;
+            assert_eq!(false, super::MerkleTree::verify(tree.root(), index, &proof, hash::blake3));
+            assert_eq!(false, super::MerkleTree::verify_ct(tree.root(), index, &proof, hash::blake3));
+        }
+    }
+
     #[test]
     fn prove_batch() {
         let leaves = LEAVES8.to_vec();
@@ -517,6 +603,24 @@ mod tests {
         assert_eq!(true, super::MerkleTree::verify_batch(tree.root(), &[0, 1, 2, 3, 4, 5, 6, 7], &proof, hash::poseidon));
     }
 
+    #[test]
+    fn update_leaf() {
+        let mut leaves = LEAVES8.to_vec();
+        let mut tree = super::MerkleTree::new(leaves.clone(), hash::poseidon);
+
+        // update leaves at both even and odd positions, including the same leaf twice
+        for &(index, value) in [(0, LEAVES4[0]), (5, LEAVES4[1]), (7, LEAVES4[2]), (0, LEAVES4[3])].iter() {
+            leaves[index] = value;
+            let expected = super::MerkleTree::new(leaves.clone(), hash::poseidon);
+            assert_eq!(expected.root(), tree.update_leaf(index, value, hash::poseidon));
+            assert_eq!(expected.leaves(), tree.leaves());
+        }
+
+        // proofs against the updated tree are valid
+        let proof = tree.prove(5);
+        assert_eq!(true, super::MerkleTree::verify(tree.root(), 5, &proof, hash::poseidon));
+    }
+
     // HELPER FUNCTIONS
     // --------------------------------------------------------------------------------------------
     fn hash_2x1(v1: &[u8; 32], v2: &[u8; 32]) -> [u8; 32] {
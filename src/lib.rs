@@ -1,6 +1,6 @@
 use log::debug;
 use std::ops::Range;
-use std::time::Instant;
+use std::time::{ Duration, Instant };
 
 #[cfg(test)]
 mod tests;
@@ -12,13 +12,31 @@ pub mod math;
 pub mod utils;
 
 mod stark;
-pub use stark::{ StarkProof, ProofOptions };
+pub use stark::{
+    StarkProof, OutputTape, ProofOptions, OptionsError, ProverContext, MIN_PARALLEL_TRACE_LENGTH, ProverCheckpoint, ProverError, VerifierCost, VerifierSession, VerifierStatus, VerifyError, VerifyPolicy, DEFAULT_MAX_PROOF_SIZE, VerificationKey,
+    ProofVersion, ProofError, PROOF_VERSION, ProofHeader, PROOF_HEADER_SIZE, TraceTable, TraceState, TraceStep, LdeStep, EvalStep, Constraint, CustomConstraints, MAX_CUSTOM_CONSTRAINTS,
+    HashEvaluator, compose_constraints, compose_constraints_chunked, composition_buffer_size, DEFAULT_COMPOSITION_CHUNK_SIZE,
+    AuxTrace, AuxFrame, MAX_AUX_REGISTERS, MAX_AUX_CHALLENGES,
+    FriProof, FriLayer, FriCommitment, prove_fri, verify_fri, fold_fri_layer, max_constraint_degree, lde_domain, lde_domain_generator, lde_domain_offset,
+    Challenges, ConstraintCoefficients, CompositionCoefficients,
+};
 
 mod processor;
-pub use processor::{ OpCode, OpHint };
+pub use processor::{ OpCode, OpFlag, OpHint, ExecutionError, debug };
 
 mod programs;
-pub use programs::{ Program, ProgramInputs, assembly, blocks };
+pub use programs::{
+    Program, ProgramInputs, ProgramOutputs, InputValue, InputError, commit_to_inputs, pack_bytes, unpack_bytes, BYTES_PER_ELEMENT,
+    assembly, blocks };
+
+mod membership;
+pub use membership::{ prove_set_membership, verify_set_membership };
+
+mod streaming;
+pub use streaming::{ StreamingExecutor };
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 // EXECUTOR
 // ================================================================================================
@@ -29,39 +47,256 @@ pub use programs::{ Program, ProgramInputs, assembly, blocks };
 /// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
 pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof)
 {
-    assert!(num_outputs <= MAX_OUTPUTS, 
-        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    return execute_with_context(program, inputs, num_outputs, options, &mut ProverContext::new());
+}
+
+/// Same as execute(), but uses the provided `context` to cache values (e.g. FFT twiddles) which
+/// can be re-used across many proofs of programs with the same execution trace length.
+pub fn execute_with_context(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions, context: &mut ProverContext) -> (Vec<u128>, StarkProof)
+{
+    return try_execute_with_context(program, inputs, num_outputs, options, context)
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+/// Same as execute(), but returns an error instead of panicking when the proof cannot be
+/// generated (e.g. within the memory budget specified by `options`, or because more than
+/// MAX_OUTPUTS outputs are requested).
+pub fn try_execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> Result<(Vec<u128>, StarkProof), ProverError>
+{
+    return try_execute_with_context(program, inputs, num_outputs, options, &mut ProverContext::new());
+}
+
+/// Same as execute_with_context(), but returns an error instead of panicking when the proof
+/// cannot be generated; see try_execute().
+pub fn try_execute_with_context(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions, context: &mut ProverContext) -> Result<(Vec<u128>, StarkProof), ProverError>
+{
+    let (outputs, proof) = execute_and_prove(program, inputs, num_outputs, &CustomConstraints::new(), options, context)?;
+    return Ok((outputs.stack().to_vec(), proof));
+}
+
+/// Same as execute(), but in addition to the top `num_outputs` values of the stack, returns all
+/// values written to the output tape by the program. The tape is bound into the proof only if
+/// it is made public via `options`; in such a case, the proof can be verified against the tape
+/// with verify_with_outputs().
+pub fn execute_with_outputs(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> (ProgramOutputs, StarkProof)
+{
+    return execute_and_prove(program, inputs, num_outputs, &CustomConstraints::new(), options, &mut ProverContext::new())
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+/// Same as execute(), but also returns a verification key for the proof; the key can be
+/// distributed to verifiers in advance and used to verify proofs with verify_with_key().
+pub fn execute_with_key(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof, VerificationKey)
+{
+    let (outputs, proof) = execute(program, inputs, num_outputs, options);
+    let vk = VerificationKey::new(program.hash(), options);
+    return (outputs, proof, vk);
+}
+
+/// Same as execute(), but the generated proof also attests that the execution trace satisfies
+/// the `custom` constraints; the same constraints must be supplied to verify_with_constraints().
+pub fn execute_with_constraints(program: &Program, inputs: &ProgramInputs, num_outputs: usize, custom: &CustomConstraints, options: &ProofOptions) -> (Vec<u128>, StarkProof)
+{
+    let (outputs, proof) = execute_and_prove(program, inputs, num_outputs, custom, options, &mut ProverContext::new())
+        .unwrap_or_else(|err| panic!("{}", err));
+    return (outputs.stack().to_vec(), proof);
+}
+
+fn execute_and_prove(program: &Program, inputs: &ProgramInputs, num_outputs: usize, custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> Result<(ProgramOutputs, StarkProof), ProverError>
+{
+    if num_outputs > MAX_OUTPUTS {
+        return Err(ProverError::TooManyOutputs { requested: num_outputs, max: MAX_OUTPUTS });
+    }
 
     // execute the program to create an execution trace
     let now = Instant::now();
-    let (trace, ctx_depth, loop_depth) = processor::execute(program, inputs);
-    let mut trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+    let (trace, ctx_depth, loop_depth) = processor::execute_with_limit(program, inputs, options.execution_step_limit())
+        .map_err(ProverError::ExecutionAborted)?;
     debug!("Generated execution trace of {} registers and {} steps in {} ms",
-        trace.register_count(),
-        trace.unextended_length(),
+        trace.len(),
+        trace[0].len(),
         now.elapsed().as_millis());
 
+    let (outputs, proof) = prove_trace(program, trace, ctx_depth, loop_depth,
+        inputs.get_public_inputs(), num_outputs, custom, options, context)?;
+
+    // make sure the simulator agrees with the executor
+    debug_assert!(processor::simulate(program, inputs, num_outputs) == outputs.stack(),
+        "simulated outputs do not match outputs of the execution trace");
+
+    return Ok((outputs, proof));
+}
+
+/// Generates a proof of execution of a program with the specified `program_hash` from an
+/// execution trace produced earlier (e.g. via `debug::trace_execution()`) without executing the
+/// program again. The trace is not trusted: the proof is generated only if the trace satisfies
+/// transition constraints of the VM, starts with `public_inputs` at the top of the stack, and
+/// ends with `program_hash`. The proof is the same as the one generated by execute() with the
+/// same parameters; its outputs are the top `num_outputs` values of the stack at the last step.
+pub fn prove_from_trace(program_hash: &[u8; 32], trace: debug::ExecutionTrace, public_inputs: &[u128], num_outputs: usize, options: &ProofOptions) -> Result<StarkProof, ProverError>
+{
+    if num_outputs > MAX_OUTPUTS {
+        return Err(ProverError::TooManyOutputs { requested: num_outputs, max: MAX_OUTPUTS });
+    }
+
+    // make sure the trace is well-formed before evaluating any constraints against it
+    trace.validate().map_err(ProverError::InvalidTrace)?;
+
+    // make sure transitions between all steps of the trace are valid
+    for &group in [debug::ConstraintGroup::OpBits, debug::ConstraintGroup::FlowControl,
+        debug::ConstraintGroup::Stack, debug::ConstraintGroup::Hash].iter() {
+        debug::check_trace_group(&trace, group).map_err(|violation| ProverError::InvalidTrace(violation.to_string()))?;
+    }
+
+    // make sure the trace starts with the public inputs
+    let ctx_depth = trace.ctx_depth();
+    let loop_depth = trace.loop_depth();
+    let stack_start = TraceState::compute_decoder_width(ctx_depth, loop_depth);
+    if public_inputs.len() > trace.width() - stack_start {
+        return Err(ProverError::InvalidTrace(format!("trace stack is too shallow for {} public inputs", public_inputs.len())));
+    }
+    for (i, &input) in public_inputs.iter().enumerate() {
+        if trace.registers()[stack_start + i][0] != input {
+            return Err(ProverError::InvalidTrace(format!("stack register {} at the first step does not match public input {}", i, input)));
+        }
+    }
+
+    let mut table = stark::TraceTable::new(trace.into_registers(), ctx_depth, loop_depth, options.extension_factor());
+    let outputs = check_trace_table(&table, program_hash, num_outputs).map_err(|err| ProverError::InvalidTrace(err))?;
+    return stark::prove(&mut table, public_inputs, &outputs, &CustomConstraints::new(), options, &mut ProverContext::new());
+}
+
+/// Executes the specified `program` and commits to its execution trace without completing the
+/// proof; the returned checkpoint can be serialized, and the proof can be completed later via
+/// resume_from_checkpoint(). This is useful for long proofs which may get interrupted.
+pub fn execute_to_checkpoint(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> Result<ProverCheckpoint, ProverError>
+{
+    if num_outputs > MAX_OUTPUTS {
+        return Err(ProverError::TooManyOutputs { requested: num_outputs, max: MAX_OUTPUTS });
+    }
+
+    let (trace, ctx_depth, loop_depth) = processor::execute_with_limit(program, inputs, options.execution_step_limit())
+        .map_err(ProverError::ExecutionAborted)?;
+    let (trace, outputs) = build_trace_table(program, trace, ctx_depth, loop_depth, num_outputs, options);
+    return stark::checkpoint(trace, inputs.get_public_inputs(), &outputs, options, &mut ProverContext::new());
+}
+
+/// Completes a proof from the specified `checkpoint` and returns the outputs of the program
+/// together with the proof; the proof is the same as the one generated by execute() with the
+/// same parameters. `options` must be equivalent to the options used to create the checkpoint;
+/// otherwise, an error is returned.
+pub fn resume_from_checkpoint(checkpoint: &ProverCheckpoint, options: &ProofOptions) -> Result<(Vec<u128>, StarkProof), ProverError>
+{
+    let proof = stark::resume(checkpoint, &CustomConstraints::new(), options, &mut ProverContext::new())?;
+    return Ok((checkpoint.outputs().to_vec(), proof));
+}
+
+/// Generates a proof of execution of the `program` from the execution trace produced by
+/// the processor, and returns the top `num_outputs` values of the stack and the values written
+/// to the output tape together with the proof.
+fn prove_trace(program: &Program, trace: Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, public_inputs: &[u128], num_outputs: usize, custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> Result<(ProgramOutputs, StarkProof), ProverError>
+{
+    let (mut trace, outputs) = build_trace_table(program, trace, ctx_depth, loop_depth, num_outputs, options);
+
+    // read values written to the output tape before the trace is extended
+    let tape = trace.get_output_tape();
+
+    // generate STARK proof
+    let proof = stark::prove(&mut trace, public_inputs, &outputs, custom, options, context)?;
+
+    return Ok((ProgramOutputs::new(&outputs, &tape.values), proof));
+}
+
+/// Builds a trace table from the execution trace produced by the processor, makes sure the
+/// trace is an execution trace of the `program`, and returns the table together with the top
+/// `num_outputs` values of the stack at the last step.
+fn build_trace_table(program: &Program, trace: Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, num_outputs: usize, options: &ProofOptions) -> (stark::TraceTable, Vec<u128>)
+{
+    let trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+    let outputs = check_trace_table(&trace, program.hash(), num_outputs).unwrap_or_else(|err| panic!("{}", err));
+    return (trace, outputs);
+}
+
+/// Makes sure the number of operations executed in the `trace` is sufficient and that the
+/// `trace` is an execution trace of a program with the specified hash; if so, returns the top
+/// `num_outputs` values of the stack at the last step.
+fn check_trace_table(trace: &stark::TraceTable, program_hash: &[u8; 32], num_outputs: usize) -> Result<Vec<u128>, String>
+{
     // copy the user stack state the the last step to return as output
     let last_state = trace.get_last_state();
     let outputs = last_state.user_stack()[..num_outputs].to_vec();
 
     // make sure number of executed operations was sufficient
-    assert!(last_state.op_counter() as usize >= MIN_TRACE_LENGTH,
-        "a program must consist of at least {} operation, but only {} were executed",
-        MIN_TRACE_LENGTH,
-        last_state.op_counter());
+    if (last_state.op_counter() as usize) < MIN_TRACE_LENGTH {
+        return Err(format!("a program must consist of at least {} operation, but only {} were executed",
+            MIN_TRACE_LENGTH, last_state.op_counter()));
+    }
 
     // make sure program hash generated by the VM matches the hash of the program
-    let program_hash = utils::as_bytes(last_state.program_hash());
-    assert!(program.hash() == program_hash,
-        "expected program hash {} does not match trace hash {}",
-        hex::encode(program.hash()),
-        hex::encode(program_hash));
+    let trace_hash = utils::as_bytes(last_state.program_hash());
+    if program_hash != trace_hash {
+        return Err(format!("expected program hash {} does not match trace hash {}",
+            hex::encode(program_hash), hex::encode(trace_hash)));
+    }
 
-    // generate STARK proof
-    let proof = stark::prove(&mut trace, inputs.get_public_inputs(), &outputs, options);
+    return Ok(outputs);
+}
+
+/// Executes the specified `program` and returns coefficients of the constraint polynomial of its
+/// execution trace without generating a proof; this is intended for auditing the constraint
+/// system. The polynomial is the same as the one committed to in a proof generated by execute()
+/// with the same parameters. Its degree must not exceed max_constraint_degree() of the trace
+/// length; the trace length is 1/8 of the number of coefficients.
+pub fn composition_coefficients(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> Vec<u128>
+{
+    assert!(num_outputs <= MAX_OUTPUTS,
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+
+    let (trace, ctx_depth, loop_depth) = processor::execute_with_limit(program, inputs, options.execution_step_limit())
+        .unwrap_or_else(|err| panic!("{}", err));
+    let mut trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+    let outputs = trace.get_last_state().user_stack()[..num_outputs].to_vec();
 
-    return (outputs, proof);
+    return stark::composition_coefficients(&mut trace, inputs.get_public_inputs(), &outputs,
+        &CustomConstraints::new(), options, &mut ProverContext::new());
+}
+
+/// Executes the specified `program` and returns the number of field multiplications performed
+/// when evaluating constraints of its execution trace during proof generation; this is intended
+/// for comparing costs of constraint systems analytically. The count is an upper bound since some
+/// constraints are cheaper to evaluate at steps of the execution trace.
+pub fn constraint_mul_count(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> u64
+{
+    assert!(num_outputs <= MAX_OUTPUTS,
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+
+    let (trace, ctx_depth, loop_depth) = processor::execute_with_limit(program, inputs, options.execution_step_limit())
+        .unwrap_or_else(|err| panic!("{}", err));
+    let trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+    let outputs = trace.get_last_state().user_stack()[..num_outputs].to_vec();
+
+    return stark::constraint_mul_count(&trace, inputs.get_public_inputs(), &outputs,
+        &CustomConstraints::new(), options, &mut ProverContext::new());
+}
+
+/// Returns a rough estimate of the time it would take to generate a proof of execution of the
+/// `program` against the provided `inputs`. The program is executed to determine the shape of
+/// its execution trace, but no proof is generated. The estimate is based on field multiplication
+/// throughput of this machine, which is measured on the first call.
+pub fn estimate_proving_time(program: &Program, inputs: &ProgramInputs, options: &ProofOptions) -> Duration
+{
+    let (trace, _, _) = processor::execute_with_limit(program, inputs, options.execution_step_limit())
+        .unwrap_or_else(|err| panic!("{}", err));
+    return stark::estimate_time(trace[0].len(), trace.len(), options, stark::get_mul_throughput());
+}
+
+/// Executes the specified `program` and returns the top `num_outputs` elements of the stack
+/// without generating a proof; this is intended for testing and debugging of programs.
+pub fn simulate(program: &Program, inputs: &ProgramInputs, num_outputs: usize) -> Vec<u128>
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    return processor::simulate(program, inputs, num_outputs);
 }
 
 // VERIFIER
@@ -69,11 +304,78 @@ pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, op
 
 /// Verifies that if a program with the specified `program_hash` is executed with the 
 /// provided `public_inputs` and some secret inputs, the result is equal to the `outputs`.
+///
+/// The program itself is not needed for verification: the program hash is the only public value
+/// which depends on the program, and thus, the program can be kept private by the prover. Note,
+/// however, that proofs are not zero-knowledge; the proof reveals the length of the execution
+/// trace and the nesting depths of program blocks, as well as some evaluations of the extended
+/// execution trace.
 pub fn verify(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
 {
     return stark::verify(program_hash, public_inputs, outputs, proof);
 }
 
+/// Verifies that if a program with the specified `program_hash` is executed with the provided
+/// `public_inputs` and some secret inputs, the result is exactly `expected_outputs`. Unlike
+/// verify(), this function reports which output differs when the proof is valid but attests to
+/// different outputs.
+pub fn verify_expecting(program_hash: &[u8; 32], public_inputs: &[u128], expected_outputs: &[u128], proof: &StarkProof) -> Result<(), VerifyError>
+{
+    return stark::verify_expecting(program_hash, public_inputs, expected_outputs, proof);
+}
+
+/// Verifies a batch of proofs of a program with the specified `program_hash`, e.g. proofs of the
+/// same program executed against many inputs. Every item of the batch consists of public inputs,
+/// outputs, and a proof, and is verified in the same way as by verify(); if some proof is not
+/// valid, the index of the first such proof is returned together with the error.
+pub fn verify_batch_same_program(program_hash: &[u8; 32], items: &[(&[u128], &[u128], &StarkProof)]) -> Result<(), (usize, VerifyError)>
+{
+    return stark::verify_batch_same_program(program_hash, items);
+}
+
+/// Same as verify(), but also checks that exactly the values in `outputs.tape()` were written to
+/// the output tape by the program; an error is returned if the output tape was not made public
+/// when the proof was generated. The proof attests that the claimed values were written at the
+/// claimed steps, and that the program did not write any other values to the tape: WRITE
+/// operations are counted by an auxiliary register bound to the number of claimed values.
+pub fn verify_with_outputs(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &ProgramOutputs, proof: &StarkProof) -> Result<bool, String>
+{
+    return stark::verify_with_tape(program_hash, public_inputs, outputs.stack(), outputs.tape(), proof);
+}
+
+/// Checks only that the proof carries a commitment to the specified `public_inputs`; this is
+/// not a soundness check, but it is much cheaper than verify() and can be used to filter out
+/// proofs before fully verifying them. A proof rejected by this function is always rejected by
+/// verify(), but a proof accepted by this function may still be invalid.
+pub fn check_input_commitment(public_inputs: &[u128], proof: &StarkProof) -> bool
+{
+    return stark::check_input_commitment(public_inputs, proof);
+}
+
+/// Reads a proof from the `reader` (e.g. a file or a socket) and verifies that if a program with
+/// the specified `program_hash` is executed with the provided `public_inputs` and some secret
+/// inputs, the result is equal to the outputs claimed by the prover; these outputs are returned
+/// if the proof is valid. The proof is rejected without being verified if it does not meet the
+/// requirements of the `policy`; truncated streams result in `ProofError::UnexpectedEof`.
+pub fn verify_from_reader<R: std::io::Read>(program_hash: &[u8; 32], public_inputs: &[u128], reader: R, policy: &VerifyPolicy) -> Result<Vec<u128>, ProofError>
+{
+    return stark::verify_from_reader(program_hash, public_inputs, reader, policy);
+}
+
+/// Verifies the `proof` against the verification key `vk` and the provided `public_inputs`, and
+/// returns the outputs claimed by the prover if the proof is valid. The proof is rejected if it
+/// was generated with options other than the ones specified by the key.
+pub fn verify_with_key(vk: &VerificationKey, public_inputs: &[u128], proof: &StarkProof) -> Result<Vec<u128>, String>
+{
+    return stark::verify_with_key(vk, public_inputs, proof);
+}
+
+/// Same as verify(), but also checks that the execution trace satisfies the `custom` constraints.
+pub fn verify_with_constraints(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof, custom: &CustomConstraints) -> Result<bool, String>
+{
+    return stark::verify_with_constraints(program_hash, public_inputs, outputs, proof, custom);
+}
+
 // GLOBAL CONSTANTS
 // ================================================================================================
 
@@ -84,6 +386,13 @@ const MAX_REGISTER_COUNT    : usize = 128;
 const MIN_EXTENSION_FACTOR  : usize = 16;
 const BASE_CYCLE_LENGTH     : usize = 16;
 
+/// Number of steps after which program execution is aborted unless a different limit is set via
+/// `ProofOptions::with_execution_step_limit()`. Every register of the execution trace takes 16
+/// bytes per step, and thus, a trace of 2^20 steps with 32 registers takes 512 MB; extending the
+/// trace for proof generation multiplies this by the extension factor (32 by default), which is
+/// already more memory than most machines have.
+pub const DEFAULT_STEP_LIMIT: usize = 1 << 20;
+
 const MIN_STACK_DEPTH       : usize = 8;
 const MIN_CONTEXT_DEPTH     : usize = 1;
 const MIN_LOOP_DEPTH        : usize = 1;
@@ -94,11 +403,21 @@ const PUSH_OP_ALIGNMENT     : usize = 8;
 
 // HASH OPERATION
 // ------------------------------------------------------------------------------------------------
-const HASH_STATE_RATE       : usize = 4;
-const HASH_STATE_CAPACITY   : usize = 2;
-const HASH_STATE_WIDTH      : usize = HASH_STATE_RATE + HASH_STATE_CAPACITY;
-const HASH_NUM_ROUNDS       : usize = 10;
-const HASH_DIGEST_SIZE      : usize = 2;
+/// Number of field elements absorbed by a single invocation of the hash function; this is also
+/// the maximum number of stack items which can be hashed with `hash.n` instruction.
+pub const HASH_STATE_RATE       : usize = 4;
+/// Number of field elements in the capacity portion of the hash function state.
+pub const HASH_STATE_CAPACITY   : usize = 2;
+/// Number of stack items occupied by the hash function state during hashing.
+pub const HASH_STATE_WIDTH      : usize = HASH_STATE_RATE + HASH_STATE_CAPACITY;
+/// Number of rounds of the hash function; each round is executed by a single RESCR operation.
+pub const HASH_NUM_ROUNDS       : usize = 10;
+/// Number of field elements in a hash digest.
+pub const HASH_DIGEST_SIZE      : usize = 2;
+/// Number of steps after which round constants of the hash function repeat; the first round of
+/// a hash must be executed on a step which is a multiple of this value. See
+/// `assembly::hash_step_cost()` for the number of steps taken by `hash.n` instruction.
+pub const HASH_CYCLE_LENGTH     : usize = BASE_CYCLE_LENGTH;
 
 // OPERATION SPONGE
 // ------------------------------------------------------------------------------------------------
@@ -1,5 +1,6 @@
 use std::ops::Range;
 use std::convert::TryInto;
+use std::fmt;
 use rand::prelude::*;
 use rand::distributions::{ Distribution, Uniform };
 use crate::utils::{ uninit_vector };
@@ -13,6 +14,14 @@ pub const M: u128 = 340282366920938463463374557953744961537;
 // 2^40 root of unity
 pub const G: u128 = 23953097886125630542083529559205016746;
 
+// generator of the entire multiplicative group of the field; powers of the generator outside of
+// the 2^40 subgroup are used to shift domains into cosets of the subgroup
+pub const GENERATOR: u128 = 3;
+
+// log2 of the order of the largest multiplicative subgroup of size 2^k; this limits the size of
+// domains over which FFT can be performed
+pub const TWO_ADICITY: u32 = 40;
+
 // public constants
 pub const MODULUS: u128 = M;
 pub const RANGE: Range<u128> = Range { start: 0, end: M };
@@ -20,9 +29,32 @@ pub const RANGE: Range<u128> = Range { start: 0, end: M };
 pub const ZERO: u128 = 0;
 pub const ONE: u128 = 1;
 
+// number of multiplications performed by the current thread; tests use it to validate
+// analytical multiplication counts
+#[cfg(test)]
+thread_local! {
+    static MUL_COUNT: std::cell::Cell<u64> = std::cell::Cell::new(0);
+}
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A field element wrapper for printing; values in the upper half of the field are usually
+/// negations of small values, and thus, they are displayed together with their signed form
+/// (e.g. MODULUS - 1 is displayed as "340282366920938463463374557953744961536 (=-1)").
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Element(pub u128);
+
 // BASIC ARITHMETIC
 // --------------------------------------------------------------------------------------------
 
+/// Reduces any `u128` value into a valid field element in [0, MODULUS). Arithmetic functions
+/// in this module assume canonical inputs, and thus, values coming from untrusted sources
+/// should be reduced first; since MODULUS > 2^127, a single subtraction is sufficient.
+pub fn reduce(x: u128) -> u128 {
+    return if x >= M { x - M } else { x };
+}
+
 /// Computes (a + b) % m; a and b are assumed to be valid field elements.
 pub fn add(a: u128, b: u128) -> u128 {
     let z = M - b;
@@ -36,6 +68,8 @@ pub fn sub(a: u128, b: u128) -> u128 {
 
 /// Computes (a * b) % m; a and b are assumed to be valid field elements.
 pub fn mul(a: u128, b: u128) -> u128 {
+    #[cfg(test)]
+    MUL_COUNT.with(|count| count.set(count.get() + 1));
 
     let (x0, x1, x2) = mul_128x64(a, (b >> 64) as u64);         // x = a * b_hi
     let (mut x0, mut x1, x2) = mul_reduce(x0, x1, x2);          // x = x - (x >> 128) * m
@@ -218,6 +252,12 @@ pub fn exp(b: u128, p: u128) -> u128 {
     return r;
 }
 
+/// Returns the number of multiplications performed by the current thread so far.
+#[cfg(test)]
+pub fn mul_count() -> u64 {
+    return MUL_COUNT.with(|count| count.get());
+}
+
 /// Computes (0 - x) % m; x is assumed to be a valid field element.
 pub fn neg(x: u128) -> u128 {
     return sub(ZERO, x);
@@ -228,13 +268,16 @@ pub fn neg(x: u128) -> u128 {
 pub fn get_root_of_unity(order: usize) -> u128 {
     assert!(order != 0, "cannot get root of unity for order 0");
     assert!(order.is_power_of_two(), "order must be a power of 2");
-    assert!(order.trailing_zeros() <= 40, "order cannot exceed 2^40");
-    let p = 1u128 << (40 - order.trailing_zeros());
+    assert!(order.trailing_zeros() <= TWO_ADICITY, "order cannot exceed 2^{}", TWO_ADICITY);
+    let p = 1u128 << (TWO_ADICITY - order.trailing_zeros());
     return exp(G, p);
 }
 
-/// Generates a vector with values [1, b, b^2, b^3, b^4, ..., b^length].
+/// Generates a vector with values [1, b, b^2, b^3, b^4, ..., b^(length - 1)]; the values are
+/// computed iteratively using length - 1 multiplications. If length is 0, the vector is empty.
 pub fn get_power_series(b: u128, length: usize) -> Vec<u128> {
+    if length == 0 { return Vec::new(); }
+
     let mut result = uninit_vector(length);
     result[0] = ONE;
     for i in 1..result.len() {
@@ -280,6 +323,53 @@ pub fn from_bytes(bytes: &[u8]) -> u128 {
     return u128::from_le_bytes(bytes.try_into().unwrap());
 }
 
+/// Serializes a field element into 16 bytes using little-endian byte order.
+pub fn to_bytes_le(value: u128) -> [u8; 16] {
+    return value.to_le_bytes();
+}
+
+/// Serializes a field element into 16 bytes using big-endian byte order.
+pub fn to_bytes_be(value: u128) -> [u8; 16] {
+    return value.to_be_bytes();
+}
+
+/// Deserializes a field element from 16 bytes in little-endian byte order; returns an error
+/// if the encoded value is not a valid field element (i.e. is greater than or equal to MODULUS).
+pub fn from_bytes_le(bytes: &[u8; 16]) -> Result<u128, String> {
+    let value = u128::from_le_bytes(*bytes);
+    if value >= M {
+        return Err(format!("value {} is not a valid field element", value));
+    }
+    return Ok(value);
+}
+
+/// Deserializes a field element from 16 bytes in big-endian byte order; returns an error
+/// if the encoded value is not a valid field element (i.e. is greater than or equal to MODULUS).
+pub fn from_bytes_be(bytes: &[u8; 16]) -> Result<u128, String> {
+    let value = u128::from_be_bytes(*bytes);
+    if value >= M {
+        return Err(format!("value {} is not a valid field element", value));
+    }
+    return Ok(value);
+}
+
+// ELEMENT IMPLEMENTATION
+// ================================================================================================
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 > M / 2 {
+            return write!(f, "{} (=-{})", self.0, M - self.0);
+        }
+        return write!(f, "{}", self.0);
+    }
+}
+
+impl fmt::Debug for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return fmt::Display::fmt(self, f);
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
@@ -381,6 +471,37 @@ mod tests {
         assert_eq!(m - 2, super::sub(3u128, 5));
     }
 
+    #[test]
+    fn sub_neg_boundaries() {
+        let m: u128 = super::MODULUS;
+        let r: u128 = super::rand();
+
+        // values at the modulus boundary
+        assert_eq!(m - 1, super::sub(0, 1));
+        assert_eq!(1, super::sub(0, m - 1));
+        assert_eq!(m - 1, super::sub(m - 2, m - 1));
+        assert_eq!(m - 2, super::sub(m - 1, 1));
+        assert_eq!(0, super::sub(0, 0));
+        assert_eq!(0, super::sub(m - 1, m - 1));
+        assert_eq!(0, super::sub(r, r));
+
+        // negation of boundary values
+        assert_eq!(0, super::neg(0));
+        assert_eq!(m - 1, super::neg(1));
+        assert_eq!(1, super::neg(m - 1));
+        assert_eq!(r, super::neg(super::neg(r)));
+        assert_eq!(0, super::add(r, super::neg(r)));
+
+        // subtraction is the same as adding the negation
+        let r2: u128 = super::rand();
+        assert_eq!(super::add(r, super::neg(r2)), super::sub(r, r2));
+
+        let expected = (BigUint::from(r) + BigUint::from(m) - BigUint::from(r2)) % BigUint::from(m);
+        let mut expected = expected.to_bytes_le();
+        expected.resize(16, 0);
+        assert_eq!(u128::from_le_bytes(expected[..].try_into().unwrap()), super::sub(r, r2));
+    }
+
     #[test]
     fn mul() {
         // identity
@@ -446,4 +567,76 @@ mod tests {
         assert_eq!(expected, root_39);
         assert_eq!(1, super::exp(root_39, u128::pow(2, 39)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn generator() {
+        // the generator is not in any subgroup of order 2^k, and thus cosets it produces are
+        // disjoint from such subgroups
+        assert_ne!(1, super::exp(super::GENERATOR, u128::pow(2, 40)));
+        assert_eq!(super::M - 1, super::exp(super::GENERATOR, (super::M - 1) / 2));
+    }
+
+    #[test]
+    fn get_power_series() {
+        let b: u128 = super::rand();
+        let expected: Vec<u128> = (0..16).map(|i| super::exp(b, i)).collect();
+        assert_eq!(expected, super::get_power_series(b, 16));
+
+        // edge cases
+        assert_eq!(vec![1], super::get_power_series(b, 1));
+        assert_eq!(Vec::<u128>::new(), super::get_power_series(b, 0));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let values = [0, 1, 255, super::G, super::MODULUS - 1, super::rand()];
+        for &value in values.iter() {
+            let le = super::to_bytes_le(value);
+            assert_eq!(Ok(value), super::from_bytes_le(&le));
+
+            let be = super::to_bytes_be(value);
+            assert_eq!(Ok(value), super::from_bytes_be(&be));
+
+            let mut reversed = be;
+            reversed.reverse();
+            assert_eq!(le, reversed);
+        }
+
+        assert_eq!([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], super::to_bytes_le(1));
+        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1], super::to_bytes_be(1));
+    }
+
+    #[test]
+    fn reduce() {
+        let m = super::MODULUS;
+        assert_eq!(0, super::reduce(m));
+        assert_eq!(5, super::reduce(m + 5));
+        assert_eq!(u128::MAX - m, super::reduce(u128::MAX));
+        for &value in [0, 1, super::G, m - 1].iter() {
+            assert_eq!(value, super::reduce(value));
+        }
+    }
+
+    #[test]
+    fn bytes_out_of_range() {
+        for &value in [super::MODULUS, super::MODULUS + 1, u128::MAX].iter() {
+            assert!(super::from_bytes_le(&value.to_le_bytes()).is_err());
+            assert!(super::from_bytes_be(&value.to_be_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn element_display() {
+        assert_eq!("0", format!("{}", super::Element(0)));
+        assert_eq!("1", format!("{}", super::Element(1)));
+
+        // the largest value which is displayed without the signed form
+        let mid = super::M / 2;
+        assert_eq!(format!("{}", mid), format!("{}", super::Element(mid)));
+        assert_eq!(format!("{} (=-{})", mid + 1, mid), format!("{}", super::Element(mid + 1)));
+
+        let m = super::MODULUS;
+        assert_eq!("340282366920938463463374557953744961536 (=-1)", format!("{}", super::Element(m - 1)));
+        assert_eq!(format!("{}", super::Element(m - 1)), format!("{:?}", super::Element(m - 1)));
+    }
+}
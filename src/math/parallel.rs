@@ -1,5 +1,5 @@
 use crossbeam_utils::thread;
-use crate::math::{ field };
+use crate::math::{ field, polynom };
 use crate::utils::{ uninit_vector };
 
 // ADDITION
@@ -178,11 +178,36 @@ pub fn inv(values: &[u128], num_threads: usize) -> Vec<u128> {
     return result;
 }
 
+// INTERPOLATION
+// ================================================================================================
+
+/// Uses FFT algorithm to interpolate polynomials from evaluations in all `columns`; the
+/// interpolation is done in-place, meaning every column is updated with coefficients of its
+/// polynomial. The columns are split into batches which are distributed across multiple
+/// threads; the result is the same as interpolating each column via
+/// `polynom::interpolate_fft_twiddles()`.
+pub fn interpolate_fft_twiddles(columns: &mut [Vec<u128>], inv_twiddles: &[u128], unpermute: bool, num_threads: usize) {
+    assert!(num_threads > 0, "number of threads must be greater than 0");
+    if columns.len() == 0 { return; }
+    let batch_size = (columns.len() + num_threads - 1) / num_threads;
+
+    // interpolate batches of columns in separate threads
+    thread::scope(|s| {
+        for batch in columns.chunks_mut(batch_size) {
+            s.spawn(move |_| {
+                for column in batch.iter_mut() {
+                    polynom::interpolate_fft_twiddles(column, inv_twiddles, unpermute);
+                }
+            });
+        }
+    }).unwrap();
+}
+
 // TESTS
 // ================================================================================================
 #[cfg(test)]
 mod tests {
-    use crate::math::{ field };
+    use crate::math::{ field, polynom, fft };
 
     #[test]
     fn add() {
@@ -302,4 +327,24 @@ mod tests {
 
         assert_eq!(expected, super::inv(&v, num_threads));
     }
+
+    #[test]
+    fn interpolate_fft_twiddles() {
+        let n: usize = 256;
+        let columns: Vec<Vec<u128>> = (0..7).map(|_| field::rand_vector(n)).collect();
+        let inv_twiddles = fft::get_inv_twiddles(field::get_root_of_unity(n), n);
+
+        // compute expected results
+        let mut expected = columns.clone();
+        for column in expected.iter_mut() {
+            polynom::interpolate_fft_twiddles(column, &inv_twiddles, true);
+        }
+
+        // the results are the same regardless of the number of threads
+        for &num_threads in [1, 2, 4, 7, 16].iter() {
+            let mut actual = columns.clone();
+            super::interpolate_fft_twiddles(&mut actual, &inv_twiddles, true, num_threads);
+            assert_eq!(expected, actual);
+        }
+    }
 }
\ No newline at end of file
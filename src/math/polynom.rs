@@ -151,6 +151,16 @@ pub fn mul_by_const(p: &[u128], k: u128) -> Vec<u128> {
     return result;
 }
 
+/// Replaces polynomial `p(x)` with `p(k * x)` by multiplying the i-th coefficient of `p` by
+/// `k^i`; evaluating the result over a domain D yields evaluations of `p` over the coset k * D.
+pub fn scale_argument(p: &mut [u128], k: u128) {
+    let mut power_of_k = field::ONE;
+    for i in 0..p.len() {
+        p[i] = field::mul(p[i], power_of_k);
+        power_of_k = field::mul(power_of_k, k);
+    }
+}
+
 /// Divides polynomial `a` by polynomial `b`; if the polynomials don't divide evenly,
 /// the remainder is ignored.
 pub fn div(a: &[u128], b: &[u128]) -> Vec<u128> {
@@ -435,6 +445,25 @@ mod tests {
         assert_eq!(pr, super::mul_by_const(&poly, c));
     }
 
+    #[test]
+    fn scale_argument() {
+        let poly: Vec<u128> = vec![384863712573444386, 7682273369345308472, 13294661765012277990, 16234810094004944758];
+        let k: u128 = 11269864713250585702;
+        let mut scaled = poly.clone();
+        super::scale_argument(&mut scaled, k);
+
+        let x: u128 = 9180446487902492385;
+        assert_eq!(super::eval(&poly, field::mul(k, x)), super::eval(&scaled, x));
+
+        // evaluating a scaled polynomial over a subgroup gives evaluations over a coset
+        let root = field::get_root_of_unity(poly.len());
+        super::eval_fft(&mut scaled, true);
+        for (i, &value) in scaled.iter().enumerate() {
+            let x = field::mul(k, field::exp(root, i as u128));
+            assert_eq!(super::eval(&poly, x), value);
+        }
+    }
+
     #[test]
     fn div() {
         let poly1: Vec<u128> = vec![384863712573444386, 7682273369345308472, 13294661765012277990];
@@ -0,0 +1,163 @@
+use crate::{
+    processor::debug, Program, ProgramInputs, ProofOptions, ProverContext, ProverError, StarkProof,
+    assembly,
+};
+
+// SET MEMBERSHIP
+// ================================================================================================
+
+/// Generates a proof that `element` is a leaf of a Merkle tree with the specified `set_root`.
+/// The membership is established by computing the root of the authentication `path` for the
+/// leaf at position `index` inside the VM; `path` must contain sibling nodes of all levels of
+/// the tree starting with the sibling of the leaf. Both the element and its position remain
+/// secret - only the root of the set is a public input.
+///
+/// If the path is empty or longer than 255 nodes, or if it does not resolve to `set_root`, an
+/// error is returned and no proof is generated.
+pub fn prove_set_membership(set_root: [u128; 2], element: [u128; 2], path: &[[u128; 2]], index: usize, options: &ProofOptions) -> Result<StarkProof, ProverError>
+{
+    let program = build_membership_program(path.len() + 1).map_err(ProverError::InvalidTrace)?;
+
+    // the index is read first, followed by the element; the nodes of the path are read by
+    // the pmpath instruction
+    let mut tape_a = vec![index as u128, element[0]];
+    let mut tape_b = vec![element[1]];
+    for node in path.iter() {
+        tape_a.push(node[0]);
+        tape_b.push(node[1]);
+    }
+    let inputs = ProgramInputs::new(&set_root, &tape_a, &tape_b);
+
+    // make sure the path resolves to the root before trying to generate a proof
+    if let Err(violation) = debug::check_execution(&program, &inputs) {
+        return Err(ProverError::ExecutionFailed(violation.message));
+    }
+
+    let (_, proof) = crate::try_execute_with_context(&program, &inputs, 0, options, &mut ProverContext::new())?;
+    return Ok(proof);
+}
+
+/// Verifies that a proof generated by prove_set_membership() attests to the membership of some
+/// element in a set with the specified `set_root`; `depth` is the depth of the Merkle tree
+/// (i.e. the length of authentication paths plus one), and must be between 2 and 256.
+pub fn verify_set_membership(set_root: [u128; 2], depth: usize, proof: &StarkProof) -> Result<bool, String>
+{
+    let program = build_membership_program(depth)?;
+    return crate::verify(program.hash(), &set_root, &[], proof);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Builds a program which computes the root of a Merkle authentication path for a tree of the
+/// specified depth, and makes sure the root is equal to the public inputs; an error is returned
+/// if the depth is not supported by the pmpath instruction.
+fn build_membership_program(depth: usize) -> Result<Program, String> {
+    if depth < 2 || depth > 256 {
+        return Err(format!("tree depth must be between 2 and 256, but was {}", depth));
+    }
+
+    // merkle_verify expects the leaf at the top of the stack followed by the leaf's index and
+    // the expected root; here, the root is the set root (the public inputs)
+    let source = format!("
+    begin
+        read.a
+        read.ab
+        exec.merkle_verify.{}
+    end", depth);
+
+    return assembly::compile(&source).map_err(|err| err.to_string());
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ utils::hasher, ProofOptions, ProverError };
+
+    #[test]
+    fn prove_set_membership() {
+        let leaves: Vec<[u128; 2]> = (0..8).map(|i| [i * 2 + 1, i * 2 + 2]).collect();
+        let (root, path) = build_merkle_path(&leaves, 5);
+        let options = ProofOptions::default();
+
+        let proof = super::prove_set_membership(root, leaves[5], &path, 5, &options).unwrap();
+        assert_eq!(Ok(true), super::verify_set_membership(root, 4, &proof));
+
+        // the proof does not verify against a different set
+        let (other_root, _) = build_merkle_path(&leaves[..4], 0);
+        assert!(super::verify_set_membership(other_root, 4, &proof).is_err());
+
+        // an element which is not in the set cannot be proven to be a member
+        let result = super::prove_set_membership(root, [17, 18], &path, 5, &options);
+        match result {
+            Err(ProverError::ExecutionFailed(message)) => assert!(message.starts_with("ASSERTEQ failed")),
+            _ => panic!("expected execution failure"),
+        }
+
+        // and neither can an element at a different position
+        let result = super::prove_set_membership(root, leaves[5], &path, 4, &options);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_set_membership_fail() {
+        let leaves: Vec<[u128; 2]> = (0..8).map(|i| [i * 2 + 1, i * 2 + 2]).collect();
+        let (root, _) = build_merkle_path(&leaves, 5);
+        let options = ProofOptions::default();
+
+        // a valid proof of membership of an element in a set which contains it is rejected by
+        // the verifier of a set which does not contain the element
+        let mut other_leaves = leaves.clone();
+        other_leaves[5] = [17, 18];
+        let (other_root, other_path) = build_merkle_path(&other_leaves, 5);
+        let proof = super::prove_set_membership(other_root, [17, 18], &other_path, 5, &options).unwrap();
+        assert_eq!(Ok(true), super::verify_set_membership(other_root, 4, &proof));
+        assert!(super::verify_set_membership(root, 4, &proof).is_err());
+
+        // the proof is also rejected for a tree of a different depth
+        assert!(super::verify_set_membership(other_root, 5, &proof).is_err());
+    }
+
+    #[test]
+    fn unsupported_tree_depth() {
+        let leaves: Vec<[u128; 2]> = (0..8).map(|i| [i * 2 + 1, i * 2 + 2]).collect();
+        let (root, path) = build_merkle_path(&leaves, 5);
+        let options = ProofOptions::default();
+        let proof = super::prove_set_membership(root, leaves[5], &path, 5, &options).unwrap();
+
+        // an empty path does not describe a tree which pmpath can verify
+        let result = super::prove_set_membership(root, leaves[5], &[], 0, &options);
+        let expected = String::from("tree depth must be between 2 and 256, but was 1");
+        assert_eq!(Some(ProverError::InvalidTrace(expected)), result.err());
+
+        // and neither does a path which is too long
+        let long_path = vec![leaves[0]; 256];
+        let result = super::prove_set_membership(root, leaves[5], &long_path, 0, &options);
+        let expected = String::from("tree depth must be between 2 and 256, but was 257");
+        assert_eq!(Some(ProverError::InvalidTrace(expected)), result.err());
+
+        // the verifier rejects such depths as well
+        for &depth in [0, 1, 257, usize::MAX].iter() {
+            let result = super::verify_set_membership(root, depth, &proof);
+            assert_eq!(Err(format!("tree depth must be between 2 and 256, but was {}", depth)), result);
+        }
+    }
+
+    /// Builds a Merkle tree from the specified leaves, and returns the root of the tree together
+    /// with the authentication path for the leaf at the specified index.
+    fn build_merkle_path(leaves: &[[u128; 2]], mut index: usize) -> ([u128; 2], Vec<[u128; 2]>) {
+        let mut path = Vec::new();
+        let mut nodes = leaves.to_vec();
+        while nodes.len() > 1 {
+            path.push(nodes[index ^ 1]);
+            nodes = nodes.chunks(2).map(|pair| {
+                let digest = hasher::digest(&[pair[0][0], pair[0][1], pair[1][0], pair[1][1]]);
+                [digest[0], digest[1]]
+            }).collect();
+            index = index >> 1;
+        }
+        return (nodes[0], path);
+    }
+}
@@ -0,0 +1,553 @@
+use std::convert::TryInto;
+use crate::{
+    math::field,
+    programs::{ Program, ProgramInputs },
+    stark::{ TraceState, check_constraint_group },
+    MIN_TRACE_LENGTH, MAX_REGISTER_COUNT, LD_OP_BITS_RANGE, HD_OP_BITS_RANGE, DEFAULT_STEP_LIMIT,
+};
+use super::{ Decoder, Stack, OpCode, Cursor, execute_program };
+
+pub use crate::stark::{ ConstraintGroup, ConstraintViolation };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes an operation which could not be executed. `instruction` is the index of the source
+/// instruction which produced the operation; it can be mapped to a source line using the source
+/// map returned from `assembly::compile_with_source_map()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub step        : usize,
+    pub op_code     : OpCode,
+    pub instruction : Option<usize>,
+    pub message     : String,
+}
+
+/// Register traces produced by executing a program: decoder registers are followed by stack
+/// registers, and every register contains one value per step. The trace can be exported into
+/// a binary format via `to_bytes()`: a header of 4 little-endian u32 values (width, length,
+/// context depth, loop depth) is followed by the values of each register ordered by step, with
+/// every value encoded as 16 little-endian bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionTrace {
+    registers   : Vec<Vec<u128>>,
+    ctx_depth   : usize,
+    loop_depth  : usize,
+}
+
+/// A value read from one of the secret input tapes by an operation executed at the specified
+/// `step`; tapes are read by READ, READ2, CMP, BINACC, and BITDEC operations. Values pushed onto
+/// the tapes by the VM itself (e.g. binary decompositions for `gt`, `rc`, or `bits`
+/// instructions) are consumed in the same way as values provided by the prover, and thus, are
+/// included as well.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AdviceRead {
+    pub step    : usize,
+    pub tape    : AdviceTape,
+    pub value   : u128,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AdviceTape {
+    A,
+    B,
+}
+
+/// Size of the header which precedes register values in binary encoding of an execution trace.
+pub const TRACE_HEADER_SIZE: usize = 16;
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Executes the specified `program` against the provided `inputs` and returns the first
+/// operation which could not be executed (e.g. a failed assertion or a stack underflow). A
+/// program which does not terminate within DEFAULT_STEP_LIMIT steps is reported as well.
+pub fn check_execution(program: &Program, inputs: &ProgramInputs) -> Result<(), Violation>
+{
+    return check_execution_with_limit(program, inputs, DEFAULT_STEP_LIMIT);
+}
+
+/// Same as check_execution(), but execution is aborted as soon as the program executes more
+/// than `step_limit` steps; the violation is then attributed to the last tracked operation.
+pub fn check_execution_with_limit(program: &Program, inputs: &ProgramInputs, step_limit: usize) -> Result<(), Violation>
+{
+    let mut decoder = Decoder::new(MIN_TRACE_LENGTH);
+    let mut stack = Stack::new(inputs, MIN_TRACE_LENGTH);
+    let mut cursor = Cursor::new();
+
+    return execute_program(program, &mut decoder, &mut stack, &mut cursor, step_limit)
+        .map_err(|err| Violation {
+            step        : cursor.step,
+            op_code     : cursor.op_code,
+            instruction : cursor.instruction,
+            message     : err.to_string(),
+        });
+}
+
+/// Executes the specified `program` against the provided `inputs` and returns the resulting
+/// execution trace.
+pub fn trace_execution(program: &Program, inputs: &ProgramInputs) -> ExecutionTrace
+{
+    let (registers, ctx_depth, loop_depth) = super::execute(program, inputs);
+    return ExecutionTrace { registers, ctx_depth, loop_depth };
+}
+
+/// Checks that `trace` is the execution trace of the `program` executed against the provided
+/// `inputs`; if the program cannot be executed, the failing operation is returned (same as
+/// for check_execution()); otherwise, the first step at which the trace deviates from the
+/// execution is reported together with the operation executed at the previous step.
+pub fn check_trace(program: &Program, inputs: &ProgramInputs, trace: &ExecutionTrace) -> Result<(), Violation>
+{
+    check_execution(program, inputs)?;
+    let expected = trace_execution(program, inputs);
+
+    if trace.ctx_depth != expected.ctx_depth || trace.loop_depth != expected.loop_depth
+        || trace.width() != expected.width() || trace.length() != expected.length()
+    {
+        return Err(Violation {
+            step        : 0,
+            op_code     : OpCode::Begin,
+            instruction : None,
+            message     : format!("expected trace of {} registers and {} steps, but received {} registers and {} steps",
+                expected.width(), expected.length(), trace.width(), trace.length()),
+        });
+    }
+
+    for step in 0..expected.length() {
+        for (i, register) in expected.registers.iter().enumerate() {
+            if trace.registers[i][step] == register[step] { continue; }
+            return Err(Violation {
+                step,
+                op_code     : if step == 0 { OpCode::Begin } else { expected.get_op_code(step - 1) },
+                instruction : None,
+                message     : format!("register {} at step {} is {}, but execution produced {}",
+                    i, step, trace.registers[i][step], register[step]),
+            });
+        }
+    }
+
+    return Ok(());
+}
+
+/// Executes the specified `program` against the provided `inputs` and evaluates only the
+/// constraints of the specified `group` over the resulting execution trace; this does not
+/// generate a proof, and can be used to isolate bugs in a single constraint evaluator.
+pub fn check_group(program: &Program, inputs: &ProgramInputs, group: ConstraintGroup) -> Result<(), ConstraintViolation>
+{
+    return check_trace_group(&trace_execution(program, inputs), group);
+}
+
+/// Evaluates only the constraints of the specified `group` over the `trace` and returns the
+/// first constraint which was not satisfied.
+pub fn check_trace_group(trace: &ExecutionTrace, group: ConstraintGroup) -> Result<(), ConstraintViolation>
+{
+    return check_constraint_group(&trace.registers, trace.ctx_depth, trace.loop_depth, group);
+}
+
+// EXECUTION TRACE IMPLEMENTATION
+// ================================================================================================
+impl ExecutionTrace {
+
+    /// Returns the number of registers in the trace.
+    pub fn width(&self) -> usize {
+        return self.registers.len();
+    }
+
+    /// Returns the number of steps in the trace.
+    pub fn length(&self) -> usize {
+        return self.registers[0].len();
+    }
+
+    pub fn ctx_depth(&self) -> usize {
+        return self.ctx_depth;
+    }
+
+    pub fn loop_depth(&self) -> usize {
+        return self.loop_depth;
+    }
+
+    pub fn registers(&self) -> &[Vec<u128>] {
+        return &self.registers;
+    }
+
+    /// Returns values read from secret input tapes in the order in which they were consumed
+    /// by the program. Every operation which reads a tape places the value it read into a fixed
+    /// stack register at the next step (the top of the stack for READ, READ2, and BINACC, the
+    /// 2nd and 3rd registers for CMP, and the 3rd register for BITDEC), so the sequence is
+    /// determined by the trace itself: reads are ordered by step, and operations which read
+    /// both tapes (READ2 and CMP) read tape A before tape B.
+    pub fn advice_reads(&self) -> Vec<AdviceRead> {
+        let stack_start = TraceState::compute_decoder_width(self.ctx_depth, self.loop_depth);
+        let mut result = Vec::new();
+        for step in 0..(self.length() - 1) {
+            match self.get_op_code(step) {
+                OpCode::Read => {
+                    let value = self.registers[stack_start][step + 1];
+                    result.push(AdviceRead { step, tape: AdviceTape::A, value });
+                },
+                OpCode::Read2 => {
+                    let value_a = self.registers[stack_start + 1][step + 1];
+                    let value_b = self.registers[stack_start][step + 1];
+                    result.push(AdviceRead { step, tape: AdviceTape::A, value: value_a });
+                    result.push(AdviceRead { step, tape: AdviceTape::B, value: value_b });
+                },
+                OpCode::Cmp => {
+                    let value_a = self.registers[stack_start + 1][step + 1];
+                    let value_b = self.registers[stack_start + 2][step + 1];
+                    result.push(AdviceRead { step, tape: AdviceTape::A, value: value_a });
+                    result.push(AdviceRead { step, tape: AdviceTape::B, value: value_b });
+                },
+                OpCode::BinAcc => {
+                    let value = self.registers[stack_start][step + 1];
+                    result.push(AdviceRead { step, tape: AdviceTape::A, value });
+                },
+                OpCode::BitDec => {
+                    let value = self.registers[stack_start + 2][step + 1];
+                    result.push(AdviceRead { step, tape: AdviceTape::A, value });
+                },
+                _ => (),
+            }
+        }
+        return result;
+    }
+
+    /// Consumes the trace and returns its registers.
+    pub fn into_registers(self) -> Vec<Vec<u128>> {
+        return self.registers;
+    }
+
+    /// Serializes the trace into TRACE_HEADER_SIZE + width * length * 16 bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(TRACE_HEADER_SIZE + self.width() * self.length() * 16);
+        for &value in [self.width(), self.length(), self.ctx_depth, self.loop_depth].iter() {
+            result.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        for register in self.registers.iter() {
+            for &value in register.iter() {
+                result.extend_from_slice(&field::to_bytes_le(value));
+            }
+        }
+        return result;
+    }
+
+    /// Deserializes a trace from bytes produced by `to_bytes()`; an error is returned if the
+    /// bytes do not encode a well-formed execution trace.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ExecutionTrace, String> {
+        if bytes.len() < TRACE_HEADER_SIZE {
+            return Err(format!("expected at least {} bytes, but received {}", TRACE_HEADER_SIZE, bytes.len()));
+        }
+        let header: Vec<usize> = bytes[..TRACE_HEADER_SIZE].chunks(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()) as usize)
+            .collect();
+        let (width, length, ctx_depth, loop_depth) = (header[0], header[1], header[2], header[3]);
+
+        validate_shape(width, length, ctx_depth, loop_depth)?;
+        if bytes.len() != TRACE_HEADER_SIZE + width * length * 16 {
+            return Err(format!("expected {} bytes for a trace of {} registers and {} steps, but received {}",
+                TRACE_HEADER_SIZE + width * length * 16, width, length, bytes.len()));
+        }
+
+        let mut registers = Vec::with_capacity(width);
+        for column in bytes[TRACE_HEADER_SIZE..].chunks(length * 16) {
+            let mut register = Vec::with_capacity(length);
+            for chunk in column.chunks(16) {
+                register.push(field::from_bytes_le(chunk.try_into().unwrap())?);
+            }
+            registers.push(register);
+        }
+
+        return Ok(ExecutionTrace { registers, ctx_depth, loop_depth });
+    }
+
+    /// Makes sure the trace has a shape of a valid execution trace: the number of registers must
+    /// be consistent with context and loop depths, and all registers must have the same length,
+    /// which must be a power of 2 and no smaller than MIN_TRACE_LENGTH.
+    pub fn validate(&self) -> Result<(), String> {
+        let length = self.registers.get(0).map_or(0, |register| register.len());
+        validate_shape(self.width(), length, self.ctx_depth, self.loop_depth)?;
+        if let Some(i) = self.registers.iter().position(|register| register.len() != length) {
+            return Err(format!("register {} contains {} steps, but register 0 contains {} steps",
+                i, self.registers[i].len(), length));
+        }
+        return Ok(());
+    }
+
+    /// Returns the operation encoded by the op_bits registers at the specified step.
+    fn get_op_code(&self, step: usize) -> OpCode {
+        let op_bits = self.registers[LD_OP_BITS_RANGE.start..HD_OP_BITS_RANGE.end].iter();
+        let value = op_bits.enumerate().fold(0, |acc, (i, register)| acc | ((register[step] as u8) << i));
+        return OpCode::from_u8(value).unwrap_or(OpCode::Noop);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Makes sure an execution trace of `width` registers and `length` steps with the specified
+/// context and loop depths is well-formed.
+fn validate_shape(width: usize, length: usize, ctx_depth: usize, loop_depth: usize) -> Result<(), String>
+{
+    if ctx_depth > crate::MAX_CONTEXT_DEPTH || loop_depth > crate::MAX_LOOP_DEPTH {
+        return Err(format!("context depth {} or loop depth {} is invalid", ctx_depth, loop_depth));
+    }
+    let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth);
+    if width <= decoder_width || width >= MAX_REGISTER_COUNT {
+        return Err(format!("trace width {} is invalid; must be between {} and {}",
+            width, decoder_width + 1, MAX_REGISTER_COUNT - 1));
+    }
+    if !length.is_power_of_two() {
+        return Err(format!("trace length {} is not a power of 2", length));
+    }
+    if length < MIN_TRACE_LENGTH {
+        return Err(format!("trace length {} is smaller than the minimum of {} steps", length, MIN_TRACE_LENGTH));
+    }
+    return Ok(());
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ programs::assembly, math::field, processor::opcodes::FlowOps, OpCode, ProgramInputs, TraceState, CF_OP_BITS_RANGE };
+    use super::{ ExecutionTrace, ConstraintGroup, AdviceRead, AdviceTape, TRACE_HEADER_SIZE };
+
+    const ALL_GROUPS: [ConstraintGroup; 4] = [
+        ConstraintGroup::OpBits, ConstraintGroup::FlowControl, ConstraintGroup::Stack, ConstraintGroup::Hash
+    ];
+
+    #[test]
+    fn check_execution() {
+        let source = "begin
+            push.3 push.5 add
+            read.a if.true
+                push.7 mul
+            else
+                dup
+                assert
+            end
+        end";
+        let (program, source_map) = assembly::compile_with_source_map(source).unwrap();
+
+        // the true branch executes successfully
+        let inputs = ProgramInputs::new(&[], &[1], &[]);
+        assert_eq!(Ok(()), super::check_execution(&program, &inputs));
+
+        // the false branch fails on the assertion on line 7
+        let inputs = ProgramInputs::new(&[], &[0], &[]);
+        let violation = super::check_execution(&program, &inputs).unwrap_err();
+        assert_eq!(OpCode::Assert, violation.op_code);
+        assert_eq!(format!("ASSERT failed at step {}", violation.step), violation.message);
+
+        let instruction = violation.instruction.unwrap();
+        assert_eq!(7, source_map.line(instruction));
+        assert_eq!("assert", source_map.instruction(instruction));
+    }
+
+    #[test]
+    fn check_execution_multi_step() {
+        // hash.2 expands into several operations, all of which map to line 3
+        let source = "begin
+            push.1 push.2
+            drop drop hash.2
+        end";
+        let (program, source_map) = assembly::compile_with_source_map(source).unwrap();
+
+        let violation = super::check_execution(&program, &ProgramInputs::none()).unwrap_err();
+        assert_eq!(format!("stack underflow at step {}", violation.step), violation.message);
+
+        let instruction = violation.instruction.unwrap();
+        assert_eq!(3, source_map.line(instruction));
+        assert_eq!("hash.2", source_map.instruction(instruction));
+    }
+
+    #[test]
+    fn check_execution_with_limit() {
+        // the loop never exits because every iteration leaves 1 at the top of the stack
+        let program = assembly::compile("begin push.1 while.true push.1 end end").unwrap();
+        let violation = super::check_execution_with_limit(&program, &ProgramInputs::none(), 4096).unwrap_err();
+        assert_eq!("program execution exceeded the limit of 4096 steps", violation.message);
+    }
+
+    #[test]
+    fn trace_to_bytes() {
+        let program = assembly::compile("begin push.3 push.5 add read.a mul end").unwrap();
+        let inputs = ProgramInputs::new(&[], &[7], &[]);
+        let trace = super::trace_execution(&program, &inputs);
+
+        // the binary encoding is a header followed by 16 bytes per trace value
+        let bytes = trace.to_bytes();
+        assert_eq!(TRACE_HEADER_SIZE + trace.width() * trace.length() * 16, bytes.len());
+
+        let decoded = ExecutionTrace::from_bytes(&bytes).unwrap();
+        assert_eq!(trace, decoded);
+        assert_eq!(Ok(()), super::check_trace(&program, &inputs, &decoded));
+
+        // truncated and malformed encodings are rejected
+        assert!(ExecutionTrace::from_bytes(&bytes[..(bytes.len() - 16)]).is_err());
+        let mut malformed = bytes.clone();
+        malformed[TRACE_HEADER_SIZE..(TRACE_HEADER_SIZE + 16)].copy_from_slice(&field::MODULUS.to_le_bytes());
+        assert!(ExecutionTrace::from_bytes(&malformed).is_err());
+
+        // traces shorter than MIN_TRACE_LENGTH are rejected even if the encoding is consistent
+        for &length in [1usize, 2].iter() {
+            let mut short = Vec::new();
+            for &value in [trace.width(), length, trace.ctx_depth(), trace.loop_depth()].iter() {
+                short.extend_from_slice(&(value as u32).to_le_bytes());
+            }
+            short.resize(TRACE_HEADER_SIZE + trace.width() * length * 16, 0);
+            let error = ExecutionTrace::from_bytes(&short).unwrap_err();
+            assert!(error.ends_with("is smaller than the minimum of 16 steps"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn check_trace() {
+        let program = assembly::compile("begin push.3 push.5 add read.a mul end").unwrap();
+        let inputs = ProgramInputs::new(&[], &[7], &[]);
+        let trace = super::trace_execution(&program, &inputs);
+
+        // the trace does not match execution with different inputs
+        let other = ProgramInputs::new(&[], &[8], &[]);
+        let violation = super::check_trace(&program, &other, &trace).unwrap_err();
+        assert_eq!(OpCode::Read, violation.op_code);
+        assert!(violation.message.ends_with("is 7, but execution produced 8"));
+    }
+
+    #[test]
+    fn check_group() {
+        let program = assembly::compile("begin push.1 push.2 hash.2 end").unwrap();
+        let inputs = ProgramInputs::none();
+        for &group in [ConstraintGroup::OpBits, ConstraintGroup::FlowControl, ConstraintGroup::Stack, ConstraintGroup::Hash].iter() {
+            assert_eq!(Ok(()), super::check_group(&program, &inputs, group));
+        }
+
+        // corrupt the hash state in the middle of hash rounds
+        let trace = super::trace_execution(&program, &inputs);
+        let rescr_step = (0..trace.length()).find(|&step| trace.get_op_code(step) == OpCode::RescR).unwrap();
+        let mut registers = trace.registers().to_vec();
+        let stack_start = TraceState::compute_decoder_width(trace.ctx_depth(), trace.loop_depth());
+        registers[stack_start][rescr_step + 2] = field::add(registers[stack_start][rescr_step + 2], field::ONE);
+        let corrupted = ExecutionTrace { registers, ctx_depth: trace.ctx_depth(), loop_depth: trace.loop_depth() };
+
+        // only the hash group (and the stack group which includes it) catches the corruption
+        let violation = super::check_trace_group(&corrupted, ConstraintGroup::Hash).unwrap_err();
+        assert_eq!(ConstraintGroup::Hash, violation.group);
+        assert_eq!(rescr_step + 1, violation.step);
+        assert!(super::check_trace_group(&corrupted, ConstraintGroup::Stack).is_err());
+        assert_eq!(Ok(()), super::check_trace_group(&corrupted, ConstraintGroup::OpBits));
+        assert_eq!(Ok(()), super::check_trace_group(&corrupted, ConstraintGroup::FlowControl));
+    }
+
+    #[test]
+    fn advice_reads() {
+        let program = assembly::compile("begin read push.3 read.ab add read mul end").unwrap();
+        let inputs = ProgramInputs::new(&[], &[1, 2, 3], &[4]);
+        let trace = super::trace_execution(&program, &inputs);
+
+        // values are recorded in the order in which the program issued read operations
+        let read_steps: Vec<usize> = (0..(trace.length() - 1))
+            .filter(|&step| trace.get_op_code(step) == OpCode::Read || trace.get_op_code(step) == OpCode::Read2)
+            .collect();
+        assert_eq!(3, read_steps.len());
+        assert_eq!(vec![
+            AdviceRead { step: read_steps[0], tape: AdviceTape::A, value: 1 },
+            AdviceRead { step: read_steps[1], tape: AdviceTape::A, value: 2 },
+            AdviceRead { step: read_steps[1], tape: AdviceTape::B, value: 4 },
+            AdviceRead { step: read_steps[2], tape: AdviceTape::A, value: 3 },
+        ], trace.advice_reads());
+
+        // values pushed onto the tape by the VM are read as well
+        let program = assembly::compile("begin eq end").unwrap();
+        let trace = super::trace_execution(&program, &ProgramInputs::from_public(&[3, 5]));
+        let reads = trace.advice_reads();
+        assert_eq!(1, reads.len());
+        assert_eq!(field::inv(field::sub(3, 5)), reads[0].value);
+
+        // comparisons read bits of both values from both tapes, most significant bit first:
+        // 6 = 0b0110 and 5 = 0b0101
+        let program = assembly::compile("begin gt.4 end").unwrap();
+        let trace = super::trace_execution(&program, &ProgramInputs::from_public(&[6, 5]));
+        let reads = trace.advice_reads();
+        let cmp_steps: Vec<usize> = (0..(trace.length() - 1))
+            .filter(|&step| trace.get_op_code(step) == OpCode::Cmp)
+            .collect();
+        assert_eq!(4, cmp_steps.len());
+        let expected: Vec<AdviceRead> = cmp_steps.iter().zip([(0, 0), (1, 1), (1, 0), (0, 1)].iter())
+            .flat_map(|(&step, &(a, b))| vec![
+                AdviceRead { step, tape: AdviceTape::A, value: a },
+                AdviceRead { step, tape: AdviceTape::B, value: b },
+            ])
+            .collect();
+        assert_eq!(expected, reads);
+
+        // range checks and bit decompositions read bits from tape A
+        for &(source, op_code) in [("begin rc.4 end", OpCode::BinAcc), ("begin bits.4 end", OpCode::BitDec)].iter() {
+            let program = assembly::compile(source).unwrap();
+            let trace = super::trace_execution(&program, &ProgramInputs::from_public(&[6]));
+            let bits: Vec<AdviceRead> = trace.advice_reads().into_iter()
+                .filter(|read| trace.get_op_code(read.step) == op_code)
+                .collect();
+            assert_eq!(4, bits.len(), "{}", source);
+            assert!(bits.iter().all(|read| read.tape == AdviceTape::A && read.value <= 1));
+        }
+    }
+
+    #[test]
+    fn padded_tail() {
+        // the program ends well before the end of the trace, and thus, the trace has a long tail
+        let program = assembly::compile("begin push.1 push.2 hash.2 push.3 end").unwrap();
+        let inputs = ProgramInputs::none();
+        let trace = super::trace_execution(&program, &inputs);
+        let tail_start = get_tail_start(&trace);
+        assert!(tail_start < trace.length() - 16);
+
+        // within the tail, only VOID and NOOP flags are set; all other operations are masked out
+        let stack_depth = trace.width() - TraceState::compute_decoder_width(trace.ctx_depth(), trace.loop_depth());
+        let mut state = TraceState::new(trace.ctx_depth(), trace.loop_depth(), stack_depth);
+        for step in tail_start..trace.length() {
+            state.update_from_trace(&trace.registers, step);
+            assert_eq!(OpCode::Noop, trace.get_op_code(step));
+            assert_eq!(field::ONE, state.cf_op_flags()[FlowOps::Void as usize], "VOID flag is not set at step {}", step);
+            assert_eq!(field::ZERO, state.hd_op_flags()[OpCode::RescR.hd_index()], "RESCR flag is set at step {}", step);
+        }
+
+        // and the tail contributes zero to every constraint
+        for &group in ALL_GROUPS.iter() {
+            assert_eq!(Ok(()), super::check_trace_group(&trace, group));
+        }
+    }
+
+    #[test]
+    fn padded_tail_naive() {
+        let program = assembly::compile("begin push.1 push.2 hash.2 push.3 end").unwrap();
+        let inputs = ProgramInputs::none();
+        let trace = super::trace_execution(&program, &inputs);
+        let tail_start = get_tail_start(&trace);
+
+        // pad the trace by repeating the last executed step, including its operation; the
+        // operation is not masked out, and its constraints are violated within the tail
+        let mut registers = trace.registers().to_vec();
+        for register in registers.iter_mut() {
+            let last_value = register[tail_start - 1];
+            register[tail_start..].iter_mut().for_each(|value| *value = last_value);
+        }
+        let naive = ExecutionTrace { registers, ctx_depth: trace.ctx_depth(), loop_depth: trace.loop_depth() };
+
+        let violation = ALL_GROUPS.iter()
+            .find_map(|&group| super::check_trace_group(&naive, group).err())
+            .expect("naively padded trace should violate constraints");
+        assert!(violation.step >= tail_start - 1);
+    }
+
+    /// Returns the first step of the padded tail of the trace; at all steps of the tail, flow
+    /// control operation is VOID.
+    fn get_tail_start(trace: &ExecutionTrace) -> usize {
+        let is_void = |step: usize| trace.registers()[CF_OP_BITS_RANGE].iter().all(|r| r[step] == field::ONE);
+        let mut step = trace.length();
+        while step > 0 && is_void(step - 1) {
+            step -= 1;
+        }
+        return step;
+    }
+}
@@ -83,8 +83,12 @@ impl Decoder {
         return self.op_counter.len();
     }
 
+    /// Returns value of the operation counter at the current step.
+    pub fn op_counter(&self) -> u128 {
+        return self.op_counter[self.step];
+    }
+
     /// Returns value of the current step pointer.
-    #[cfg(test)]
     pub fn current_step(&self) -> usize {
         return self.step;
     }
@@ -249,7 +253,10 @@ impl Decoder {
     }
 
     /// Populate all register traces with values for steps between the current step
-    /// and the end of the trace.
+    /// and the end of the trace. Padding steps execute VOID and NOOP operations: flags of all
+    /// other operations are zero at these steps, and thus, constraints of these operations (e.g.
+    /// hash rounds) are masked out in the padded tail, while constraints of VOID and NOOP hold
+    /// because padded register values do not change.
     pub fn finalize_trace(&mut self) {
         // don't increase counter for void instructions
         let last_op_count = self.op_counter[self.step];
@@ -1,7 +1,7 @@
 use crate::{
     math::field,
     programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Loop } },
-    MIN_TRACE_LENGTH, HACC_NUM_ROUNDS,
+    MIN_TRACE_LENGTH, HACC_NUM_ROUNDS, DEFAULT_STEP_LIMIT,
 };
 
 // RE-EXPORTS
@@ -13,22 +13,57 @@ pub use decoder::{ Decoder };
 mod stack;
 pub use stack::{ Stack };
 
+mod tape;
+pub use tape::{ Tape, InputCallback };
+
 pub mod opcodes;
-pub use opcodes::{ UserOps as OpCode, OpHint };
+pub use opcodes::{ UserOps as OpCode, OpFlag, OpHint };
+
+pub mod debug;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Errors which cause program execution to be aborted before the program terminates.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExecutionError {
+    StepLimitExceeded { limit: usize },
+    OperationFailed(String),
+}
+
+/// Operation which is currently being executed; this is updated before every operation so that
+/// when execution fails, the offending operation can be identified. Operations executed
+/// implicitly (e.g. when blocks are opened or closed) are not tracked, and failures in such
+/// operations are attributed to the last tracked operation.
+struct Cursor {
+    step        : usize,
+    op_code     : OpCode,
+    instruction : Option<usize>,
+}
 
 // PUBLIC FUNCTIONS
 // ================================================================================================
 
-/// Returns register traces resulting from executing the `program` against the specified inputs.
+/// Returns register traces resulting from executing the `program` against the specified inputs;
+/// panics if the program cannot be executed, or if it does not terminate within
+/// DEFAULT_STEP_LIMIT steps.
 pub fn execute(program: &Program, inputs: &ProgramInputs) -> (Vec<Vec<u128>>, usize, usize)
+{
+    return execute_with_limit(program, inputs, DEFAULT_STEP_LIMIT)
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+/// Same as execute(), but an error is returned if the program cannot be executed (e.g. on a
+/// failed assertion), and execution is aborted as soon as the program executes more than
+/// `step_limit` steps; this guarantees termination for programs with loops which never exit.
+pub fn execute_with_limit(program: &Program, inputs: &ProgramInputs, step_limit: usize) -> Result<(Vec<Vec<u128>>, usize, usize), ExecutionError>
 {
     // initialize decoder and stack components
     let mut decoder = Decoder::new(MIN_TRACE_LENGTH);
     let mut stack = Stack::new(inputs, MIN_TRACE_LENGTH);
 
     // execute body of the program
-    execute_blocks(program.root().body(), &mut decoder, &mut stack);
-    close_block(&mut decoder, &mut stack, field::ZERO, true);
+    execute_program(program, &mut decoder, &mut stack, &mut Cursor::new(), step_limit)?;
 
     // fill in remaining steps to make sure the length of the trace is a power of 2
     decoder.finalize_trace();
@@ -42,140 +77,253 @@ pub fn execute(program: &Program, inputs: &ProgramInputs) -> (Vec<Vec<u128>>, us
     let mut register_traces = decoder.into_register_traces();
     register_traces.append(&mut stack.into_register_traces());
 
-    return (register_traces, context_depth, loop_depth);
+    return Ok((register_traces, context_depth, loop_depth));
+}
+
+/// Same as execute(), but secret inputs are read from the provided tapes; this way, inputs can
+/// be supplied while the program is being executed. Unlike execute(), an error is returned if
+/// the tapes still contain unread values once the program has been executed, or if the program
+/// does not terminate within `step_limit` steps.
+pub fn execute_with_tapes(program: &Program, public_inputs: &[u128], tape_a: Tape, tape_b: Tape, step_limit: usize) -> Result<(Vec<Vec<u128>>, usize, usize), String>
+{
+    let mut decoder = Decoder::new(MIN_TRACE_LENGTH);
+    let mut stack = Stack::with_tapes(public_inputs, tape_a, tape_b, MIN_TRACE_LENGTH);
+
+    execute_program(program, &mut decoder, &mut stack, &mut Cursor::new(), step_limit)
+        .map_err(|err| err.to_string())?;
+
+    if stack.has_unread_inputs() {
+        return Err(String::from("program execution completed with secret inputs still pending"));
+    }
+
+    decoder.finalize_trace();
+    stack.finalize_trace();
+
+    let context_depth = decoder.max_ctx_stack_depth();
+    let loop_depth = decoder.max_loop_stack_depth();
+
+    let mut register_traces = decoder.into_register_traces();
+    register_traces.append(&mut stack.into_register_traces());
+
+    return Ok((register_traces, context_depth, loop_depth));
+}
+
+/// Executes the specified `program` against the provided `inputs` and returns the top
+/// `num_outputs` values of the stack; unlike execute(), the trace is not finalized and
+/// register traces are discarded. Panics if the program cannot be executed, or if it does not
+/// terminate within DEFAULT_STEP_LIMIT steps.
+pub fn simulate(program: &Program, inputs: &ProgramInputs, num_outputs: usize) -> Vec<u128>
+{
+    let mut decoder = Decoder::new(MIN_TRACE_LENGTH);
+    let mut stack = Stack::new(inputs, MIN_TRACE_LENGTH);
+
+    execute_program(program, &mut decoder, &mut stack, &mut Cursor::new(), DEFAULT_STEP_LIMIT)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    return stack.get_stack_values(num_outputs);
+}
+
+// EXECUTION ERROR IMPLEMENTATION
+// ================================================================================================
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            ExecutionError::StepLimitExceeded { limit } => write!(f,
+                "program execution exceeded the limit of {} steps", limit),
+            ExecutionError::OperationFailed(message) => write!(f, "{}", message),
+        };
+    }
+}
+
+// CURSOR IMPLEMENTATION
+// ================================================================================================
+impl Cursor {
+
+    fn new() -> Cursor {
+        return Cursor { step: 0, op_code: OpCode::Begin, instruction: None };
+    }
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn execute_blocks(blocks: &[ProgramBlock], decoder: &mut Decoder, stack: &mut Stack)
+
+/// Executes the body of the `program` and closes the program block; `cursor` is updated before
+/// every executed instruction.
+fn execute_program(program: &Program, decoder: &mut Decoder, stack: &mut Stack, cursor: &mut Cursor, step_limit: usize) -> Result<(), ExecutionError>
+{
+    execute_blocks(program.root().body(), decoder, stack, cursor, step_limit)?;
+    close_block(decoder, stack, field::ZERO, true)?;
+    return check_step_limit(decoder, step_limit);
+}
+
+fn execute_blocks(blocks: &[ProgramBlock], decoder: &mut Decoder, stack: &mut Stack, cursor: &mut Cursor, step_limit: usize) -> Result<(), ExecutionError>
 {
     // execute first block in the sequence, which mast be a Span block
     match &blocks[0] {
-        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true),
+        ProgramBlock::Span(block) => execute_span(block, decoder, stack, cursor, true)?,
         _ => panic!("first block in a sequence must be a Span block"),
     }
 
     // execute all other blocks in the sequence one after another
     for block in blocks.iter().skip(1) {
         match block {
-            ProgramBlock::Span(block) => execute_span(block, decoder, stack, false),
+            ProgramBlock::Span(block) => execute_span(block, decoder, stack, cursor, false)?,
             ProgramBlock::Group(block) => {
-                start_block(decoder, stack);
-                execute_blocks(block.body(), decoder, stack);
-                close_block(decoder, stack, field::ZERO, true);
+                start_block(decoder, stack)?;
+                execute_blocks(block.body(), decoder, stack, cursor, step_limit)?;
+                close_block(decoder, stack, field::ZERO, true)?;
             },
             ProgramBlock::Switch(block) => {
-                start_block(decoder, stack);
+                start_block(decoder, stack)?;
                 let condition = stack.get_stack_top();
                 match condition {
                     0 => {
-                        execute_blocks(block.false_branch(), decoder, stack);
-                        close_block(decoder, stack, block.true_branch_hash(), false);
+                        execute_blocks(block.false_branch(), decoder, stack, cursor, step_limit)?;
+                        close_block(decoder, stack, block.true_branch_hash(), false)?;
                     },
                     1 => {
-                        execute_blocks(block.true_branch(), decoder, stack);
-                        close_block(decoder, stack, block.false_branch_hash(), true);
+                        execute_blocks(block.true_branch(), decoder, stack, cursor, step_limit)?;
+                        close_block(decoder, stack, block.false_branch_hash(), true)?;
                     },
-                    _ => panic!("cannot select a branch based on a non-binary condition {}", condition)
+                    _ => return Err(ExecutionError::OperationFailed(format!(
+                        "cannot select a branch based on a non-binary condition {}", field::Element(condition))))
                 };
             },
             ProgramBlock::Loop(block) => {
                 let condition = stack.get_stack_top();
                 match condition {
                     0 => {
-                        start_block(decoder, stack);
-                        execute_blocks(block.skip(), decoder, stack);
-                        close_block(decoder, stack, block.body_hash(), false);
+                        start_block(decoder, stack)?;
+                        execute_blocks(block.skip(), decoder, stack, cursor, step_limit)?;
+                        close_block(decoder, stack, block.body_hash(), false)?;
                     },
-                    1 => execute_loop(block, decoder, stack),
-                    _ => panic!("cannot enter loop based on a non-binary condition {}", condition)
+                    1 => execute_loop(block, decoder, stack, cursor, step_limit)?,
+                    _ => return Err(ExecutionError::OperationFailed(format!(
+                        "cannot enter loop based on a non-binary condition {}", field::Element(condition))))
                 }
             },
         }
     }
+
+    return Ok(());
 }
 
 /// Executes all instructions in a Span block.
-fn execute_span(block: &Span, decoder: &mut Decoder, stack: &mut Stack, is_first: bool)
+fn execute_span(block: &Span, decoder: &mut Decoder, stack: &mut Stack, cursor: &mut Cursor, is_first: bool) -> Result<(), ExecutionError>
 {
     // if this is the first Span block in a sequence of blocks, it needs to be
     // pre-padded with a NOOP to make sure the first instruction in the block
     // starts executing on a step which is a multiple of 16
     if !is_first {
         decoder.decode_op(OpCode::Noop, field::ZERO);
-        stack.execute(OpCode::Noop, OpHint::None);
+        execute_op(stack, OpCode::Noop, OpHint::None)?;
     }
 
     // execute all other instructions in the block
     for i in 0..block.length() {
         let (op_code, op_hint) = block.get_op(i);
+
+        // the stack reports errors using the step after the current one
+        cursor.step = stack.current_step() + 1;
+        cursor.op_code = op_code;
+        cursor.instruction = block.get_source(i);
+
         decoder.decode_op(op_code, op_hint.value());
-        stack.execute(op_code, op_hint);
+
+        // CLK operation pushes the value of the operation counter onto the stack; this value
+        // is tracked by the decoder, and thus, it is passed to the stack via an op hint
+        let op_hint = match op_code {
+            OpCode::Clk => OpHint::PushValue(decoder.op_counter()),
+            _ => op_hint,
+        };
+        execute_op(stack, op_code, op_hint)?;
     }
+
+    return Ok(());
 }
 
 /// Starts executing a new program block.
-fn start_block(decoder: &mut Decoder, stack: &mut Stack)
+fn start_block(decoder: &mut Decoder, stack: &mut Stack) -> Result<(), ExecutionError>
 {
     decoder.start_block();
-    stack.execute(OpCode::Noop, OpHint::None);
+    return execute_op(stack, OpCode::Noop, OpHint::None);
 }
 
 /// Closes the currently executing program block.
-fn close_block(decoder: &mut Decoder, stack: &mut Stack, sibling_hash: u128, is_true_branch: bool)
+fn close_block(decoder: &mut Decoder, stack: &mut Stack, sibling_hash: u128, is_true_branch: bool) -> Result<(), ExecutionError>
 {
     // a sequence of blocks always ends on a step which is one less than a multiple of 16;
     // all sequences end one operation short of multiple of 16 - so, we need to pad them
     // with a single NOOP ensure proper alignment
     decoder.decode_op(OpCode::Noop, field::ZERO);
-    stack.execute(OpCode::Noop, OpHint::None);
+    execute_op(stack, OpCode::Noop, OpHint::None)?;
 
     // end the block, this prepares decoder registers for merging block hash into
     // program hash
     decoder.end_block(sibling_hash, is_true_branch);
-    stack.execute(OpCode::Noop, OpHint::None);
+    execute_op(stack, OpCode::Noop, OpHint::None)?;
 
     // execute NOOPs to merge block hash into the program hash
     for _ in 0..HACC_NUM_ROUNDS {
         decoder.decode_op(OpCode::Noop, field::ZERO);
-        stack.execute(OpCode::Noop, OpHint::None);
+        execute_op(stack, OpCode::Noop, OpHint::None)?;
     }
+
+    return Ok(());
 }
 
-/// Executes the specified loop.
-fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
+/// Executes the specified loop; execution is aborted if the number of executed steps exceeds
+/// `step_limit` after any iteration of the loop.
+fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack, cursor: &mut Cursor, step_limit: usize) -> Result<(), ExecutionError>
 {
     // mark the beginning of the loop block
     decoder.start_loop(block.image());
-    stack.execute(OpCode::Noop, OpHint::None);
+    execute_op(stack, OpCode::Noop, OpHint::None)?;
 
     // execute blocks in loop body until top of the stack becomes 0
     loop {
-        execute_blocks(block.body(), decoder, stack);
+        execute_blocks(block.body(), decoder, stack, cursor, step_limit)?;
+        check_step_limit(decoder, step_limit)?;
 
         let condition = stack.get_stack_top();
         match condition {
             0 => {
                 decoder.break_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
+                execute_op(stack, OpCode::Noop, OpHint::None)?;
                 break;
             },
             1 => {
                 decoder.wrap_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
+                execute_op(stack, OpCode::Noop, OpHint::None)?;
             },
-            _ => panic!("cannot exit loop based on a non-binary condition {}", condition)
+            _ => return Err(ExecutionError::OperationFailed(format!(
+                "cannot exit loop based on a non-binary condition {}", field::Element(condition))))
         };
     }
 
     // execute the contents of the skip block to make sure the loop was exited correctly
     match &block.skip()[0] {
-        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true),
+        ProgramBlock::Span(block) => execute_span(block, decoder, stack, cursor, true)?,
         _ => panic!("invalid skip block content: content must be a Span block"),
     }
 
     // close block
-    close_block(decoder, stack, block.skip_hash(), true);
+    return close_block(decoder, stack, block.skip_hash(), true);
+}
+
+/// Executes a single operation against the stack.
+fn execute_op(stack: &mut Stack, op_code: OpCode, op_hint: OpHint) -> Result<(), ExecutionError>
+{
+    return stack.execute(op_code, op_hint).map_err(ExecutionError::OperationFailed);
+}
+
+/// Returns an error if the decoder has executed more than `step_limit` steps.
+fn check_step_limit(decoder: &Decoder, step_limit: usize) -> Result<(), ExecutionError>
+{
+    if decoder.current_step() > step_limit {
+        return Err(ExecutionError::StepLimitExceeded { limit: step_limit });
+    }
+    return Ok(());
 }
 
 // TESTS
@@ -185,7 +333,7 @@ fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
 mod tests {
 
     use crate::{ programs::assembly, stark::TraceState, utils::as_bytes };
-    use super::{ ProgramInputs };
+    use super::{ ProgramInputs, ExecutionError };
 
     #[test]
     fn execute_span() {
@@ -345,6 +493,19 @@ mod tests {
         assert_eq!([43143988327398919500410556793212890625, 0, 0, 0, 0, 0, 0, 0], state.user_stack());
     }
 
+    #[test]
+    fn execute_failed_operation() {
+        // operations which cannot be executed abort execution with an error
+        let program = assembly::compile("begin push.3 assert end").unwrap();
+        let result = super::execute_with_limit(&program, &ProgramInputs::none(), 4096);
+        assert_eq!(Err(ExecutionError::OperationFailed(String::from("ASSERT failed at step 10"))), result);
+
+        // and so do branches on non-binary conditions
+        let program = assembly::compile("begin push.3 if.true push.1 else push.2 end end").unwrap();
+        let err = super::execute_with_limit(&program, &ProgramInputs::none(), 4096).unwrap_err();
+        assert!(err.to_string().starts_with("cannot select a branch based on a non-binary condition"), "{}", err);
+    }
+
     fn build_trace_state(num_registers: usize, ctx_depth: usize, loop_depth: usize) -> TraceState {
         let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth);
         let stack_depth = num_registers - decoder_width;
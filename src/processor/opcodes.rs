@@ -42,7 +42,8 @@ impl std::fmt::Display for FlowOps {
 // USER OPERATIONS
 // ================================================================================================
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum UserOps {
     
     // low-degree operations
@@ -62,7 +63,7 @@ pub enum UserOps {
     Inv         = 0b0_11_01100,         // no shift
     Neg         = 0b0_11_01101,         // no shift
     Not         = 0b0_11_01110,         // no shift
-    //???       = 0b0_11_01111,
+    Write       = 0b0_11_01111,         // left shift: 1
 
     Read        = 0b0_11_10000,         // right shift: 1
     Read2       = 0b0_11_10001,         // right shift: 2
@@ -70,8 +71,8 @@ pub enum UserOps {
     Dup2        = 0b0_11_10011,         // right shift: 2
     Dup4        = 0b0_11_10100,         // right shift: 4
     Pad2        = 0b0_11_10101,         // right shift: 2
-    //???       = 0b0_11_10110,
-    //???       = 0b0_11_10111,
+    IsZero      = 0b0_11_10110,         // left shift: 1
+    Clk         = 0b0_11_10111,         // right shift: 1
 
     Swap        = 0b0_11_11000,         // no shift
     Swap2       = 0b0_11_11001,         // no shift
@@ -79,7 +80,7 @@ pub enum UserOps {
     Roll4       = 0b0_11_11011,         // no shift
     Roll8       = 0b0_11_11100,         // no shift
     BinAcc      = 0b0_11_11101,         // no shift
-    //???       = 0b0_11_11110,
+    BitDec      = 0b0_11_11110,         // right shift: 1
 
     // high-degree operations
     Push        = 0b0_00_11111,         // right shift: 1
@@ -91,8 +92,43 @@ pub enum UserOps {
     Noop        = 0b0_11_11111,         // no shift
 }
 
+/// Identifies operation flags which select constraints of an operation: low-degree operations
+/// are selected by a single ld flag, high-degree operations by a single hd flag, and composite
+/// operations by a product of ld and hd flags.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OpFlag {
+    Ld(usize),
+    Hd(usize),
+    Composite(usize, usize),
+}
+
 impl UserOps {
 
+    /// All user operations; every operation must be listed here exactly once.
+    pub const ALL: [UserOps; 36] = [
+        UserOps::Assert,    UserOps::AssertEq,  UserOps::Eq,        UserOps::Drop,
+        UserOps::Drop4,     UserOps::Choose,    UserOps::Choose2,   UserOps::CSwap2,
+        UserOps::Add,       UserOps::Mul,       UserOps::And,       UserOps::Or,
+        UserOps::Inv,       UserOps::Neg,       UserOps::Not,       UserOps::Write,
+        UserOps::Read,      UserOps::Read2,     UserOps::Dup,       UserOps::Dup2,
+        UserOps::Dup4,      UserOps::Pad2,      UserOps::IsZero,    UserOps::Clk,
+        UserOps::Swap,      UserOps::Swap2,     UserOps::Swap4,     UserOps::Roll4,
+        UserOps::Roll8,     UserOps::BinAcc,    UserOps::BitDec,
+        UserOps::Push,      UserOps::Cmp,       UserOps::RescR,
+        UserOps::Begin,     UserOps::Noop,
+    ];
+
+    /// Returns an operation encoded by the specified value, or None if the value does not
+    /// encode any operation.
+    pub fn from_u8(value: u8) -> Option<UserOps> {
+        return UserOps::ALL.iter().find(|op| op.to_u8() == value).cloned();
+    }
+
+    /// Returns the value encoding this operation in the op_bits registers of the decoder.
+    pub fn to_u8(&self) -> u8 {
+        return *self as u8;
+    }
+
     pub fn ld_index(&self) -> usize {
         return match self {
             UserOps::Push | UserOps::Cmp | UserOps::RescR => {
@@ -114,66 +150,141 @@ impl UserOps {
             }
         };
     }
-}
 
-impl std::fmt::Display for UserOps {
+    /// Returns the flag which selects constraints of this operation in the stack constraint
+    /// evaluator.
+    pub fn constraint_flag(&self) -> OpFlag {
+        return match self {
+            UserOps::Push | UserOps::Cmp | UserOps::RescR => OpFlag::Hd(self.hd_index()),
+            UserOps::Begin | UserOps::Noop => OpFlag::Composite(self.ld_index(), self.hd_index()),
+            _ => OpFlag::Ld(self.ld_index()),
+        };
+    }
 
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    /// Returns the change in the depth of the stack caused by this operation; positive values
+    /// indicate a right shift (the stack grows), negative values indicate a left shift.
+    pub fn stack_effect(&self) -> i32 {
         return match self {
+            UserOps::Begin      =>  0,
+            UserOps::Noop       =>  0,
+
+            UserOps::Assert     => -1,
+            UserOps::AssertEq   => -2,
+
+            UserOps::Push       =>  1,
+            UserOps::Read       =>  1,
+            UserOps::Read2      =>  2,
+            UserOps::Clk        =>  1,
+            UserOps::Write      => -1,
+
+            UserOps::Dup        =>  1,
+            UserOps::Dup2       =>  2,
+            UserOps::Dup4       =>  4,
+            UserOps::Pad2       =>  2,
 
-            UserOps::Begin      => write!(f, "begin"),
-            UserOps::Noop       => write!(f, "noop"),
+            UserOps::Drop       => -1,
+            UserOps::Drop4      => -4,
 
-            UserOps::Assert     => write!(f, "assert"),
-            UserOps::AssertEq   => write!(f, "asserteq"),
+            UserOps::Swap       =>  0,
+            UserOps::Swap2      =>  0,
+            UserOps::Swap4      =>  0,
+
+            UserOps::Roll4      =>  0,
+            UserOps::Roll8      =>  0,
+
+            UserOps::Choose     => -2,
+            UserOps::Choose2    => -4,
+            UserOps::CSwap2     => -2,
+
+            UserOps::Add        => -1,
+            UserOps::Mul        => -1,
+            UserOps::Inv        =>  0,
+            UserOps::Neg        =>  0,
+            UserOps::Not        =>  0,
+            UserOps::And        => -1,
+            UserOps::Or         => -1,
+
+            UserOps::Eq         => -2,
+            UserOps::IsZero     => -1,
+            UserOps::Cmp        =>  0,
+            UserOps::BinAcc     =>  0,
+            UserOps::BitDec     =>  1,
+
+            UserOps::RescR      =>  0,
+        };
+    }
+
+    /// Returns the mnemonic of this operation.
+    pub fn mnemonic(&self) -> &'static str {
+        return match self {
+
+            UserOps::Begin      => "begin",
+            UserOps::Noop       => "noop",
+
+            UserOps::Assert     => "assert",
+            UserOps::AssertEq   => "asserteq",
     
-            UserOps::Push       => write!(f, "push"),
-            UserOps::Read       => write!(f, "read"),
-            UserOps::Read2      => write!(f, "read2"),
+            UserOps::Push       => "push",
+            UserOps::Read       => "read",
+            UserOps::Read2      => "read2",
+            UserOps::Clk        => "clk",
+            UserOps::Write      => "write",
     
-            UserOps::Dup        => write!(f, "dup"),
-            UserOps::Dup2       => write!(f, "dup2"),
-            UserOps::Dup4       => write!(f, "dup4"),
-            UserOps::Pad2       => write!(f, "pad2"),
+            UserOps::Dup        => "dup",
+            UserOps::Dup2       => "dup2",
+            UserOps::Dup4       => "dup4",
+            UserOps::Pad2       => "pad2",
     
-            UserOps::Drop       => write!(f, "drop"),
-            UserOps::Drop4      => write!(f, "drop4"),
+            UserOps::Drop       => "drop",
+            UserOps::Drop4      => "drop4",
     
-            UserOps::Swap       => write!(f, "swap"),
-            UserOps::Swap2      => write!(f, "swap2"),
-            UserOps::Swap4      => write!(f, "swap4"),
+            UserOps::Swap       => "swap",
+            UserOps::Swap2      => "swap2",
+            UserOps::Swap4      => "swap4",
     
-            UserOps::Roll4      => write!(f, "roll4"),
-            UserOps::Roll8      => write!(f, "roll8"),
+            UserOps::Roll4      => "roll4",
+            UserOps::Roll8      => "roll8",
     
-            UserOps::Choose     => write!(f, "choose"),
-            UserOps::Choose2    => write!(f, "choose2"),
-            UserOps::CSwap2     => write!(f, "cswap2"),
+            UserOps::Choose     => "choose",
+            UserOps::Choose2    => "choose2",
+            UserOps::CSwap2     => "cswap2",
     
-            UserOps::Add        => write!(f, "add"),
-            UserOps::Mul        => write!(f, "mul"),
-            UserOps::Inv        => write!(f, "inv"),
-            UserOps::Neg        => write!(f, "neg"),
-            UserOps::Not        => write!(f, "not"),
-            UserOps::And        => write!(f, "and"),
-            UserOps::Or         => write!(f, "or"),
+            UserOps::Add        => "add",
+            UserOps::Mul        => "mul",
+            UserOps::Inv        => "inv",
+            UserOps::Neg        => "neg",
+            UserOps::Not        => "not",
+            UserOps::And        => "and",
+            UserOps::Or         => "or",
     
-            UserOps::Eq         => write!(f, "eq"),
-            UserOps::Cmp        => write!(f, "cmp"),
-            UserOps::BinAcc     => write!(f, "binacc"),
+            UserOps::Eq         => "eq",
+            UserOps::IsZero     => "iszero",
+            UserOps::Cmp        => "cmp",
+            UserOps::BinAcc     => "binacc",
+            UserOps::BitDec     => "bitdec",
     
-            UserOps::RescR      => write!(f, "rescr")
+            UserOps::RescR      => "rescr"
         };
     }
 }
 
+impl std::fmt::Display for UserOps {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return write!(f, "{}", self.mnemonic());
+    }
+}
+
 // OPERATION HINTS
 // ================================================================================================
 #[derive(Copy, Clone, Debug)]
 pub enum OpHint {
     EqStart,
+    IsZeroStart,
     RcStart(u32),
     CmpStart(u32),
+    BitsStart(u32),
+    BytesStart(u32),
     PmpathStart(u32),
     PushValue(u128),
     None,
@@ -192,11 +303,49 @@ impl std::fmt::Display for OpHint {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         return match self {
             OpHint::EqStart          => write!(f, "::eq"),
+            OpHint::IsZeroStart      => write!(f, "::iszero"),
             OpHint::RcStart(value)   => write!(f, ".{}", value),
             OpHint::CmpStart(value)     => write!(f, ".{}", value),
+            OpHint::BitsStart(value)    => write!(f, ".{}", value),
+            OpHint::BytesStart(value)   => write!(f, ".{}", value),
             OpHint::PmpathStart(value)  => write!(f, ".{}", value),
             OpHint::PushValue(value)    => write!(f, "({})", value),
             OpHint::None             => Ok(()),
         };
     }
-}
\ No newline at end of file
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+    use super::{ UserOps, OpFlag };
+
+    #[test]
+    fn from_u8_to_u8() {
+        for &op in UserOps::ALL.iter() {
+            assert_eq!(Some(op), UserOps::from_u8(op.to_u8()));
+        }
+
+        // values which do not encode any operation
+        assert_eq!(None, UserOps::from_u8(0b0_00_00001));
+        assert_eq!(None, UserOps::from_u8(0b1_00_00000));
+    }
+
+    #[test]
+    fn unique_encodings() {
+        let values: HashSet<u8> = UserOps::ALL.iter().map(|op| op.to_u8()).collect();
+        assert_eq!(UserOps::ALL.len(), values.len());
+
+        let mnemonics: HashSet<&str> = UserOps::ALL.iter().map(|op| op.mnemonic()).collect();
+        assert_eq!(UserOps::ALL.len(), mnemonics.len());
+
+        // no two operations are selected by the same constraint flag
+        let flags: Vec<OpFlag> = UserOps::ALL.iter().map(|op| op.constraint_flag()).collect();
+        for i in 0..flags.len() {
+            assert!(!flags[(i + 1)..].contains(&flags[i]), "duplicate flag for {}", UserOps::ALL[i]);
+        }
+    }
+}
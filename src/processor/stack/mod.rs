@@ -1,7 +1,7 @@
 use crate::{
     math::field,
     utils::hasher,
-    ProgramInputs, OpCode, OpHint,
+    processor::Tape, ProgramInputs, OpCode, OpHint,
     HASH_STATE_WIDTH, MIN_STACK_DEPTH, MAX_STACK_DEPTH,
 };
 
@@ -12,8 +12,8 @@ mod tests;
 // ================================================================================================
 pub struct Stack {
     registers   : Vec<Vec<u128>>,
-    tape_a      : Vec<u128>,
-    tape_b      : Vec<u128>,
+    tape_a      : Tape,
+    tape_b      : Tape,
     max_depth   : usize,
     depth       : usize,
     step        : usize,
@@ -27,9 +27,16 @@ impl Stack {
     /// of `init_trace_length` steps. Register traces will be expanded dynamically if the number
     /// of actual steps exceeds this initial setting.
     pub fn new(inputs: &ProgramInputs, init_trace_length: usize) -> Stack {
+        let [secret_inputs_a, secret_inputs_b] = inputs.get_secret_inputs();
+        let tape_a = Tape::new(secret_inputs_a);
+        let tape_b = Tape::new(secret_inputs_b);
+        return Stack::with_tapes(inputs.get_public_inputs(), tape_a, tape_b, init_trace_length);
+    }
+
+    /// Same as new(), but secret inputs are read from the provided tapes.
+    pub fn with_tapes(public_inputs: &[u128], tape_a: Tape, tape_b: Tape, init_trace_length: usize) -> Stack {
 
         // allocate space for register traces and initialize the first state with public inputs
-        let public_inputs = inputs.get_public_inputs();
         let init_stack_depth = std::cmp::max(public_inputs.len(), MIN_STACK_DEPTH);
         let mut registers: Vec<Vec<u128>> = Vec::with_capacity(init_stack_depth);
         for i in 0..init_stack_depth {
@@ -40,13 +47,6 @@ impl Stack {
             registers.push(register);
         }
 
-        // reverse secret inputs so that they are consumed in FIFO order
-        let [secret_inputs_a, secret_inputs_b] = inputs.get_secret_inputs();
-        let mut tape_a = secret_inputs_a.clone();
-        tape_a.reverse();
-        let mut tape_b = secret_inputs_b.clone();
-        tape_b.reverse();
-
         return Stack {
             registers,
             tape_a,
@@ -57,11 +57,13 @@ impl Stack {
         };
     }
 
-    /// Executes `opcode` against the current state of the stack.
-    pub fn execute(&mut self, op_code: OpCode, op_hint: OpHint) {
+    /// Executes `opcode` against the current state of the stack; an error is returned if the
+    /// operation cannot be executed (e.g. on a failed assertion or a stack underflow).
+    pub fn execute(&mut self, op_code: OpCode, op_hint: OpHint) -> Result<(), String> {
 
         // increment step pointer and make sure there is enough memory allocated to hold the trace
         self.advance_step();
+        let init_depth = self.depth as i32;
 
         // execute the appropriate action against the current state of the stack
         match op_code {
@@ -75,6 +77,8 @@ impl Stack {
             OpCode::Push        => self.op_push(op_hint),
             OpCode::Read        => self.op_read(op_hint),
             OpCode::Read2       => self.op_read2(op_hint),
+            OpCode::Clk         => self.op_clk(op_hint),
+            OpCode::Write       => self.op_write(),
 
             OpCode::Dup         => self.op_dup(),
             OpCode::Dup2        => self.op_dup2(),
@@ -104,11 +108,17 @@ impl Stack {
             OpCode::Or          => self.op_or(),
 
             OpCode::Eq          => self.op_eq(),
+            OpCode::IsZero      => self.op_iszero(),
             OpCode::Cmp         => self.op_cmp(op_hint),
             OpCode::BinAcc      => self.op_binacc(op_hint),
+            OpCode::BitDec      => self.op_bitdec(op_hint),
 
             OpCode::RescR       => self.op_rescr(),
-        }
+        }?;
+
+        debug_assert!(self.depth as i32 - init_depth == op_code.stack_effect(),
+            "unexpected stack depth change for {} at step {}", op_code, self.step);
+        return Ok(());
     }
 
     /// Returns trace length of register traces in the decoder.
@@ -117,7 +127,6 @@ impl Stack {
     }
 
     /// Returns value of the current step pointer.
-    #[cfg(test)]
     pub fn current_step(&self) -> usize {
         return self.step;
     }
@@ -127,6 +136,22 @@ impl Stack {
         return self.registers[0][self.step];
     }
 
+    /// Returns the top `count` values of the stack at the current step; if the stack has
+    /// fewer than `count` registers, missing values are returned as zeros.
+    pub fn get_stack_values(&self, count: usize) -> Vec<u128> {
+        let mut values = vec![field::ZERO; count];
+        for (i, register) in self.registers.iter().take(count).enumerate() {
+            values[i] = register[self.step];
+        }
+        return values;
+    }
+
+    /// Returns true if any of the secret input tapes still contains values which have not been
+    /// read; for tapes backed by a callback, this waits until the callback is exhausted.
+    pub fn has_unread_inputs(&mut self) -> bool {
+        return self.tape_a.has_values(1) || self.tape_b.has_values(1);
+    }
+
     /// Populate all register traces with values for steps between the current step
     /// and the end of the trace.
     pub fn finalize_trace(&mut self) {
@@ -148,44 +173,68 @@ impl Stack {
 
     // FLOW CONTROL OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_noop(&mut self) {
+    fn op_noop(&mut self) -> Result<(), String> {
         self.copy_state(0);
+        return Ok(());
     }
 
-    fn op_assert(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_assert(&mut self) -> Result<(), String> {
+        if self.depth < 1 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let value = self.registers[0][self.step - 1];
-        assert!(value == field::ONE, "ASSERT failed at step {}", self.step);
+        if value != field::ONE {
+            return Err(format!("ASSERT failed at step {}", self.step));
+        }
         self.shift_left(1, 1);
+        return Ok(());
     }
 
-    fn op_asserteq(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_asserteq(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(x == y, "ASSERTEQ failed at step {}", self.step);
+        if x != y {
+            return Err(format!("ASSERTEQ failed at step {}", self.step));
+        }
         self.shift_left(2, 2);
+        return Ok(());
     }
 
     // INPUT OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_push(&mut self, hint: OpHint) {
-        self.shift_right(0, 1);
+    fn op_push(&mut self, hint: OpHint) -> Result<(), String> {
+        self.shift_right(0, 1)?;
         let op_value = match hint {
             OpHint::PushValue(value) => value,
-            _ => panic!("invalid value for PUSH operation at step {}", self.step),
+            _ => return Err(format!("invalid value for PUSH operation at step {}", self.step)),
         };
         self.registers[0][self.step] = op_value;
+        return Ok(());
     }
 
-    fn op_read(&mut self, hint: OpHint) {
+    fn op_clk(&mut self, hint: OpHint) -> Result<(), String> {
+        self.shift_right(0, 1)?;
+        let op_counter = match hint {
+            OpHint::PushValue(value) => value,
+            _ => return Err(format!("invalid value for CLK operation at step {}", self.step)),
+        };
+        self.registers[0][self.step] = op_counter;
+        return Ok(());
+    }
+
+    fn op_read(&mut self, hint: OpHint) -> Result<(), String> {
         // process execution hint
         match hint {
             OpHint::EqStart => {
                 // if we are about to equality comparison sequence, push inverse of the difference
                 // between top two stack values onto secret tape A, if they are equal; otherwise
                 // push value 1
-                assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+                if self.depth < 2 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
                 let x = self.registers[0][self.step - 1];
                 let y = self.registers[1][self.step - 1];
                 if x == y {
@@ -195,33 +244,69 @@ impl Stack {
                     self.tape_a.push(field::inv(field::sub(x, y)));
                 }
             },
+            OpHint::IsZeroStart => {
+                // if we are about to check whether the top stack value is zero, push its inverse
+                // onto secret tape A if it is not zero; otherwise, any value would do, and value
+                // 0 is pushed
+                if self.depth < 1 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
+                let x = self.registers[0][self.step - 1];
+                if x == field::ZERO {
+                    self.tape_a.push(field::ZERO);
+                }
+                else {
+                    self.tape_a.push(field::inv(x));
+                }
+            },
+            OpHint::BytesStart(n) => {
+                // if we are about to start byte decomposition, push bytes of the value below the
+                // accumulator onto tape A; least significant byte is pushed first so that the
+                // most significant byte is read first
+                if self.depth < 2 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
+                let val = self.registers[1][self.step - 1];
+                for i in 0..n {
+                    self.tape_a.push((val >> (8 * i)) & 0xFF);
+                }
+            },
             OpHint::None => {
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
+                if !self.tape_a.has_values(1) {
+                    return Err(format!("attempt to read from empty tape A at step {}", self.step));
+                }
             },
-            _ => panic!("execution hint {:?} is not valid for READ operation", hint)
+            _ => return Err(format!("execution hint {:?} is not valid for READ operation", hint)),
         }
 
-        self.shift_right(0, 1);
+        self.shift_right(0, 1)?;
         let value = self.tape_a.pop().unwrap();
         self.registers[0][self.step] = value;
+        return Ok(());
     }
 
-    fn op_read2(&mut self, hint: OpHint) {
+    fn op_read2(&mut self, hint: OpHint) -> Result<(), String> {
         // process execution hint
         match hint {
             OpHint::PmpathStart(n) => {
-                assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+                if self.depth < 3 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
 
                 let n = (n - 1) as usize;
-                assert!(self.tape_a.len() >= n, "too few items on tape A for pmpath macro");
-                assert!(self.tape_b.len() >= n, "too few items on tape B for pmpath macro");
+                if !self.tape_a.has_values(n) {
+                    return Err(String::from("too few items on tape A for pmpath macro"));
+                }
+                if !self.tape_b.has_values(n) {
+                    return Err(String::from("too few items on tape B for pmpath macro"));
+                }
 
                 let idx = self.registers[2][self.step - 1];
 
                 // we need to insert binary decomposition of index into tape A, but we need to make
                 // sure it is interlaced with node values already present there. To do this,
                 // we first remove top n values from tape A
-                let v_a = self.tape_a.split_off(self.tape_a.len() - n);
+                let v_a = self.tape_a.split_off(n);
 
                 // then, we reinsert them while interlacing node and leaf index binary values
                 for i in 0..n {
@@ -231,77 +316,116 @@ impl Stack {
                 }
             },
             OpHint::None => {
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
-                assert!(self.tape_b.len() > 0, "attempt to read from empty tape B at step {}", self.step);
+                if !self.tape_a.has_values(1) {
+                    return Err(format!("attempt to read from empty tape A at step {}", self.step));
+                }
+                if !self.tape_b.has_values(1) {
+                    return Err(format!("attempt to read from empty tape B at step {}", self.step));
+                }
             },
-            _ => panic!("execution hint {:?} is not valid for READ2 operation", hint)
+            _ => return Err(format!("execution hint {:?} is not valid for READ2 operation", hint)),
         }
 
-        self.shift_right(0, 2);
+        self.shift_right(0, 2)?;
         let value_a = self.tape_a.pop().unwrap();
         let value_b = self.tape_b.pop().unwrap();
         self.registers[0][self.step] = value_b;
         self.registers[1][self.step] = value_a;
+        return Ok(());
+    }
+
+    fn op_write(&mut self) -> Result<(), String> {
+        // the value is recorded on the output tape from the trace: it is the top of the stack
+        // at the step at which WRITE is executed
+        if self.depth < 1 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
+        self.shift_left(1, 1);
+        return Ok(());
     }
 
     // STACK MANIPULATION OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_dup(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        self.shift_right(0, 1);
+    fn op_dup(&mut self) -> Result<(), String> {
+        if self.depth < 1 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
+        self.shift_right(0, 1)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
+        return Ok(());
     }
 
-    fn op_dup2(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        self.shift_right(0, 2);
+    fn op_dup2(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
+        self.shift_right(0, 2)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
         self.registers[1][self.step] = self.registers[1][self.step - 1];
+        return Ok(());
     }
 
-    fn op_dup4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-        self.shift_right(0, 4);
+    fn op_dup4(&mut self) -> Result<(), String> {
+        if self.depth < 4 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
+        self.shift_right(0, 4)?;
         self.registers[0][self.step] = self.registers[0][self.step - 1];
         self.registers[1][self.step] = self.registers[1][self.step - 1];
         self.registers[2][self.step] = self.registers[2][self.step - 1];
         self.registers[3][self.step] = self.registers[3][self.step - 1];
+        return Ok(());
     }
 
-    fn op_pad2(&mut self) {
-        self.shift_right(0, 2);
+    fn op_pad2(&mut self) -> Result<(), String> {
+        self.shift_right(0, 2)?;
         self.registers[0][self.step] = field::ZERO;
         self.registers[1][self.step] = field::ZERO;
+        return Ok(());
     }
 
-    fn op_drop(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_drop(&mut self) -> Result<(), String> {
+        if self.depth < 1 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         self.shift_left(1, 1);
+        return Ok(());
     }
 
-    fn op_drop4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_drop4(&mut self) -> Result<(), String> {
+        if self.depth < 4 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         self.shift_left(4, 4);
+        return Ok(());
     }
 
-    fn op_swap(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_swap(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         self.registers[0][self.step] = self.registers[1][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.copy_state(2);
+        return Ok(());
     }
 
-    fn op_swap2(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_swap2(&mut self) -> Result<(), String> {
+        if self.depth < 4 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         self.registers[0][self.step] = self.registers[2][self.step - 1];
         self.registers[1][self.step] = self.registers[3][self.step - 1];
         self.registers[2][self.step] = self.registers[0][self.step - 1];
         self.registers[3][self.step] = self.registers[1][self.step - 1];
         self.copy_state(4);
+        return Ok(());
     }
 
-    fn op_swap4(&mut self) {
-        assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+    fn op_swap4(&mut self) -> Result<(), String> {
+        if self.depth < 8 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         self.registers[0][self.step] = self.registers[4][self.step - 1];
         self.registers[1][self.step] = self.registers[5][self.step - 1];
         self.registers[2][self.step] = self.registers[6][self.step - 1];
@@ -311,19 +435,25 @@ impl Stack {
         self.registers[6][self.step] = self.registers[2][self.step - 1];
         self.registers[7][self.step] = self.registers[3][self.step - 1];
         self.copy_state(8);
+        return Ok(());
     }
 
-    fn op_roll4(&mut self) {
-        assert!(self.depth >= 4, "stack underflow at step {}", self.step);
+    fn op_roll4(&mut self) -> Result<(), String> {
+        if self.depth < 4 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         self.registers[0][self.step] = self.registers[3][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.registers[2][self.step] = self.registers[1][self.step - 1];
         self.registers[3][self.step] = self.registers[2][self.step - 1];
         self.copy_state(4);
+        return Ok(());
     }
 
-    fn op_roll8(&mut self) {
-        assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+    fn op_roll8(&mut self) -> Result<(), String> {
+        if self.depth < 8 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         self.registers[0][self.step] = self.registers[7][self.step - 1];
         self.registers[1][self.step] = self.registers[0][self.step - 1];
         self.registers[2][self.step] = self.registers[1][self.step - 1];
@@ -333,12 +463,15 @@ impl Stack {
         self.registers[6][self.step] = self.registers[5][self.step - 1];
         self.registers[7][self.step] = self.registers[6][self.step - 1];
         self.copy_state(8);
+        return Ok(());
     }
 
     // SELECTION OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_choose(&mut self) {
-        assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+    fn op_choose(&mut self) -> Result<(), String> {
+        if self.depth < 3 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let condition = self.registers[2][self.step - 1];
         if condition == field::ONE {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -347,13 +480,16 @@ impl Stack {
             self.registers[0][self.step] = self.registers[1][self.step - 1];
         }
         else {
-            assert!(false, "CHOOSE on a non-binary condition at step {}", self.step);
+            return Err(format!("CHOOSE on a non-binary condition at step {}", self.step));
         }
         self.shift_left(3, 2);
+        return Ok(());
     }
 
-    fn op_choose2(&mut self) {
-        assert!(self.depth >= 6, "stack underflow at step {}", self.step);
+    fn op_choose2(&mut self) -> Result<(), String> {
+        if self.depth < 6 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let condition = self.registers[4][self.step - 1];
         if condition == field::ONE {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -364,13 +500,16 @@ impl Stack {
             self.registers[1][self.step] = self.registers[3][self.step - 1];
         }
         else {
-            assert!(false, "CHOOSE2 on a non-binary condition at step {}", self.step);
+            return Err(format!("CHOOSE2 on a non-binary condition at step {}", self.step));
         }
         self.shift_left(6, 4);
+        return Ok(());
     }
 
-    fn op_cswap2(&mut self) {
-        assert!(self.depth >= 6, "stack underflow at step {}", self.step);
+    fn op_cswap2(&mut self) -> Result<(), String> {
+        if self.depth < 6 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let condition = self.registers[4][self.step - 1];
         if condition == field::ZERO {
             self.registers[0][self.step] = self.registers[0][self.step - 1];
@@ -385,79 +524,114 @@ impl Stack {
             self.registers[3][self.step] = self.registers[1][self.step - 1];
         }
         else {
-            assert!(false, "CSWAP2 on a non-binary condition at step {}", self.step);
+            return Err(format!("CSWAP2 on a non-binary condition at step {}", self.step));
         }
         self.shift_left(6, 2);
+        return Ok(());
     }
 
     // ARITHMETIC AND BOOLEAN OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_add(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_add(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
         self.registers[0][self.step] = field::add(x, y);
         self.shift_left(2, 1);
+        return Ok(());
     }
 
-    fn op_mul(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_mul(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
         self.registers[0][self.step] = field::mul(x, y);
         self.shift_left(2, 1);
+        return Ok(());
     }
 
-    fn op_inv(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_inv(&mut self) -> Result<(), String> {
+        if self.depth < 1 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
+        // INV of 0 is set to 0; this does not satisfy the x * inv(x) = 1 constraint, and thus,
+        // the resulting execution trace cannot be used to generate a valid proof
         let x = self.registers[0][self.step - 1];
-        assert!(x != field::ZERO, "cannot compute INV of {} at step {}", field::ZERO, self.step);
         self.registers[0][self.step] = field::inv(x);
         self.copy_state(1);
+        return Ok(());
     }
 
-    fn op_neg(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_neg(&mut self) -> Result<(), String> {
+        if self.depth < 1 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let x = self.registers[0][self.step - 1];
         self.registers[0][self.step] = field::neg(x);
         self.copy_state(1);
+        return Ok(());
     }
 
-    fn op_not(&mut self) {
-        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+    fn op_not(&mut self) -> Result<(), String> {
+        if self.depth < 1 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let x = self.registers[0][self.step - 1];
-        assert!(is_binary(x), "cannot compute NOT of a non-binary value at step {}", self.step);
+        if !is_binary(x) {
+            return Err(format!("cannot compute NOT of a non-binary value at step {}", self.step));
+        }
         self.registers[0][self.step] = field::sub(field::ONE, x);
         self.copy_state(1);
+        return Ok(());
     }
 
-    fn op_and(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_and(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(is_binary(x), "cannot compute AND for a non-binary value at step {}", self.step);
-        assert!(is_binary(y), "cannot compute AND for a non-binary value at step {}", self.step);
+        if !is_binary(x) {
+            return Err(format!("cannot compute AND for a non-binary value at step {}", self.step));
+        }
+        if !is_binary(y) {
+            return Err(format!("cannot compute AND for a non-binary value at step {}", self.step));
+        }
 
         self.registers[0][self.step] = if x == field::ONE && y == field::ONE { field::ONE } else { field::ZERO };
         self.shift_left(2, 1);
+        return Ok(());
     }
 
-    fn op_or(&mut self) {
-        assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+    fn op_or(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let x = self.registers[0][self.step - 1];
         let y = self.registers[1][self.step - 1];
-        assert!(is_binary(x), "cannot compute OR for a non-binary value at step {}", self.step);
-        assert!(is_binary(y), "cannot compute OR for a non-binary value at step {}", self.step);
+        if !is_binary(x) {
+            return Err(format!("cannot compute OR for a non-binary value at step {}", self.step));
+        }
+        if !is_binary(y) {
+            return Err(format!("cannot compute OR for a non-binary value at step {}", self.step));
+        }
 
         self.registers[0][self.step] = if x == field::ONE || y == field::ONE { field::ONE } else { field::ZERO };
         self.shift_left(2, 1);
+        return Ok(());
     }
 
 
     // COMPARISON OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_eq(&mut self) {
-        assert!(self.depth >= 3, "stack underflow at step {}", self.step);
+    fn op_eq(&mut self) -> Result<(), String> {
+        if self.depth < 3 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let aux = self.registers[0][self.step - 1];
         let x = self.registers[1][self.step - 1];
         let y = self.registers[2][self.step - 1];
@@ -465,19 +639,42 @@ impl Stack {
             self.registers[0][self.step] = field::ONE;
         } else {
             let diff = field::sub(x, y);
-            assert!(aux == field::inv(diff), "invalid AUX value for EQ operation at step {}", self.step);
+            if aux != field::inv(diff) {
+                return Err(format!("invalid AUX value for EQ operation at step {}", self.step));
+            }
             self.registers[0][self.step] = field::ZERO;
         }
         self.shift_left(3, 2);
+        return Ok(());
+    }
+
+    fn op_iszero(&mut self) -> Result<(), String> {
+        if self.depth < 2 {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
+        let aux = self.registers[0][self.step - 1];
+        let x = self.registers[1][self.step - 1];
+        if x == field::ZERO {
+            self.registers[0][self.step] = field::ONE;
+        } else {
+            if aux != field::inv(x) {
+                return Err(format!("invalid AUX value for ISZERO operation at step {}", self.step));
+            }
+            self.registers[0][self.step] = field::ZERO;
+        }
+        self.shift_left(2, 1);
+        return Ok(());
     }
 
-    fn op_cmp(&mut self, hint: OpHint) {
+    fn op_cmp(&mut self, hint: OpHint) -> Result<(), String> {
         // process execution hint
         match hint {
             OpHint::CmpStart(n) => {
                 // if we are about to start comparison sequence, push binary decompositions
                 // of a and b values onto the tapes
-                assert!(self.depth >= 10, "stack underflow at step {}", self.step);
+                if self.depth < 10 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
                 let a_val = self.registers[8][self.step - 1];
                 let b_val = self.registers[9][self.step - 1];
                 for i in 0..n {
@@ -486,20 +683,28 @@ impl Stack {
                 }
             },
             OpHint::None => {
-                assert!(self.depth >= 8, "stack underflow at step {}", self.step);
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
-                assert!(self.tape_b.len() > 0, "attempt to read from empty tape B at step {}", self.step);
+                if self.depth < 8 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
+                if !self.tape_a.has_values(1) {
+                    return Err(format!("attempt to read from empty tape A at step {}", self.step));
+                }
+                if !self.tape_b.has_values(1) {
+                    return Err(format!("attempt to read from empty tape B at step {}", self.step));
+                }
             },
-            _ => panic!("execution hint {:?} is not valid for CMP operation", hint)
+            _ => return Err(format!("execution hint {:?} is not valid for CMP operation", hint)),
         }
 
         // get next bits of a and b values from the tapes
         let a_bit = self.tape_a.pop().unwrap();
-        assert!(a_bit == field::ZERO || a_bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, a_bit);
+        if !is_binary(a_bit) {
+            return Err(format!("expected binary input at step {} but received: {}", self.step, a_bit));
+        }
         let b_bit = self.tape_b.pop().unwrap();
-        assert!(b_bit == field::ZERO || b_bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, b_bit);
+        if !is_binary(b_bit) {
+            return Err(format!("expected binary input at step {} but received: {}", self.step, b_bit));
+        }
 
         // determine which bit is greater
         let bit_gt = field::mul(a_bit, field::sub(field::ONE, b_bit));
@@ -507,8 +712,10 @@ impl Stack {
 
         // compute current power of 2 for binary decomposition
         let power_of_two = self.registers[0][self.step - 1];
-        assert!(power_of_two.is_power_of_two(),
-            "expected top of the stack at step {} to be a power of 2, but received {}", self.step, power_of_two);
+        if !power_of_two.is_power_of_two() {
+            return Err(format!("expected top of the stack at step {} to be a power of 2, but received {}",
+                self.step, power_of_two));
+        }
         let next_power_of_two = if power_of_two == 1 {
             field::div(power_of_two, 2)
         }
@@ -532,15 +739,18 @@ impl Stack {
         self.registers[7][self.step] = field::add(self.registers[7][self.step - 1], field::mul(a_bit, power_of_two));
 
         self.copy_state(8);
+        return Ok(());
     }
 
-    fn op_binacc(&mut self, hint: OpHint) {
+    fn op_binacc(&mut self, hint: OpHint) -> Result<(), String> {
         // process execution hint
         match hint {
             OpHint::RcStart(n) => {
                 // if we are about to start range check sequence, push binary decompositions
                 // of the value onto tape A
-                assert!(self.depth >= 5, "stack underflow at step {}", self.step);
+                if self.depth < 5 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
                 let val = self.registers[4][self.step - 1];
                 for i in 0..n {
                     // most significant bit is pushed first
@@ -548,22 +758,28 @@ impl Stack {
                 }
             },
             OpHint::None => {
-                assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
+                if self.depth < 4 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
+                if !self.tape_a.has_values(1) {
+                    return Err(format!("attempt to read from empty tape A at step {}", self.step));
+                }
             },
-            _ => panic!("execution hint {:?} is not valid for BINACC operation", hint)
+            _ => return Err(format!("execution hint {:?} is not valid for BINACC operation", hint)),
         }
 
         // get the next bit of the value from tape A
         let bit = self.tape_a.pop().unwrap();
-        assert!(bit == field::ZERO || bit == field::ONE,
-            "expected binary input at step {} but received: {}", self.step, bit);
+        if !is_binary(bit) {
+            return Err(format!("expected binary input at step {} but received: {}", self.step, bit));
+        }
 
         // compute current power of 2 for binary decomposition
         let power_of_two = self.registers[2][self.step - 1];
-        assert!(power_of_two.is_power_of_two(),
-            "expected 3rd value from the top of the stack at step {} to be a power of 2, but received {}",
-            self.step, power_of_two);
+        if !power_of_two.is_power_of_two() {
+            return Err(format!("expected 3rd value from the top of the stack at step {} to be a power of 2, but received {}",
+                self.step, power_of_two));
+        }
         let next_power_of_two = field::mul(power_of_two, 2);
 
         let acc = self.registers[3][self.step - 1];
@@ -575,12 +791,58 @@ impl Stack {
         self.registers[3][self.step] = field::add(acc, field::mul(bit, power_of_two));
 
         self.copy_state(4);
+        return Ok(());
+    }
+
+    fn op_bitdec(&mut self, hint: OpHint) -> Result<(), String> {
+        // process execution hint
+        match hint {
+            OpHint::BitsStart(n) => {
+                // if we are about to start bit decomposition, push binary decomposition of the
+                // value onto tape A; least significant bit is pushed first so that the most
+                // significant bit is read first
+                if self.depth < 2 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
+                let val = self.registers[1][self.step - 1];
+                for i in 0..n {
+                    self.tape_a.push((val >> i) & 1);
+                }
+            },
+            OpHint::None => {
+                if self.depth < 2 {
+                    return Err(format!("stack underflow at step {}", self.step));
+                }
+                if !self.tape_a.has_values(1) {
+                    return Err(format!("attempt to read from empty tape A at step {}", self.step));
+                }
+            },
+            _ => return Err(format!("execution hint {:?} is not valid for BITDEC operation", hint)),
+        }
+
+        // get the next bit of the value from tape A
+        let bit = self.tape_a.pop().unwrap();
+        if !is_binary(bit) {
+            return Err(format!("expected binary input at step {} but received: {}", self.step, bit));
+        }
+
+        let acc = self.registers[0][self.step - 1];
+
+        // update the next state of the computation; the bit is inserted below the accumulator
+        // and the original value
+        self.shift_right(2, 1)?;
+        self.registers[0][self.step] = field::add(field::mul(acc, 2), bit);
+        self.registers[1][self.step] = self.registers[1][self.step - 1];
+        self.registers[2][self.step] = bit;
+        return Ok(());
     }
 
     // CRYPTOGRAPHIC OPERATIONS
     // --------------------------------------------------------------------------------------------
-    fn op_rescr(&mut self) {
-        assert!(self.depth >= HASH_STATE_WIDTH, "stack underflow at step {}", self.step);
+    fn op_rescr(&mut self) -> Result<(), String> {
+        if self.depth < HASH_STATE_WIDTH {
+            return Err(format!("stack underflow at step {}", self.step));
+        }
         let mut state = [
             self.registers[0][self.step - 1],
             self.registers[1][self.step - 1],
@@ -600,6 +862,7 @@ impl Stack {
         self.registers[5][self.step] = state[5];
 
         self.copy_state(HASH_STATE_WIDTH);
+        return Ok(());
     }
 
     // HELPER METHODS
@@ -628,10 +891,12 @@ impl Stack {
         self.depth -= pos_count;
     }
 
-    fn shift_right(&mut self, start: usize, pos_count: usize) {
+    fn shift_right(&mut self, start: usize, pos_count: usize) -> Result<(), String> {
         
         self.depth += pos_count;
-        assert!(self.depth <= MAX_STACK_DEPTH, "stack overflow at step {}", self.step);
+        if self.depth > MAX_STACK_DEPTH {
+            return Err(format!("stack overflow at step {}", self.step));
+        }
 
         if self.depth > self.max_depth {
             self.max_depth += pos_count;
@@ -643,6 +908,7 @@ impl Stack {
         for i in start..(self.depth - pos_count) {
             self.registers[i + pos_count][self.step] = self.registers[i][self.step - 1];
         }
+        return Ok(());
     }
 
     /// Extends the stack by the specified number of registers.
@@ -10,15 +10,15 @@ fn eq() {
     let inv_diff = field::inv(field::sub(1, 4));
     let mut stack = init_stack(&[3, 3, 4, 5], &[0, inv_diff], &[], TRACE_LENGTH);
 
-    stack.execute(OpCode::Read, OpHint::None);
-    stack.execute(OpCode::Eq, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
+    stack.execute(OpCode::Eq, OpHint::None).unwrap();
     assert_eq!(vec![1, 4, 5, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(3, stack.depth);
     assert_eq!(5, stack.max_depth);
 
-    stack.execute(OpCode::Read, OpHint::None);
-    stack.execute(OpCode::Eq, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
+    stack.execute(OpCode::Eq, OpHint::None).unwrap();
     assert_eq!(vec![0, 5, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 4));
 
     assert_eq!(2, stack.depth);
@@ -29,21 +29,63 @@ fn eq() {
 fn eq_with_hint() {
     let mut stack = init_stack(&[3, 3, 4, 5], &[], &[], TRACE_LENGTH);
 
-    stack.execute(OpCode::Read, OpHint::EqStart);
-    stack.execute(OpCode::Eq, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::EqStart).unwrap();
+    stack.execute(OpCode::Eq, OpHint::None).unwrap();
     assert_eq!(vec![1, 4, 5, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
 
     assert_eq!(3, stack.depth);
     assert_eq!(5, stack.max_depth);
 
-    stack.execute(OpCode::Read, OpHint::EqStart);
-    stack.execute(OpCode::Eq, OpHint::None);
+    stack.execute(OpCode::Read, OpHint::EqStart).unwrap();
+    stack.execute(OpCode::Eq, OpHint::None).unwrap();
     assert_eq!(vec![0, 5, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 4));
 
     assert_eq!(2, stack.depth);
     assert_eq!(5, stack.max_depth);
 }
 
+#[test]
+fn iszero() {
+    let mut stack = init_stack(&[0, 4, 5], &[7, field::inv(4)], &[], TRACE_LENGTH);
+
+    // when the value is zero, any witness is accepted
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
+    stack.execute(OpCode::IsZero, OpHint::None).unwrap();
+    assert_eq!(vec![1, 4, 5, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
+
+    assert_eq!(3, stack.depth);
+    assert_eq!(4, stack.max_depth);
+
+    stack.execute(OpCode::Swap, OpHint::None).unwrap();
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
+    stack.execute(OpCode::IsZero, OpHint::None).unwrap();
+    assert_eq!(vec![0, 1, 5, 0, 0, 0, 0, 0], get_stack_state(&stack, 5));
+
+    assert_eq!(3, stack.depth);
+    assert_eq!(4, stack.max_depth);
+}
+
+#[test]
+fn iszero_with_hint() {
+    let mut stack = init_stack(&[0, 4, 5], &[], &[], TRACE_LENGTH);
+
+    stack.execute(OpCode::Read, OpHint::IsZeroStart).unwrap();
+    stack.execute(OpCode::IsZero, OpHint::None).unwrap();
+    assert_eq!(vec![1, 4, 5, 0, 0, 0, 0, 0], get_stack_state(&stack, 2));
+
+    stack.execute(OpCode::Swap, OpHint::None).unwrap();
+    stack.execute(OpCode::Read, OpHint::IsZeroStart).unwrap();
+    stack.execute(OpCode::IsZero, OpHint::None).unwrap();
+    assert_eq!(vec![0, 1, 5, 0, 0, 0, 0, 0], get_stack_state(&stack, 5));
+}
+
+#[test]
+fn iszero_invalid_witness() {
+    let mut stack = init_stack(&[4, 5], &[7], &[], TRACE_LENGTH);
+    stack.execute(OpCode::Read, OpHint::None).unwrap();
+    assert_eq!(Err(String::from("invalid AUX value for ISZERO operation at step 2")), stack.execute(OpCode::IsZero, OpHint::None));
+}
+
 // COMPARISON OPERATION
 // ================================================================================================
 
@@ -57,12 +99,12 @@ fn cmp_128() {
     // initialize the stack
     let (inputs_a, inputs_b) = build_inputs_for_cmp(a, b, 128);
     let mut stack = init_stack(&[0, 0, 0, 0, 0, a, b], &inputs_a, &inputs_b, 256);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Push, OpHint::PushValue(p127));
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Push, OpHint::PushValue(p127)).unwrap();
 
     // execute CMP operations
     for i in 2..130 {
-        stack.execute(OpCode::Cmp, OpHint::None);
+        stack.execute(OpCode::Cmp, OpHint::None).unwrap();
 
         let state = get_stack_state(&stack, i);
         let next  = get_stack_state(&stack, i + 1);
@@ -91,12 +133,12 @@ fn cmp_64() {
     // initialize the stack
     let (inputs_a, inputs_b) = build_inputs_for_cmp(a, b, 64);
     let mut stack = init_stack(&[0, 0, 0, 0, 0, a, b], &inputs_a, &inputs_b, 256);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Push, OpHint::PushValue(p63));
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Push, OpHint::PushValue(p63)).unwrap();
 
     // execute CMP operations
     for i in 2..66 {
-        stack.execute(OpCode::Cmp, OpHint::None);
+        stack.execute(OpCode::Cmp, OpHint::None).unwrap();
 
         let state = get_stack_state(&stack, i);
         let next  = get_stack_state(&stack, i + 1);
@@ -128,12 +170,12 @@ fn lt() {
     // initialize the stack
     let (inputs_a, inputs_b) = build_inputs_for_cmp(a, b, 128);
     let mut stack = init_stack(&[0, 0, 0, a, b, 7, 11], &inputs_a, &inputs_b, 256);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Push, OpHint::PushValue(p127));
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Push, OpHint::PushValue(p127)).unwrap();
 
     // execute CMP operations
-    for _ in 3..131 { stack.execute(OpCode::Cmp, OpHint::None); }
+    for _ in 3..131 { stack.execute(OpCode::Cmp, OpHint::None).unwrap(); }
 
     // execute program finale
     lt_finale(&mut stack);
@@ -154,12 +196,12 @@ fn gt() {
     // initialize the stack
     let (inputs_a, inputs_b) = build_inputs_for_cmp(a, b, 128);
     let mut stack = init_stack(&[0, 0, 0, a, b, 7, 11], &inputs_a, &inputs_b, 256);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Push, OpHint::PushValue(p127));
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Push, OpHint::PushValue(p127)).unwrap();
 
     // execute CMP operations
-    for _ in 3..131 { stack.execute(OpCode::Cmp, OpHint::None); }
+    for _ in 3..131 { stack.execute(OpCode::Cmp, OpHint::None).unwrap(); }
 
     // execute program finale
     gt_finale(&mut stack);
@@ -190,12 +232,12 @@ fn binacc_128() {
         256);
 
     // execute binary aggregation operations
-    for _ in 0..128 { stack.execute(OpCode::BinAcc, OpHint::None); }
+    for _ in 0..128 { stack.execute(OpCode::BinAcc, OpHint::None).unwrap(); }
 
     // check the result
-    stack.execute(OpCode::Drop, OpHint::None);
-    stack.execute(OpCode::Drop, OpHint::None);
-    stack.execute(OpCode::Drop, OpHint::None);
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
     let state = get_stack_state(&stack, 131);
     assert_eq!(vec![x, x, 7, 11, 0, 0, 0, 0], state);
 }
@@ -217,16 +259,33 @@ fn binacc_64() {
         256);
 
     // execute binary aggregation operations
-    for _ in 0..64 { stack.execute(OpCode::BinAcc, OpHint::None); }
+    for _ in 0..64 { stack.execute(OpCode::BinAcc, OpHint::None).unwrap(); }
 
     // check the result
-    stack.execute(OpCode::Drop, OpHint::None);
-    stack.execute(OpCode::Drop, OpHint::None);
-    stack.execute(OpCode::Drop, OpHint::None);
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
     let state = get_stack_state(&stack, 67);
     assert_eq!(vec![x, x, 7, 11, 0, 0, 0, 0], state);
 }
 
+#[test]
+fn bitdec_8() {
+
+    let x: u128 = 0b1011_0101;
+
+    // initialize the stack; bits are placed onto tape A by the hint
+    let mut stack = init_stack(&[0, x, 7, 11], &[], &[], 16);
+
+    // execute bit decomposition operations
+    stack.execute(OpCode::BitDec, OpHint::BitsStart(8)).unwrap();
+    for _ in 1..8 { stack.execute(OpCode::BitDec, OpHint::None).unwrap(); }
+
+    // check the result
+    let state = get_stack_state(&stack, 8);
+    assert_eq!(vec![x, x, 1, 0, 1, 0, 1, 1, 0, 1, 7, 11], state);
+}
+
 #[test]
 fn isodd_128() {
 
@@ -245,21 +304,21 @@ fn isodd_128() {
         256);
 
     // read the first bit and make sure it is saved at the end of the stack
-    stack.execute(OpCode::BinAcc, OpHint::None);
-    stack.execute(OpCode::Swap2, OpHint::None);
-    stack.execute(OpCode::Roll4, OpHint::None);
-    stack.execute(OpCode::Dup, OpHint::None);
+    stack.execute(OpCode::BinAcc, OpHint::None).unwrap();
+    stack.execute(OpCode::Swap2, OpHint::None).unwrap();
+    stack.execute(OpCode::Roll4, OpHint::None).unwrap();
+    stack.execute(OpCode::Dup, OpHint::None).unwrap();
 
     // execute remaining binary aggregation operations
-    for _ in 0..127 { stack.execute(OpCode::BinAcc, OpHint::None); }
+    for _ in 0..127 { stack.execute(OpCode::BinAcc, OpHint::None).unwrap(); }
 
     // check the result
-    stack.execute(OpCode::Drop, OpHint::None);
-    stack.execute(OpCode::Drop, OpHint::None);
-    stack.execute(OpCode::Swap, OpHint::None);
-    stack.execute(OpCode::Roll4, OpHint::None);
-    stack.execute(OpCode::AssertEq, OpHint::None);
-    stack.execute(OpCode::Drop, OpHint::None);
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
+    stack.execute(OpCode::Swap, OpHint::None).unwrap();
+    stack.execute(OpCode::Roll4, OpHint::None).unwrap();
+    stack.execute(OpCode::AssertEq, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop, OpHint::None).unwrap();
     let state = get_stack_state(&stack, 137);
     assert_eq!(vec![is_odd, 7, 11, 0, 0, 0, 0, 0], state);
 }
@@ -281,24 +340,24 @@ fn build_inputs_for_cmp(a: u128, b: u128, size: usize) -> (Vec<u128>, Vec<u128>)
 }
 
 fn lt_finale(stack: &mut Stack) {
-    stack.execute(OpCode::Drop4, OpHint::None);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Swap4, OpHint::None);
-    stack.execute(OpCode::Roll4, OpHint::None);
-    stack.execute(OpCode::AssertEq, OpHint::None);
-    stack.execute(OpCode::AssertEq, OpHint::None);
-    stack.execute(OpCode::Dup, OpHint::None);
-    stack.execute(OpCode::Drop4, OpHint::None);
+    stack.execute(OpCode::Drop4, OpHint::None).unwrap();
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Swap4, OpHint::None).unwrap();
+    stack.execute(OpCode::Roll4, OpHint::None).unwrap();
+    stack.execute(OpCode::AssertEq, OpHint::None).unwrap();
+    stack.execute(OpCode::AssertEq, OpHint::None).unwrap();
+    stack.execute(OpCode::Dup, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop4, OpHint::None).unwrap();
 }
 
 fn gt_finale(stack: &mut Stack) {
-    stack.execute(OpCode::Drop4, OpHint::None);
-    stack.execute(OpCode::Pad2, OpHint::None);
-    stack.execute(OpCode::Swap4, OpHint::None);
-    stack.execute(OpCode::Roll4, OpHint::None);
-    stack.execute(OpCode::AssertEq, OpHint::None);
-    stack.execute(OpCode::AssertEq, OpHint::None);
-    stack.execute(OpCode::Roll4, OpHint::None);
-    stack.execute(OpCode::Dup, OpHint::None);
-    stack.execute(OpCode::Drop4, OpHint::None);
+    stack.execute(OpCode::Drop4, OpHint::None).unwrap();
+    stack.execute(OpCode::Pad2, OpHint::None).unwrap();
+    stack.execute(OpCode::Swap4, OpHint::None).unwrap();
+    stack.execute(OpCode::Roll4, OpHint::None).unwrap();
+    stack.execute(OpCode::AssertEq, OpHint::None).unwrap();
+    stack.execute(OpCode::AssertEq, OpHint::None).unwrap();
+    stack.execute(OpCode::Roll4, OpHint::None).unwrap();
+    stack.execute(OpCode::Dup, OpHint::None).unwrap();
+    stack.execute(OpCode::Drop4, OpHint::None).unwrap();
 }
\ No newline at end of file
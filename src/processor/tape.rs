@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A callback which supplies values for a secret input tape; the callback is invoked every time
+/// the VM needs a value which is not yet on the tape, and should return None once no more values
+/// will become available. The callback may block until the next value arrives.
+pub type InputCallback = Box<dyn FnMut() -> Option<u128> + Send>;
+
+/// A secret input tape. Values are read from the tape in FIFO order; once all values initially
+/// placed onto the tape have been consumed, new values are pulled from the callback (if any).
+pub struct Tape {
+    values      : VecDeque<u128>,
+    callback    : Option<InputCallback>,
+}
+
+// TAPE IMPLEMENTATION
+// ================================================================================================
+impl Tape {
+
+    /// Returns a new tape containing the specified `values`.
+    pub fn new(values: &[u128]) -> Tape {
+        return Tape { values: values.iter().cloned().collect(), callback: None };
+    }
+
+    /// Returns a new tape which pulls all of its values from the provided `callback`.
+    pub fn from_callback(callback: InputCallback) -> Tape {
+        return Tape { values: VecDeque::new(), callback: Some(callback) };
+    }
+
+    /// Returns true if at least `n` values can be read from the tape; values are pulled from
+    /// the callback only when there are fewer than `n` values on the tape.
+    pub fn has_values(&mut self, n: usize) -> bool {
+        while self.values.len() < n {
+            let value = match self.callback.as_mut().and_then(|callback| callback()) {
+                Some(value) => value,
+                None => {
+                    // once the callback is exhausted, it is never invoked again
+                    self.callback = None;
+                    return false;
+                }
+            };
+            self.values.push_back(value);
+        }
+        return true;
+    }
+
+    /// Removes the next value from the tape and returns it, or returns None if no more values
+    /// can be read from the tape.
+    pub fn pop(&mut self) -> Option<u128> {
+        if !self.has_values(1) { return None; }
+        return self.values.pop_front();
+    }
+
+    /// Places `value` onto the tape so that it is the next value to be read.
+    pub fn push(&mut self, value: u128) {
+        self.values.push_front(value);
+    }
+
+    /// Removes the next `n` values from the tape and returns them in reverse order (i.e. the
+    /// next value to be read is the last one in the returned vector).
+    pub fn split_off(&mut self, n: usize) -> Vec<u128> {
+        assert!(self.has_values(n), "expected at least {} values on the tape", n);
+        let mut result: Vec<u128> = self.values.drain(..n).collect();
+        result.reverse();
+        return result;
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::Tape;
+
+    #[test]
+    fn pull_from_callback() {
+        let mut source = vec![3, 2, 1];
+        let mut tape = Tape::from_callback(Box::new(move || source.pop()));
+
+        tape.push(7);
+        assert!(tape.has_values(2));
+        assert_eq!(vec![1, 7], tape.split_off(2));
+        assert_eq!(Some(2), tape.pop());
+        assert_eq!(Some(3), tape.pop());
+        assert_eq!(None, tape.pop());
+        assert!(!tape.has_values(1));
+    }
+}
@@ -93,6 +93,14 @@ impl AssemblyError {
         };
     }
 
+    pub fn invalid_directive(op: &[&str], step: usize, reason: String) -> AssemblyError {
+        return AssemblyError {
+            message : format!("malformed directive {}: {}", op[0], reason),
+            step    : step,
+            op      : op.join("."),
+        };
+    }
+
     pub fn invalid_block_head(op: &[&str], step: usize) -> AssemblyError {
         return AssemblyError {
             message : format!("invalid block head '{}'", op.join(".")),
@@ -141,6 +149,14 @@ impl AssemblyError {
         };
     }
 
+    pub fn unmatched_proc(step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure without matching end"),
+            step    : step,
+            op      : String::from(".proc"),
+        };
+    }
+
     pub fn unmatched_repeat(step: usize, op: &[&str]) -> AssemblyError {
         return AssemblyError {
             message : format!("repeat without matching end"),
@@ -157,6 +173,15 @@ impl AssemblyError {
         };
     }
 
+    pub fn unbalanced_branches(step: usize, t_delta: i32, f_delta: i32) -> AssemblyError {
+        return AssemblyError {
+            message : format!("if.true branches must change stack depth by the same amount, but true branch changes it by {} and false branch by {}",
+                t_delta, f_delta),
+            step    : step,
+            op      : String::from("if.true"),
+        };
+    }
+
     // PUBLIC ACCESSORS
     // --------------------------------------------------------------------------------------------
     pub fn message(&self) -> &String {
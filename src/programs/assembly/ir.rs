@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use super::{
+    Program, ProgramBlock, Span, Group, Switch, Loop, OpCode, BASE_CYCLE_LENGTH,
+    AssemblyError, HintMap, ConstTables, parse_op_token,
+};
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Intermediate representation of an assembly program. A program is represented by a list of
+/// IR operations; the list starts with a BEGIN operation, and blocks are represented by nested
+/// lists of IR operations. Passes can transform the IR (e.g. fold constants or expand
+/// instructions) before it is lowered into a program via `ir_to_program()`.
+///
+/// Instructions implied by block heads (e.g. ASSERT at the start of a `while.true` block) are
+/// explicit in the IR; thus, the first operation of every block body is the same as the first
+/// operation of the corresponding program block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrOp {
+    /// A single VM operation which does not come from an assembly instruction (e.g. the ASSERT
+    /// implied by `if.true` block head); `source` is the index of the instruction stream token
+    /// the operation was implied by, if any.
+    Op { op_code: OpCode, source: Option<usize> },
+
+    /// An assembly instruction split into its parts (e.g. `push.3` is ["push", "3"]); `source`
+    /// is the index of the instruction in the instruction stream. References to constant tables
+    /// are resolved before instructions are placed into the IR.
+    Instruction { parts: Vec<String>, source: usize },
+
+    /// A `block` block.
+    Block(Vec<IrOp>),
+
+    /// An `if.true` block together with its `else` branch.
+    Switch(Vec<IrOp>, Vec<IrOp>),
+
+    /// A `while.true` block.
+    Loop(Vec<IrOp>),
+
+    /// A `repeat.n` block with the body which is repeated the specified number of times.
+    Repeat(usize, Vec<IrOp>),
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Lowers the provided IR into a program. Instructions are translated into VM operations in
+/// the same way as during compilation of assembly source code; thus, lowering the IR of a
+/// program produces the same program as compiling its source code directly. Returns an error
+/// if the IR does not describe a valid program structure (e.g. if the program does not start
+/// with BEGIN operation, or a block body is empty).
+pub fn ir_to_program(ir: &[IrOp]) -> Result<Program, AssemblyError> {
+    if ir.len() == 0 {
+        return Err(AssemblyError::empty_program());
+    }
+
+    let root = lower_branch(ir)?;
+    if !starts_with(&root, &[OpCode::Begin]) {
+        return Err(AssemblyError::invalid_program_start(&get_op_name(&ir[0])));
+    }
+    return Ok(Program::new(Group::new(root)));
+}
+
+/// Returns the change in the depth of the stack caused by executing the provided IR, or None
+/// if the change cannot be determined statically (e.g. because the number of iterations of a
+/// `while.true` loop whose body changes stack depth depends on the data). Returns an error if
+/// branches of an `if.true` block change the depth of the stack by different amounts; `tables`
+/// are the constant tables of the program the IR was parsed from.
+pub fn get_stack_delta(ir: &[IrOp], tables: &ConstTables) -> Result<Option<i32>, AssemblyError> {
+    let mut delta = Some(0);
+    for op in ir.iter() {
+        let op_delta = match op {
+            IrOp::Op { op_code, .. } => Some(op_code.stack_effect()),
+            IrOp::Instruction { parts, source } => {
+                let parts: Vec<&str> = parts.iter().map(|part| part.as_str()).collect();
+                let mut op_codes = Vec::new();
+                parse_op_token(parts, &mut op_codes, &mut HashMap::new(), tables, *source)?;
+                Some(op_codes.iter().map(|op_code| op_code.stack_effect()).sum())
+            },
+            IrOp::Block(body) => get_stack_delta(body, tables)?,
+            IrOp::Switch(t_branch, f_branch) => {
+                let t_delta = get_stack_delta(t_branch, tables)?;
+                let f_delta = get_stack_delta(f_branch, tables)?;
+                match (t_delta, f_delta) {
+                    (Some(t_delta), Some(f_delta)) if t_delta != f_delta => {
+                        // both branches start with instructions which pop the condition off
+                        // the stack; these are not included in the reported deltas
+                        return Err(AssemblyError::unbalanced_branches(get_source(t_branch), t_delta + 1, f_delta + 1));
+                    },
+                    (Some(t_delta), Some(_)) => Some(t_delta),
+                    _ => None,
+                }
+            },
+            IrOp::Loop(body) => match get_stack_delta(body, tables)? {
+                // when the loop body leaves a new condition at the top of the stack, the loop
+                // consumes only the condition which is pushed onto the stack last
+                Some(0) => Some(-1),
+                _ => None,
+            },
+            IrOp::Repeat(num_iterations, body) => {
+                get_stack_delta(body, tables)?.map(|delta| delta * (*num_iterations as i32))
+            },
+        };
+        delta = match (delta, op_delta) {
+            (Some(delta), Some(op_delta)) => Some(delta + op_delta),
+            _ => None,
+        };
+    }
+    return Ok(delta);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the index of the instruction from which a block body comes from; the first operation
+/// of every body is implied by the block head.
+fn get_source(body: &[IrOp]) -> usize {
+    return match body.first() {
+        Some(IrOp::Op { source: Some(source), .. }) => *source,
+        Some(IrOp::Instruction { source, .. }) => *source,
+        _ => 0,
+    };
+}
+
+/// Lowers a body of a program block into a list of program blocks; consecutive operations and
+/// instructions are combined into Span blocks.
+fn lower_branch(ir: &[IrOp]) -> Result<Vec<ProgramBlock>, AssemblyError> {
+    let mut body = Vec::new();
+    let mut op_codes = Vec::new();
+    let mut op_hints: HintMap = HashMap::new();
+    let mut op_sources = Vec::new();
+
+    let tables = ConstTables::new();
+    for op in ir.iter() {
+        match op {
+            IrOp::Op { op_code, source } => {
+                op_codes.push(*op_code);
+                op_sources.push(*source);
+            },
+            IrOp::Instruction { parts, source } => {
+                let parts: Vec<&str> = parts.iter().map(|part| part.as_str()).collect();
+                parse_op_token(parts, &mut op_codes, &mut op_hints, &tables, *source)?;
+                op_sources.resize(op_codes.len(), Some(*source));
+            },
+            _ => {
+                let force_span = body.len() == 0;
+                add_span(&mut body, &mut op_codes, &mut op_hints, &mut op_sources, force_span);
+                body.push(lower_block(op)?);
+            }
+        }
+    }
+    add_span(&mut body, &mut op_codes, &mut op_hints, &mut op_sources, false);
+
+    return Ok(body);
+}
+
+/// Lowers a single IR block into a program block.
+fn lower_block(op: &IrOp) -> Result<ProgramBlock, AssemblyError> {
+    return match op {
+        IrOp::Block(body) => Ok(Group::new_block(lower_body(body, &[], "block")?)),
+        IrOp::Switch(t_branch, f_branch) => {
+            let t_branch = lower_body(t_branch, &[OpCode::Assert], "if.true")?;
+            let f_branch = lower_body(f_branch, &[OpCode::Not, OpCode::Assert], "else")?;
+            Ok(Switch::new_block(t_branch, f_branch))
+        },
+        IrOp::Loop(body) => Ok(Loop::new_block(lower_body(body, &[OpCode::Assert], "while.true")?)),
+        IrOp::Repeat(num_iterations, body) => {
+            if *num_iterations < 2 {
+                let num_iterations = num_iterations.to_string();
+                return Err(AssemblyError::invalid_num_iterations(&["repeat", &num_iterations], get_source(body)));
+            }
+            let body = repeat_block_sequence(lower_body(body, &[], "repeat")?, *num_iterations);
+            Ok(Group::new_block(body))
+        },
+        _ => panic!("IR operation {:?} is not a block", op),
+    };
+}
+
+/// Lowers a body of a program block headed by `head`; returns an error if the body is empty or
+/// does not start with the operations implied by the block head.
+fn lower_body(ir: &[IrOp], implied_ops: &[OpCode], head: &str) -> Result<Vec<ProgramBlock>, AssemblyError> {
+    if ir.len() == 0 {
+        return Err(AssemblyError::empty_block(&[head], 0));
+    }
+
+    let body = lower_branch(ir)?;
+    if !starts_with(&body, implied_ops) {
+        return Err(AssemblyError::invalid_block_head(&[head], get_source(ir)));
+    }
+    return Ok(body);
+}
+
+/// Returns true if the first block of the `body` is a Span block which starts with the
+/// specified operations.
+fn starts_with(body: &[ProgramBlock], op_codes: &[OpCode]) -> bool {
+    return match body.first() {
+        Some(ProgramBlock::Span(block)) => block.starts_with(op_codes),
+        _ => false,
+    };
+}
+
+/// Returns the name of an IR operation as it would appear in assembly source code.
+fn get_op_name(op: &IrOp) -> String {
+    return match op {
+        IrOp::Op { op_code, .. } => op_code.to_string(),
+        IrOp::Instruction { parts, .. } => parts.join("."),
+        IrOp::Block(_) => String::from("block"),
+        IrOp::Switch(..) => String::from("if.true"),
+        IrOp::Loop(_) => String::from("while.true"),
+        IrOp::Repeat(num_iterations, _) => format!("repeat.{}", num_iterations),
+    };
+}
+
+/// Adds a new Span block to a program block body based on currently lowered instructions.
+fn add_span(body: &mut Vec<ProgramBlock>, op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap, op_sources: &mut Vec<Option<usize>>, force: bool) {
+
+    // if there were no instructions in the current span, don't do anything
+    if op_codes.len() == 0 && !force { return };
+
+    // pad the instructions to make ensure 16-cycle alignment
+    let mut span_op_codes = op_codes.clone();
+    let pad_length = BASE_CYCLE_LENGTH - (span_op_codes.len() % BASE_CYCLE_LENGTH) - 1;
+    span_op_codes.resize(span_op_codes.len() + pad_length, OpCode::Noop);
+
+    // padding instructions do not come from any source instruction
+    let mut span_op_sources = op_sources.clone();
+    span_op_sources.resize(span_op_codes.len(), None);
+
+    // add a new Span block to the body
+    body.push(ProgramBlock::Span(Span::with_sources(span_op_codes, op_hints.clone(), span_op_sources)));
+
+    // clear op_codes, op_hints, and op_sources for the next Span block
+    op_codes.clear();
+    op_hints.clear();
+    op_sources.clear();
+}
+
+fn repeat_block_sequence(template: Vec<ProgramBlock>, num_iterations: usize) -> Vec<ProgramBlock> {
+    let mut body = Vec::with_capacity(template.len() * num_iterations);
+
+    let last_idx = template.len() - 1;
+    if !template[last_idx].is_span() {
+        for _ in 0..num_iterations {
+            body.extend_from_slice(&template);
+        }
+    }
+    else {
+        body.extend_from_slice(&template);
+        for _ in 1..num_iterations {
+            let last_idx = body.len() - 1;
+            body[last_idx] = merge_spans(&body[last_idx], &template[0]);
+            body.extend_from_slice(&template[1..]);
+        }
+    }
+
+    return body;
+}
+
+fn merge_spans(span1: &ProgramBlock, span2: &ProgramBlock) -> ProgramBlock {
+    return match span1 {
+        ProgramBlock::Span(first_span) => {
+            match span2 {
+                ProgramBlock::Span(last_span) => {
+                    ProgramBlock::Span(Span::merge(first_span, last_span))
+                },
+                _ => panic!("span1 is not a Span block")
+            }
+        },
+        _ => panic!("span2 is not a Span block")
+    };
+}
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{ HashMap, HashSet };
 use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, OpCode, OpHint, BASE_CYCLE_LENGTH };
+use crate::{ HASH_CYCLE_LENGTH, HASH_STATE_RATE };
 
 mod parsers;
 use parsers::*;
@@ -7,51 +8,234 @@ use parsers::*;
 mod errors;
 use errors::{ AssemblyError };
 
+mod ir;
+pub use ir::{ IrOp, ir_to_program };
+use ir::{ get_stack_delta };
+
+/// Standard library of common assembly gadgets.
+///
+/// Every gadget is a straight-line sequence of assembly instructions. A gadget can be invoked
+/// from assembly source code via `exec.<name>` instruction (with parameters appended as usual,
+/// e.g. `exec.assert_range.32`), or its source can be spliced into a program directly using the
+/// functions of this module.
+///
+/// The example below selects the larger of two 32-bit values, makes sure it fits into 16 bits,
+/// and checks that the hash of the selected value is equal to the expected digest:
+/// ```
+/// use distaff::{ assembly, simulate, ProgramInputs, utils::hasher };
+///
+/// let program = assembly::compile("
+/// begin
+///     exec.max.32
+///     dup exec.assert_range.16
+///     exec.assert_hash.1
+///     push.1
+/// end").unwrap();
+///
+/// let digest = hasher::digest(&[700]);
+/// let inputs = ProgramInputs::from_public(&[700, 300, digest[0], digest[1]]);
+/// assert_eq!(vec![1], simulate(&program, &inputs, 1));
+/// ```
+pub mod stdlib;
+
 #[cfg(test)]
 mod tests;
 
 type HintMap = HashMap<usize, OpHint>;
+type ConstTables = HashMap<String, Vec<u128>>;
+
+/// Maximum number of IR operations which can be inlined into a program via `exec.<proc>`
+/// instructions; since procedures can invoke other procedures, the size of the inlined code
+/// can grow exponentially with the length of the source, and this limit bounds it.
+pub const MAX_INLINED_OPS: usize = 1 << 20;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Procedures defined by a program together with the number of their IR operations, and the
+/// total number of IR operations inlined so far.
+struct Procedures {
+    bodies  : HashMap<String, (Vec<IrOp>, usize)>,
+    inlined : usize,
+}
+
+/// Maps assembly instructions to the lines of the source code they came from. Instructions are
+/// identified by their index in the instruction stream; this is the same index as the one
+/// reported in assembly errors and returned from `Span::get_source()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMap {
+    lines           : Vec<usize>,
+    instructions    : Vec<String>,
+}
 
 // ASSEMBLY COMPILER
 // ================================================================================================
 
 /// Compiles provided assembly code into a program.
 pub fn compile(source: &str) -> Result<Program, AssemblyError> {
+    let (program, _) = compile_with_source_map(source)?;
+    return Ok(program);
+}
+
+/// Same as compile(), but also statically checks that both branches of every `if.true` block
+/// change the depth of the stack by the same amount (when there is no `else` branch, the
+/// `if.true` branch must leave the depth unchanged); an error is returned otherwise. Branches
+/// containing `while.true` loops which change the depth of the stack are not checked, since the
+/// number of loop iterations is not known statically.
+pub fn compile_checked(source: &str) -> Result<Program, AssemblyError> {
+    let (tokens, _) = tokenize(source);
+    let ir = build_ir(&tokens, true)?;
+    return ir_to_program(&ir);
+}
+
+/// Same as compile(), but also returns a source map for the program; every instruction of the
+/// compiled program can be traced back to a source line via `Span::get_source()` and this map.
+pub fn compile_with_source_map(source: &str) -> Result<(Program, SourceMap), AssemblyError> {
+    let (tokens, lines) = tokenize(source);
+
+    // parse the program into IR, and lower the IR into a program
+    let ir = build_ir(&tokens, false)?;
+    let program = ir_to_program(&ir)?;
+
+    let source_map = SourceMap {
+        lines,
+        instructions: tokens.iter().map(|&token| String::from(token)).collect(),
+    };
+    return Ok((program, source_map));
+}
+
+/// Same as compile(), but also resolves `.include "name"` directives: `resolver` maps the name
+/// of an included file to its source (or returns None if the file does not exist), and the
+/// source is spliced into the program in place of the directive. Included files usually define
+/// procedures and constant tables shared by many programs, and can be included only before the
+/// `begin` instruction of the program. Every file is included at most once,
+/// and cyclic includes result in an error. Steps reported in errors refer to positions in the
+/// instruction stream with all included files spliced in.
+pub fn compile_with_resolver<R>(source: &str, resolver: R) -> Result<Program, AssemblyError>
+    where R: Fn(&str) -> Option<String>
+{
+    let mut tokens = Vec::new();
+    expand_includes(source, &resolver, &mut Vec::new(), &mut HashSet::new(), &mut tokens)?;
+    let tokens: Vec<&str> = tokens.iter().map(|token| token.as_str()).collect();
+
+    let ir = build_ir(&tokens, false)?;
+    return ir_to_program(&ir);
+}
+
+/// Parses provided assembly code into an intermediate representation; the IR can be transformed
+/// and then lowered into a program via `ir_to_program()`. All instructions are validated during
+/// parsing; thus, lowering of an unmodified IR always succeeds.
+pub fn parse_ir(source: &str) -> Result<Vec<IrOp>, AssemblyError> {
+    let (tokens, _) = tokenize(source);
+    return build_ir(&tokens, false);
+}
+
+/// Returns the number of steps it takes to execute `hash.n` instruction starting at the specified
+/// `step`. This includes the steps needed to prepare the stack, to pad the program with NOOPs so
+/// that hashing starts on a step which is a multiple of HASH_CYCLE_LENGTH, and to truncate the
+/// state once hashing is done. Thus, in a sequence of `hash.2` instructions, every instruction
+/// except for the first one takes exactly HASH_CYCLE_LENGTH steps.
+pub fn hash_step_cost(n: usize, step: usize) -> usize {
+    assert!(n >= 1 && n <= HASH_STATE_RATE,
+        "cannot hash {} values; n must be between 1 and {}", n, HASH_STATE_RATE);
+
+    // operations are aligned on multiples of HASH_CYCLE_LENGTH, and thus, the cost depends only
+    // on the position of the step within the cycle
+    let offset = step % HASH_CYCLE_LENGTH;
+    let mut program = vec![OpCode::Noop; offset];
+    let param = n.to_string();
+    parse_hash(&mut program, &["hash", &param], 0).unwrap();
+    return program.len() - offset;
+}
+
+// SOURCE MAP IMPLEMENTATION
+// ================================================================================================
+impl SourceMap {
 
-    // break assembly string into tokens
-    let tokens: Vec<&str> = source.split_whitespace().collect();
+    /// Returns the (1-based) source line of the instruction with the specified index.
+    pub fn line(&self, instruction: usize) -> usize {
+        return self.lines[instruction];
+    }
+
+    /// Returns the text of the instruction with the specified index.
+    pub fn instruction(&self, instruction: usize) -> &str {
+        return &self.instructions[instruction];
+    }
+}
+
+// PARSER FUNCTIONS
+// ================================================================================================
+
+/// Breaks assembly source code into tokens, and for each token returns the line it came from.
+fn tokenize(source: &str) -> (Vec<&str>, Vec<usize>) {
+    let mut tokens: Vec<&str> = Vec::new();
+    let mut lines = Vec::new();
+    for (line, text) in source.lines().enumerate() {
+        for token in text.split_whitespace() {
+            tokens.push(token);
+            lines.push(line + 1);
+        }
+    }
+    return (tokens, lines);
+}
+
+/// Parses the `token` stream into a list of IR operations describing the program; if
+/// `check_stack` is set, branches of all `if.true` blocks must leave the stack at the same depth.
+fn build_ir(tokens: &[&str], check_stack: bool) -> Result<Vec<IrOp>, AssemblyError> {
 
     // perform basic validation
     if tokens.len() == 0 {
         return Err(AssemblyError::empty_program());
     }
-    else if tokens[0] != "begin" {
-        return Err(AssemblyError::invalid_program_start(tokens[0]));
+
+    // read constant table and procedure definitions which can appear before the program body
+    let mut tables = ConstTables::new();
+    let mut procs = Procedures { bodies: HashMap::new(), inlined: 0 };
+    let mut i = 0;
+    while i < tokens.len() {
+        i = match tokens[i] {
+            ".const_table"  => parse_const_table(&mut tables, &tokens, i)?,
+            ".proc"         => parse_proc(&mut procs, &tokens, &tables, i)?,
+            ".include"      => return Err(AssemblyError::invalid_directive(&[tokens[i]], i,
+                String::from("included files can be resolved only by compile_with_resolver()"))),
+            _ => break,
+        };
+    }
+
+    if i == tokens.len() {
+        return Err(AssemblyError::empty_program());
+    }
+    else if tokens[i] != "begin" {
+        return Err(AssemblyError::invalid_program_start(tokens[i]));
     }
     else if tokens[tokens.len() - 1] != "end" {
         return Err(AssemblyError::invalid_program_end(tokens[tokens.len() - 1]));
     }
+    else if tokens.len() - i == 2 {
+        // the program consists of just 'begin end'
+        return Err(AssemblyError::empty_program());
+    }
 
     // read the program from the token stream
-    let mut root_blocks = Vec::new();
-    let i = parse_branch(&mut root_blocks, &tokens, 0)?;
-    let root = Group::new(root_blocks);
+    let mut root = Vec::new();
+    let i = parse_branch(&mut root, &tokens, &tables, &mut procs, i)?;
 
     // make sure there is nothing left after the last token
     if i < tokens.len() - 1 {
         return Err(AssemblyError::dangling_instructions(i));
     }
 
-    // build and return the program
-    return Ok(Program::new(root));
-}
+    // make sure branches of all if.true blocks leave the stack at the same depth
+    if check_stack {
+        get_stack_delta(&root, &tables)?;
+    }
 
-// PARSER FUNCTIONS
-// ================================================================================================
+    return Ok(root);
+}
 
 /// Parses a single program block from the `token` stream, and appends this block to the `parent`
-/// list of blocks.
-fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
+/// list of IR operations.
+fn parse_block(parent: &mut Vec<IrOp>, tokens: &[&str], tables: &ConstTables, procs: &mut Procedures, mut i: usize) -> Result<usize, AssemblyError> {
 
     // read the block header
     let head: Vec<&str> = tokens[i].split(".").collect();
@@ -65,8 +249,8 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             }
             // then parse the body of the block, add the new block to the parent, and return
             let mut body = Vec::new();
-            i = parse_branch(&mut body, tokens, i)?;
-            parent.push(Group::new_block(body));
+            i = parse_branch(&mut body, tokens, tables, procs, i)?;
+            parent.push(IrOp::Block(body));
             return Ok(i + 1);
         },
         "if" => {
@@ -77,25 +261,22 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
 
             // parse the body of the true branch
             let mut t_branch = Vec::new();
-            i = parse_branch(&mut t_branch, tokens, i)?;
+            i = parse_branch(&mut t_branch, tokens, tables, procs, i)?;
 
             // if the false branch is present, parse it as well; otherwise
             // create an empty false branch
             let mut f_branch = Vec::new();
             if tokens[i] == "else" {
-                i = parse_branch(&mut f_branch, tokens, i)?;
+                i = parse_branch(&mut f_branch, tokens, tables, procs, i)?;
             }
             else {
-                f_branch.push(Span::new_block(vec![
-                    OpCode::Not,  OpCode::Assert, OpCode::Noop, OpCode::Noop,
-                    OpCode::Noop, OpCode::Noop,   OpCode::Noop, OpCode::Noop,
-                    OpCode::Noop, OpCode::Noop,   OpCode::Noop, OpCode::Noop,
-                    OpCode::Noop, OpCode::Noop,   OpCode::Noop,
-                ]));
+                let mut op_codes = vec![OpCode::Not, OpCode::Assert];
+                op_codes.resize(BASE_CYCLE_LENGTH - 1, OpCode::Noop);
+                f_branch.extend(op_codes.into_iter().map(|op_code| IrOp::Op { op_code, source: None }));
             }
 
             // create a Switch block, add it to the parent, and return
-            parent.push(Switch::new_block(t_branch, f_branch));
+            parent.push(IrOp::Switch(t_branch, f_branch));
             return Ok(i + 1);
         },
         "repeat" => {
@@ -105,15 +286,10 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
                 return Err(AssemblyError::invalid_num_iterations(&head, i));
             }
 
-            // parse loop body
-            let mut body_template = Vec::new();
-            i = parse_branch(&mut body_template, tokens, i)?;
-
-            // duplicate loop body as many times as needed
-            let body = repeat_block_sequence(body_template, num_iterations);
-
-            // create a Group block with all iterations expanded, and return
-            parent.push(Group::new_block(body));
+            // parse loop body; the body is duplicated as many times as needed during lowering
+            let mut body = Vec::new();
+            i = parse_branch(&mut body, tokens, tables, procs, i)?;
+            parent.push(IrOp::Repeat(num_iterations, body));
             return Ok(i + 1);
         },
         "while" => {
@@ -123,8 +299,8 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             }
             // then parse the body of the block, add the new block to the parent, and return
             let mut body = Vec::new();
-            i = parse_branch(&mut body, tokens, i)?;
-            parent.push(Loop::new_block(body));
+            i = parse_branch(&mut body, tokens, tables, procs, i)?;
+            parent.push(IrOp::Loop(body));
             return Ok(i + 1);
         },
         _ => return Err(AssemblyError::invalid_block_head(&head, i)),
@@ -132,12 +308,12 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
 }
 
 /// Builds a body of a program block by parsing tokens from the stream and transforming
-/// them into program blocks.
-fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
+/// them into IR operations.
+fn parse_branch(body: &mut Vec<IrOp>, tokens: &[&str], tables: &ConstTables, procs: &mut Procedures, mut i: usize) -> Result<usize, AssemblyError> {
 
     // determine starting instructions of the branch based on branch head
     let mut head: Vec<&str> = tokens[i].split(".").collect();
-    let mut op_codes: Vec<OpCode> = match head[0] {
+    let op_codes: Vec<OpCode> = match head[0] {
         "begin"  => {
             // this is a first block of a program
             head[0] = "block";
@@ -148,13 +324,21 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
         "else"   => vec![OpCode::Not, OpCode::Assert],
         "repeat" => vec![],
         "while"  => vec![OpCode::Assert],
+        "" if tokens[i] == ".proc" => {
+            // this is a procedure definition; the body starts after the procedure name
+            head = vec!["proc"];
+            vec![]
+        },
         _ => return Err(AssemblyError::invalid_block_head(&head, i)),
     };
-    let mut op_hints: HintMap = HashMap::new();
 
-    // save first step to check for empty branches
+    // instructions implied by the branch head come from the head instruction
+    body.extend(op_codes.into_iter().map(|op_code| IrOp::Op { op_code, source: Some(i) }));
+
+    // save first step and the start of the body to check for empty branches
     let first_step = i;
-    i += 1;
+    i += if head[0] == "proc" { 2 } else { 1 };
+    let body_start = i;
 
     // iterate over tokens and parse them one by one until the end of the block is reached;
     // if a new block is encountered, parse it recursively
@@ -162,28 +346,36 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
         let op: Vec<&str> = tokens[i].split(".").collect();
         i = match op[0] {
             "block" | "if" | "repeat" | "while" => {
-                let force_span = body.len() == 0;
-                add_span(body, &mut op_codes, &mut op_hints, force_span);
-                parse_block(body, tokens, i)?
+                parse_block(body, tokens, tables, procs, i)?
+            },
+            "exec" if op.len() == 2 && procs.bodies.contains_key(op[1]) => {
+                // procedures are inlined at every place they are invoked from
+                let (proc_body, size) = &procs.bodies[op[1]];
+                procs.inlined += size;
+                if procs.inlined > MAX_INLINED_OPS {
+                    return Err(AssemblyError::invalid_param_reason(&op, i,
+                        format!("procedure '{}' cannot be inlined; a program can inline at most {} operations",
+                            op[1], MAX_INLINED_OPS)));
+                }
+                body.extend(proc_body.iter().cloned());
+                i + 1
             },
             "else" => {
                 if head[0] != "if" {
                     return Err(AssemblyError::dangling_else(i));
                 }
-                else if i - first_step < 2 {
+                else if i == body_start {
                     return Err(AssemblyError::empty_block(&head, first_step));
                 }
-                add_span(body, &mut op_codes, &mut op_hints, false);
                 return Ok(i);
             },
             "end" => {
-                if i - first_step < 2 {
+                if i == body_start {
                     return Err(AssemblyError::empty_block(&head, first_step));
                 }
-                add_span(body, &mut op_codes, &mut op_hints, false);
                 return Ok(i);
             },
-            _ => parse_op_token(op, &mut op_codes, &mut op_hints, i)?
+            _ => parse_instruction(body, op, tables, i)?,
         };
     }
 
@@ -194,12 +386,113 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
         "else"   => Err(AssemblyError::unmatched_else(first_step)),
         "repeat" => Err(AssemblyError::unmatched_repeat(first_step, &head)),
         "while"  => Err(AssemblyError::unmatched_while(first_step)),
+        "proc"   => Err(AssemblyError::unmatched_proc(first_step)),
         _ => Err(AssemblyError::invalid_block_head(&head, first_step)),
     };
 }
 
+/// Parses a `.proc <name> <instructions> end` directive which defines a procedure; the procedure
+/// can be invoked from the program (and from procedures defined after it) via `exec.<name>`
+/// instruction. Since procedures are defined in order, recursive invocations are not possible.
+fn parse_proc(procs: &mut Procedures, tokens: &[&str], tables: &ConstTables, i: usize) -> Result<usize, AssemblyError> {
+    let directive = [tokens[i]];
+    if i + 1 >= tokens.len() {
+        return Err(AssemblyError::invalid_directive(&directive, i, String::from("procedure name is missing")));
+    }
+
+    // procedure names cannot contain parameters, and cannot shadow standard library gadgets
+    let name = tokens[i + 1];
+    if name.len() == 0 || name.contains('.') {
+        return Err(AssemblyError::invalid_directive(&directive, i,
+            format!("procedure name '{}' is invalid", name)));
+    }
+    else if procs.bodies.contains_key(name) || stdlib::is_gadget(name) {
+        return Err(AssemblyError::invalid_directive(&directive, i,
+            format!("procedure '{}' is already defined", name)));
+    }
+
+    let mut body = Vec::new();
+    let end = parse_branch(&mut body, tokens, tables, procs, i)?;
+    let size = get_ir_size(&body);
+    procs.bodies.insert(String::from(name), (body, size));
+    return Ok(end + 1);
+}
+
+/// Splices sources of included files into the token stream in place of `.include` directives;
+/// `stack` holds the names of files which are currently being expanded, and `included` holds
+/// the names of all files expanded so far.
+fn expand_includes<R>(source: &str, resolver: &R, stack: &mut Vec<String>, included: &mut HashSet<String>, result: &mut Vec<String>) -> Result<(), AssemblyError>
+    where R: Fn(&str) -> Option<String>
+{
+    let (tokens, _) = tokenize(source);
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i] != ".include" {
+            result.push(String::from(tokens[i]));
+            i += 1;
+            continue;
+        }
+
+        // files can be included only before the program body
+        let directive = [tokens[i]];
+        let step = result.len();
+        if result.iter().any(|token| token == "begin") {
+            return Err(AssemblyError::invalid_directive(&directive, step,
+                String::from("files can be included only before the program body")));
+        }
+
+        // the name of the file must be enclosed in double quotes
+        let name = match tokens.get(i + 1) {
+            Some(name) if name.len() > 2 && name.starts_with('"') && name.ends_with('"') => &name[1..(name.len() - 1)],
+            _ => return Err(AssemblyError::invalid_directive(&directive, step,
+                String::from("name of the included file must be enclosed in \"\""))),
+        };
+
+        if stack.iter().any(|file| file == name) {
+            let cycle: Vec<&str> = stack.iter().map(|file| file.as_str()).chain(std::iter::once(name)).collect();
+            return Err(AssemblyError::invalid_directive(&directive, step,
+                format!("cyclic include of '{}': {}", name, cycle.join(" -> "))));
+        }
+
+        if !included.contains(name) {
+            let source = match resolver(name) {
+                Some(source) => source,
+                None => return Err(AssemblyError::invalid_directive(&directive, step,
+                    format!("included file '{}' could not be resolved", name))),
+            };
+
+            stack.push(String::from(name));
+            included.insert(String::from(name));
+            expand_includes(&source, resolver, stack, included, result)?;
+            stack.pop();
+        }
+        i += 2;
+    }
+
+    return Ok(());
+}
+
+/// Validates an assembly instruction and appends it to the `body`; references to constant
+/// tables are replaced with PUSH instructions of the referenced values.
+fn parse_instruction(body: &mut Vec<IrOp>, op: Vec<&str>, tables: &ConstTables, step: usize) -> Result<usize, AssemblyError> {
+
+    // translate the instruction into VM instructions to make sure it is valid
+    let next = parse_op_token(op.clone(), &mut Vec::new(), &mut HashMap::new(), tables, step)?;
+
+    let parts = if op[0] == "load_const" {
+        let index = op[2].parse::<usize>().unwrap();
+        vec![String::from("push"), tables[op[1]][index].to_string()]
+    }
+    else {
+        op.iter().map(|&part| String::from(part)).collect()
+    };
+
+    body.push(IrOp::Instruction { parts, source: step });
+    return Ok(next);
+}
+
 /// Transforms an assembly instruction into a sequence of one or more VM instructions.
-fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap, step: usize) -> Result<usize, AssemblyError> {
+fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap, tables: &ConstTables, step: usize) -> Result<usize, AssemblyError> {
 
     // based on the instruction, invoke the correct parser for the operation
     match op[0] {
@@ -208,6 +501,9 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
 
         "push"   => parse_push(op_codes, op_hints, &op, step),
         "read"   => parse_read(op_codes, &op, step),
+        "clk"    => parse_clk(op_codes, &op, step),
+        "write"  => parse_write(op_codes, &op, step),
+        "load_const" => parse_load_const(op_codes, op_hints, tables, &op, step),
 
         "dup"    => parse_dup(op_codes, &op, step),
         "pad"    => parse_pad(op_codes, &op, step),
@@ -215,6 +511,8 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
         "drop"   => parse_drop(op_codes, &op, step),
         "swap"   => parse_swap(op_codes, &op, step),
         "roll"   => parse_roll(op_codes, &op, step),
+        "movup"  => parse_movup(op_codes, &op, step),
+        "movdn"  => parse_movdn(op_codes, &op, step),
 
         "add"    => parse_add(op_codes, &op, step),
         "sub"    => parse_sub(op_codes, &op, step),
@@ -228,17 +526,24 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
 
         "eq"     => parse_eq(op_codes, op_hints, &op, step),
         "ne"     => parse_ne(op_codes, op_hints, &op, step),
+        "iszero" => parse_iszero(op_codes, op_hints, &op, step),
         "gt"     => parse_gt(op_codes, op_hints, &op, step),
         "lt"     => parse_lt(op_codes, op_hints, &op, step),
         "rc"     => parse_rc(op_codes, op_hints, &op, step),
         "isodd"  => parse_isodd(op_codes, op_hints, &op, step),
+        "bits"   => parse_bits(op_codes, op_hints, &op, step),
+        "bytes"  => parse_bytes(op_codes, op_hints, &op, step),
 
         "choose" => parse_choose(op_codes, &op, step),
 
         "hash"   => parse_hash(op_codes, &op, step),
+        "hashchain" => parse_hashchain(op_codes, op_hints, &op, step),
+        "hashseq" => parse_hashseq(op_codes, op_hints, &op, step),
         "smpath" => parse_smpath(op_codes, &op, step),
         "pmpath" => parse_pmpath(op_codes, op_hints, &op, step),
 
+        "exec"   => parse_exec(op_codes, op_hints, tables, &op, step),
+
         _ => return Err(AssemblyError::invalid_op(&op, step))
     }?;
 
@@ -246,62 +551,30 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
     return Ok(step + 1);
 }
 
-// HELPER FUNCTIONS
-// ================================================================================================
-
-/// Adds a new Span block to a program block body based on currently parsed instructions.
-fn add_span(body: &mut Vec<ProgramBlock>, op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap, force: bool) {
-
-    // if there were no instructions in the current span, don't do anything
-    if op_codes.len() == 0 && !force { return };
-
-    // pad the instructions to make ensure 16-cycle alignment
-    let mut span_op_codes = op_codes.clone();
-    let pad_length = BASE_CYCLE_LENGTH - (span_op_codes.len() % BASE_CYCLE_LENGTH) - 1;
-    span_op_codes.resize(span_op_codes.len() + pad_length, OpCode::Noop);
-
-    // add a new Span block to the body
-    body.push(ProgramBlock::Span(Span::new(span_op_codes, op_hints.clone())));
-
-    // clear op_codes and op_hints for the next Span block
-    op_codes.clear();
-    op_hints.clear();
-}
+/// Expands a standard library gadget invoked via `exec` instruction into VM instructions; all
+/// instructions of the gadget are attributed to the `exec` instruction.
+fn parse_exec(op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap, tables: &ConstTables, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() < 2 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
 
-fn repeat_block_sequence(template: Vec<ProgramBlock>, num_iterations: usize) -> Vec<ProgramBlock> {
-    let mut body = Vec::with_capacity(template.len() * num_iterations);
+    let source = match stdlib::get_gadget_source(op[1], &op[2..]) {
+        Ok(source) => source,
+        Err(reason) => return Err(AssemblyError::invalid_param_reason(op, step, reason)),
+    };
 
-    let last_idx = template.len() - 1;
-    if !template[last_idx].is_span() {
-        for _ in 0..num_iterations {
-            body.extend_from_slice(&template);
-        }
-    }
-    else {
-        body.extend_from_slice(&template);
-        for _ in 1..num_iterations {
-            let last_idx = body.len() - 1;
-            body[last_idx] = merge_spans(&body[last_idx], &template[0]);
-            body.extend_from_slice(&template[1..]);
+    for token in source.split_whitespace() {
+        let gadget_op: Vec<&str> = token.split(".").collect();
+        if let Err(err) = parse_op_token(gadget_op, op_codes, op_hints, tables, step) {
+            return Err(AssemblyError::invalid_param_reason(op, step, err.message().clone()));
         }
     }
 
-    return body;
+    return Ok(true);
 }
 
-fn merge_spans(span1: &ProgramBlock, span2: &ProgramBlock) -> ProgramBlock {
-    return match span1 {
-        ProgramBlock::Span(first_span) => {
-            match span2 {
-                ProgramBlock::Span(last_span) => {
-                    ProgramBlock::Span(Span::merge(first_span, last_span))
-                },
-                _ => panic!("span1 is not a Span block")
-            }
-        },
-        _ => panic!("span2 is not a Span block")
-    };
-}
+// HELPER FUNCTIONS
+// ================================================================================================
 
 fn read_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
     if op.len() > 2 {
@@ -315,4 +588,13 @@ fn read_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
     };
 
     return Ok(result);
-}
\ No newline at end of file
+}
+
+/// Returns the number of operations in the IR, including operations of nested blocks.
+fn get_ir_size(ir: &[IrOp]) -> usize {
+    return ir.iter().map(|op| match op {
+        IrOp::Op { .. } | IrOp::Instruction { .. } => 1,
+        IrOp::Block(body) | IrOp::Loop(body) | IrOp::Repeat(_, body) => 1 + get_ir_size(body),
+        IrOp::Switch(t_branch, f_branch) => 1 + get_ir_size(t_branch) + get_ir_size(f_branch),
+    }).sum();
+}
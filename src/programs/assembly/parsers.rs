@@ -1,10 +1,13 @@
-use crate::{ math::field };
-use super::{ AssemblyError, HintMap, OpCode, OpHint };
+use crate::{ math::field, MAX_STACK_DEPTH, HASH_CYCLE_LENGTH, HASH_NUM_ROUNDS, BYTES_PER_ELEMENT };
+use super::{ AssemblyError, HintMap, ConstTables, OpCode, OpHint };
 
 // CONSTANTS
 // ================================================================================================
 const PUSH_OP_ALIGNMENT: usize = 8;
-const HASH_OP_ALIGNMENT: usize = 16;
+const HASH_OP_ALIGNMENT: usize = HASH_CYCLE_LENGTH;
+
+/// Maximum number of stack items which can be hashed with `hashchain.n` instruction.
+pub const MAX_HASH_VALUES: u32 = 16;
 
 // CONTROL FLOW OPERATIONS
 // ================================================================================================
@@ -47,6 +50,26 @@ pub fn parse_push(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], s
     return Ok(true);
 }
 
+/// Appends a sequence of operations which replaces the top value on the stack with 1 if the
+/// value is less than 2^n, and with 0 otherwise.
+fn append_rc_ops(program: &mut Vec<OpCode>, hints: &mut HintMap, n: u32) {
+    // prepare the stack
+    program.push(OpCode::Pad2);
+    append_push_op(program, hints, field::ONE);
+    program.extend_from_slice(&[OpCode::Swap, OpCode::Dup]);
+
+    // add a hint indicating that range-checking is about to start
+    hints.insert(program.len(), OpHint::RcStart(n));
+
+    // append BINACC operations
+    program.resize(program.len() + (n as usize), OpCode::BinAcc);
+
+    // compare binary aggregation value with the original value
+    program.extend_from_slice(&[OpCode::Dup, OpCode::Drop4]);
+    hints.insert(program.len(), OpHint::EqStart);
+    program.extend_from_slice(&[OpCode::Read, OpCode::Eq]);
+}
+
 /// Makes sure PUSH operation alignment is correct and appends PUSH opcode to the program.
 fn append_push_op(program: &mut Vec<OpCode>, hints: &mut HintMap, value: u128) {
     // pad the program with NOOPs to make sure PUSH happens on steps which are multiples of 8
@@ -80,6 +103,54 @@ pub fn parse_read(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result
     return Ok(true);
 }
 
+/// Appends CLK operation to the program.
+pub fn parse_clk(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() > 1 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+    program.push(OpCode::Clk);
+    return Ok(true);
+}
+
+/// Appends WRITE operation to the program.
+pub fn parse_write(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() > 1 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+    program.push(OpCode::Write);
+    return Ok(true);
+}
+
+/// Appends a PUSH operation for the value at the specified index of a constant table.
+pub fn parse_load_const(program: &mut Vec<OpCode>, hints: &mut HintMap, tables: &ConstTables, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() < 3 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 3 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    let table = match tables.get(op[1]) {
+        Some(table) => table,
+        None => return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("constant table '{}' is not defined", op[1])))
+    };
+
+    let index = match op[2].parse::<usize>() {
+        Ok(i) => i,
+        Err(_) => return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("index '{}' is invalid", op[2])))
+    };
+
+    if index >= table.len() {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("index {} is out of bounds for constant table '{}' of length {}", index, op[1], table.len())));
+    }
+
+    append_push_op(program, hints, table[index]);
+    return Ok(true);
+}
+
 // STACK MANIPULATION OPERATIONS
 // ================================================================================================
 
@@ -180,6 +251,58 @@ pub fn parse_roll(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result
     return Ok(true);
 }
 
+/// Appends a sequence of operations to the program to move n-th item to the top of the stack;
+/// items above the n-th item are shifted down by one position.
+pub fn parse_movup(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_index(op, step)?;
+    match n {
+        0 => (),
+        1 => program.push(OpCode::Swap),
+        2 => program.extend_from_slice(&[OpCode::Dup, OpCode::Roll4, OpCode::Swap, OpCode::Drop]),
+        3 => program.push(OpCode::Roll4),
+        4 => program.extend_from_slice(&[
+            OpCode::Roll4, OpCode::Dup4, OpCode::Drop, OpCode::Swap4, OpCode::Dup, OpCode::Drop4
+        ]),
+        5 => program.extend_from_slice(&[
+            OpCode::Dup2, OpCode::Roll8, OpCode::Swap2, OpCode::Dup2, OpCode::Drop4
+        ]),
+        6 => program.extend_from_slice(&[OpCode::Dup, OpCode::Roll8, OpCode::Swap, OpCode::Drop]),
+        7 => program.push(OpCode::Roll8),
+        _ => return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; allowed values are: [0, 1, 2, 3, 4, 5, 6, 7]", n)))
+    }
+
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to move the top item of the stack to
+/// position n; items above position n are shifted up by one position.
+pub fn parse_movdn(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_index(op, step)?;
+    match n {
+        0 => (),
+        1 => program.push(OpCode::Swap),
+        2 => program.extend_from_slice(&[OpCode::Dup, OpCode::Swap2, OpCode::Roll4, OpCode::Drop]),
+        3 => program.extend_from_slice(&[OpCode::Swap2, OpCode::Roll4]),
+        4 => program.extend_from_slice(&[
+            OpCode::Dup4, OpCode::Drop, OpCode::Swap4, OpCode::Dup, OpCode::Roll8, OpCode::Drop4,
+            OpCode::Swap2
+        ]),
+        5 => program.extend_from_slice(&[
+            OpCode::Dup2, OpCode::Swap4, OpCode::Roll8, OpCode::Roll8, OpCode::Roll8, OpCode::Dup2,
+            OpCode::Drop4
+        ]),
+        6 => program.extend_from_slice(&[
+            OpCode::Dup, OpCode::Swap4, OpCode::Roll8, OpCode::Roll8, OpCode::Roll8, OpCode::Drop
+        ]),
+        7 => program.extend_from_slice(&[OpCode::Swap4, OpCode::Roll8, OpCode::Roll8, OpCode::Roll8]),
+        _ => return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; allowed values are: [0, 1, 2, 3, 4, 5, 6, 7]", n)))
+    }
+
+    return Ok(true);
+}
+
 // ARITHMETIC AND BOOLEAN OPERATIONS
 // ================================================================================================
 
@@ -258,6 +381,15 @@ pub fn parse_eq(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], ste
     return Ok(true);
 }
 
+/// Appends a sequence of operations to the the program to determine whether the top value on the
+/// stack is equal to zero.
+pub fn parse_iszero(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() > 1 { return Err(AssemblyError::extra_param(op, step)); }
+    hints.insert(program.len(), OpHint::IsZeroStart);
+    program.extend_from_slice(&[OpCode::Read, OpCode::IsZero]);
+    return Ok(true);
+}
+
 /// Appends a sequence of operations to the the program to determine whether the top value on the
 /// stack is not equal to the following value.
 pub fn parse_ne(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> { 
@@ -340,21 +472,7 @@ pub fn parse_rc(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], ste
             format!("parameter {} is invalid; value must be between 4 and 128", n)))
     }
 
-    // prepare the stack
-    program.push(OpCode::Pad2);
-    append_push_op(program, hints, field::ONE);
-    program.extend_from_slice(&[OpCode::Swap, OpCode::Dup]);
-
-    // add a hint indicating that range-checking is about to start
-    hints.insert(program.len(), OpHint::RcStart(n));
-
-    // append BINACC operations
-    program.resize(program.len() + (n as usize), OpCode::BinAcc);
-
-    // compare binary aggregation value with the original value
-    program.extend_from_slice(&[OpCode::Dup, OpCode::Drop4]);
-    hints.insert(program.len(), OpHint::EqStart);
-    program.extend_from_slice(&[OpCode::Read, OpCode::Eq]);
+    append_rc_ops(program, hints, n);
     return Ok(true);
 }
 
@@ -392,6 +510,69 @@ pub fn parse_isodd(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str],
     return Ok(true);
 }
 
+/// Appends a sequence of operations to the program to replace the top value on the stack with
+/// its n-bit binary decomposition; the least significant bit ends up at the top of the stack.
+/// If the value cannot be represented with n bits, the operation will fail.
+pub fn parse_bits(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    // n is the number of bits into which the value is decomposed; all bits are kept on
+    // the stack together with the accumulator and the original value
+    let n = read_param(op, step)?;
+    if n < 1 || n as usize > MAX_STACK_DEPTH - 2 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 1 and {}", n, MAX_STACK_DEPTH - 2)))
+    }
+
+    // prepare the stack: accumulator is initialized to 0 and is placed at the top of the stack
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop]);
+
+    // add a hint indicating that bit decomposition is about to start
+    hints.insert(program.len(), OpHint::BitsStart(n));
+
+    // append BITDEC operations
+    program.resize(program.len() + (n as usize), OpCode::BitDec);
+
+    // make sure the bits aggregate to the original value
+    program.push(OpCode::AssertEq);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to replace the top value on the stack with
+/// its n-byte decomposition; the least significant byte ends up at the top of the stack. If the
+/// value cannot be represented with n bytes, the operation will fail.
+pub fn parse_bytes(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    // n is the number of bytes into which the value is decomposed; a value of at most
+    // BYTES_PER_ELEMENT bytes has a unique decomposition because it is smaller than the modulus
+    let n = read_param(op, step)?;
+    if n < 1 || n as usize > BYTES_PER_ELEMENT {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 1 and {}", n, BYTES_PER_ELEMENT)))
+    }
+
+    // prepare the stack: accumulator is initialized to 0 and is placed at the top of the stack
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop]);
+
+    // add a hint indicating that byte decomposition is about to start; bytes are read from
+    // tape A starting with the most significant byte
+    hints.insert(program.len(), OpHint::BytesStart(n));
+
+    for _ in 0..n {
+        // read the next byte and make sure it is less than 256
+        program.extend_from_slice(&[OpCode::Read, OpCode::Dup]);
+        append_rc_ops(program, hints, 8);
+        program.push(OpCode::Assert);
+
+        // move a copy of the byte below the original value and update the accumulator; the
+        // stack goes from [byte, acc, value] to [acc * 256 + byte, value, byte]
+        program.extend_from_slice(&[OpCode::Dup, OpCode::Swap2, OpCode::Roll4, OpCode::Swap]);
+        append_push_op(program, hints, 256);
+        program.extend_from_slice(&[OpCode::Mul, OpCode::Add]);
+    }
+
+    // make sure the bytes aggregate to the original value
+    program.push(OpCode::AssertEq);
+    return Ok(true);
+}
+
 // SELECTOR OPERATIONS
 // ================================================================================================
 
@@ -410,35 +591,200 @@ pub fn parse_choose(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Resu
 // CRYPTO OPERATIONS
 // ================================================================================================
 
-/// Appends a sequence of operations to the program to hash top n values of the stack.
+/// Appends a sequence of operations to the program to hash top n values of the stack with a
+/// single invocation of the hash function; n can be at most HASH_STATE_RATE. The result is the
+/// same as the one computed by `hasher::digest()` function for the values in the order in which
+/// they were pushed onto the stack.
 pub fn parse_hash(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
     let n = read_param(op, step)?;
-    match n {
-        1 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Pad2, OpCode::Drop]),
-        2 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2]),
-        3 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Pad2, OpCode::Drop]),
-        4 => program.push(OpCode::Pad2),
-        _ => return Err(AssemblyError::invalid_param_reason(op, step,
+    match get_hash_prep_ops(n) {
+        Some(prep_ops) => program.extend_from_slice(prep_ops),
+        None => return Err(AssemblyError::invalid_param_reason(op, step,
             format!("parameter {} is invalid; allowed values are: [1, 2, 3, 4]", n)))
     }
+    append_hash_rounds(program);
+    return Ok(true);
+}
 
-    // pad with NOOPs to make sure hashing starts on a step which is a multiple of 16
+/// Appends a sequence of operations to the program to hash top n values of the stack by
+/// chaining invocations of the hash function. The result is the same as the one computed by
+/// `hasher::hash()` function for the values listed starting from the top of the stack (the same
+/// order in which stack outputs are committed to): the chain is initialized with n, and the
+/// values are absorbed in blocks of 2 starting from the top of the stack.
+pub fn parse_hashchain(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n > MAX_HASH_VALUES {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 1 and {}", n, MAX_HASH_VALUES)));
+    }
+
+    // initialize the chain with the number of hashed values
+    append_push_op(program, hints, n as u128);
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop]);
+
+    for _ in 0..(n / 2) {
+        // move the next 2 values above the chain (the value closer to the top of the stack goes
+        // first in the block), and pad the stack to prepare it for hashing
+        program.extend_from_slice(&[OpCode::Swap2, OpCode::Swap, OpCode::Pad2]);
+        append_hash_rounds(program);
+    }
+
+    if n % 2 == 1 {
+        // move the last value above the chain, and pad the block with a ZERO
+        program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop, OpCode::Roll4, OpCode::Swap, OpCode::Pad2]);
+        append_hash_rounds(program);
+    }
+
+    return Ok(true);
+}
+
+/// Returns operations which prepare the stack for hashing of top n values of the stack, or
+/// None if n values cannot be hashed in a single invocation of the hash function.
+pub fn get_hash_prep_ops(n: u32) -> Option<&'static [OpCode]> {
+    return match n {
+        1 => Some(&[OpCode::Pad2, OpCode::Pad2, OpCode::Pad2, OpCode::Drop]),
+        2 => Some(&[OpCode::Pad2, OpCode::Pad2]),
+        3 => Some(&[OpCode::Pad2, OpCode::Pad2, OpCode::Drop]),
+        4 => Some(&[OpCode::Pad2]),
+        _ => None,
+    };
+}
+
+/// Appends operations to execute 10 rounds of Rescue over the top of the stack, and to truncate
+/// the state once hashing is done; the program is padded with NOOPs to make sure hashing starts
+/// on a step which is a multiple of 16.
+fn append_hash_rounds(program: &mut Vec<OpCode>) {
     let alignment = program.len() % HASH_OP_ALIGNMENT;
     let pad_length = (HASH_OP_ALIGNMENT - alignment) % HASH_OP_ALIGNMENT;
     program.resize(program.len() + pad_length, OpCode::Noop);
 
-    // append operations to execute 10 rounds of Rescue
-    program.extend_from_slice(&[
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR
-    ]);
+    program.resize(program.len() + HASH_NUM_ROUNDS, OpCode::RescR);
+    program.push(OpCode::Drop4);
+}
+
+/// Appends a sequence of operations to the program to compute a hash of n values read from
+/// input tape A. The result is the same as the one computed by `hasher::hash()` function.
+///
+/// Hashing of a sequence can also be split across several programs (e.g. to hash a sequence
+/// which is too long for a single execution): `hashseq.start.t.n` starts hashing a sequence of
+/// t values, absorbs the first n of them, and leaves the full state of the hash function
+/// (HASH_STATE_WIDTH items) on the stack; `hashseq.resume.n` expects such a state at the top of
+/// the stack, absorbs the remaining n values of the sequence, and leaves the hash of the entire
+/// sequence on the stack.
+pub fn parse_hashseq(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() == 1 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+
+    match op[1] {
+        "start"  => return parse_hashseq_start(program, hints, op, step),
+        "resume" => return parse_hashseq_resume(program, op, step),
+        _ => (),
+    }
+
+    if op.len() > 2 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+    let n = read_hashseq_length(op, 1, step)?;
+
+    // initialize the chain with the length of the sequence
+    append_push_op(program, hints, n as u128);
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop]);
+
+    let num_blocks = std::cmp::max(1, (n + 1) / 2);
+    append_hashseq_blocks(program, n, num_blocks, false);
+
+    return Ok(true);
+}
+
+/// Appends operations to start hashing a sequence of t values; the first n values of the
+/// sequence are absorbed, and the full state of the hash function is left on the stack.
+fn parse_hashseq_start(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() < 4 {
+        return Err(AssemblyError::missing_param(op, step));
+    } else if op.len() > 4 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    let t = read_hashseq_length(op, 2, step)?;
+    let n = read_hashseq_length(op, 3, step)?;
+    if n == 0 || n % 2 == 1 || n > t {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("number of absorbed values must be an even number between 2 and {}, but was {}", t, n)));
+    }
+
+    // initialize the chain with the length of the entire sequence
+    append_push_op(program, hints, t as u128);
+    program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop]);
+
+    append_hashseq_blocks(program, n, n / 2, true);
+    return Ok(true);
+}
 
-    // truncate the state
+/// Appends operations to continue hashing a sequence from the full state of the hash function
+/// at the top of the stack; the remaining n values of the sequence are absorbed, and the hash
+/// of the sequence is left on the stack.
+fn parse_hashseq_resume(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() < 3 {
+        return Err(AssemblyError::missing_param(op, step));
+    } else if op.len() > 3 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+    let n = read_hashseq_length(op, 2, step)?;
+
+    // truncate the imported state to get the current value of the chain
     program.push(OpCode::Drop4);
 
+    append_hashseq_blocks(program, n, (n + 1) / 2, false);
     return Ok(true);
 }
 
+/// Appends `num_blocks` invocations of the hash function, each absorbing the next 2 of n values
+/// read from input tape A (padding with ZEROs as needed); the state of the hash function is not
+/// truncated after the last invocation if `export_state` is true.
+fn append_hashseq_blocks(program: &mut Vec<OpCode>, n: u32, num_blocks: u32, export_state: bool) {
+    for i in 0..num_blocks {
+        // move the next 2 values of the sequence onto the stack (padding with ZEROs as needed),
+        // and pad the stack to prepare it for hashing
+        match n - std::cmp::min(n, i * 2) {
+            0 => program.push(OpCode::Pad2),
+            1 => program.extend_from_slice(&[OpCode::Read, OpCode::Pad2, OpCode::Drop]),
+            _ => program.extend_from_slice(&[OpCode::Read, OpCode::Read]),
+        }
+        program.push(OpCode::Pad2);
+
+        // pad with NOOPs to make sure hashing starts on a step which is a multiple of 16
+        let alignment = program.len() % HASH_OP_ALIGNMENT;
+        let pad_length = (HASH_OP_ALIGNMENT - alignment) % HASH_OP_ALIGNMENT;
+        program.resize(program.len() + pad_length, OpCode::Noop);
+
+        // append operations to execute 10 rounds of Rescue
+        program.extend_from_slice(&[
+            OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
+            OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR
+        ]);
+
+        // truncate the state
+        if !export_state || i + 1 < num_blocks {
+            program.push(OpCode::Drop4);
+        }
+    }
+}
+
+/// Reads the length of a hashed sequence from op[idx]; zero-length sequences are allowed, so
+/// read_param() can't be used here.
+fn read_hashseq_length(op: &[&str], idx: usize, step: usize) -> Result<u32, AssemblyError> {
+    let n = match op[idx].parse::<u32>() {
+        Ok(n) => n,
+        Err(_) => return Err(AssemblyError::invalid_param(op, step))
+    };
+    if n > 1024 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 0 and 1024", n)))
+    }
+    return Ok(n);
+}
+
 /// Appends a sequence of operations to the program to compute the root of Merkle authentication
 /// path for a tree of depth n. Leaf index is expected to be provided via input tapes A and B.
 pub fn parse_smpath(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
@@ -537,10 +883,76 @@ pub fn parse_pmpath(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str],
     return Ok(true);
 }
 
+// DIRECTIVES
+// ================================================================================================
+
+/// Parses a `.const_table name [v0, v1, ...]` directive starting at token `i` and adds the table
+/// to the set of constant tables. Returns the index of the first token after the directive.
+pub fn parse_const_table(tables: &mut ConstTables, tokens: &[&str], i: usize) -> Result<usize, AssemblyError> {
+    let directive = [tokens[i]];
+    if i + 1 >= tokens.len() || tokens[i + 1].starts_with("[") {
+        return Err(AssemblyError::invalid_directive(&directive, i, String::from("table name is missing")));
+    }
+
+    let name = tokens[i + 1];
+    if tables.contains_key(name) {
+        return Err(AssemblyError::invalid_directive(&directive, i,
+            format!("constant table '{}' is already defined", name)));
+    }
+
+    // collect all tokens which make up the list of table values
+    let mut j = i + 2;
+    if j >= tokens.len() || !tokens[j].starts_with("[") {
+        return Err(AssemblyError::invalid_directive(&directive, i,
+            format!("values of constant table '{}' must be enclosed in []", name)));
+    }
+    let mut values_str = String::new();
+    loop {
+        values_str.push_str(tokens[j]);
+        j += 1;
+        if values_str.ends_with("]") { break; }
+        if j >= tokens.len() {
+            return Err(AssemblyError::invalid_directive(&directive, i,
+                format!("values of constant table '{}' must be enclosed in []", name)));
+        }
+    }
+
+    // parse the values
+    let values_str = &values_str[1..(values_str.len() - 1)];
+    let mut values = Vec::new();
+    for value in values_str.split(",") {
+        if value.len() == 0 {
+            return Err(AssemblyError::invalid_directive(&directive, i,
+                format!("constant table '{}' contains an empty value", name)));
+        }
+        match read_value(&[tokens[i], value], i) {
+            Ok(value) => values.push(value),
+            Err(_) => return Err(AssemblyError::invalid_directive(&directive, i,
+                format!("value '{}' in constant table '{}' is not a valid field element", value, name)))
+        }
+    }
+
+    tables.insert(String::from(name), values);
+    return Ok(j);
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
 fn read_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
+    let result = read_index(op, step)?;
+
+    // parameter value 0 is never valid
+    if result == 0 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter value must be greater than 0")));
+    }
+
+    return Ok(result);
+}
+
+/// Same as read_param(), but parameter value 0 is allowed.
+fn read_index(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
     if op.len() == 1 {
         // if no parameters were provided, assume parameter value 1
         return Ok(1);
@@ -549,18 +961,10 @@ fn read_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
     }
 
     // try to parse the parameter value
-    let result = match op[1].parse::<u32>() {
-        Ok(i) => i,
-        Err(_) => return Err(AssemblyError::invalid_param(op, step))
+    return match op[1].parse::<u32>() {
+        Ok(i) => Ok(i),
+        Err(_) => Err(AssemblyError::invalid_param(op, step))
     };
-
-    // parameter value 0 is never valid
-    if result == 0 {
-        return Err(AssemblyError::invalid_param_reason(op, step,
-            format!("parameter value must be greater than 0")));
-    }
-
-    return Ok(result);
 }
 
 fn read_value(op: &[&str], step: usize) -> Result<u128, AssemblyError> {
@@ -0,0 +1,77 @@
+// GADGETS
+// ================================================================================================
+
+/// Pops the top item from the stack and makes sure it is less than 2^n; the program fails
+/// otherwise. n can be any integer between 4 and 128.
+pub fn assert_range(n: u32) -> String {
+    return format!("rc.{} assert", n);
+}
+
+/// Pops a condition and two values from the stack, and pushes the 1st value back onto the stack
+/// if the condition is `1`, or the 2nd value if the condition is `0`. For example, assuming `S0`
+/// is the top of the stack, `1 S1 S2` becomes `S1`, while `0 S1 S2` becomes `S2`. The program
+/// fails if the condition is not a binary value.
+pub fn select() -> String {
+    return String::from("movdn.2 choose");
+}
+
+/// Pops top two items from the stack, and pushes the larger of them back onto the stack. If
+/// either of the values is greater than 2^n, the program fails.
+pub fn max(n: u32) -> String {
+    return format!("dup.2 gt.{} {}", n, select());
+}
+
+/// Pops top n items from the stack, computes their hash in the same way as `hash.n` instruction,
+/// and makes sure the result is equal to the 2 items below them; the expected digest is
+/// consumed as well. The program fails if the digests are different.
+pub fn assert_hash(n: u32) -> String {
+    return format!("hash.{} {}", n, assert_digest_eq());
+}
+
+/// Computes the root of a Merkle authentication path for a tree of the specified depth in the
+/// same way as `pmpath.n` instruction, and makes sure the root is equal to the 2 items below
+/// the leaf index. That is, the stack is expected to contain the leaf (2 items) followed by the
+/// leaf's index and the expected root. The program fails if the path does not resolve to the
+/// expected root.
+pub fn merkle_verify(depth: u32) -> String {
+    return format!("pmpath.{} {}", depth, assert_digest_eq());
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns true if a gadget with the specified `name` exists in the standard library.
+pub fn is_gadget(name: &str) -> bool {
+    return matches!(name, "select" | "assert_range" | "max" | "assert_hash" | "merkle_verify");
+}
+
+/// Returns the source of the gadget invoked via `exec` instruction with the specified gadget
+/// name and parameters, or an error if such a gadget does not exist.
+pub fn get_gadget_source(name: &str, params: &[&str]) -> Result<String, String> {
+    let param = match params.len() {
+        0 => None,
+        1 => match params[0].parse::<u32>() {
+            Ok(value) => Some(value),
+            Err(_) => return Err(format!("parameter '{}' is invalid", params[0])),
+        },
+        _ => return Err(String::from("too many parameters provided")),
+    };
+
+    return match (name, param) {
+        ("select", None)                => Ok(select()),
+        ("assert_range", Some(n))       => Ok(assert_range(n)),
+        ("max", Some(n))                => Ok(max(n)),
+        ("assert_hash", Some(n))        => Ok(assert_hash(n)),
+        ("merkle_verify", Some(depth))  => Ok(merkle_verify(depth)),
+        ("select", Some(_))             => Err(String::from("too many parameters provided")),
+        ("assert_range", None) | ("max", None) | ("assert_hash", None) | ("merkle_verify", None)
+            => Err(String::from("parameter is missing")),
+        _ => Err(format!("gadget '{}' does not exist", name)),
+    };
+}
+
+/// Compares the digest at the top of the stack with the digest below it, and fails if they
+/// are different.
+fn assert_digest_eq() -> String {
+    return String::from("roll.4 assert.eq assert.eq");
+}
@@ -14,6 +14,23 @@ fn single_block() {
     assert_eq!(expected, format!("{:?}", program));
 }
 
+#[test]
+fn empty_program() {
+    for &source in ["", "begin end", ".const_table sbox [3] begin end"].iter() {
+        let err = super::compile(source).unwrap_err();
+        assert_eq!("a program must contain at least one instruction", err.message());
+        assert_eq!(0, err.step());
+    }
+
+    // the shortest valid program is padded to the minimum trace length and proves the identity
+    let program = super::compile("begin noop end").unwrap();
+    let inputs = crate::ProgramInputs::from_public(&[3, 5]);
+    let options = crate::ProofOptions::default();
+    let (outputs, proof) = crate::execute(&program, &inputs, 2, &options);
+    assert_eq!(vec![3, 5], outputs);
+    assert_eq!(Ok(true), crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof));
+}
+
 #[test]
 fn sequence_of_blocks() {
     let source = "begin block push.1 push.2 add end block push.3 push.4 add end end";
@@ -181,6 +198,38 @@ fn nested_if_else() {
     assert_eq!(expected, format!("{:?}", program));
 }
 
+#[test]
+fn unbalanced_if_else() {
+    // the true branch pushes one more value onto the stack than the false branch; this is
+    // allowed by compile(), but rejected by compile_checked()
+    let source = "begin push.3 push.5 read if.true push.7 add push.2 else add end end";
+    assert!(super::compile(source).is_ok());
+    let err = super::compile_checked(source).unwrap_err();
+    assert_eq!("if.true branches must change stack depth by the same amount, but true branch changes it by 1 and false branch by -1", err.message());
+    assert_eq!(4, err.step());
+
+    // without an else branch, the true branch must not change stack depth
+    let err = super::compile_checked("begin push.3 read if.true drop end end").unwrap_err();
+    assert_eq!("if.true branches must change stack depth by the same amount, but true branch changes it by -1 and false branch by 0", err.message());
+
+    // nested blocks are checked as well
+    let source = "begin push.3 read if.true read if.true push.1 end else noop end end";
+    let err = super::compile_checked(source).unwrap_err();
+    assert_eq!(5, err.step());
+
+    // effects of nested blocks and of constant table references are accounted for
+    let source = "begin push.3 read if.true read if.true push.1 else push.2 end else push.4 end end";
+    assert!(super::compile_checked(source).is_ok());
+    let source = "begin push.3 read if.true repeat.2 push.1 end else push.1 push.2 end end";
+    assert!(super::compile_checked(source).is_ok());
+    let source = ".const_table roots [7, 9] begin push.3 read if.true load_const.roots.1 else push.2 end end";
+    assert!(super::compile_checked(source).is_ok());
+
+    // stack depth after a loop which changes it is not known, and so it is not checked
+    let source = "begin push.3 read if.true read while.true push.1 read end else noop end end";
+    assert!(super::compile_checked(source).is_ok());
+}
+
 // LOOP BLOCKS
 // ================================================================================================
 #[test]
@@ -400,4 +449,368 @@ fn repeat_2_blocks_with_suffix() {
     end";
 
     assert_eq!(expected, format!("{:?}", program));
-}
\ No newline at end of file
+}
+
+// STACK MANIPULATION OPERATIONS
+// ================================================================================================
+
+#[test]
+fn movup_and_movdn() {
+    let source = "begin movup.0 movup.3 movdn.3 movup end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin roll4 swap2 roll4 swap noop noop noop \
+        noop noop noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let source = "begin movup.8 end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed instruction movup: parameter 8 is invalid; allowed values are: [0, 1, 2, 3, 4, 5, 6, 7]", err.message());
+}
+
+// ARITHMETIC OPERATIONS
+// ================================================================================================
+
+#[test]
+fn inv_and_div() {
+    let source = "begin push.6 push.2 div inv end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin noop noop noop noop noop noop noop \
+        push(6) noop noop noop noop noop noop noop \
+        push(2) inv mul inv noop noop noop noop \
+        noop noop noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+}
+
+// CRYPTOGRAPHIC OPERATIONS
+// ================================================================================================
+
+#[test]
+fn hash() {
+    use crate::utils::hasher;
+
+    // values are hashed in the order in which they were pushed onto the stack
+    for n in 1..=4 {
+        let values: Vec<u128> = (0..n).map(|i| i * i + 7).collect();
+        let pushes: Vec<String> = values.iter().map(|value| format!("push.{}", value)).collect();
+        let program = super::compile(&format!("begin {} hash.{} end", pushes.join(" "), n)).unwrap();
+
+        let mut expected = hasher::digest(&values);
+        expected.reverse();
+
+        let outputs = crate::simulate(&program, &crate::ProgramInputs::none(), 2);
+        assert_eq!(expected, outputs, "hash.{} result is not the same as the one computed by hasher", n);
+    }
+
+    let source = "begin hash.5 end";
+    let expected = "malformed instruction hash: parameter 5 is invalid; allowed values are: [1, 2, 3, 4]";
+    assert_eq!(expected, super::compile(source).unwrap_err().message());
+}
+
+#[test]
+fn hashchain() {
+    use crate::utils::hasher;
+
+    // values are hashed starting from the top of the stack
+    for n in 1..=16 {
+        let values: Vec<u128> = (0..n).map(|i| i * i + 7).collect();
+        let pushes: Vec<String> = values.iter().map(|value| format!("push.{}", value)).collect();
+        let program = super::compile(&format!("begin {} hashchain.{} end", pushes.join(" "), n)).unwrap();
+
+        let mut expected = hasher::hash(&values.iter().rev().cloned().collect::<Vec<u128>>());
+        expected.reverse();
+
+        let outputs = crate::simulate(&program, &crate::ProgramInputs::none(), 2);
+        assert_eq!(expected, outputs, "hashchain.{} result is not the same as the one computed by hasher", n);
+    }
+
+    let source = "begin hashchain.17 end";
+    let expected = "malformed instruction hashchain: parameter 17 is invalid; value must be between 1 and 16";
+    assert_eq!(expected, super::compile(source).unwrap_err().message());
+}
+
+#[test]
+fn hashseq() {
+    let source = "begin hashseq.1 end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin noop noop noop noop noop noop noop \
+        push(1) pad2 drop read pad2 drop pad2 noop \
+        rescr rescr rescr rescr rescr rescr rescr rescr \
+        rescr rescr drop4 noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let source = "begin hashseq end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed instruction hashseq: parameter is missing", err.message());
+
+    let source = "begin hashseq.start.2.2 end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin noop noop noop noop noop noop noop \
+        push(2) pad2 drop read read pad2 noop noop \
+        rescr rescr rescr rescr rescr rescr rescr rescr \
+        rescr rescr noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let source = "begin hashseq.start.5.3 end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed instruction hashseq: number of absorbed values must be an even number between 2 and 5, but was 3", err.message());
+
+    let source = "begin hashseq.resume end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed instruction hashseq: parameter is missing", err.message());
+}
+
+// CONSTANT TABLES
+// ================================================================================================
+
+#[test]
+fn const_table() {
+    let source = "
+    .const_table sbox [3, 0x10, 11]
+    .const_table roots [7]
+    begin
+        load_const.sbox.2 load_const.roots.0 add load_const.sbox.1
+    end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin noop noop noop noop noop noop noop \
+        push(11) noop noop noop noop noop noop noop \
+        push(7) add noop noop noop noop noop noop \
+        push(16) noop noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let inputs = crate::ProgramInputs::none();
+    let options = crate::ProofOptions::default();
+    let (outputs, _) = crate::execute(&program, &inputs, 2, &options);
+    assert_eq!(vec![16, 18], outputs);
+}
+
+#[test]
+fn const_table_errors() {
+    // index out of bounds
+    let source = ".const_table sbox [3, 5] begin load_const.sbox.2 end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed instruction load_const: index 2 is out of bounds for constant table 'sbox' of length 2", err.message());
+    assert_eq!(5, err.step());
+
+    // undefined table
+    let source = "begin load_const.sbox.0 end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed instruction load_const: constant table 'sbox' is not defined", err.message());
+
+    // duplicate table
+    let source = ".const_table sbox [3] .const_table sbox [5] begin load_const.sbox.0 end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed directive .const_table: constant table 'sbox' is already defined", err.message());
+
+    // invalid value
+    let source = ".const_table sbox [3, x] begin load_const.sbox.0 end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed directive .const_table: value 'x' in constant table 'sbox' is not a valid field element", err.message());
+
+    // unterminated table
+    let source = ".const_table sbox [3, 5";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed directive .const_table: values of constant table 'sbox' must be enclosed in []", err.message());
+}
+
+// INTERMEDIATE REPRESENTATION
+// ================================================================================================
+
+#[test]
+fn ir_round_trip() {
+    use super::IrOp;
+    use crate::OpCode;
+
+    let source = "
+    .const_table roots [7]
+    begin
+        push.3 load_const.roots.0
+        if.true
+            add
+        end
+        repeat.2
+            dup mul
+        end
+    end";
+    let ir = super::parse_ir(source).unwrap();
+
+    let instruction = |parts: &[&str], source: usize| IrOp::Instruction {
+        parts: parts.iter().map(|&part| String::from(part)).collect(), source
+    };
+    let mut f_branch = vec![
+        IrOp::Op { op_code: OpCode::Not, source: None },
+        IrOp::Op { op_code: OpCode::Assert, source: None },
+    ];
+    f_branch.resize(15, IrOp::Op { op_code: OpCode::Noop, source: None });
+
+    let expected = vec![
+        IrOp::Op { op_code: OpCode::Begin, source: Some(3) },
+        instruction(&["push", "3"], 4),
+        instruction(&["push", "7"], 5),
+        IrOp::Switch(vec![IrOp::Op { op_code: OpCode::Assert, source: Some(6) }, instruction(&["add"], 7)], f_branch),
+        IrOp::Repeat(2, vec![instruction(&["dup"], 10), instruction(&["mul"], 11)]),
+    ];
+    assert_eq!(expected, ir);
+
+    // lowering the IR produces the same program as direct compilation
+    let program = super::ir_to_program(&ir).unwrap();
+    let expected = super::compile(source).unwrap();
+    assert_eq!(format!("{:?}", expected), format!("{:?}", program));
+    assert_eq!(expected.hash(), program.hash());
+}
+
+#[test]
+fn ir_transformation() {
+    use super::IrOp;
+
+    // fold PUSH, PUSH, ADD sequence into a single PUSH
+    let mut ir = super::parse_ir("begin push.3 push.5 add push.2 mul end").unwrap();
+    ir.splice(1..4, vec![IrOp::Instruction { parts: vec![String::from("push"), String::from("8")], source: 1 }]);
+
+    let program = super::ir_to_program(&ir).unwrap();
+    let expected = super::compile("begin push.8 push.2 mul end").unwrap();
+    assert_eq!(format!("{:?}", expected), format!("{:?}", program));
+
+    // instructions inserted by passes are validated during lowering
+    ir.push(IrOp::Instruction { parts: vec![String::from("hash"), String::from("5")], source: 4 });
+    let err = super::ir_to_program(&ir).unwrap_err();
+    assert_eq!("malformed instruction hash: parameter 5 is invalid; allowed values are: [1, 2, 3, 4]", err.message());
+}
+
+#[test]
+fn ir_errors() {
+    use super::IrOp;
+    use crate::OpCode;
+
+    let instruction = |parts: &[&str], source: usize| IrOp::Instruction {
+        parts: parts.iter().map(|&part| String::from(part)).collect(), source
+    };
+    let begin = IrOp::Op { op_code: OpCode::Begin, source: Some(0) };
+
+    // empty program
+    let err = super::ir_to_program(&[]).unwrap_err();
+    assert_eq!("a program must contain at least one instruction", err.message());
+
+    // program which does not start with BEGIN
+    let err = super::ir_to_program(&[instruction(&["push", "3"], 0)]).unwrap_err();
+    assert_eq!("a program must start with a 'being' instruction", err.message());
+    assert_eq!("push.3", err.operation());
+
+    // empty repeat body
+    let err = super::ir_to_program(&[begin.clone(), IrOp::Repeat(2, vec![])]).unwrap_err();
+    assert_eq!("a program block must contain at least one instruction", err.message());
+    assert_eq!("repeat", err.operation());
+
+    // repeat block with too few iterations
+    let err = super::ir_to_program(&[begin.clone(), IrOp::Repeat(0, vec![instruction(&["add"], 1)])]).unwrap_err();
+    assert_eq!("repeat.0", err.operation());
+
+    // loop body which does not start with the ASSERT implied by the block head
+    let err = super::ir_to_program(&[begin.clone(), IrOp::Loop(vec![instruction(&["add"], 1)])]).unwrap_err();
+    assert_eq!("invalid block head 'while.true'", err.message());
+    assert_eq!(1, err.step());
+}
+
+// PROCEDURES AND INCLUDES
+// ================================================================================================
+#[test]
+fn proc() {
+    let source = ".proc inc push.1 add end .proc inc2 exec.inc exec.inc end begin exec.inc2 exec.inc end";
+    let program = super::compile(source).unwrap();
+
+    // procedures are inlined at the places they are invoked from
+    let expected = super::compile("begin push.1 add push.1 add push.1 add end").unwrap();
+    assert_eq!(expected.hash(), program.hash());
+
+    let inputs = crate::ProgramInputs::from_public(&[3]);
+    assert_eq!(vec![6], crate::simulate(&program, &inputs, 1));
+}
+
+#[test]
+fn proc_errors() {
+    // duplicate procedure
+    let source = ".proc inc push.1 add end .proc inc push.2 add end begin exec.inc end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed directive .proc: procedure 'inc' is already defined", err.message());
+
+    // procedures cannot shadow standard library gadgets
+    let source = ".proc max push.1 add end begin exec.max end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed directive .proc: procedure 'max' is already defined", err.message());
+
+    // a procedure can invoke only procedures defined before it
+    let source = ".proc inc2 exec.inc exec.inc end .proc inc push.1 add end begin exec.inc2 end";
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed instruction exec: gadget 'inc' does not exist", err.message());
+
+    // empty and unterminated procedures
+    let err = super::compile(".proc inc end begin push.1 end").unwrap_err();
+    assert_eq!("a program block must contain at least one instruction", err.message());
+    let err = super::compile(".proc inc push.1 add").unwrap_err();
+    assert_eq!("procedure without matching end", err.message());
+
+    // every procedure doubles the size of the previous one; the limit applies to all inlined
+    // code, and so it is exceeded while defining p19 rather than while inlining p20
+    let mut source = String::from(".proc p0 push.1 drop end");
+    for i in 1..=20 {
+        source.push_str(&format!(" .proc p{} exec.p{} exec.p{} end", i, i - 1, i - 1));
+    }
+    source.push_str(" begin exec.p20 end");
+    let err = super::compile(&source).unwrap_err();
+    assert_eq!(format!("malformed instruction exec: procedure 'p18' cannot be inlined; a program can inline at most {} operations",
+        super::MAX_INLINED_OPS), *err.message());
+}
+
+#[test]
+fn include() {
+    let mut files = std::collections::HashMap::new();
+    files.insert("math", ".include \"base\" .proc inc2 exec.inc exec.inc end");
+    files.insert("base", ".proc inc push.1 add end");
+    let resolver = |name: &str| files.get(name).map(|&source| String::from(source));
+
+    // files included more than once are spliced in only the first time
+    let source = ".include \"math\" .include \"base\" begin exec.inc2 exec.inc end";
+    let program = super::compile_with_resolver(source, &resolver).unwrap();
+    let expected = super::compile("begin push.1 add push.1 add push.1 add end").unwrap();
+    assert_eq!(expected.hash(), program.hash());
+
+    // included files cannot be resolved without a resolver
+    let err = super::compile(source).unwrap_err();
+    assert_eq!("malformed directive .include: included files can be resolved only by compile_with_resolver()", err.message());
+
+    // missing files and malformed names
+    let err = super::compile_with_resolver(".include \"other\" begin push.1 end", &resolver).unwrap_err();
+    assert_eq!("malformed directive .include: included file 'other' could not be resolved", err.message());
+    let err = super::compile_with_resolver(".include math begin push.1 end", &resolver).unwrap_err();
+    assert_eq!("malformed directive .include: name of the included file must be enclosed in \"\"", err.message());
+
+    // files cannot be included into the program body
+    let err = super::compile_with_resolver("begin push.1 .include \"base\" exec.inc end", &resolver).unwrap_err();
+    assert_eq!("malformed directive .include: files can be included only before the program body", err.message());
+}
+
+#[test]
+fn include_cycle() {
+    let mut files = std::collections::HashMap::new();
+    files.insert("a", ".include \"b\" .proc inc push.1 add end");
+    files.insert("b", ".include \"c\"");
+    files.insert("c", ".include \"a\"");
+    let resolver = |name: &str| files.get(name).map(|&source| String::from(source));
+
+    let err = super::compile_with_resolver(".include \"a\" begin exec.inc end", &resolver).unwrap_err();
+    assert_eq!("malformed directive .include: cyclic include of 'a': a -> b -> c -> a", err.message());
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use crate::math::field;
 use super::{ OpCode, OpHint, hash_seq, hash_op, BASE_CYCLE_LENGTH };
 
 #[cfg(test)]
@@ -38,6 +39,7 @@ pub enum ProgramBlock {
 pub struct Span {
     op_codes    : Vec<OpCode>,
     op_hints    : HashMap<usize, OpHint>,
+    op_sources  : Vec<Option<usize>>,
 }
 
 #[derive(Clone)]
@@ -87,7 +89,10 @@ impl std::fmt::Debug for ProgramBlock {
 // ================================================================================================
 impl Span {
 
-    pub fn new(instructions: Vec<OpCode>, hints: HashMap<usize, OpHint>) -> Span {
+    /// Returns a span of the specified instructions; PUSH values which are not valid field
+    /// elements are reduced by the field modulus, so that the program hash and execution of
+    /// the program use the same values.
+    pub fn new(instructions: Vec<OpCode>, mut hints: HashMap<usize, OpHint>) -> Span {
         let alignment = instructions.len() % BASE_CYCLE_LENGTH;
         assert!(alignment == BASE_CYCLE_LENGTH - 1,
             "invalid number of instructions: expected one less than a multiple of {}, but was {}",
@@ -98,10 +103,10 @@ impl Span {
             let op_code = instructions[i];
             if op_code == OpCode::Push {
                 assert!(i % 8 == 0, "PUSH is not allowed on step {}, must be on step which is a multiple of 8", i);
-                let hint = hints.get(&i);
+                let hint = hints.get_mut(&i);
                 assert!(hint.is_some(), "invalid PUSH operation on step {}: operation value is missing", i);
                 match hint.unwrap() {
-                    OpHint::PushValue(_) => (),
+                    OpHint::PushValue(value) => *value = field::reduce(*value),
                     _ => panic!("invalid PUSH operation on step {}: operation value is of wrong type", i)
                 }
             }
@@ -113,12 +118,25 @@ impl Span {
                 instructions.len(), step);
         }
 
+        let op_sources = vec![None; instructions.len()];
         return Span {
             op_codes: instructions,
-            op_hints: hints
+            op_hints: hints,
+            op_sources,
         };
     }
 
+    /// Same as new(), but also records the index of the source instruction from which each
+    /// instruction was produced; `None` indicates instructions which were inserted implicitly
+    /// (e.g. for alignment). Source instructions do not affect the hash of the span.
+    pub fn with_sources(instructions: Vec<OpCode>, hints: HashMap<usize, OpHint>, sources: Vec<Option<usize>>) -> Span {
+        assert!(sources.len() == instructions.len(),
+            "expected {} instruction sources, but received {}", instructions.len(), sources.len());
+        let mut span = Span::new(instructions, hints);
+        span.op_sources = sources;
+        return span;
+    }
+
     pub fn new_block(instructions: Vec<OpCode>) -> ProgramBlock {
         return ProgramBlock::Span(Span::new(instructions, HashMap::new()));
     }
@@ -139,6 +157,12 @@ impl Span {
         return (self.op_codes[step], self.get_hint(step));
     }
 
+    /// Returns the index of the source instruction from which the operation at the specified
+    /// index was produced, if this information is available.
+    pub fn get_source(&self, op_index: usize) -> Option<usize> {
+        return self.op_sources[op_index];
+    }
+
     pub fn get_hint(&self, op_index: usize) -> OpHint {
         return match self.op_hints.get(&op_index) {
             Some(&hint) => hint,
@@ -173,8 +197,13 @@ impl Span {
             new_hints.insert(step + offset, hint);
         }
 
+        // merge sources
+        let mut new_sources = span1.op_sources.clone();
+        new_sources.push(None);
+        new_sources.extend_from_slice(&span2.op_sources);
+
         // build and return a new Span
-        return Span::new(new_op_codes, new_hints);
+        return Span::with_sources(new_op_codes, new_hints, new_sources);
     }
 }
 
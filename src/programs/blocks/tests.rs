@@ -47,4 +47,24 @@ fn span_hash() {
         238085520613464573032580920836572617149,  98362585914038709664139524327351111560,
         159064915881679512167348007665307977960, 152057468867502483682425300737565245134,
     ], hash);
-}
\ No newline at end of file
+}
+
+#[test]
+fn span_push_value_reduced() {
+    let ops = vec![
+        OpCode::Push, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop
+    ];
+
+    // PUSH values which are not valid field elements are reduced by the field modulus
+    let mut hints = HashMap::new();
+    hints.insert(0, OpHint::PushValue(crate::math::field::MODULUS + 5));
+    let block = Span::new(ops.clone(), hints);
+    assert_eq!(5, block.get_hint(0).value());
+
+    let mut hints = HashMap::new();
+    hints.insert(0, OpHint::PushValue(5));
+    assert_eq!(Span::new(ops, hints).hash([0, 0, 0, 0]), block.hash([0, 0, 0, 0]));
+}
@@ -1,4 +1,15 @@
-use crate::{ MAX_PUBLIC_INPUTS };
+use std::convert::{ TryFrom, TryInto };
+use crate::{ math::field, utils::hasher, MAX_PUBLIC_INPUTS, HASH_DIGEST_SIZE };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Number of bytes packed into a single field element by `pack_bytes()`; any 15-byte value is
+/// smaller than the field modulus, and thus, can be represented by a field element.
+pub const BYTES_PER_ELEMENT: usize = 15;
+
+// TYPES AND INTERFACES
+// ================================================================================================
 
 #[derive(Clone, Debug)]
 pub struct ProgramInputs {
@@ -6,21 +17,37 @@ pub struct ProgramInputs {
     secret: [Vec<u128>; 2],
 }
 
+/// A value which is guaranteed to be a valid field element. Values can be created from `u32`
+/// and `u64` integers directly, and from `u128` integers and 16-byte arrays via `TryFrom`;
+/// byte arrays are interpreted as little-endian encodings of a `u128` integer. Conversions
+/// from `u128` values and byte arrays fail if the value is greater than or equal to MODULUS.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InputValue(u128);
+
+/// Errors which can occur when program inputs are constructed. Public inputs are the initial
+/// state of the stack, and thus, they must fit into the stack registers which are initialized
+/// from public inputs (MAX_PUBLIC_INPUTS).
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputError {
+    InitialStackTooLarge { len: usize, max: usize },
+}
+
+// PROGRAM INPUTS IMPLEMENTATION
+// ================================================================================================
+
 impl ProgramInputs {
 
-    /// Returns `ProgramInputs` initialized with the provided public and secret inputs.
+    /// Returns `ProgramInputs` initialized with the provided public and secret inputs; values
+    /// which are not valid field elements are reduced by the field modulus.
     pub fn new(public: &[u128], secret_a: &[u128], secret_b: &[u128]) -> ProgramInputs {
 
-        assert!(public.len() <= MAX_PUBLIC_INPUTS,
-            "expected no more than {} public inputs, but received {}",
-            MAX_PUBLIC_INPUTS,
-            public.len());
+        if let Err(err) = validate_initial_stack(public) { panic!("{}", err); }
         assert!(secret_a.len() >= secret_b.len(), 
             "number of primary secret inputs cannot be smaller than the number of secondary secret inputs");
 
         return ProgramInputs {
-            public  : public.to_vec(),
-            secret  : [secret_a.to_vec(), secret_b.to_vec()]
+            public  : reduce_values(public),
+            secret  : [reduce_values(secret_a), reduce_values(secret_b)]
         };
     }
 
@@ -33,14 +60,52 @@ impl ProgramInputs {
     }
 
     /// Returns `ProgramInputs` initialized with the provided public inputs and secret
-    /// input tapes set to empty vectors.
+    /// input tapes set to empty vectors; values which are not valid field elements are reduced
+    /// by the field modulus.
     pub fn from_public(public: &[u128]) -> ProgramInputs {
+        if let Err(err) = validate_initial_stack(public) { panic!("{}", err); }
         return ProgramInputs {
-            public: public.to_vec(),
+            public: reduce_values(public),
             secret: [vec![], vec![]]
         };
     }
 
+    /// Returns `ProgramInputs` initialized with the provided initial stack and secret input
+    /// tapes set to empty vectors; an error is returned if the initial stack does not fit into
+    /// the stack registers which are initialized from public inputs.
+    pub fn try_from_stack(stack: &[u128]) -> Result<ProgramInputs, InputError> {
+        validate_initial_stack(stack)?;
+        return Ok(ProgramInputs::from_public(stack));
+    }
+
+    /// Returns `ProgramInputs` initialized with the provided public inputs and secret input
+    /// tapes set to empty vectors; public inputs are converted into field elements, and an
+    /// error is returned if any of the values cannot be represented by a field element.
+    pub fn try_from_public<T>(public: &[T]) -> Result<ProgramInputs, String>
+        where T: Copy + TryInto<InputValue>, T::Error: ToString
+    {
+        let public = convert_values(public)?;
+        validate_initial_stack(&public).map_err(|err| err.to_string())?;
+        return Ok(ProgramInputs::from_public(&public));
+    }
+
+    /// Returns `ProgramInputs` initialized with the provided public and secret inputs; all
+    /// inputs are converted into field elements, and an error is returned if any of the values
+    /// cannot be represented by a field element.
+    pub fn try_new<T>(public: &[T], secret_a: &[T], secret_b: &[T]) -> Result<ProgramInputs, String>
+        where T: Copy + TryInto<InputValue>, T::Error: ToString
+    {
+        let public = convert_values(public)?;
+        let secret_a = convert_values(secret_a)?;
+        let secret_b = convert_values(secret_b)?;
+
+        validate_initial_stack(&public).map_err(|err| err.to_string())?;
+        if secret_a.len() < secret_b.len() {
+            return Err(String::from("number of primary secret inputs cannot be smaller than the number of secondary secret inputs"));
+        }
+        return Ok(ProgramInputs::new(&public, &secret_a, &secret_b));
+    }
+
     pub fn get_public_inputs(&self) -> &[u128] {
         return &self.public;
     }
@@ -48,4 +113,275 @@ impl ProgramInputs {
     pub fn get_secret_inputs(&self) -> &[Vec<u128>; 2] {
         return &self.secret;
     }
-}
\ No newline at end of file
+
+    /// Returns a commitment to public inputs computed with the Rescue hasher used by the VM;
+    /// this is the commitment included into proofs generated with input commitment enabled.
+    /// Secret inputs are not included, and since the number of inputs is hashed together with
+    /// the inputs, empty public inputs commit to the hash of an empty sequence.
+    pub fn commitment(&self) -> [u128; HASH_DIGEST_SIZE] {
+        return commit_to_inputs(&self.public);
+    }
+}
+
+/// Returns the Rescue hash of public `inputs`; see ProgramInputs::commitment().
+pub fn commit_to_inputs(inputs: &[u128]) -> [u128; HASH_DIGEST_SIZE] {
+    let mut result = [field::ZERO; HASH_DIGEST_SIZE];
+    result.copy_from_slice(&hasher::hash(inputs));
+    return result;
+}
+
+// INPUT ERROR IMPLEMENTATION
+// ================================================================================================
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            InputError::InitialStackTooLarge { len, max } => write!(f,
+                "expected no more than {} public inputs, but received {}", max, len),
+        };
+    }
+}
+
+// INPUT VALUE IMPLEMENTATION
+// ================================================================================================
+impl InputValue {
+
+    pub fn as_u128(&self) -> u128 {
+        return self.0;
+    }
+}
+
+impl From<u32> for InputValue {
+    fn from(value: u32) -> InputValue {
+        return InputValue(value as u128);
+    }
+}
+
+impl From<u64> for InputValue {
+    fn from(value: u64) -> InputValue {
+        return InputValue(value as u128);
+    }
+}
+
+impl TryFrom<u128> for InputValue {
+    type Error = String;
+
+    fn try_from(value: u128) -> Result<InputValue, String> {
+        if value >= field::MODULUS {
+            return Err(format!("value {} is not a valid field element", value));
+        }
+        return Ok(InputValue(value));
+    }
+}
+
+impl TryFrom<[u8; 16]> for InputValue {
+    type Error = String;
+
+    fn try_from(bytes: [u8; 16]) -> Result<InputValue, String> {
+        return Ok(InputValue(field::from_bytes_le(&bytes)?));
+    }
+}
+
+impl From<InputValue> for u128 {
+    fn from(value: InputValue) -> u128 {
+        return value.0;
+    }
+}
+
+// BYTE PACKING
+// ================================================================================================
+
+/// Packs `bytes` into a list of field elements: the first element is the number of bytes, and
+/// every subsequent element contains up to BYTES_PER_ELEMENT bytes interpreted as a
+/// little-endian integer (the last element is padded with zeros). Packed values can be unpacked
+/// inside the VM with `bytes.n` instruction.
+pub fn pack_bytes(bytes: &[u8]) -> Vec<u128> {
+    let mut result = Vec::with_capacity(1 + (bytes.len() + BYTES_PER_ELEMENT - 1) / BYTES_PER_ELEMENT);
+    result.push(bytes.len() as u128);
+    for chunk in bytes.chunks(BYTES_PER_ELEMENT) {
+        let value = chunk.iter().rev().fold(0u128, |acc, &byte| (acc << 8) | byte as u128);
+        result.push(value);
+    }
+    return result;
+}
+
+/// Recovers bytes from field elements produced by `pack_bytes()`; an error is returned if the
+/// elements are not a valid packing of a byte string (e.g. if the length prefix does not match
+/// the number of elements, or if padding bytes are not zeros).
+pub fn unpack_bytes(values: &[u128]) -> Result<Vec<u8>, String> {
+    let length = match values.first() {
+        Some(&length) => length,
+        None => return Err(String::from("packed bytes must start with a length prefix")),
+    };
+
+    // the number of elements is computed without rounding the length up, so that large length
+    // prefixes cannot overflow
+    let num_elements = length / BYTES_PER_ELEMENT as u128 + (length % BYTES_PER_ELEMENT as u128 != 0) as u128;
+    if (values.len() - 1) as u128 != num_elements {
+        return Err(format!("expected {} elements for {} packed bytes, but received {}",
+            num_elements, length, values.len() - 1));
+    }
+
+    let length = length as usize;
+    let capacity = match (values.len() - 1).checked_mul(BYTES_PER_ELEMENT) {
+        Some(capacity) => capacity,
+        None => return Err(format!("{} packed bytes cannot be unpacked", length)),
+    };
+
+    let mut result = Vec::with_capacity(capacity);
+    for &value in values[1..].iter() {
+        if value >> (8 * BYTES_PER_ELEMENT) != 0 {
+            return Err(format!("value {} cannot be unpacked into {} bytes", value, BYTES_PER_ELEMENT));
+        }
+        result.extend_from_slice(&value.to_le_bytes()[..BYTES_PER_ELEMENT]);
+    }
+
+    if result[length..].iter().any(|&byte| byte != 0) {
+        return Err(String::from("padding of packed bytes must consist of zeros"));
+    }
+    result.truncate(length);
+    return Ok(result);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn validate_initial_stack(stack: &[u128]) -> Result<(), InputError> {
+    if stack.len() > MAX_PUBLIC_INPUTS {
+        return Err(InputError::InitialStackTooLarge { len: stack.len(), max: MAX_PUBLIC_INPUTS });
+    }
+    return Ok(());
+}
+
+fn reduce_values(values: &[u128]) -> Vec<u128> {
+    return values.iter().map(|&value| field::reduce(value)).collect();
+}
+
+fn convert_values<T>(values: &[T]) -> Result<Vec<u128>, String>
+    where T: Copy + TryInto<InputValue>, T::Error: ToString
+{
+    let mut result = Vec::with_capacity(values.len());
+    for &value in values.iter() {
+        match value.try_into() {
+            Ok(value) => result.push(value.as_u128()),
+            Err(err) => return Err(err.to_string())
+        }
+    }
+    return Ok(result);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+    use crate::math::field;
+    use crate::utils::hasher;
+    use super::{ ProgramInputs, InputValue, InputError, pack_bytes, unpack_bytes };
+
+    #[test]
+    fn commitment() {
+        let inputs = ProgramInputs::new(&[1, 2, 3], &[4], &[]);
+        assert_eq!(hasher::hash(&[1, 2, 3]), inputs.commitment().to_vec());
+
+        // secret inputs do not affect the commitment
+        assert_eq!(ProgramInputs::from_public(&[1, 2, 3]).commitment(), inputs.commitment());
+
+        // empty inputs commit to the hash of an empty sequence, which differs from the
+        // commitment to a single zero
+        assert_eq!(hasher::hash(&[]), ProgramInputs::none().commitment().to_vec());
+        assert!(ProgramInputs::none().commitment() != ProgramInputs::from_public(&[0]).commitment());
+    }
+
+    #[test]
+    fn from_u32() {
+        let inputs = ProgramInputs::try_from_public(&[1u32, 2, u32::MAX]).unwrap();
+        assert_eq!(&[1, 2, u32::MAX as u128], inputs.get_public_inputs());
+    }
+
+    #[test]
+    fn from_u64() {
+        let inputs = ProgramInputs::try_new(&[1u64], &[2, u64::MAX], &[3]).unwrap();
+        assert_eq!(&[1], inputs.get_public_inputs());
+        assert_eq!(&[vec![2, u64::MAX as u128], vec![3]], inputs.get_secret_inputs());
+    }
+
+    #[test]
+    fn from_u128() {
+        let inputs = ProgramInputs::try_from_public(&[0u128, field::MODULUS - 1]).unwrap();
+        assert_eq!(&[0, field::MODULUS - 1], inputs.get_public_inputs());
+
+        let result = ProgramInputs::try_from_public(&[1u128, field::MODULUS]);
+        let err_msg = format!("value {} is not a valid field element", field::MODULUS);
+        assert_eq!(err_msg, result.unwrap_err());
+
+        assert!(InputValue::try_from(u128::MAX).is_err());
+
+        // infallible constructors reduce values instead
+        let inputs = ProgramInputs::new(&[field::MODULUS + 5], &[field::MODULUS, 7], &[]);
+        assert_eq!(&[5], inputs.get_public_inputs());
+        assert_eq!(&[vec![0, 7], vec![]], inputs.get_secret_inputs());
+        assert_eq!(&[1], ProgramInputs::from_public(&[field::MODULUS + 1]).get_public_inputs());
+    }
+
+    #[test]
+    fn from_bytes() {
+        let mut bytes = [0u8; 16];
+        bytes[0] = 1;
+        bytes[1] = 2;
+        let inputs = ProgramInputs::try_from_public(&[bytes]).unwrap();
+        assert_eq!(&[0x0201], inputs.get_public_inputs());
+
+        let bytes = field::MODULUS.to_le_bytes();
+        let result = ProgramInputs::try_from_public(&[bytes]);
+        let err_msg = format!("value {} is not a valid field element", field::MODULUS);
+        assert_eq!(err_msg, result.unwrap_err());
+    }
+
+    #[test]
+    fn too_many_inputs() {
+        let result = ProgramInputs::try_from_public(&[0u32; 9]);
+        assert_eq!("expected no more than 8 public inputs, but received 9", result.unwrap_err());
+    }
+
+    #[test]
+    fn initial_stack_too_large() {
+        let inputs = ProgramInputs::try_from_stack(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], inputs.get_public_inputs());
+
+        let result = ProgramInputs::try_from_stack(&[0; 9]);
+        assert_eq!(InputError::InitialStackTooLarge { len: 9, max: 8 }, result.unwrap_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected no more than 8 public inputs, but received 9")]
+    fn from_public_initial_stack_too_large() {
+        ProgramInputs::from_public(&[0; 9]);
+    }
+
+    #[test]
+    fn pack_unpack_bytes() {
+        let bytes: Vec<u8> = (1..=20).collect();
+        let packed = pack_bytes(&bytes);
+        assert_eq!(3, packed.len());
+        assert_eq!(20, packed[0]);
+        assert_eq!(u128::from_le_bytes([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0]), packed[1]);
+        assert_eq!(0x1413121110, packed[2]);
+        assert_eq!(Ok(bytes), unpack_bytes(&packed));
+
+        // trailing zero bytes are preserved by the length prefix
+        assert_eq!(vec![3, 0], pack_bytes(&[0, 0, 0]));
+        assert_eq!(Ok(vec![0, 0, 0]), unpack_bytes(&[3, 0]));
+        assert_eq!(vec![0], pack_bytes(&[]));
+        assert_eq!(Ok(vec![]), unpack_bytes(&[0]));
+
+        // invalid packings
+        assert!(unpack_bytes(&[]).is_err());
+        assert!(unpack_bytes(&[16, 1]).is_err());
+        assert!(unpack_bytes(&[1, 0x0101]).is_err());
+        assert!(unpack_bytes(&[1, 1u128 << 120]).is_err());
+
+        // length prefixes which do not fit into the number of elements do not overflow
+        assert!(unpack_bytes(&[u128::MAX, 0]).is_err());
+        assert!(unpack_bytes(&[usize::MAX as u128, 0]).is_err());
+    }
+}
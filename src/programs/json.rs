@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use serde::{ Serialize, Deserialize };
+use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, OpCode, OpHint, field, BASE_CYCLE_LENGTH };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// JSON representation of a program block. Spans list all of their operations (including
+/// NOOPs inserted for alignment) so that the exact program can be rebuilt from the listing;
+/// other blocks list blocks of their bodies.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonBlock {
+    Span    { ops: Vec<JsonOp> },
+    Group   { body: Vec<JsonBlock> },
+    Switch  { true_branch: Vec<JsonBlock>, false_branch: Vec<JsonBlock> },
+    Loop    { body: Vec<JsonBlock> },
+}
+
+/// JSON representation of a single operation: `op` is the mnemonic of the operation, `value`
+/// is the immediate value of a PUSH operation, and `hint` is a hint for the processor (e.g.
+/// "rc.32" or "eq"). Values are encoded as decimal strings because field elements do not fit
+/// into numbers supported by most JSON parsers.
+#[derive(Serialize, Deserialize)]
+struct JsonOp {
+    op      : String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value   : Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hint    : Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonProgram {
+    hash    : String,
+    blocks  : Vec<JsonBlock>,
+}
+
+// PROGRAM IMPLEMENTATION
+// ================================================================================================
+impl Program {
+
+    /// Returns a JSON representation of the program which lists block structure of the program
+    /// together with operations of every block. Unlike the assembly source, the listing
+    /// describes the program exactly: converting it back via `from_json()` produces a program
+    /// with the same hash.
+    pub fn to_json(&self) -> String {
+        let program = JsonProgram {
+            hash    : hex::encode(self.hash()),
+            blocks  : self.root().body().iter().map(block_to_json).collect(),
+        };
+        return serde_json::to_string_pretty(&program).unwrap();
+    }
+
+    /// Builds a program from the JSON representation produced by `to_json()`; returns an error
+    /// if the JSON is malformed, does not describe a valid program, or if the hash of the
+    /// built program does not match the hash in the JSON.
+    pub fn from_json(json: &str) -> Result<Program, String> {
+        let program: JsonProgram = match serde_json::from_str(json) {
+            Ok(program) => program,
+            Err(err) => return Err(format!("malformed program JSON: {}", err)),
+        };
+
+        let mut body = Vec::with_capacity(program.blocks.len());
+        for block in program.blocks.iter() {
+            body.push(block_from_json(block)?);
+        }
+
+        // block constructors validate program structure by panicking; thus, the structure is
+        // validated here before the program is built
+        validate_block_list(&body, &[OpCode::Begin])?;
+        let result = Program::new(Group::new(body));
+
+        if hex::encode(result.hash()) != program.hash {
+            return Err(format!("expected program hash {} does not match program hash {}",
+                program.hash, hex::encode(result.hash())));
+        }
+        return Ok(result);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn block_to_json(block: &ProgramBlock) -> JsonBlock {
+    return match block {
+        ProgramBlock::Span(block) => {
+            let ops = (0..block.length()).map(|i| {
+                let (op_code, op_hint) = block.get_op(i);
+                let (value, hint) = match op_hint {
+                    OpHint::PushValue(value)    => (Some(value.to_string()), None),
+                    OpHint::None                => (None, None),
+                    _                           => (None, Some(hint_to_string(op_hint))),
+                };
+                JsonOp { op: String::from(op_code.mnemonic()), value, hint }
+            }).collect();
+            JsonBlock::Span { ops }
+        },
+        ProgramBlock::Group(block) => JsonBlock::Group {
+            body: block.body().iter().map(block_to_json).collect()
+        },
+        ProgramBlock::Switch(block) => JsonBlock::Switch {
+            true_branch : block.true_branch().iter().map(block_to_json).collect(),
+            false_branch: block.false_branch().iter().map(block_to_json).collect(),
+        },
+        ProgramBlock::Loop(block) => JsonBlock::Loop {
+            body: block.body().iter().map(block_to_json).collect()
+        },
+    };
+}
+
+fn block_from_json(block: &JsonBlock) -> Result<ProgramBlock, String> {
+    let result = match block {
+        JsonBlock::Span { ops } => {
+            let mut op_codes = Vec::with_capacity(ops.len());
+            let mut op_hints = HashMap::new();
+            for (i, op) in ops.iter().enumerate() {
+                op_codes.push(parse_op_code(&op.op)?);
+                if let Some(value) = &op.value {
+                    match value.parse::<u128>() {
+                        Ok(value) if value < field::MODULUS => { op_hints.insert(i, OpHint::PushValue(value)); },
+                        _ => return Err(format!("invalid value '{}' of operation {}", value, i)),
+                    }
+                }
+                if let Some(hint) = &op.hint {
+                    op_hints.insert(i, parse_hint(hint)?);
+                }
+            }
+            validate_span(&op_codes, &op_hints)?;
+            ProgramBlock::Span(Span::new(op_codes, op_hints))
+        },
+        JsonBlock::Group { body } => {
+            let body = blocks_from_json(body)?;
+            validate_block_list(&body, &[])?;
+            Group::new_block(body)
+        },
+        JsonBlock::Switch { true_branch, false_branch } => {
+            let true_branch = blocks_from_json(true_branch)?;
+            let false_branch = blocks_from_json(false_branch)?;
+            validate_block_list(&true_branch, &[OpCode::Assert])?;
+            validate_block_list(&false_branch, &[OpCode::Not, OpCode::Assert])?;
+            Switch::new_block(true_branch, false_branch)
+        },
+        JsonBlock::Loop { body } => {
+            let body = blocks_from_json(body)?;
+            validate_block_list(&body, &[OpCode::Assert])?;
+            Loop::new_block(body)
+        },
+    };
+
+    return Ok(result);
+}
+
+fn blocks_from_json(blocks: &[JsonBlock]) -> Result<Vec<ProgramBlock>, String> {
+    return blocks.iter().map(block_from_json).collect();
+}
+
+fn parse_op_code(mnemonic: &str) -> Result<OpCode, String> {
+    return match OpCode::ALL.iter().find(|op| op.mnemonic() == mnemonic) {
+        Some(&op_code) => Ok(op_code),
+        None => Err(format!("operation '{}' is not valid", mnemonic)),
+    };
+}
+
+fn hint_to_string(hint: OpHint) -> String {
+    return match hint {
+        OpHint::EqStart             => String::from("eq"),
+        OpHint::IsZeroStart         => String::from("iszero"),
+        OpHint::RcStart(value)      => format!("rc.{}", value),
+        OpHint::CmpStart(value)     => format!("cmp.{}", value),
+        OpHint::BitsStart(value)    => format!("bits.{}", value),
+        OpHint::BytesStart(value)   => format!("bytes.{}", value),
+        OpHint::PmpathStart(value)  => format!("pmpath.{}", value),
+        OpHint::PushValue(value)    => value.to_string(),
+        OpHint::None                => String::new(),
+    };
+}
+
+fn parse_hint(hint: &str) -> Result<OpHint, String> {
+    let parts: Vec<&str> = hint.split('.').collect();
+    let value = if parts.len() == 2 {
+        match parts[1].parse::<u32>() {
+            Ok(value) => Some(value),
+            Err(_) => return Err(format!("hint '{}' is not valid", hint)),
+        }
+    } else {
+        None
+    };
+
+    return match (parts[0], parts.len(), value) {
+        ("eq", 1, _)                => Ok(OpHint::EqStart),
+        ("iszero", 1, _)            => Ok(OpHint::IsZeroStart),
+        ("rc", 2, Some(value))      => Ok(OpHint::RcStart(value)),
+        ("cmp", 2, Some(value))     => Ok(OpHint::CmpStart(value)),
+        ("bits", 2, Some(value))    => Ok(OpHint::BitsStart(value)),
+        ("bytes", 2, Some(value))   => Ok(OpHint::BytesStart(value)),
+        ("pmpath", 2, Some(value))  => Ok(OpHint::PmpathStart(value)),
+        _ => Err(format!("hint '{}' is not valid", hint)),
+    };
+}
+
+/// Makes sure that operations of a span satisfy the same rules as the ones enforced by
+/// `Span::new()`.
+fn validate_span(op_codes: &[OpCode], op_hints: &HashMap<usize, OpHint>) -> Result<(), String> {
+    if op_codes.len() % BASE_CYCLE_LENGTH != BASE_CYCLE_LENGTH - 1 {
+        return Err(format!("invalid program structure: a span must contain one less than a multiple of {} operations, but contained {}",
+            BASE_CYCLE_LENGTH, op_codes.len()));
+    }
+
+    for (i, &op_code) in op_codes.iter().enumerate() {
+        if op_code != OpCode::Push { continue; }
+        if i % 8 != 0 {
+            return Err(format!("invalid program structure: PUSH is not allowed on step {}, must be on step which is a multiple of 8", i));
+        }
+        match op_hints.get(&i) {
+            Some(OpHint::PushValue(_)) => (),
+            _ => return Err(format!("invalid program structure: value of PUSH operation on step {} is missing", i)),
+        }
+    }
+
+    return Ok(());
+}
+
+/// Makes sure that a sequence of blocks satisfies the same rules as the ones enforced by block
+/// constructors: the sequence must start with a span which starts with `starts_with` operations,
+/// and a span cannot follow another span.
+fn validate_block_list(blocks: &[ProgramBlock], starts_with: &[OpCode]) -> Result<(), String> {
+    match blocks.first() {
+        Some(ProgramBlock::Span(block)) if block.starts_with(starts_with) => (),
+        Some(ProgramBlock::Span(_)) => {
+            let ops: Vec<&str> = starts_with.iter().map(|op| op.mnemonic()).collect();
+            return Err(format!("invalid program structure: the first block must start with '{}'", ops.join(" ")));
+        },
+        Some(_) => return Err(String::from("invalid program structure: a sequence of blocks must start with a Span block")),
+        None => return Err(String::from("invalid program structure: a sequence of blocks must contain at least one block")),
+    }
+
+    if let Some(ProgramBlock::Span(_)) = blocks.get(1) {
+        return Err(String::from("invalid program structure: a Span block cannot be followed by another Span block"));
+    }
+
+    return Ok(());
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use crate::{
     math::field,
     processor::{ OpCode, OpHint },
@@ -11,11 +12,17 @@ pub mod blocks;
 use blocks::{ ProgramBlock, Span, Group, Switch, Loop };
 
 mod inputs;
-pub use inputs::{ ProgramInputs };
+pub use inputs::{ ProgramInputs, InputValue, InputError, commit_to_inputs, pack_bytes, unpack_bytes, BYTES_PER_ELEMENT };
+
+mod outputs;
+pub use outputs::{ ProgramOutputs };
 
 mod hashing;
 use hashing::{ hash_op, hash_acc, hash_seq };
 
+#[cfg(feature = "json")]
+mod json;
+
 #[cfg(test)]
 mod tests;
 
@@ -51,6 +58,49 @@ impl Program {
 
         return Program { root, hash: hash_bytes };
     }
+
+    /// Returns a program which executes this program, and then executes `other` starting from
+    /// the state left by this program: `other` sees the final stack of this program as its
+    /// initial stack, and reads secret inputs which were not consumed by this program. The
+    /// hash of the returned program differs from hashes of both of the composed programs.
+    ///
+    /// Compatibility of the programs is not checked: if `other` expects more items on the
+    /// stack than this program leaves, execution of the composed program fails with a stack
+    /// underflow.
+    pub fn then(&self, other: &Program) -> Program {
+        let mut body = self.root.body().to_vec();
+
+        // BEGIN operation of the other program is replaced with NOOP, and the first span of the
+        // other program is merged into the last span of this program, if possible
+        let head = match &other.root.body()[0] {
+            ProgramBlock::Span(block) => {
+                let mut op_codes = Vec::with_capacity(block.length());
+                let mut op_hints = HashMap::new();
+                let mut op_sources = Vec::with_capacity(block.length());
+                for i in 0..block.length() {
+                    let (op_code, op_hint) = block.get_op(i);
+                    op_codes.push(if i == 0 { OpCode::Noop } else { op_code });
+                    match op_hint {
+                        OpHint::None => (),
+                        _ => { op_hints.insert(i, op_hint); },
+                    }
+                    op_sources.push(block.get_source(i));
+                }
+                Span::with_sources(op_codes, op_hints, op_sources)
+            },
+            _ => panic!("a program must start with a Span block"),
+        };
+
+        let last_idx = body.len() - 1;
+        match &body[last_idx] {
+            ProgramBlock::Span(block) => body[last_idx] = ProgramBlock::Span(Span::merge(block, &head)),
+            _ => body.push(ProgramBlock::Span(head)),
+        }
+        body.extend_from_slice(&other.root.body()[1..]);
+
+        return Program::new(Group::new(body));
+    }
+
     /// Returns the root block of the program.
     pub fn root(&self) -> &Group {
         return &self.root;
@@ -0,0 +1,170 @@
+use std::fmt;
+use crate::{ math::field, utils::hasher, HASH_DIGEST_SIZE };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Results of a program execution: `stack` contains the requested number of items from the top
+/// of the stack at the end of the execution, and `tape` contains all values written to the
+/// output tape via WRITE operations (in the order in which they were written).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramOutputs {
+    stack   : Vec<u128>,
+    tape    : Vec<u128>,
+}
+
+// PROGRAM OUTPUTS IMPLEMENTATION
+// ================================================================================================
+impl ProgramOutputs {
+
+    /// Returns `ProgramOutputs` consisting of the provided stack items and output tape values.
+    pub fn new(stack: &[u128], tape: &[u128]) -> ProgramOutputs {
+        return ProgramOutputs { stack: stack.to_vec(), tape: tape.to_vec() };
+    }
+
+    /// Returns items from the top of the stack at the end of the execution.
+    pub fn stack(&self) -> &[u128] {
+        return &self.stack;
+    }
+
+    /// Returns values written to the output tape.
+    pub fn tape(&self) -> &[u128] {
+        return &self.tape;
+    }
+
+    /// Returns a commitment to the stack items computed with the Rescue hasher used by the VM;
+    /// this is the same digest hasher::hash() produces for the items. The output tape is not
+    /// included, and the commitment to an empty stack is the hash of an empty sequence.
+    pub fn commitment(&self) -> [u128; HASH_DIGEST_SIZE] {
+        let mut result = [field::ZERO; HASH_DIGEST_SIZE];
+        result.copy_from_slice(&hasher::hash(&self.stack));
+        return result;
+    }
+
+    /// Interprets the stack item at the specified `index` as a signed integer of the specified
+    /// bit width; negative integers are represented by field elements close to the modulus (e.g.
+    /// -1 is MODULUS - 1), which is what subtracting a larger value from a smaller value yields.
+    /// An error is returned if the item is outside of the range of `bits`-wide two's complement
+    /// integers; that is, outside of [-2^(bits - 1), 2^(bits - 1)).
+    pub fn as_signed(&self, index: usize, bits: u32) -> Result<i128, String> {
+        assert!(bits >= 1 && bits <= 127, "bit width must be between 1 and 127, but was {}", bits);
+        let value = match self.stack.get(index) {
+            Some(&value) => value,
+            None => return Err(format!("output index {} is out of bounds; there are only {} outputs",
+                index, self.stack.len())),
+        };
+
+        let bound = 1u128 << (bits - 1);
+        if value < bound {
+            return Ok(value as i128);
+        }
+        else if field::MODULUS - value <= bound {
+            return Ok(-((field::MODULUS - value) as i128));
+        }
+        return Err(format!("output {} is not a valid {}-bit signed integer", value, bits));
+    }
+
+    // FORMATTING
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns stack items formatted as hexadecimal numbers (e.g. "0x1f").
+    pub fn to_hex(&self) -> Vec<String> {
+        return self.stack.iter().map(|value| format!("{:#x}", value)).collect();
+    }
+
+    /// Returns stack items formatted as decimal numbers.
+    pub fn to_decimal(&self) -> Vec<String> {
+        return self.stack.iter().map(|value| value.to_string()).collect();
+    }
+
+    /// Returns stack items formatted as signed integers of the specified bit width (see
+    /// as_signed()); items which are not valid `bits`-wide signed integers are formatted as
+    /// decimal numbers.
+    pub fn to_signed(&self, bits: u32) -> Vec<String> {
+        return (0..self.stack.len()).map(|i| match self.as_signed(i, bits) {
+            Ok(value) => value.to_string(),
+            Err(_) => self.stack[i].to_string(),
+        }).collect();
+    }
+}
+
+/// Stack items (and tape values, if any) are displayed as field elements; that is, values in the
+/// upper half of the field are displayed together with their signed form.
+impl fmt::Display for ProgramOutputs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.stack.iter().map(|&v| field::Element(v)).collect::<Vec<_>>())?;
+        if !self.tape.is_empty() {
+            write!(f, ", tape: {:?}", self.tape.iter().map(|&v| field::Element(v)).collect::<Vec<_>>())?;
+        }
+        return Ok(());
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, utils::hasher };
+    use super::ProgramOutputs;
+
+    #[test]
+    fn as_signed() {
+        let outputs = ProgramOutputs::new(&[5, field::sub(3, 5), 127, field::neg(128), 128, field::neg(129)], &[]);
+
+        // positive and negative values
+        assert_eq!(Ok(5), outputs.as_signed(0, 8));
+        assert_eq!(Ok(-2), outputs.as_signed(1, 8));
+        assert_eq!(Ok(127), outputs.as_signed(2, 8));
+        assert_eq!(Ok(-128), outputs.as_signed(3, 8));
+
+        // values out of range for 8-bit integers, but not for 16-bit integers
+        assert_eq!(Err(String::from("output 128 is not a valid 8-bit signed integer")), outputs.as_signed(4, 8));
+        assert!(outputs.as_signed(5, 8).is_err());
+        assert_eq!(Ok(128), outputs.as_signed(4, 16));
+        assert_eq!(Ok(-129), outputs.as_signed(5, 16));
+
+        // index out of bounds
+        let err = outputs.as_signed(6, 8).unwrap_err();
+        assert_eq!("output index 6 is out of bounds; there are only 6 outputs", err);
+    }
+
+    #[test]
+    fn commitment() {
+        let outputs = ProgramOutputs::new(&[1, 2, 3], &[4]);
+        assert_eq!(hasher::hash(&[1, 2, 3]), outputs.commitment().to_vec());
+
+        // tape values do not affect the commitment
+        assert_eq!(ProgramOutputs::new(&[1, 2, 3], &[]).commitment(), outputs.commitment());
+
+        // empty outputs commit to the hash of an empty sequence, which differs from the
+        // commitment to a single zero
+        let empty = ProgramOutputs::new(&[], &[]);
+        assert_eq!(hasher::hash(&[]), empty.commitment().to_vec());
+        assert!(empty.commitment() != ProgramOutputs::new(&[0], &[]).commitment());
+    }
+
+    #[test]
+    fn formatting() {
+        let outputs = ProgramOutputs::new(&[0, 31, field::neg(1), field::neg(200)], &[]);
+
+        assert_eq!(vec!["0x0", "0x1f", "0xffffffffffffffffffffd30000000000", "0xffffffffffffffffffffd2ffffffff39"], outputs.to_hex());
+        assert_eq!(vec![
+            String::from("0"), String::from("31"),
+            field::neg(1).to_string(), field::neg(200).to_string(),
+        ], outputs.to_decimal());
+
+        // values near the modulus are negative; values out of range are rendered as is
+        assert_eq!(vec!["0", "31", "-1", "-200"], outputs.to_signed(16));
+        assert_eq!(vec![String::from("0"), String::from("31"), String::from("-1"), field::neg(200).to_string()], outputs.to_signed(8));
+    }
+
+    #[test]
+    fn display() {
+        let outputs = ProgramOutputs::new(&[5, field::neg(2)], &[]);
+        assert_eq!(format!("[5, {} (=-2)]", field::neg(2)), outputs.to_string());
+
+        let outputs = ProgramOutputs::new(&[5], &[3, 4]);
+        assert_eq!("[5], tape: [3, 4]", outputs.to_string());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::utils::{ as_bytes };
 use crate::processor::{ OpCode };
-use super::{ Program, ProgramBlock, Span, Group, Switch, Loop };
+use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, assembly };
 
 mod utils;
 use utils::{ traverse, close_block };
@@ -145,6 +145,64 @@ fn simple_loop() {
     assert_eq!(111, step);
 }
 
+#[test]
+#[cfg(feature = "json")]
+fn json_round_trip() {
+    let source = "begin push.3 push.5 add eq if.true push.7 mul else rc.16 end while.true push.0 end end";
+    let program = assembly::compile(source).unwrap();
+
+    // the JSON is re-assembled into an equivalent program
+    let json = program.to_json();
+    let program2 = Program::from_json(&json).unwrap();
+    assert_eq!(program.hash(), program2.hash());
+    assert_eq!(format!("{:?}", program), format!("{:?}", program2));
+
+    // converting the re-assembled program back into JSON produces the same JSON
+    assert_eq!(json, program2.to_json());
+
+    // push immediates and block structure are listed explicitly
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let blocks = value["blocks"].as_array().unwrap();
+    assert_eq!(3, blocks.len());
+    assert_eq!("span", blocks[0]["type"]);
+    assert_eq!("begin", blocks[0]["ops"][0]["op"]);
+    assert_eq!("push", blocks[0]["ops"][8]["op"]);
+    assert_eq!("3", blocks[0]["ops"][8]["value"]);
+    assert_eq!("switch", blocks[1]["type"]);
+    assert_eq!("loop", blocks[2]["type"]);
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn json_invalid() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let json = program.to_json();
+
+    let result = Program::from_json(&json.replace("\"add\"", "\"sub\""));
+    assert_eq!(Err(String::from("operation 'sub' is not valid")), result.map(|_| ()));
+
+    // changing a push immediate changes the program hash
+    let result = Program::from_json(&json.replace("\"5\"", "\"6\""));
+    assert!(result.unwrap_err().starts_with("expected program hash"));
+
+    // spans must be aligned on cycle boundaries
+    let result = Program::from_json(&json.replacen("{\n          \"op\": \"noop\"\n        },", "", 1));
+    assert!(result.unwrap_err().starts_with("invalid program structure"));
+
+    // push immediates must be valid field elements
+    let modulus = crate::math::field::MODULUS.to_string();
+    let result = Program::from_json(&json.replace("\"5\"", &format!("\"{}\"", modulus)));
+    assert_eq!(Err(format!("invalid value '{}' of operation 16", modulus)), result.map(|_| ()));
+
+    // pushes must have values, and programs must contain at least one block
+    let result = Program::from_json(&json.replace(",\n          \"value\": \"5\"", ""));
+    assert_eq!(Err(String::from("invalid program structure: value of PUSH operation on step 16 is missing")), result.map(|_| ()));
+    let result = Program::from_json("{ \"hash\": \"\", \"blocks\": [] }");
+    assert_eq!(Err(String::from("invalid program structure: a sequence of blocks must contain at least one block")), result.map(|_| ()));
+
+    assert!(Program::from_json("{ \"blocks\": [] }").unwrap_err().starts_with("malformed program JSON"));
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_first_block(op_code: OpCode, length: usize) -> ProgramBlock {
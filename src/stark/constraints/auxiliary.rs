@@ -0,0 +1,178 @@
+use std::sync::Arc;
+use crate::{
+    math::field,
+    crypto::HashFunction,
+    stark::{ TraceState, utils::{ Transcript, draw_field_elements } },
+};
+use super::evaluator::{ group_transition_constraints, get_boundary_constraint_adjustment_degree };
+
+// CONSTANTS
+// ================================================================================================
+pub const MAX_AUX_REGISTERS: usize = 16;
+pub const MAX_AUX_CHALLENGES: usize = 8;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Auxiliary trace registers which are built from the execution trace and from random
+/// challenges. The challenges are drawn only after the prover has committed to the execution
+/// trace; this enables arguments which require randomness, such as permutation and lookup
+/// arguments.
+///
+/// Similar to custom constraints, transition constraints of auxiliary registers are evaluated
+/// at x coordinates, and thus, the same functions are used by the prover and by the verifier.
+pub trait AuxTrace: Send + Sync {
+
+    /// Returns the number of auxiliary registers.
+    fn width(&self) -> usize;
+
+    /// Returns the number of random challenges used to build auxiliary registers.
+    fn num_challenges(&self) -> usize;
+
+    /// Builds auxiliary registers from the `trace` states (one per step of the execution trace)
+    /// and the `challenges`; every register must have the same length as the execution trace.
+    fn build_registers(&self, trace: &[TraceState], challenges: &[u128]) -> Vec<Vec<u128>>;
+
+    /// Returns degrees of transition constraints (as polynomials in main and auxiliary registers).
+    fn constraint_degrees(&self) -> Vec<usize>;
+
+    /// Evaluates transition constraints against the `frame` at the specified x coordinate and
+    /// saves the evaluations into `result`.
+    fn evaluate(&self, frame: &AuxFrame, challenges: &[u128], x: u128, result: &mut [u128]);
+
+    /// Returns values which auxiliary registers must have at the first and at the last steps
+    /// of the execution trace.
+    fn boundary_values(&self, challenges: &[u128]) -> (Vec<u128>, Vec<u128>);
+}
+
+/// Current and next states of the execution trace together with the current and next rows of
+/// auxiliary registers.
+pub struct AuxFrame<'a> {
+    pub current     : &'a TraceState,
+    pub next        : &'a TraceState,
+    pub aux_current : &'a [u128],
+    pub aux_next    : &'a [u128],
+}
+
+/// Evaluates transition and boundary constraints of auxiliary registers; the constraints are
+/// combined using coefficients drawn from the root of auxiliary trace commitment.
+pub struct AuxEvaluator {
+    aux             : Arc<dyn AuxTrace>,
+    challenges      : Vec<u128>,
+    coefficients    : Vec<u128>,
+
+    t_constraint_num: usize,
+    t_degree_groups : Vec<(u128, Vec<usize>)>,
+
+    i_values        : Vec<u128>,
+    f_values        : Vec<u128>,
+    b_degree_adj    : u128,
+}
+
+// AUX EVALUATOR IMPLEMENTATION
+// ================================================================================================
+impl AuxEvaluator {
+
+    /// Creates an evaluator for auxiliary registers built from the specified `challenges` and
+    /// committed to by `aux_root`.
+    pub fn new(aux: &Arc<dyn AuxTrace>, challenges: &[u128], aux_root: &[u8; 32], transcript_fn: HashFunction, trace_length: usize) -> AuxEvaluator {
+        let t_constraint_degrees = aux.constraint_degrees();
+        let t_constraint_num = t_constraint_degrees.len();
+        let (i_values, f_values) = aux.boundary_values(challenges);
+        assert!(i_values.len() == aux.width() && f_values.len() == aux.width(),
+            "expected {} boundary values for auxiliary registers", aux.width());
+
+        let num_coefficients = 2 * (t_constraint_num + i_values.len() + f_values.len());
+        return AuxEvaluator {
+            aux             : aux.clone(),
+            challenges      : challenges.to_vec(),
+            coefficients    : draw_field_elements(aux_root, num_coefficients, transcript_fn),
+            t_constraint_num: t_constraint_num,
+            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            i_values        : i_values,
+            f_values        : f_values,
+            b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
+        };
+    }
+
+    pub fn constraint_count(&self) -> usize {
+        return self.t_constraint_num + self.i_values.len() + self.f_values.len();
+    }
+
+    /// Returns the number of transition constraints of auxiliary registers.
+    pub fn transition_constraint_count(&self) -> usize {
+        return self.t_constraint_num;
+    }
+
+    /// Computes pseudo-random linear combination of auxiliary transition constraints at point x;
+    /// the same function is used for points of the evaluation domain and out-of-domain points.
+    /// `evaluations` must hold transition_constraint_count() values; it is overwritten with
+    /// evaluations of individual constraints, and can be re-used across steps.
+    pub fn evaluate_transition_at(&self, frame: &AuxFrame, x: u128, evaluations: &mut [u128]) -> u128 {
+        evaluations.iter_mut().for_each(|value| *value = field::ZERO);
+        self.aux.evaluate(frame, &self.challenges, x, evaluations);
+        return self.combine_transition_constraints(evaluations, x);
+    }
+
+    /// Computes pseudo-random linear combination of boundary constraints of auxiliary registers
+    /// at point x separately for the first and for the last steps of the program.
+    pub fn evaluate_boundaries(&self, aux_current: &[u128], x: u128) -> (u128, u128) {
+        let xp = field::exp(x, self.b_degree_adj);
+        let i_cc = &self.coefficients[(2 * self.t_constraint_num)..];
+        let f_cc = &i_cc[(2 * self.i_values.len())..];
+
+        let i_result = combine_boundary_constraints(aux_current, &self.i_values, i_cc, xp);
+        let f_result = combine_boundary_constraints(aux_current, &self.f_values, f_cc, xp);
+        return (i_result, f_result);
+    }
+
+    // HELPER METHODS
+    // -------------------------------------------------------------------------------------------
+
+    fn combine_transition_constraints(&self, evaluations: &[u128], x: u128) -> u128 {
+        let cc = &self.coefficients;
+        let mut result = field::ZERO;
+
+        let mut i = 0;
+        for (incremental_degree, constraints) in self.t_degree_groups.iter() {
+            let mut result_adj = field::ZERO;
+            for &constraint_idx in constraints.iter() {
+                let evaluation = evaluations[constraint_idx];
+                result = field::add(result, field::mul(evaluation, cc[i * 2]));
+                result_adj = field::add(result_adj, field::mul(evaluation, cc[i * 2 + 1]));
+                i += 1;
+            }
+
+            let xp = field::exp(x, *incremental_degree);
+            result = field::add(result, field::mul(result_adj, xp));
+        }
+
+        return result;
+    }
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Draws `count` challenges for building auxiliary registers from the root of the
+/// execution trace commitment. Coefficients for combining built-in constraints are drawn from
+/// the trace root directly; absorbing the root into a transcript first makes the challenges
+/// independent of these coefficients.
+pub fn draw_aux_challenges(trace_root: &[u8; 32], count: usize, hash_fn: HashFunction) -> Vec<u128> {
+    let mut transcript = Transcript::new(hash_fn);
+    transcript.absorb_commitments(trace_root);
+    return transcript.draw_field_elements(count);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn combine_boundary_constraints(registers: &[u128], values: &[u128], cc: &[u128], xp: u128) -> u128 {
+    let mut result = field::ZERO;
+    let mut result_adj = field::ZERO;
+    for i in 0..values.len() {
+        let val = field::sub(registers[i], values[i]);
+        result = field::add(result, field::mul(val, cc[i * 2]));
+        result_adj = field::add(result_adj, field::mul(val, cc[i * 2 + 1]));
+    }
+    return field::add(result, field::mul(result_adj, xp));
+}
@@ -0,0 +1,177 @@
+use crate::{ math::field, stark::TraceState, PROGRAM_DIGEST_SIZE };
+use super::utils::{ are_equal, is_zero };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Evaluates boundary constraints against the first and the last states of an execution trace.
+/// Unlike the combined evaluation performed by ConstraintEvaluator, evaluations of individual
+/// constraints are written into the result; all evaluations are zero for a valid trace. The
+/// evaluator is used by ConstraintEvaluator, and is not exported from the crate.
+pub(crate) struct BoundaryEvaluator {
+    program_hash    : Vec<u128>,
+    op_count        : u128,
+    inputs          : Vec<u128>,
+    outputs         : Vec<u128>,
+}
+
+// BOUNDARY EVALUATOR IMPLEMENTATION
+// ================================================================================================
+impl BoundaryEvaluator {
+
+    pub fn new(program_hash: &[u128], op_count: u128, inputs: &[u128], outputs: &[u128]) -> BoundaryEvaluator {
+        assert!(program_hash.len() == PROGRAM_DIGEST_SIZE,
+            "program hash must consist of {} elements", PROGRAM_DIGEST_SIZE);
+        return BoundaryEvaluator {
+            program_hash    : program_hash.to_vec(),
+            op_count        : op_count,
+            inputs          : inputs.to_vec(),
+            outputs         : outputs.to_vec(),
+        };
+    }
+
+    /// Returns the number of boundary constraints at the first step for the specified state.
+    pub fn first_constraint_count(&self, state: &TraceState) -> usize {
+        return 1 + state.sponge().len() + get_op_bits_count(state)
+            + state.ctx_stack().len() + state.loop_stack().len() + self.inputs.len();
+    }
+
+    /// Returns the number of boundary constraints at the last step for the specified state.
+    pub fn last_constraint_count(&self, state: &TraceState) -> usize {
+        return 1 + self.program_hash.len() + get_op_bits_count(state)
+            + state.ctx_stack().len() + state.loop_stack().len() + self.outputs.len();
+    }
+
+    // EVALUATOR FUNCTIONS
+    // --------------------------------------------------------------------------------------------
+
+    /// Evaluates boundary constraints at the first step of the execution trace and saves the
+    /// evaluations into `result`. The constraints are in the following order: op_counter,
+    /// sponge, op_bits, context stack, loop stack, and user stack (public inputs).
+    pub fn evaluate_first(&self, state: &TraceState, result: &mut [u128]) {
+        assert!(result.len() == self.first_constraint_count(state),
+            "expected result of length {}, but was {}", self.first_constraint_count(state), result.len());
+
+        let mut i = 0;
+
+        // op_counter is set to 0
+        result[i] = is_zero(state.op_counter());
+        i += 1;
+
+        // operation sponge registers are set to 0s
+        for &value in state.sponge() {
+            result[i] = is_zero(value);
+            i += 1;
+        }
+
+        // op_bits are set to HACC (000) and BEGIN (0000000)
+        for &value in state.cf_op_bits().iter().chain(state.ld_op_bits()).chain(state.hd_op_bits()) {
+            result[i] = is_zero(value);
+            i += 1;
+        }
+
+        // context and loop stack registers are set to 0s
+        for &value in state.ctx_stack().iter().chain(state.loop_stack()) {
+            result[i] = is_zero(value);
+            i += 1;
+        }
+
+        // user stack registers are set to inputs
+        let user_stack = state.user_stack();
+        for j in 0..self.inputs.len() {
+            result[i] = are_equal(user_stack[j], self.inputs[j]);
+            i += 1;
+        }
+    }
+
+    /// Evaluates boundary constraints at the last step of the execution trace and saves the
+    /// evaluations into `result`. The constraints are in the following order: op_counter,
+    /// program hash, op_bits, context stack, loop stack, and user stack (outputs).
+    pub fn evaluate_last(&self, state: &TraceState, result: &mut [u128]) {
+        assert!(result.len() == self.last_constraint_count(state),
+            "expected result of length {}, but was {}", self.last_constraint_count(state), result.len());
+
+        let mut i = 0;
+
+        // op_counter is set to the claimed number of operations
+        result[i] = are_equal(state.op_counter(), self.op_count);
+        i += 1;
+
+        // operation sponge contains program hash
+        let program_hash = state.program_hash();
+        for j in 0..self.program_hash.len() {
+            result[i] = are_equal(program_hash[j], self.program_hash[j]);
+            i += 1;
+        }
+
+        // op_bits are set to VOID (111) and NOOP (1111111)
+        for &value in state.cf_op_bits().iter().chain(state.ld_op_bits()).chain(state.hd_op_bits()) {
+            result[i] = are_equal(value, field::ONE);
+            i += 1;
+        }
+
+        // context and loop stack registers are set to 0s
+        for &value in state.ctx_stack().iter().chain(state.loop_stack()) {
+            result[i] = is_zero(value);
+            i += 1;
+        }
+
+        // user stack registers are set to outputs
+        let user_stack = state.user_stack();
+        for j in 0..self.outputs.len() {
+            result[i] = are_equal(user_stack[j], self.outputs[j]);
+            i += 1;
+        }
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn get_op_bits_count(state: &TraceState) -> usize {
+    return state.cf_op_bits().len() + state.ld_op_bits().len() + state.hd_op_bits().len();
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ processor, programs::assembly, stark::TraceState, ProgramInputs };
+    use super::BoundaryEvaluator;
+
+    #[test]
+    fn evaluate_boundaries() {
+        let program = assembly::compile("begin add push.5 mul push.7 end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1, 2]);
+
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &inputs);
+        let trace_length = trace[0].len();
+        let stack_depth = trace.len() - TraceState::compute_decoder_width(ctx_depth, loop_depth);
+
+        let mut first = TraceState::new(ctx_depth, loop_depth, stack_depth);
+        first.update_from_trace(&trace, 0);
+        let mut last = TraceState::new(ctx_depth, loop_depth, stack_depth);
+        last.update_from_trace(&trace, trace_length - 1);
+
+        let program_hash = last.program_hash().to_vec();
+        let outputs = [7, 15];
+        let evaluator = BoundaryEvaluator::new(&program_hash, last.op_counter(), &[1, 2], &outputs);
+
+        // valid trace: all constraints evaluate to zero
+        let mut result = vec![1; evaluator.first_constraint_count(&first)];
+        evaluator.evaluate_first(&first, &mut result);
+        assert_eq!(vec![0; result.len()], result);
+
+        let mut result = vec![1; evaluator.last_constraint_count(&last)];
+        evaluator.evaluate_last(&last, &mut result);
+        assert_eq!(vec![0; result.len()], result);
+
+        // incorrect final stack value: the corresponding constraint is nonzero
+        let evaluator = BoundaryEvaluator::new(&program_hash, last.op_counter(), &[1, 2], &[7, 16]);
+        let mut result = vec![0; evaluator.last_constraint_count(&last)];
+        evaluator.evaluate_last(&last, &mut result);
+        let last_idx = result.len() - 1;
+        assert_ne!(0, result[last_idx]);
+        assert_eq!(vec![0; last_idx], result[..last_idx].to_vec());
+    }
+}
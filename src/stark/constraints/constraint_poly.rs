@@ -1,5 +1,5 @@
 use crate::math::{ field, polynom, parallel };
-use crate::stark::{ MAX_CONSTRAINT_DEGREE, utils::CompositionCoefficients };
+use crate::stark::{ MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET, utils::CompositionCoefficients };
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -25,12 +25,18 @@ impl ConstraintPoly {
         return get_expected_degree(&self.poly);
     }
 
+    /// Returns coefficients of the polynomial in the order of increasing powers of x.
+    pub fn into_coefficients(self) -> Vec<u128> {
+        return self.poly;
+    }
+
     pub fn eval(&self, twiddles: &[u128]) -> Vec<u128> {
         let domain_size = twiddles.len() * 2;
         assert!(domain_size > self.poly.len(), "domain size must be greater than poly length");
 
         let mut evaluations = vec![field::ZERO; domain_size];
         evaluations[..self.poly.len()].copy_from_slice(&self.poly);
+        polynom::scale_argument(&mut evaluations[..self.poly.len()], DOMAIN_OFFSET);
         polynom::eval_fft_twiddles(&mut evaluations, twiddles, true);
 
         return evaluations;
@@ -1,34 +1,54 @@
+use crate::crypto::HashFunction;
 use crate::math::{ field, parallel, fft, polynom };
-use crate::stark::{ TraceTable, TraceState };
+use crate::stark::{ OutputTape, TraceTable, TraceState, EvalStep, DOMAIN_OFFSET };
 use crate::utils::{ uninit_vector };
-use super::{ ConstraintEvaluator, ConstraintPoly };
+use std::sync::Arc;
+use super::{ ConstraintEvaluator, ConstraintPoly, CustomConstraints, ExtendedArk, AuxEvaluator, AuxFrame };
 
 // TYPES AND INTERFACES
 // ================================================================================================
 pub struct ConstraintTable {
     evaluator       : ConstraintEvaluator,
+    aux_evaluator   : Option<AuxEvaluator>,
     i_evaluations   : Vec<u128>,    // combined evaluations of boundary constraints at the first step
     f_evaluations   : Vec<u128>,    // combined evaluations of boundary constraints at the last step
     t_evaluations   : Vec<u128>,    // combined evaluations of transition constraints
+    w_evaluations   : Vec<u128>,    // combined evaluations of output tape constraints (if any)
+    t_buffer        : Vec<u128>,    // evaluations of individual transition constraints at a step
+    a_buffer        : Vec<u128>,    // evaluations of individual auxiliary constraints at a step
 }
 
 // CONSTRAINT TABLE IMPLEMENTATION
 // ================================================================================================
 impl ConstraintTable {
-    pub fn new(trace: &TraceTable, trace_root: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> ConstraintTable {
-        let evaluator = ConstraintEvaluator::from_trace(trace, trace_root, inputs, outputs);
+    /// Returns a constraint table for the specified `trace`; if `tape` is provided, the table
+    /// also includes constraints which bind values on the tape to the trace, and if
+    /// `aux_evaluator` is provided, the table also includes constraints of auxiliary registers.
+    pub fn new(trace: &TraceTable, trace_root: &[u8; 32], transcript_fn: HashFunction, inputs: &[u128], outputs: &[u128], tape: Option<&OutputTape>, custom: &CustomConstraints, aux_evaluator: Option<AuxEvaluator>, stack_ark: Arc<ExtendedArk>) -> ConstraintTable {
+        let evaluator = ConstraintEvaluator::from_trace(trace, trace_root, transcript_fn, inputs, outputs, tape, custom, stack_ark);
+        assert!(trace.extension_factor() >= evaluator.min_extension_factor(),
+            "extension factor {} is too small for the constraints; the factor must be at least {}",
+            trace.extension_factor(), evaluator.min_extension_factor());
         let evaluation_domain_size = evaluator.domain_size();
+        let w_evaluations = if evaluator.has_tape_constraints() { uninit_vector(evaluation_domain_size) } else { Vec::new() };
+        let t_buffer = vec![field::ZERO; evaluator.transition_constraint_count()];
+        let a_buffer = vec![field::ZERO; aux_evaluator.as_ref().map_or(0, |aux| aux.transition_constraint_count())];
         return ConstraintTable {
             evaluator       : evaluator,
+            aux_evaluator   : aux_evaluator,
             i_evaluations   : uninit_vector(evaluation_domain_size),
             f_evaluations   : uninit_vector(evaluation_domain_size),
             t_evaluations   : uninit_vector(evaluation_domain_size),
+            w_evaluations   : w_evaluations,
+            t_buffer        : t_buffer,
+            a_buffer        : a_buffer,
         };
     }
 
     /// Returns the total number of transition and boundary constraints.
     pub fn constraint_count(&self) -> usize {
-        return self.evaluator.constraint_count();
+        let aux_count = self.aux_evaluator.as_ref().map_or(0, |aux| aux.constraint_count());
+        return self.evaluator.constraint_count() + aux_count;
     }
 
     /// Returns the size of the evaluation domain = trace_length * MAX_CONSTRAINT_DEGREE
@@ -41,20 +61,46 @@ impl ConstraintTable {
         return self.evaluator.trace_length();
     }
 
-    /// Evaluates transition and boundary constraints at the specified step.
-    pub fn evaluate(&mut self, current: &TraceState, next: &TraceState, x: u128, step: usize) {
-        let (init_bound, last_bound) = self.evaluator.evaluate_boundaries(current, x);
-        self.i_evaluations[step] = init_bound;
-        self.f_evaluations[step] = last_bound;
-        self.t_evaluations[step] = self.evaluator.evaluate_transition(current, next, x, step);
+    /// Returns combined evaluations of transition constraints at all steps of the evaluation
+    /// domain; can be called only after the table has been filled by evaluate().
+    #[cfg(test)]
+    pub fn get_transition_evaluations(&self) -> &[u128] {
+        return &self.t_evaluations;
+    }
+
+    /// Evaluates transition and boundary constraints at the specified step of the evaluation
+    /// domain; `aux_current` and `aux_next` are rows of auxiliary registers (empty if the table
+    /// does not include constraints of auxiliary registers).
+    pub fn evaluate(&mut self, current: &TraceState, next: &TraceState, aux_current: &[u128], aux_next: &[u128], x: u128, step: EvalStep) {
+        let (mut init_bound, mut last_bound) = self.evaluator.evaluate_boundaries(current, x);
+        let mut transition = self.evaluator.evaluate_transition(current, next, x, step, &mut self.t_buffer);
+
+        // constraints of auxiliary registers have the same divisors as other boundary and
+        // transition constraints, and thus, are added to the same evaluations
+        if let Some(aux_evaluator) = &self.aux_evaluator {
+            let frame = AuxFrame { current, next, aux_current, aux_next };
+            let (aux_init_bound, aux_last_bound) = aux_evaluator.evaluate_boundaries(aux_current, x);
+            init_bound = field::add(init_bound, aux_init_bound);
+            last_bound = field::add(last_bound, aux_last_bound);
+            transition = field::add(transition, aux_evaluator.evaluate_transition_at(&frame, x, &mut self.a_buffer));
+        }
+
+        self.i_evaluations[step.0] = init_bound;
+        self.f_evaluations[step.0] = last_bound;
+        self.t_evaluations[step.0] = transition;
+        if self.w_evaluations.len() > 0 {
+            self.w_evaluations[step.0] = self.evaluator.evaluate_tape(current, x);
+        }
     }
 
     /// Interpolates all constraint evaluations into polynomials and combines all these 
-    /// polynomials into a single polynomial using pseudo-random linear combination.
+    /// polynomials into a single polynomial using pseudo-random linear combination. The
+    /// evaluation domain is a coset of the subgroup shifted by DOMAIN_OFFSET.
     pub fn combine_polys(mut self) -> ConstraintPoly
     {
         let combination_root = field::get_root_of_unity(self.evaluation_domain_size());
         let inv_twiddles = fft::get_inv_twiddles(combination_root, self.evaluation_domain_size());
+        let inv_offset = field::inv(DOMAIN_OFFSET);
      
         #[cfg(debug_assertions)]
         self.validate_transition_degrees();
@@ -64,14 +110,14 @@ impl ConstraintTable {
         // 1 ----- boundary constraints for the initial step --------------------------------------
         // interpolate initial step boundary constraint combination into a polynomial, divide the 
         // polynomial by Z(x) = (x - 1), and add it to the result
-        polynom::interpolate_fft_twiddles(&mut self.i_evaluations, &inv_twiddles, true);
+        interpolate_over_coset(&mut self.i_evaluations, &inv_twiddles, inv_offset);
         polynom::syn_div_in_place(&mut self.i_evaluations, field::ONE);
         combined_poly.copy_from_slice(&self.i_evaluations);
 
         // 2 ----- boundary constraints for the final step ----------------------------------------
         // interpolate final step boundary constraint combination into a polynomial, divide the 
         // polynomial by Z(x) = (x - x_at_last_step), and add it to the result
-        polynom::interpolate_fft_twiddles(&mut self.f_evaluations, &inv_twiddles, true);
+        interpolate_over_coset(&mut self.f_evaluations, &inv_twiddles, inv_offset);
         let x_at_last_step = self.evaluator.get_x_at_last_step();
         polynom::syn_div_in_place(&mut self.f_evaluations, x_at_last_step);
         parallel::add_in_place(&mut combined_poly, &self.f_evaluations, 1);
@@ -80,10 +126,22 @@ impl ConstraintTable {
         // interpolate transition constraint combination into a polynomial, divide the polynomial
         // by Z(x) = (x^steps - 1) / (x - x_at_last_step), and add it to the result
         let trace_length = self.trace_length();
-        polynom::interpolate_fft_twiddles(&mut self.t_evaluations, &inv_twiddles, true);
+        interpolate_over_coset(&mut self.t_evaluations, &inv_twiddles, inv_offset);
         polynom::syn_div_expanded_in_place(&mut self.t_evaluations, trace_length, &[x_at_last_step]);
         parallel::add_in_place(&mut combined_poly, &self.t_evaluations, 1);
 
+        // 4 ----- output tape constraints --------------------------------------------------------
+        // interpolate output tape constraint combination into a polynomial, divide the polynomial
+        // by Z(x) = (x - x_0) * (x - x_1) * ... for all steps at which values were written to the
+        // tape, and add it to the result
+        if self.w_evaluations.len() > 0 {
+            interpolate_over_coset(&mut self.w_evaluations, &inv_twiddles, inv_offset);
+            for &x_at_write in self.evaluator.get_tape_points() {
+                polynom::syn_div_in_place(&mut self.w_evaluations, x_at_write);
+            }
+            parallel::add_in_place(&mut combined_poly, &self.w_evaluations, 1);
+        }
+
         return ConstraintPoly::new(combined_poly);
     }
 
@@ -110,4 +168,14 @@ impl ConstraintTable {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Interpolates evaluations over a coset of the subgroup defined by `inv_twiddles`; since the
+/// interpolated polynomial is p(offset * x), its arguments are scaled by 1 / offset.
+fn interpolate_over_coset(evaluations: &mut [u128], inv_twiddles: &[u128], inv_offset: u128) {
+    polynom::interpolate_fft_twiddles(evaluations, inv_twiddles, true);
+    polynom::scale_argument(evaluations, inv_offset);
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+use crate::stark::{ TraceState, OutputTape, MAX_CONSTRAINT_DEGREE };
+use super::{ AuxTrace, TapeCounter, MAX_AUX_REGISTERS, MAX_AUX_CHALLENGES };
+
+// CONSTANTS
+// ================================================================================================
+pub const MAX_CUSTOM_CONSTRAINTS: usize = 16;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// An application-specific transition constraint. The constraint must evaluate to zero for all
+/// valid transitions from `current` to `next` state, and the degree of the constraint (as a
+/// polynomial in trace registers) must not exceed the value returned from `degree()`.
+///
+/// Unlike built-in constraint evaluators, custom constraints are evaluated at x coordinates
+/// rather than at steps of the evaluation domain; this way, the same function can be used by
+/// the prover (over the evaluation domain) and by the verifier (at the DEEP point).
+pub trait Constraint: Send + Sync {
+
+    /// Returns the degree of this constraint.
+    fn degree(&self) -> usize;
+
+    /// Evaluates this constraint against `current` and `next` states of the execution trace
+    /// at the specified x coordinate.
+    fn evaluate(&self, current: &TraceState, next: &TraceState, x: u128) -> u128;
+}
+
+/// A list of custom constraints which are evaluated after the built-in decoder and stack
+/// constraints, optionally together with auxiliary trace registers and their constraints; the
+/// same list must be provided to the prover and to the verifier.
+#[derive(Clone, Default)]
+pub struct CustomConstraints {
+    constraints : Vec<Arc<dyn Constraint>>,
+    aux_trace   : Option<Arc<dyn AuxTrace>>,
+}
+
+// CUSTOM CONSTRAINTS IMPLEMENTATION
+// ================================================================================================
+impl CustomConstraints {
+
+    pub fn new() -> CustomConstraints {
+        return CustomConstraints { constraints: Vec::new(), aux_trace: None };
+    }
+
+    /// Returns a copy of this list with `constraint` appended to the end of the list.
+    pub fn with<C: Constraint + 'static>(mut self, constraint: C) -> CustomConstraints {
+        assert!(self.constraints.len() < MAX_CUSTOM_CONSTRAINTS,
+            "cannot register more than {} custom constraints", MAX_CUSTOM_CONSTRAINTS);
+        let degree = constraint.degree();
+        assert!(degree > 0 && degree <= MAX_CONSTRAINT_DEGREE,
+            "custom constraint degree must be between 1 and {}, but was {}", MAX_CONSTRAINT_DEGREE, degree);
+        self.constraints.push(Arc::new(constraint));
+        return self;
+    }
+
+    /// Returns a copy of this list which also defines auxiliary trace registers; the registers
+    /// are committed to after the execution trace, and their constraints are evaluated together
+    /// with all other constraints. At most one auxiliary trace can be defined.
+    pub fn with_aux_trace<A: AuxTrace + 'static>(mut self, aux_trace: A) -> CustomConstraints {
+        assert!(self.aux_trace.is_none(), "auxiliary trace has already been defined");
+        let width = aux_trace.width();
+        assert!(width > 0 && width <= MAX_AUX_REGISTERS,
+            "number of auxiliary registers must be between 1 and {}, but was {}", MAX_AUX_REGISTERS, width);
+        assert!(aux_trace.num_challenges() <= MAX_AUX_CHALLENGES,
+            "auxiliary trace cannot use more than {} challenges", MAX_AUX_CHALLENGES);
+        for degree in aux_trace.constraint_degrees() {
+            assert!(degree > 0 && degree <= MAX_CONSTRAINT_DEGREE,
+                "auxiliary constraint degree must be between 1 and {}, but was {}", MAX_CONSTRAINT_DEGREE, degree);
+        }
+        self.aux_trace = Some(Arc::new(aux_trace));
+        return self;
+    }
+
+    /// Returns a copy of this list which binds the public output `tape` (if any) to the
+    /// execution trace: an auxiliary register which counts WRITE operations is appended to the
+    /// auxiliary trace of this list (see TapeCounter). The prover and the verifier both use this
+    /// to derive the constraints actually enforced for a proof.
+    pub fn with_tape(&self, tape: Option<&OutputTape>) -> CustomConstraints {
+        let mut result = self.clone();
+        if let Some(tape) = tape {
+            result.aux_trace = Some(Arc::new(TapeCounter::new(self.aux_trace.as_ref(), tape)));
+        }
+        return result;
+    }
+
+    /// Returns the auxiliary trace defined for this list, if any.
+    pub fn aux_trace(&self) -> Option<&Arc<dyn AuxTrace>> {
+        return self.aux_trace.as_ref();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.constraints.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.constraints.is_empty();
+    }
+
+    pub fn constraint_degrees(&self) -> Vec<usize> {
+        return self.constraints.iter().map(|c| c.degree()).collect();
+    }
+
+    /// Evaluates all custom constraints at the specified x coordinate and saves the
+    /// evaluations into `result`.
+    pub fn evaluate(&self, current: &TraceState, next: &TraceState, x: u128, result: &mut [u128]) {
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            result[i] = constraint.evaluate(current, next, x);
+        }
+    }
+}
@@ -2,8 +2,9 @@ use std::{ cmp };
 use crate::{
     math::{ field, polynom },
     processor::opcodes::{ FlowOps, UserOps },
-    stark::trace::TraceState,
+    stark::trace::{ TraceState, EvalStep },
     utils::sponge::ARK, SPONGE_WIDTH, BASE_CYCLE_LENGTH, MIN_CONTEXT_DEPTH, MIN_LOOP_DEPTH,
+    NUM_CF_OP_BITS, NUM_LD_OP_BITS, NUM_HD_OP_BITS,
 };
 use super::utils::{
     are_equal, is_zero, is_binary, binary_not, extend_constants, EvaluationResult,
@@ -45,6 +46,30 @@ const SPONGE_CONSTRAINT_DEGREES: [usize; NUM_SPONGE_CONSTRAINTS] = [
 const LOOP_IMAGE_CONSTRAINT_DEGREE: usize = 4;
 const STACK_CONSTRAINT_DEGREE: usize = 4;
 
+// number of multiplications performed by enforce_op_bits() (not counting computation of operation
+// flags): one for every op bit to check that it is binary, one for every low- and high-degree op
+// bit to compute their products, 7 for op counter and op combination constraints, and 7 for
+// multiplying cycle masks by flags of operations which must be aligned
+const OP_BITS_MUL_COUNT: u64 = (NUM_CF_OP_BITS + 2 * (NUM_LD_OP_BITS + NUM_HD_OP_BITS)) as u64 + 7 + 7;
+
+// number of multiplications performed by enforce_hacc() not counting the S-Box applied to the
+// sponge of the next state: one for the pushed value, one for every op bit but the first when
+// composing the op code, 4 for every S-Box (x^3), SPONGE_WIDTH for every row of MDS and inverse
+// MDS matrices, and one for multiplying every sponge constraint by HACC flag
+const HACC_MUL_COUNT: u64 = (1 + (NUM_LD_OP_BITS + NUM_HD_OP_BITS - 1)
+    + 4 * SPONGE_WIDTH + 2 * SPONGE_WIDTH * SPONGE_WIDTH + SPONGE_WIDTH) as u64;
+
+// number of multiplications performed by constraints of BEGIN, TEND, FEND, LOOP, WRAP, BREAK, and
+// VOID operations in addition to multiplying the operation flag into constraints of every context
+// and loop stack register; these are the sponge and loop image constraints of every operation,
+// less one for LOOP which places no constraint on the first register of the loop stack
+const FLOW_OP_MUL_COUNTS: [u64; 7] = [4, 3, 3, 3, 5, 5, 4];
+
+/// Number of multiplications performed by the S-Box applied to the sponge of the next state;
+/// raising zeros to a power takes no multiplications, and thus, these multiplications are not
+/// performed when the sponge of the next state is reset to zeros.
+pub const NEXT_SPONGE_SBOX_MUL_COUNT: u64 = 4 * SPONGE_WIDTH as u64;
+
 const CYCLE_MASK_IDX : usize = 0;
 const PREFIX_MASK_IDX: usize = 1;
 const PUSH_MASK_IDX  : usize = 2;
@@ -72,7 +97,10 @@ pub struct Decoder {
 // ================================================================================================
 impl Decoder {
 
-    pub fn new(trace_length: usize, extension_factor: usize, ctx_depth: usize, loop_depth: usize) -> Decoder 
+    /// Creates a decoder constraint evaluator for the evaluation domain of an execution trace of
+    /// `trace_length` steps extended by `extension_factor` and shifted by `domain_offset`; the
+    /// offset is ONE when constraints are evaluated at steps of the execution trace.
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128, ctx_depth: usize, loop_depth: usize) -> Decoder 
     {
         // build an array of constraint degrees for the decoder
         let mut degrees = Vec::from(&OP_CONSTRAINT_DEGREES[..]);
@@ -87,11 +115,11 @@ impl Decoder {
         let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
 
         // extend rounds constants by the specified extension factor
-        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor);
+        let (ark_polys, ark_evaluations) = extend_constants(&ARK, trace_length, extension_factor, domain_offset);
         let ark_values = transpose_ark_constants(ark_evaluations, cycle_length);
 
         // extend mask constants by the specified extension factor
-        let (mask_polys, mask_evaluations) = extend_constants(&MASKS, extension_factor);
+        let (mask_polys, mask_evaluations) = extend_constants(&MASKS, trace_length, extension_factor, domain_offset);
         let mask_values = transpose_mask_constants(mask_evaluations, cycle_length);
 
         return Decoder {
@@ -103,12 +131,10 @@ impl Decoder {
         };
     }
 
-    #[cfg(test)]
     pub fn ctx_depth(&self) -> usize {
         return self.ctx_depth;
     }
 
-    #[cfg(test)]
     pub fn loop_depth(&self) -> usize {
         return self.loop_depth;
     }
@@ -121,21 +147,41 @@ impl Decoder {
         return &self.constraint_degrees;
     }
 
+    /// Returns the number of multiplications performed by evaluate() at a single step; this
+    /// does not include computing operation flags of the current and the next states.
+    pub fn mul_count(&self) -> u64 {
+        let stack_registers = (cmp::max(self.ctx_depth, MIN_CONTEXT_DEPTH) + cmp::max(self.loop_depth, MIN_LOOP_DEPTH)) as u64;
+        let flow_ops: u64 = FLOW_OP_MUL_COUNTS.iter().sum();
+        return OP_BITS_MUL_COUNT + HACC_MUL_COUNT + NEXT_SPONGE_SBOX_MUL_COUNT
+            + flow_ops + FLOW_OP_MUL_COUNTS.len() as u64 * stack_registers;
+    }
+
     // EVALUATOR FUNCTIONS
     // --------------------------------------------------------------------------------------------
 
     /// Evaluates decoder transition constraints at the specified step of the evaluation domain and
     /// saves the evaluations into `result`.
-    pub fn evaluate(&self, current: &TraceState, next: &TraceState, step: usize, result: &mut [u128])
+    pub fn evaluate(&self, current: &TraceState, next: &TraceState, step: EvalStep, result: &mut [u128])
     {
-        // determine round and mask constants at the specified step
-        let ark = self.ark_values[step % self.cycle_length];
-        let masks = self.mask_values[step % self.cycle_length];
+        self.evaluate_op_bits(current, next, step, result);
+        self.evaluate_flow_ops(current, next, step, result);
+    }
 
-        // evaluate constraints for decoding op codes
+    /// Evaluates only constraints for decoding op codes at the specified step of the evaluation
+    /// domain; the evaluations are saved into the same positions of `result` as by evaluate().
+    pub fn evaluate_op_bits(&self, current: &TraceState, next: &TraceState, step: EvalStep, result: &mut [u128])
+    {
+        let masks = self.mask_values[step.0 % self.cycle_length];
         enforce_op_bits(&mut result[..NUM_OP_CONSTRAINTS], current, next, &masks);
+    }
+
+    /// Evaluates only constraints for flow control operations (including program hashing) at
+    /// the specified step of the evaluation domain; the evaluations are saved into the same
+    /// positions of `result` as by evaluate().
+    pub fn evaluate_flow_ops(&self, current: &TraceState, next: &TraceState, step: EvalStep, result: &mut [u128])
+    {
+        let ark = self.ark_values[step.0 % self.cycle_length];
 
-        // evaluate constraints for flow control operations
         let result = &mut result[NUM_OP_CONSTRAINTS..];
         let op_flags = current.cf_op_flags();
 
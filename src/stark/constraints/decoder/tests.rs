@@ -1,5 +1,9 @@
+use crate::math::field;
 use crate::utils::sponge::{ apply_round as apply_hacc_round };
-use super::{ Decoder, TraceState, UserOps };
+use super::{
+    Decoder, TraceState, EvalStep, UserOps, op_bits, sponge, flow_ops,
+    OP_BITS_MUL_COUNT, HACC_MUL_COUNT, FLOW_OP_MUL_COUNTS, NEXT_SPONGE_SBOX_MUL_COUNT, NUM_OP_CONSTRAINTS,
+};
 
 // CONSTANTS
 // ================================================================================================
@@ -100,10 +104,54 @@ fn enforce_hacc() {
     assert_ne!(success_result, evaluations);
 }
 
+// MULTIPLICATION COUNTS
+// ================================================================================================
+#[test]
+fn mul_counts() {
+    for &(ctx_depth, loop_depth) in [(1, 0), (2, 1), (3, 2)].iter() {
+        let width = TraceState::compute_decoder_width(ctx_depth, loop_depth) + 1;
+        let state1 = TraceState::from_vec(ctx_depth, loop_depth, 1, &field::rand_vector(width));
+        let state2 = TraceState::from_vec(ctx_depth, loop_depth, 1, &field::rand_vector(width));
+        let stack_registers = (state1.ctx_stack().len() + state1.loop_stack().len()) as u64;
+
+        // compute operation flags in advance; they are not counted by the decoder
+        state1.cf_op_flags();
+        state2.cf_op_flags();
+
+        let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
+        let count = field::mul_count();
+        op_bits::enforce_op_bits(&mut evaluations, &state1, &state2, &[3, 5, 7]);
+        assert_eq!(OP_BITS_MUL_COUNT, field::mul_count() - count);
+
+        let decoder = new_decoder(ctx_depth, loop_depth);
+        let mut evaluations = vec![0; decoder.constraint_degrees().len()];
+        let flow_evaluations = &mut evaluations[NUM_OP_CONSTRAINTS..];
+        let count = field::mul_count();
+        sponge::enforce_hacc(flow_evaluations, &state1, &state2, &field::rand_vector(8), 11);
+        assert_eq!(HACC_MUL_COUNT + NEXT_SPONGE_SBOX_MUL_COUNT, field::mul_count() - count);
+
+        let enforce_ops = [
+            flow_ops::enforce_begin, flow_ops::enforce_tend, flow_ops::enforce_fend, flow_ops::enforce_loop,
+            flow_ops::enforce_wrap, flow_ops::enforce_break, flow_ops::enforce_void,
+        ];
+        for (i, enforce_op) in enforce_ops.iter().enumerate() {
+            let count = field::mul_count();
+            enforce_op(flow_evaluations, &state1, &state2, 11);
+            assert_eq!(FLOW_OP_MUL_COUNTS[i] + stack_registers, field::mul_count() - count,
+                "multiplication count did not match for flow operation {}", i);
+        }
+
+        // all of the above add up to the count reported by the decoder
+        let count = field::mul_count();
+        decoder.evaluate(&state1, &state2, EvalStep(3), &mut evaluations);
+        assert_eq!(decoder.mul_count(), field::mul_count() - count);
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn new_decoder(ctx_depth: usize, loop_depth: usize) -> Decoder {
-    return Decoder::new(TRACE_LENGTH, EXTENSION_FACTOR, ctx_depth, loop_depth);
+    return Decoder::new(TRACE_LENGTH, EXTENSION_FACTOR, field::ONE, ctx_depth, loop_depth);
 }
 
 fn evaluate_transition(decoder: &Decoder, step: usize, state1: Vec<u128>, state2: Vec<u128>) -> Vec<u128>
@@ -112,6 +160,6 @@ fn evaluate_transition(decoder: &Decoder, step: usize, state1: Vec<u128>, state2
     let state2 = TraceState::from_vec(decoder.ctx_depth(), decoder.loop_depth(), 1, &state2);
 
     let mut evaluations = vec![0; decoder.constraint_degrees().len()];
-    decoder.evaluate(&state1, &state2, step, &mut evaluations);
+    decoder.evaluate(&state1, &state2, EvalStep(step), &mut evaluations);
     return evaluations
 }
\ No newline at end of file
@@ -1,16 +1,23 @@
 use crate::{
-    math::field,
+    crypto::HashFunction,
+    math::{ field, polynom },
+    processor::OpCode,
     utils::uninit_vector,
-    stark::{ StarkProof, TraceTable, TraceState, ConstraintCoefficients },
-    PROGRAM_DIGEST_SIZE,
+    stark::{ StarkProof, OutputTape, TraceTable, TraceState, TraceStep, LdeStep, EvalStep, ConstraintCoefficients, OP_FLAGS_MUL_COUNT, lde_domain },
+    PROGRAM_DIGEST_SIZE, NUM_CF_OP_BITS, NUM_LD_OP_BITS, NUM_HD_OP_BITS, SPONGE_WIDTH, MIN_CONTEXT_DEPTH, MIN_LOOP_DEPTH, MIN_EXTENSION_FACTOR,
+};
+use std::{ cmp, sync::Arc };
+use super::{
+    decoder::Decoder, stack::{ Stack, ExtendedArk }, BoundaryEvaluator, Constraint, CustomConstraints,
+    super::{ MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET }
 };
-use super::{ decoder::Decoder, stack::Stack, super::MAX_CONSTRAINT_DEGREE };
 
 // TYPES AND INTERFACES
 // ================================================================================================
 pub struct Evaluator {
     decoder         : Decoder,
     stack           : Stack,
+    custom          : CustomConstraints,
 
     coefficients    : ConstraintCoefficients,
     domain_size     : usize,
@@ -26,13 +33,20 @@ pub struct Evaluator {
     inputs          : Vec<u128>,
     outputs         : Vec<u128>,
     b_degree_adj    : u128,
+
+    w_op_bits       : Vec<u128>,
+    w_points        : Vec<u128>,
+    w_poly          : Vec<u128>,
+    w_degree_adj    : u128,
 }
 
 // EVALUATOR IMPLEMENTATION
 // ================================================================================================
 impl Evaluator {
 
-    pub fn from_trace(trace: &TraceTable, trace_root: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    /// Creates a constraint evaluator for the specified trace; `stack_ark` must contain round
+    /// constants extended by MAX_CONSTRAINT_DEGREE.
+    pub fn from_trace(trace: &TraceTable, trace_root: &[u8; 32], transcript_fn: HashFunction, inputs: &[u128], outputs: &[u128], tape: Option<&OutputTape>, custom: &CustomConstraints, stack_ark: Arc<ExtendedArk>) -> Evaluator
     {
         let last_state = trace.get_last_state();
         let ctx_depth = trace.ctx_depth();
@@ -42,14 +56,35 @@ impl Evaluator {
         let extension_factor = MAX_CONSTRAINT_DEGREE;
 
         // instantiate decoder and stack constraint evaluators 
-        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
-        let stack = Stack::new(trace_length, extension_factor, stack_depth);
+        assert!(stack_ark.extension_factor() == extension_factor,
+            "stack round constants must be extended by {}", extension_factor);
+        let decoder = Decoder::new(trace_length, extension_factor, DOMAIN_OFFSET, ctx_depth, loop_depth);
+        let stack = Stack::with_ark(trace_length, stack_depth, stack_ark);
 
         // build a list of transition constraint degrees
         let t_constraint_degrees = [
-            decoder.constraint_degrees(), stack.constraint_degrees()
+            decoder.constraint_degrees(), stack.constraint_degrees(), &custom.constraint_degrees()
         ].concat();
 
+        // if we are in debug mode, make sure boundary constraints are satisfied
+        if cfg!(debug_assertions) {
+            let boundary = BoundaryEvaluator::new(last_state.program_hash(), last_state.op_counter(), inputs, outputs);
+            let first_state = trace.get_state(TraceStep(0));
+            let mut evaluations = vec![field::ZERO; boundary.first_constraint_count(&first_state)];
+            boundary.evaluate_first(&first_state, &mut evaluations);
+            assert!(evaluations.iter().all(|&v| v == field::ZERO), "boundary constraints at the first step were not satisfied");
+
+            let mut evaluations = vec![field::ZERO; boundary.last_constraint_count(&last_state)];
+            boundary.evaluate_last(&last_state, &mut evaluations);
+            assert!(evaluations.iter().all(|&v| v == field::ZERO), "boundary constraints at the last step were not satisfied");
+
+            if let Some(tape) = tape {
+                assert!(*tape == trace.get_output_tape(), "output tape does not match the execution trace");
+            }
+        }
+
+        let (w_points, w_poly, w_degree_adj) = build_tape_constraints(tape, trace_length);
+
         // if we are in debug mode, initialize vectors to hold individual evaluations
         // of transition constraints
         let domain_size = trace_length * extension_factor;
@@ -63,7 +98,8 @@ impl Evaluator {
         return Evaluator {
             decoder         : decoder,
             stack           : stack,
-            coefficients    : ConstraintCoefficients::new(*trace_root, ctx_depth, loop_depth, stack_depth),
+            custom          : custom.clone(),
+            coefficients    : ConstraintCoefficients::new(*trace_root, transcript_fn, ctx_depth, loop_depth, stack_depth, custom.len()),
             domain_size     : domain_size,
             extension_factor: extension_factor,
             t_constraint_num: t_constraint_degrees.len(),
@@ -75,10 +111,16 @@ impl Evaluator {
             inputs          : inputs.to_vec(),
             outputs         : outputs.to_vec(),
             b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
+            w_op_bits       : get_write_op_bits(),
+            w_points        : w_points,
+            w_poly          : w_poly,
+            w_degree_adj    : w_degree_adj,
         };
     }
 
-    pub fn from_proof(proof: &StarkProof, program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    /// Creates a constraint evaluator for the trace committed to in the `proof`; if the proof
+    /// contains an output tape, the tape must have been validated beforehand.
+    pub fn from_proof(proof: &StarkProof, program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], custom: &CustomConstraints) -> Evaluator
     {
         let ctx_depth = proof.ctx_depth();
         let loop_depth = proof.loop_depth();
@@ -87,18 +129,21 @@ impl Evaluator {
         let extension_factor = proof.options().extension_factor();
         
         // instantiate decoder and stack constraint evaluators 
-        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
-        let stack = Stack::new(trace_length, extension_factor, stack_depth);
+        let decoder = Decoder::new(trace_length, extension_factor, DOMAIN_OFFSET, ctx_depth, loop_depth);
+        let stack = Stack::new(trace_length, extension_factor, DOMAIN_OFFSET, stack_depth);
 
         // build a list of transition constraint degrees
         let t_constraint_degrees = [
-            decoder.constraint_degrees(), stack.constraint_degrees()
+            decoder.constraint_degrees(), stack.constraint_degrees(), &custom.constraint_degrees()
         ].concat();
 
+        let (w_points, w_poly, w_degree_adj) = build_tape_constraints(proof.tape(), trace_length);
+
         return Evaluator {
             decoder         : decoder,
             stack           : stack,
-            coefficients    : ConstraintCoefficients::new(*proof.trace_root(), ctx_depth, loop_depth, stack_depth),
+            custom          : custom.clone(),
+            coefficients    : ConstraintCoefficients::new(*proof.trace_root(), proof.options().transcript_fn(), ctx_depth, loop_depth, stack_depth, custom.len()),
             domain_size     : proof.domain_size(),
             extension_factor: extension_factor,
             t_constraint_num: t_constraint_degrees.len(),
@@ -110,6 +155,10 @@ impl Evaluator {
             inputs          : inputs.to_vec(),
             outputs         : outputs.to_vec(),
             b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
+            w_op_bits       : get_write_op_bits(),
+            w_points        : w_points,
+            w_poly          : w_poly,
+            w_degree_adj    : w_degree_adj,
         };
     }
 
@@ -117,6 +166,11 @@ impl Evaluator {
         return self.t_constraint_num + self.b_constraint_num;
     }
 
+    /// Returns the number of transition constraints, including custom constraints.
+    pub fn transition_constraint_count(&self) -> usize {
+        return self.t_constraint_num;
+    }
+
     pub fn domain_size(&self) -> usize {
         return self.domain_size;
     }
@@ -130,35 +184,103 @@ impl Evaluator {
         return field::exp(trace_root, (self.trace_length() - 1) as u128);
     }
 
+    /// Returns the smallest extension factor which accommodates transition constraints of this
+    /// evaluator (including hash round and custom constraints): evaluations of a constraint of
+    /// degree d are extended into a domain d times larger than the trace, and FRI requires the
+    /// result to be extended at least 2 times further. The factor is a power of 2, and is never
+    /// smaller than the minimum extension factor accepted by proof options.
+    pub fn min_extension_factor(&self) -> usize {
+        let max_degree = [
+            self.decoder.constraint_degrees(), self.stack.constraint_degrees(), &self.custom.constraint_degrees()
+        ].concat().into_iter().max().unwrap_or(1);
+        return cmp::max(2 * max_degree.next_power_of_two(), MIN_EXTENSION_FACTOR);
+    }
+
+    /// Returns true if the evaluator enforces constraints of a public output tape with at least
+    /// one value written to it.
+    pub fn has_tape_constraints(&self) -> bool {
+        return self.w_points.len() > 0;
+    }
+
+    /// Returns x coordinates of the steps at which values were written to the output tape;
+    /// output tape constraints must be divided by (x - x_i) for all of these.
+    pub fn get_tape_points(&self) -> &[u128] {
+        return &self.w_points;
+    }
+
+    /// Returns the number of field multiplications performed when evaluating constraints over
+    /// the evaluation domain of an execution trace of the specified length, where the domain is
+    /// `extension_factor` times larger than the trace; this is the cost of evaluating transition,
+    /// boundary, and output tape constraints at every step of the domain in the same way as the
+    /// prover does. Multiplications performed inside custom constraints are not included.
+    ///
+    /// The count is an upper bound which does not depend on the executed program: the S-Box is
+    /// not applied to the sponge of the next state when the sponge is all zeros, and the actual
+    /// count is smaller by `NEXT_SPONGE_SBOX_MUL_COUNT` for every such step. Since the evaluation
+    /// domain does not intersect the trace domain, such steps are rare.
+    pub fn mul_count(&self, trace_length: usize, extension_factor: usize) -> u64 {
+        let domain_size = (trace_length * extension_factor) as u64;
+        let stack_registers = (cmp::max(self.decoder.ctx_depth(), MIN_CONTEXT_DEPTH)
+            + cmp::max(self.decoder.loop_depth(), MIN_LOOP_DEPTH)) as u64;
+        let num_op_bits = (NUM_CF_OP_BITS + NUM_LD_OP_BITS + NUM_HD_OP_BITS) as u64;
+
+        // transition constraints are evaluated against current and next states (operation flags
+        // are computed for both), and are combined at every step of the domain
+        let mut transition = 2 * OP_FLAGS_MUL_COUNT
+            + self.decoder.mul_count() + self.stack.mul_count() + self.stack.hash_mul_count();
+        transition = transition * domain_size;
+
+        let degrees = [
+            self.decoder.constraint_degrees(), self.stack.constraint_degrees(), &self.custom.constraint_degrees()
+        ].concat();
+        let mut combination = 2 * self.t_constraint_num as u64;
+        for (incremental_degree, _) in group_transition_constraints(degrees, trace_length) {
+            combination += get_exp_mul_count(incremental_degree) + 1;
+        }
+        transition += combination * domain_size;
+
+        // boundary constraints for the first and the last steps share the degree adjustment
+        let i_boundary = 1 + SPONGE_WIDTH as u64 + num_op_bits + stack_registers + self.inputs.len() as u64;
+        let f_boundary = 1 + PROGRAM_DIGEST_SIZE as u64 + num_op_bits + stack_registers + self.outputs.len() as u64;
+        let b_degree_adj = get_boundary_constraint_adjustment_degree(trace_length);
+        let boundary = 2 * (i_boundary + f_boundary) + 2 + get_exp_mul_count(b_degree_adj);
+
+        // output tape constraints include evaluation of the polynomial of written values
+        let tape = if self.has_tape_constraints() {
+            let target_degree = get_boundary_constraint_target_degree(trace_length) + self.w_points.len() - 1;
+            let w_degree_adj = (target_degree - (trace_length - 1)) as u128;
+            2 * (num_op_bits + 1) + 2 * self.w_poly.len() as u64 + 1 + get_exp_mul_count(w_degree_adj)
+        }
+        else {
+            0
+        };
+
+        return transition + (boundary + tape) * domain_size;
+    }
+
     // CONSTRAINT EVALUATORS
     // -------------------------------------------------------------------------------------------
 
     /// Computes pseudo-random linear combination of transition constraints D_i at point x as:
     /// cc_{i * 2} * D_i + cc_{i * 2 + 1} * D_i * x^p for all i, where cc_j are the coefficients
     /// used in the linear combination and x^p is a degree adjustment factor (different for each degree).
-    pub fn evaluate_transition(&self, current: &TraceState, next: &TraceState, x: u128, step: usize) -> u128 {
+    /// `evaluations` must hold transition_constraint_count() values; it is overwritten with
+    /// evaluations of individual constraints, and can be re-used across steps.
+    pub fn evaluate_transition(&self, current: &TraceState, next: &TraceState, x: u128, step: EvalStep, evaluations: &mut [u128]) -> u128 {
         
         // evaluate transition constraints
-        let mut evaluations = vec![field::ZERO; self.t_constraint_num];
-        self.decoder.evaluate(&current, &next, step, &mut evaluations);
-        self.stack.evaluate(&current, &next, step, &mut evaluations[self.decoder.constraint_count()..]);
+        evaluations.iter_mut().for_each(|value| *value = field::ZERO);
+        self.decoder.evaluate(&current, &next, step, evaluations);
+        self.stack.evaluate(&current, &next, step, &mut evaluations[self.decoder.constraint_count()..self.custom_offset()]);
+        self.custom.evaluate(&current, &next, x, &mut evaluations[self.custom_offset()..]);
 
         // when in debug mode, save transition evaluations before they are combined
         #[cfg(debug_assertions)]
-        self.save_transition_evaluations(&evaluations, step);
-
-        // if the constraints should evaluate to all zeros at this step,
-        // make sure they do, and return
-        if self.should_evaluate_to_zero_at(step) {
-            let step = step / self.extension_factor;
-            for i in 0..evaluations.len() {
-                assert!(evaluations[i] == field::ZERO, "transition constraint at step {} were not satisfied", step);
-            }
-            return field::ZERO;
-        }
+        self.save_transition_evaluations(evaluations, step);
 
-        // compute a pseudo-random linear combination of all transition constraints
-        return self.combine_transition_constraints(&evaluations, x);
+        // the evaluation domain is shifted off the trace domain, and thus, the constraints are
+        // combined at every step; compute a pseudo-random linear combination of all transition constraints
+        return self.combine_transition_constraints(evaluations, x);
     }
 
     /// Computes pseudo-random liner combination of transition constraints at point x. This function
@@ -168,7 +290,8 @@ impl Evaluator {
         // evaluate transition constraints
         let mut evaluations = vec![field::ZERO; self.t_constraint_num];
         self.decoder.evaluate_at(&current, &next, x, &mut evaluations);
-        self.stack.evaluate_at(&current, &next, x, &mut evaluations[self.decoder.constraint_count()..]);
+        self.stack.evaluate_at(&current, &next, x, &mut evaluations[self.decoder.constraint_count()..self.custom_offset()]);
+        self.custom.evaluate(&current, &next, x, &mut evaluations[self.custom_offset()..]);
 
         // compute a pseudo-random linear combination of all transition constraints
         return self.combine_transition_constraints(&evaluations, x);
@@ -325,44 +448,54 @@ impl Evaluator {
         return (i_result, f_result);
     }
 
-    // HELPER METHODS
-    // -------------------------------------------------------------------------------------------
-    fn should_evaluate_to_zero_at(&self, step: usize) -> bool {
-        return (step & (self.extension_factor - 1) == 0) // same as: step % extension_factor == 0
-            && (step != self.domain_size - self.extension_factor);
-    }
-
-    fn combine_transition_constraints(&self, evaluations: &Vec<u128>, x: u128) -> u128 {
-        let cc = &self.coefficients.transition;
+    /// Computes pseudo-random linear combination of output tape constraints W_i at point x; the
+    /// constraints ensure that at every step at which a value was written to the tape, op_bits
+    /// are set to WRITE and the top of the stack is set to the written value. The constraints
+    /// are computed as: cc_{i * 2} * W_i + cc_{i * 2 + 1} * W_i * x^p for all i, where cc_j are
+    /// the coefficients used in the linear combination and x^p is a degree adjustment factor.
+    pub fn evaluate_tape(&self, current: &TraceState, x: u128) -> u128 {
+        let cc = &self.coefficients.tape;
         let mut result = field::ZERO;
+        let mut result_adj = field::ZERO;
 
-        let mut i = 0;
-        for (incremental_degree, constraints) in self.t_degree_groups.iter() {
-
-            // for each group of constraints with the same degree, separately compute
-            // combinations of D(x) and D(x) * x^p
-            let mut result_adj = field::ZERO;
-            for &constraint_idx in constraints.iter() {
-                let evaluation = evaluations[constraint_idx];
-                result = field::add(result, field::mul(evaluation, cc[i * 2]));
-                result_adj = field::add(result_adj, field::mul(evaluation, cc[i * 2 + 1]));
-                i += 1;
-            }
-
-            // increase the degree of D(x) * x^p
-            let xp = field::exp(x, *incremental_degree);
-            result = field::add(result, field::mul(result_adj, xp));
+        // make sure op_bits are set to HACC (000) and WRITE (1101111)
+        let op_bits = current.cf_op_bits().iter().chain(current.ld_op_bits()).chain(current.hd_op_bits());
+        for (i, (&value, &expected)) in op_bits.zip(self.w_op_bits.iter()).enumerate() {
+            let val = field::sub(value, expected);
+            result = field::add(result, field::mul(val, cc.op_bits[i * 2]));
+            result_adj = field::add(result_adj, field::mul(val, cc.op_bits[i * 2 + 1]));
         }
 
-        return result;
+        // make sure the top of the stack is set to the written value; written values are
+        // interpolated into a polynomial over the steps at which they were written
+        let val = field::sub(current.user_stack()[0], polynom::eval(&self.w_poly, x));
+        result = field::add(result, field::mul(val, cc.value[0]));
+        result_adj = field::add(result_adj, field::mul(val, cc.value[1]));
+
+        // raise the degree of adjusted terms and sum all the terms together
+        let xp = field::exp(x, self.w_degree_adj);
+        return field::add(result, field::mul(result_adj, xp));
+    }
+
+    // HELPER METHODS
+    // -------------------------------------------------------------------------------------------
+
+    /// Returns the index of the first custom constraint; custom constraints are placed after
+    /// decoder and stack constraints.
+    fn custom_offset(&self) -> usize {
+        return self.t_constraint_num - self.custom.len();
+    }
+
+    fn combine_transition_constraints(&self, evaluations: &[u128], x: u128) -> u128 {
+        return combine_transition_constraints(evaluations, x, &self.t_degree_groups, &self.coefficients.transition);
     }
 
     #[cfg(debug_assertions)]
-    fn save_transition_evaluations(&self, evaluations: &[u128], step: usize) {
+    fn save_transition_evaluations(&self, evaluations: &[u128], step: EvalStep) {
         unsafe {
             let mutable_self = &mut *(self as *const _ as *mut Evaluator);
             for i in 0..evaluations.len() {
-                mutable_self.t_evaluations[i][step] = evaluations[i];
+                mutable_self.t_evaluations[i][step.0] = evaluations[i];
             }
         }
     }
@@ -375,14 +508,114 @@ impl Evaluator {
     #[cfg(debug_assertions)]
     pub fn get_transition_degrees(&self) -> Vec<usize> {
         return [
-            self.decoder.constraint_degrees(), self.stack.constraint_degrees()
+            self.decoder.constraint_degrees(), self.stack.constraint_degrees(), &self.custom.constraint_degrees()
         ].concat();
     }
 }
 
+// GENERIC COMPOSITION
+// ================================================================================================
+
+/// Default number of evaluation domain steps composed at once by compose_constraints().
+pub const DEFAULT_COMPOSITION_CHUNK_SIZE: usize = 1024;
+
+/// Evaluates `constraints` over the constraint evaluation domain of the extended `trace` (the
+/// same steps at which the prover evaluates built-in constraints), and combines evaluations at
+/// every step into a single value in the same way as built-in transition constraints are
+/// combined: constraints are grouped by degree, and each constraint D_i contributes
+/// cc_{i * 2} * D_i + cc_{i * 2 + 1} * D_i * x^p, where i is the position of the constraint
+/// after grouping and x^p is a degree adjustment factor of its group.
+pub fn compose_constraints(constraints: &[&dyn Constraint], trace: &TraceTable, coefficients: &[u128]) -> Vec<u128> {
+    let mut result = Vec::with_capacity(trace.unextended_length() * MAX_CONSTRAINT_DEGREE);
+    compose_constraints_chunked(constraints, trace, coefficients, DEFAULT_COMPOSITION_CHUNK_SIZE,
+        |_, chunk| result.extend_from_slice(chunk));
+    return result;
+}
+
+/// Computes the same composition as compose_constraints(), but streams it in chunks of at most
+/// `chunk_size` consecutive evaluation domain steps: `sink` is invoked with the index of the
+/// first step of the chunk and combined evaluations of the chunk, and the buffer holding the
+/// chunk is reused for the next chunk. Thus, memory allocated for evaluation results is bounded
+/// by composition_buffer_size() regardless of the length of the trace; memory allocated by
+/// `constraints` themselves is not covered by the bound.
+pub fn compose_constraints_chunked<F>(constraints: &[&dyn Constraint], trace: &TraceTable, coefficients: &[u128], chunk_size: usize, mut sink: F)
+    where F: FnMut(usize, &[u128])
+{
+    assert!(trace.is_extended(), "execution trace has not been extended yet");
+    assert!(coefficients.len() >= 2 * constraints.len(),
+        "expected at least {} coefficients, but received {}", 2 * constraints.len(), coefficients.len());
+    assert!(chunk_size > 0, "chunk size must be greater than 0");
+
+    let trace_length = trace.unextended_length();
+    let extension_factor = trace.extension_factor();
+    let degrees = constraints.iter().map(|constraint| constraint.degree()).collect();
+    let degree_groups = group_transition_constraints(degrees, trace_length);
+    let lde_domain = lde_domain(trace_length, extension_factor);
+
+    let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+    let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+    let mut evaluations = vec![field::ZERO; constraints.len()];
+
+    let composition_size = trace_length * MAX_CONSTRAINT_DEGREE;
+    let mut chunk = Vec::with_capacity(cmp::min(chunk_size, composition_size));
+
+    let stride = extension_factor / MAX_CONSTRAINT_DEGREE;
+    for chunk_start in (0..composition_size).step_by(chunk_size) {
+        chunk.clear();
+        for step in chunk_start..cmp::min(chunk_start + chunk_size, composition_size) {
+            let i = step * stride;
+            trace.fill_state(&mut current, LdeStep(i));
+            trace.fill_state(&mut next, LdeStep((i + extension_factor) % trace.domain_size()));
+
+            let x = lde_domain[i];
+            for (evaluation, constraint) in evaluations.iter_mut().zip(constraints.iter()) {
+                *evaluation = constraint.evaluate(&current, &next, x);
+            }
+            chunk.push(combine_transition_constraints(&evaluations, x, &degree_groups, coefficients));
+        }
+        sink(chunk_start, &chunk);
+    }
+}
+
+/// Returns an upper bound (in bytes) on memory allocated for evaluation results by
+/// compose_constraints_chunked() for `num_constraints` constraints and the specified
+/// `chunk_size`: a chunk of combined evaluations plus evaluations of all constraints at a
+/// single step. Trace states and the LDE domain are not included in the bound.
+pub fn composition_buffer_size(num_constraints: usize, chunk_size: usize) -> usize {
+    return (chunk_size + num_constraints) * std::mem::size_of::<u128>();
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
-fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize) -> Vec<(u128, Vec<usize>)> {
+
+/// Computes pseudo-random linear combination of transition constraint `evaluations` at point x;
+/// `degree_groups` lists the constraints grouped by degree as returned from
+/// group_transition_constraints().
+fn combine_transition_constraints(evaluations: &[u128], x: u128, degree_groups: &[(u128, Vec<usize>)], cc: &[u128]) -> u128 {
+    let mut result = field::ZERO;
+
+    let mut i = 0;
+    for (incremental_degree, constraints) in degree_groups.iter() {
+
+        // for each group of constraints with the same degree, separately compute
+        // combinations of D(x) and D(x) * x^p
+        let mut result_adj = field::ZERO;
+        for &constraint_idx in constraints.iter() {
+            let evaluation = evaluations[constraint_idx];
+            result = field::add(result, field::mul(evaluation, cc[i * 2]));
+            result_adj = field::add(result_adj, field::mul(evaluation, cc[i * 2 + 1]));
+            i += 1;
+        }
+
+        // increase the degree of D(x) * x^p
+        let xp = field::exp(x, *incremental_degree);
+        result = field::add(result, field::mul(result_adj, xp));
+    }
+
+    return result;
+}
+
+pub fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize) -> Vec<(u128, Vec<usize>)> {
     let mut groups = [
         Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(),
         Vec::new(), Vec::new(), Vec::new(), Vec::new(),
@@ -405,7 +638,13 @@ fn group_transition_constraints(degrees: Vec<usize>, trace_length: usize) -> Vec
     return result;
 }
 
-fn get_boundary_constraint_adjustment_degree(trace_length: usize) -> u128 {
+/// Returns the number of multiplications performed by field::exp() for exponent `p`.
+fn get_exp_mul_count(p: u128) -> u64 {
+    if p == 0 { return 0; }
+    return (p.count_ones() + (128 - p.leading_zeros())) as u64;
+}
+
+pub fn get_boundary_constraint_adjustment_degree(trace_length: usize) -> u128 {
     let target_degree = get_boundary_constraint_target_degree(trace_length);
     let boundary_constraint_degree = trace_length - 1;
     return (target_degree - boundary_constraint_degree) as u128;
@@ -429,6 +668,38 @@ fn get_transition_constraint_target_degree(trace_length: usize) -> usize {
     return combination_degree + divisor_degree;
 }
 
+/// Returns x coordinates of the steps at which values were written to the `tape`, a polynomial
+/// interpolated from the written values over these coordinates, and the degree adjustment for
+/// output tape constraints. The degree is adjusted so that when divided by the tape constraint
+/// divisor (degree m polynomial, for m written values), the degree of the result is the same as
+/// for boundary constraints.
+fn build_tape_constraints(tape: Option<&OutputTape>, trace_length: usize) -> (Vec<u128>, Vec<u128>, u128) {
+    let tape = match tape {
+        Some(tape) if tape.steps.len() > 0 => tape,
+        _ => return (Vec::new(), Vec::new(), 0),
+    };
+
+    let trace_root = field::get_root_of_unity(trace_length);
+    let points: Vec<u128> = tape.steps.iter().map(|&step| field::exp(trace_root, step as u128)).collect();
+    let poly = polynom::interpolate(&points, &tape.values);
+
+    let divisor_degree = points.len();
+    let target_degree = get_boundary_constraint_target_degree(trace_length) + divisor_degree - 1;
+    let degree_adj = (target_degree - (trace_length - 1)) as u128;
+
+    return (points, poly, degree_adj);
+}
+
+/// Returns expected values of op_bits at a step at which WRITE operation is executed.
+fn get_write_op_bits() -> Vec<u128> {
+    let op_code = OpCode::Write.to_u8() as u128;
+    let mut result = vec![field::ZERO; NUM_CF_OP_BITS];
+    for i in 0..(NUM_LD_OP_BITS + NUM_HD_OP_BITS) {
+        result.push((op_code >> i) & 1);
+    }
+    return result;
+}
+
 fn parse_program_hash(program_hash: &[u8; 32]) -> Vec<u128> {
     return vec![
         field::from_bytes(&program_hash[..16]),
@@ -441,4 +712,221 @@ fn get_boundary_constraint_num(inputs: &[u128], outputs: &[u128]) -> usize {
         PROGRAM_DIGEST_SIZE 
         + inputs.len() + outputs.len()
         + 1 /* for op_count */;
-}
\ No newline at end of file
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use crate::{
+        crypto::hash, math::{ field, fft }, processor, programs::assembly, ProgramInputs,
+        stark::{ TraceTable, TraceState, LdeStep, MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET, lde_domain },
+    };
+    use super::{
+        Evaluator, Stack, ExtendedArk, CustomConstraints,
+        super::{ Constraint, ConstraintTable, HashEvaluator, NUM_AUX_STACK_CONSTRAINTS },
+        compose_constraints, compose_constraints_chunked, composition_buffer_size, combine_transition_constraints, group_transition_constraints,
+    };
+    use super::super::decoder::NEXT_SPONGE_SBOX_MUL_COUNT;
+
+    #[test]
+    fn mul_count() {
+        let programs = [
+            // hashing and writing to the output tape
+            "begin push.3 push.5 add push.7 hash.2 write dup mul write end",
+            // nested blocks and a deep stack
+            "begin push.1 if.true push.2 push.3 push.4 push.5 push.6 push.7 push.8 push.9 add else noop end end",
+        ];
+
+        for source in programs.iter() {
+            let program = assembly::compile(source).unwrap();
+            let (trace, ctx_depth, loop_depth) = processor::execute(&program, &ProgramInputs::none());
+            let trace_length = trace[0].len();
+            let extension_factor = 2 * MAX_CONSTRAINT_DEGREE;
+
+            let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, extension_factor);
+            let lde_root = field::get_root_of_unity(trace.domain_size());
+            let trace_root = field::get_root_of_unity(trace_length);
+            trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()), &fft::get_inv_twiddles(trace_root, trace_length), 1);
+
+            let outputs = trace.get_last_state().user_stack()[..2].to_vec();
+            let tape = trace.get_output_tape();
+            let stack_ark = Arc::new(ExtendedArk::new(trace_length, MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET));
+            let evaluator = Evaluator::from_trace(&trace, &[0; 32], hash::blake3, &[], &outputs, Some(&tape),
+                &CustomConstraints::new(), stack_ark);
+
+            // evaluate all constraints in the same way as the prover does, and keep track of
+            // steps at which the sponge of the next state was all zeros
+            let mut current = TraceState::new(ctx_depth, loop_depth, trace.stack_depth());
+            let mut next = TraceState::new(ctx_depth, loop_depth, trace.stack_depth());
+            let mut evaluations = vec![field::ZERO; evaluator.transition_constraint_count()];
+            let lde_domain = lde_domain(trace_length, extension_factor);
+
+            let mut actual = 0;
+            let mut sponge_resets = 0;
+            for i in (0..trace.domain_size()).step_by(extension_factor / MAX_CONSTRAINT_DEGREE) {
+                trace.fill_state(&mut current, LdeStep(i));
+                trace.fill_state(&mut next, LdeStep((i + extension_factor) % trace.domain_size()));
+
+                let x = lde_domain[i];
+                let count = field::mul_count();
+                evaluator.evaluate_boundaries(&current, x);
+                evaluator.evaluate_transition(&current, &next, x, LdeStep(i).to_eval(extension_factor), &mut evaluations);
+                if evaluator.has_tape_constraints() {
+                    evaluator.evaluate_tape(&current, x);
+                }
+                actual += field::mul_count() - count;
+
+                if next.sponge().iter().all(|&value| value == field::ZERO) {
+                    sponge_resets += 1;
+                }
+            }
+
+            let expected = evaluator.mul_count(trace_length, MAX_CONSTRAINT_DEGREE)
+                - sponge_resets * NEXT_SPONGE_SBOX_MUL_COUNT;
+            assert_eq!(expected, actual, "multiplication count did not match for {}", source);
+        }
+    }
+
+    #[test]
+    fn min_extension_factor() {
+        let program = assembly::compile("begin push.3 push.5 add push.7 hash.2 end").unwrap();
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &ProgramInputs::none());
+        let trace_length = trace[0].len();
+        let trace = TraceTable::new(trace, ctx_depth, loop_depth, 2 * MAX_CONSTRAINT_DEGREE);
+        let outputs = trace.get_last_state().user_stack()[..2].to_vec();
+        let stack_ark = Arc::new(ExtendedArk::new(trace_length, MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET));
+
+        // the highest degree of built-in constraints is MAX_CONSTRAINT_DEGREE
+        let evaluator = Evaluator::from_trace(&trace, &[0; 32], hash::blake3, &[], &outputs, None, &CustomConstraints::new(), stack_ark.clone());
+        assert_eq!(16, evaluator.min_extension_factor());
+
+        // custom constraints are accounted for, but cannot exceed MAX_CONSTRAINT_DEGREE
+        let custom = CustomConstraints::new().with(Degree(3)).with(Degree(MAX_CONSTRAINT_DEGREE));
+        let evaluator = Evaluator::from_trace(&trace, &[0; 32], hash::blake3, &[], &outputs, None, &custom, stack_ark);
+        assert_eq!(2 * MAX_CONSTRAINT_DEGREE, evaluator.min_extension_factor());
+    }
+
+    #[test]
+    fn compose_hash_constraints() {
+        let program = assembly::compile("begin push.3 push.5 add push.7 hash.2 end").unwrap();
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &ProgramInputs::none());
+        let trace_length = trace[0].len();
+        let extension_factor = 2 * MAX_CONSTRAINT_DEGREE;
+
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, extension_factor);
+        let lde_root = field::get_root_of_unity(trace.domain_size());
+        let trace_root = field::get_root_of_unity(trace_length);
+        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()), &fft::get_inv_twiddles(trace_root, trace_length), 1);
+
+        let outputs = trace.get_last_state().user_stack()[..2].to_vec();
+        let stack_ark = Arc::new(ExtendedArk::new(trace_length, MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET));
+        let stack = Stack::with_ark(trace_length, trace.stack_depth(), stack_ark.clone());
+        let hash_constraints = HashEvaluator::all(trace_length, trace.stack_depth());
+        assert_eq!(trace.stack_depth(), hash_constraints.len());
+
+        // build constraint tables in the same way as the prover does: one with hash constraints
+        // as custom constraints, and one with the same number of constraints of the same degree
+        // which are always zero; since coefficients are assigned by degree groups, both tables
+        // use the same coefficients for the custom constraints
+        let mut hash_custom = CustomConstraints::new();
+        let mut zero_custom = CustomConstraints::new();
+        for constraint in HashEvaluator::all(trace_length, trace.stack_depth()) {
+            zero_custom = zero_custom.with(Degree(constraint.degree()));
+            hash_custom = hash_custom.with(constraint);
+        }
+        let mut hash_table = ConstraintTable::new(&trace, &[0; 32], hash::blake3, &[], &outputs, None, &hash_custom, None, stack_ark.clone());
+        let mut zero_table = ConstraintTable::new(&trace, &[0; 32], hash::blake3, &[], &outputs, None, &zero_custom, None, stack_ark.clone());
+
+        let mut current = TraceState::new(ctx_depth, loop_depth, trace.stack_depth());
+        let mut next = TraceState::new(ctx_depth, loop_depth, trace.stack_depth());
+        let lde_domain = lde_domain(trace_length, extension_factor);
+        for i in (0..trace.domain_size()).step_by(extension_factor / MAX_CONSTRAINT_DEGREE) {
+            trace.fill_state(&mut current, LdeStep(i));
+            trace.fill_state(&mut next, LdeStep((i + extension_factor) % trace.domain_size()));
+            let step = LdeStep(i).to_eval(extension_factor);
+            hash_table.evaluate(&current, &next, &[], &[], lde_domain[i], step);
+            zero_table.evaluate(&current, &next, &[], &[], lde_domain[i], step);
+        }
+        let table_evaluations: Vec<u128> = hash_table.get_transition_evaluations().iter()
+            .zip(zero_table.get_transition_evaluations().iter())
+            .map(|(&with_hash, &without_hash)| field::sub(with_hash, without_hash))
+            .collect();
+
+        // pick the coefficients the prover assigned to the custom constraints
+        let evaluator = Evaluator::from_trace(&trace, &[0; 32], hash::blake3, &[], &outputs, None, &hash_custom, stack_ark);
+        let first_custom = evaluator.t_constraint_num - hash_constraints.len();
+        let positions: Vec<usize> = evaluator.t_degree_groups.iter().flat_map(|(_, group)| group.iter()).cloned().collect();
+        let mut coefficients = vec![field::ZERO; 2 * hash_constraints.len()];
+        for (position, &index) in positions.iter().enumerate().filter(|(_, &index)| index >= first_custom) {
+            coefficients[2 * (index - first_custom)] = evaluator.coefficients.transition[2 * position];
+            coefficients[2 * (index - first_custom) + 1] = evaluator.coefficients.transition[2 * position + 1];
+        }
+
+        // compose hash constraints via the generic path; the result matches the contribution
+        // of these constraints to the constraint table
+        let constraints: Vec<&dyn Constraint> = hash_constraints.iter().map(|c| c as &dyn Constraint).collect();
+        let actual = compose_constraints(&constraints, &trace, &coefficients);
+        assert_eq!(trace_length * MAX_CONSTRAINT_DEGREE, actual.len());
+        assert_eq!(table_evaluations, actual);
+
+        // the result also matches built-in hash constraint evaluation
+        let degree_groups = group_transition_constraints(stack.constraint_degrees()[NUM_AUX_STACK_CONSTRAINTS..].to_vec(), trace_length);
+        let mut evaluations = vec![field::ZERO; stack.constraint_degrees().len()];
+        let mut expected = Vec::new();
+        for i in (0..trace.domain_size()).step_by(extension_factor / MAX_CONSTRAINT_DEGREE) {
+            trace.fill_state(&mut current, LdeStep(i));
+            trace.fill_state(&mut next, LdeStep((i + extension_factor) % trace.domain_size()));
+            stack.evaluate_hash(&current, &next, LdeStep(i).to_eval(extension_factor), &mut evaluations);
+            let evaluations = &evaluations[NUM_AUX_STACK_CONSTRAINTS..];
+            expected.push(combine_transition_constraints(evaluations, lde_domain[i], &degree_groups, &coefficients));
+        }
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn compose_constraints_in_chunks() {
+        let program = assembly::compile("begin push.3 push.5 add push.7 hash.2 end").unwrap();
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &ProgramInputs::none());
+        let trace_length = trace[0].len();
+        let extension_factor = 2 * MAX_CONSTRAINT_DEGREE;
+
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, extension_factor);
+        let lde_root = field::get_root_of_unity(trace.domain_size());
+        let trace_root = field::get_root_of_unity(trace_length);
+        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()), &fft::get_inv_twiddles(trace_root, trace_length), 1);
+
+        let hash_constraints = HashEvaluator::all(trace_length, trace.stack_depth());
+        let constraints: Vec<&dyn Constraint> = hash_constraints.iter().map(|c| c as &dyn Constraint).collect();
+        let coefficients: Vec<u128> = (1..=(2 * constraints.len() as u128)).map(|i| field::exp(7, i)).collect();
+        let expected = compose_constraints(&constraints, &trace, &coefficients);
+        let composition_size = trace_length * MAX_CONSTRAINT_DEGREE;
+
+        for &chunk_size in [1, 7, composition_size, composition_size + 1].iter() {
+            let mut actual = Vec::new();
+            compose_constraints_chunked(&constraints, &trace, &coefficients, chunk_size, |start, chunk| {
+                assert_eq!(actual.len(), start);
+                assert!(chunk.len() > 0 && chunk.len() <= chunk_size);
+                actual.extend_from_slice(chunk);
+            });
+            assert_eq!(expected, actual);
+        }
+
+        assert_eq!((7 + constraints.len()) * 16, composition_buffer_size(constraints.len(), 7));
+    }
+
+    /// A constraint of the specified degree which is satisfied by any trace.
+    struct Degree(usize);
+
+    impl Constraint for Degree {
+        fn degree(&self) -> usize {
+            return self.0;
+        }
+
+        fn evaluate(&self, _current: &TraceState, _next: &TraceState, _x: u128) -> u128 {
+            return field::ZERO;
+        }
+    }
+}
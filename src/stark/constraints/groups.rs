@@ -0,0 +1,83 @@
+use crate::{ math::field, stark::{ TraceState, EvalStep, MAX_CONSTRAINT_DEGREE } };
+use super::{ decoder::Decoder, stack::Stack };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Groups of transition constraints of the VM which can be checked in isolation from each other.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstraintGroup {
+    /// Decoder constraints for op code bits and the operation counter.
+    OpBits,
+    /// Decoder constraints for flow control operations, including program hashing.
+    FlowControl,
+    /// All stack constraints, including hash round constraints.
+    Stack,
+    /// Stack constraints for rounds of Rescue hash function (RESCR operation) only.
+    Hash,
+}
+
+/// Describes a transition constraint which was not satisfied by an execution trace: `constraint`
+/// is the index of the constraint among the constraints of the decoder (for OpBits and
+/// FlowControl groups) or of the stack (for Stack and Hash groups), and `value` is what the
+/// constraint evaluated to at the transition from `step` to the next step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintViolation {
+    pub group       : ConstraintGroup,
+    pub step        : usize,
+    pub constraint  : usize,
+    pub value       : u128,
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Evaluates transition constraints of the specified `group` at every step of the execution
+/// trace defined by `registers` (except for the last one), and returns the first constraint
+/// which did not evaluate to zero.
+pub fn check_constraint_group(registers: &Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, group: ConstraintGroup) -> Result<(), ConstraintViolation>
+{
+    let trace_length = registers[0].len();
+    let stack_depth = registers.len() - TraceState::compute_decoder_width(ctx_depth, loop_depth);
+
+    // constraint evaluators require constants extended at least by max constraint degree; steps
+    // of the execution trace are every MAX_CONSTRAINT_DEGREE steps of the extended domain
+    let extension_factor = MAX_CONSTRAINT_DEGREE;
+    let decoder = Decoder::new(trace_length, extension_factor, field::ONE, ctx_depth, loop_depth);
+    let stack = Stack::new(trace_length, extension_factor, field::ONE, stack_depth);
+    let mut evaluations = match group {
+        ConstraintGroup::OpBits | ConstraintGroup::FlowControl => vec![field::ZERO; decoder.constraint_count()],
+        ConstraintGroup::Stack | ConstraintGroup::Hash => vec![field::ZERO; stack.constraint_degrees().len()],
+    };
+
+    let mut current = TraceState::new(ctx_depth, loop_depth, stack_depth);
+    let mut next = TraceState::new(ctx_depth, loop_depth, stack_depth);
+    for step in 0..(trace_length - 1) {
+        current.update_from_trace(registers, step);
+        next.update_from_trace(registers, step + 1);
+
+        let eval_step = EvalStep(step * extension_factor);
+        evaluations.iter_mut().for_each(|value| *value = field::ZERO);
+        match group {
+            ConstraintGroup::OpBits      => decoder.evaluate_op_bits(&current, &next, eval_step, &mut evaluations),
+            ConstraintGroup::FlowControl => decoder.evaluate_flow_ops(&current, &next, eval_step, &mut evaluations),
+            ConstraintGroup::Stack       => stack.evaluate(&current, &next, eval_step, &mut evaluations),
+            ConstraintGroup::Hash        => stack.evaluate_hash(&current, &next, eval_step, &mut evaluations),
+        }
+
+        if let Some(constraint) = evaluations.iter().position(|&value| value != field::ZERO) {
+            return Err(ConstraintViolation { group, step, constraint, value: evaluations[constraint] });
+        }
+    }
+
+    return Ok(());
+}
+
+// CONSTRAINT VIOLATION IMPLEMENTATION
+// ================================================================================================
+impl std::fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return write!(f, "{:?} constraint {} at step {} was not satisfied: evaluated to {}",
+            self.group, self.constraint, self.step, field::Element(self.value));
+    }
+}
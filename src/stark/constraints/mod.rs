@@ -1,12 +1,33 @@
 mod evaluator;
+mod boundary;
+mod custom;
+mod auxiliary;
+mod tape;
 mod decoder;
 mod stack;
 mod constraint_table;
 mod constraint_poly;
+mod groups;
 mod utils;
 
+#[cfg(debug_assertions)]
+mod self_test;
+
 pub use decoder::{ NUM_STATIC_DECODER_CONSTRAINTS };
-pub use stack::{ NUM_AUX_CONSTRAINTS as NUM_AUX_STACK_CONSTRAINTS };
-pub use evaluator::{ Evaluator as ConstraintEvaluator};
+pub use stack::{ NUM_AUX_CONSTRAINTS as NUM_AUX_STACK_CONSTRAINTS, ExtendedArk, HashEvaluator };
+pub use evaluator::{
+    Evaluator as ConstraintEvaluator, compose_constraints, compose_constraints_chunked, composition_buffer_size,
+    DEFAULT_COMPOSITION_CHUNK_SIZE };
+use boundary::{ BoundaryEvaluator };
+pub use custom::{ Constraint, CustomConstraints, MAX_CUSTOM_CONSTRAINTS };
+pub use auxiliary::{ AuxTrace, AuxFrame, AuxEvaluator, draw_aux_challenges, MAX_AUX_REGISTERS, MAX_AUX_CHALLENGES };
+pub use tape::{ TapeCounter };
+
+#[cfg(feature = "testing")]
+pub use stack::{ Stack as StackEvaluator };
 pub use constraint_table::{ ConstraintTable };
-pub use constraint_poly::{ ConstraintPoly };
\ No newline at end of file
+pub use constraint_poly::{ ConstraintPoly };
+pub use groups::{ ConstraintGroup, ConstraintViolation, check_constraint_group };
+
+#[cfg(debug_assertions)]
+pub use self_test::{ self_test };
\ No newline at end of file
@@ -0,0 +1,75 @@
+use crate::math::field;
+use crate::stark::{ TraceState, EvalStep };
+use super::{ decoder::Decoder, stack::Stack };
+
+// CONSTANTS
+// ================================================================================================
+const TRACE_LENGTH: usize = 16;
+const EXTENSION_FACTOR: usize = 8;
+const CTX_DEPTH: usize = 1;
+const LOOP_DEPTH: usize = 0;
+const STACK_DEPTH: usize = 8;
+const STEP: EvalStep = EvalStep(3 * EXTENSION_FACTOR);
+
+/// A HACC transition with RESCR operation; the states are arbitrary, and thus, most constraints
+/// evaluate to non-zero values. This makes the evaluations sensitive to changes in hash function
+/// parameters (e.g. MDS matrix or round constants).
+const CURRENT_STATE: [u128; 24] = [
+    5,  1, 2, 3, 4,  0, 0, 0,  1, 1, 1, 1, 1,  0, 1,  11,
+    1, 2, 3, 4, 5, 6, 7, 8,
+];
+const NEXT_STATE: [u128; 24] = [
+    6,  5, 6, 7, 8,  0, 0, 0,  1, 1, 1, 1, 1,  0, 1,  11,
+    9, 10, 11, 12, 13, 14, 15, 16,
+];
+
+const EXPECTED_DECODER_EVALUATIONS: [u128; 22] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    105291589941436574959458329984471458717, 162130340350616322778474160847875671859,
+    271726088756221122791838227477758470162, 313404601159331061683301760026560746894,
+    0, 0, 0,
+];
+
+const EXPECTED_STACK_EVALUATIONS: [u128; 10] = [
+    0, 0,
+    164260326890610864172519897419876849448, 32916280749381304116412033667950747185,
+    283670745211332255442649685560073802432, 296319550151880754317246124581201296633,
+    7936898432978170712584105702043120428, 59044703528496617942990269680984648032,
+    340282366920938463463374557953744961529, 340282366920938463463374557953744961529,
+];
+
+// SELF-TEST
+// ================================================================================================
+
+/// Evaluates decoder and stack constraints on a known transition and makes sure the results
+/// match the expected values; this is intended to catch accidental changes to constraint
+/// evaluators, and is executed only in debug mode.
+pub fn self_test() {
+    let current = TraceState::from_vec(CTX_DEPTH, LOOP_DEPTH, STACK_DEPTH, &CURRENT_STATE.to_vec());
+    let next = TraceState::from_vec(CTX_DEPTH, LOOP_DEPTH, STACK_DEPTH, &NEXT_STATE.to_vec());
+
+    let decoder = Decoder::new(TRACE_LENGTH, EXTENSION_FACTOR, field::ONE, CTX_DEPTH, LOOP_DEPTH);
+    let mut evaluations = vec![0; decoder.constraint_count()];
+    decoder.evaluate(&current, &next, STEP, &mut evaluations);
+    assert!(evaluations == EXPECTED_DECODER_EVALUATIONS,
+        "decoder constraint self-test failed: expected {:?}, but received {:?}",
+        EXPECTED_DECODER_EVALUATIONS, evaluations);
+
+    let stack = Stack::new(TRACE_LENGTH, EXTENSION_FACTOR, field::ONE, STACK_DEPTH);
+    let mut evaluations = vec![0; stack.constraint_degrees().len()];
+    stack.evaluate(&current, &next, STEP, &mut evaluations);
+    assert!(evaluations == EXPECTED_STACK_EVALUATIONS,
+        "stack constraint self-test failed: expected {:?}, but received {:?}",
+        EXPECTED_STACK_EVALUATIONS, evaluations);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn self_test() {
+        super::self_test();
+    }
+}
@@ -116,4 +116,57 @@ pub fn enforce_or(result: &mut [u128], aux: &mut[u128], old_stack: &[u128], new_
     // ensure that both operands are binary values
     aux.agg_constraint(0, op_flag, is_binary(x));
     aux.agg_constraint(1, op_flag, is_binary(y));
-}
\ No newline at end of file
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::{ field, are_equal };
+
+    #[test]
+    fn op_inv() {
+        // correct transition
+        let old_stack = [2, 3, 4, 0];
+        let new_stack = [field::inv(2), 3, 4, 0];
+        let mut evaluations = vec![0; 4];
+        super::enforce_inv(&mut evaluations, &old_stack, &new_stack, 1);
+        assert_eq!(vec![0, 0, 0, 0], evaluations);
+
+        // inverse of zero cannot satisfy the constraint
+        let old_stack = [0, 3, 4, 0];
+        for &inv_x in [0, 1, field::rand()].iter() {
+            let new_stack = [inv_x, 3, 4, 0];
+            let mut evaluations = vec![0; 4];
+            super::enforce_inv(&mut evaluations, &old_stack, &new_stack, 1);
+            assert_eq!(vec![field::ONE, 0, 0, 0], evaluations);
+        }
+    }
+
+    #[test]
+    fn op_div() {
+        // division is executed as INV followed by MUL: 6 / 2 = 3
+        let stack1 = [2, 6, 5, 0];
+        let stack2 = [field::inv(2), 6, 5, 0];
+        let stack3 = [3, 5, 0, 0];
+
+        let mut evaluations = vec![0; 4];
+        super::enforce_inv(&mut evaluations, &stack1, &stack2, 1);
+        super::enforce_mul(&mut evaluations, &stack2, &stack3, 1);
+        assert_eq!(vec![0, 0, 0, 0], evaluations);
+
+        // division by zero: 6 / 0
+        let stack1 = [0, 6, 5, 0];
+        let stack2 = [0, 6, 5, 0];
+        let stack3 = [0, 5, 0, 0];
+
+        let mut evaluations = vec![0; 4];
+        super::enforce_inv(&mut evaluations, &stack1, &stack2, 1);
+        assert_eq!(vec![are_equal(field::ONE, 0), 0, 0, 0], evaluations);
+
+        let mut evaluations = vec![0; 4];
+        super::enforce_mul(&mut evaluations, &stack2, &stack3, 1);
+        assert_eq!(vec![0, 0, 0, 0], evaluations);
+    }
+}
@@ -62,6 +62,27 @@ pub fn enforce_eq(result: &mut [u128], aux: &mut [u128], old_stack: &[u128], new
     aux.agg_constraint(0, op_flag, field::mul(new_stack[0], diff));
 }
 
+/// Evaluates constraints for ISZERO operation. These enforce that when x == 0, top of the stack
+/// at the next step is set to 1, otherwise top of the stack at the next step is set to 0.
+pub fn enforce_iszero(result: &mut [u128], aux: &mut [u128], old_stack: &[u128], new_stack: &[u128], op_flag: u128)
+{
+    // when x != 0, the first stack register contains inverse of x; otherwise, the register
+    // can contain any value
+    let x = old_stack[1];
+    let inv_x = old_stack[0];
+
+    // the operation is defined as 1 - x * inv(x)
+    let op_result = binary_not(field::mul(x, inv_x));
+    result.agg_constraint(0, op_flag, are_equal(new_stack[0], op_result));
+
+    // stack items beyond 2nd item are shifted the the left by 1
+    enforce_left_shift(result, old_stack, new_stack, 2, 1, op_flag);
+
+    // we also need to make sure that result * x = 0; this ensures that when x != 0 the result
+    // must be set to 0 even if the first stack register does not contain inverse of x
+    aux.agg_constraint(0, op_flag, field::mul(new_stack[0], x));
+}
+
 // INEQUALITY
 // ================================================================================================
 
@@ -131,4 +152,81 @@ pub fn enforce_binacc(result: &mut [u128], old_stack: &[u128], new_stack: &[u128
 
     // registers beyond 2nd register remained the same
     enforce_stack_copy(result, old_stack, new_stack, 4, op_flag);
-}
\ No newline at end of file
+}
+
+/// Evaluates constraints for BITDEC operation.
+pub fn enforce_bitdec(result: &mut [u128], old_stack: &[u128], new_stack: &[u128], op_flag: u128)
+{
+    // layout of first 3 registers:
+    // [accumulated value, original value, value bit]
+    // value bit is located in the next state (not current state)
+
+    // binary representation accumulator was updated correctly
+    let bit = new_stack[2];
+    let acc = field::add(field::mul(old_stack[0], 2), bit);
+    result.agg_constraint(0, op_flag, are_equal(new_stack[0], acc));
+
+    // original value remained the same
+    result.agg_constraint(1, op_flag, are_equal(new_stack[1], old_stack[1]));
+
+    // the bit was a binary value
+    result.agg_constraint(2, op_flag, is_binary(bit));
+
+    // registers after the original value were shifted right by 1
+    for i in 3..result.len() {
+        result.agg_constraint(i, op_flag, are_equal(new_stack[i], old_stack[i - 1]));
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::field;
+
+    #[test]
+    fn op_iszero() {
+        // non-zero value with its inverse as the witness
+        let old_stack = [field::inv(4), 4, 5, 0];
+        let new_stack = [0, 5, 0, 0];
+        let (evaluations, aux) = evaluate_iszero(&old_stack, &new_stack);
+        assert_eq!(vec![0, 0, 0, 0], evaluations);
+        assert_eq!(vec![0], aux);
+
+        // zero value with any witness
+        for &witness in [0, 1, field::rand()].iter() {
+            let old_stack = [witness, 0, 5, 0];
+            let new_stack = [1, 5, 0, 0];
+            let (evaluations, aux) = evaluate_iszero(&old_stack, &new_stack);
+            assert_eq!(vec![0, 0, 0, 0], evaluations);
+            assert_eq!(vec![0], aux);
+        }
+    }
+
+    #[test]
+    fn op_iszero_invalid_result() {
+        // non-zero value cannot produce 1 regardless of the witness
+        for &witness in [0, 1, field::rand()].iter() {
+            let old_stack = [witness, 4, 5, 0];
+            let new_stack = [1, 5, 0, 0];
+            let (evaluations, aux) = evaluate_iszero(&old_stack, &new_stack);
+            assert!(evaluations[0] != 0 || aux[0] != 0);
+        }
+
+        // zero value cannot produce 0 regardless of the witness
+        for &witness in [0, 1, field::rand()].iter() {
+            let old_stack = [witness, 0, 5, 0];
+            let new_stack = [0, 5, 0, 0];
+            let (evaluations, _) = evaluate_iszero(&old_stack, &new_stack);
+            assert_eq!(field::neg(1), evaluations[0]);
+        }
+    }
+
+    fn evaluate_iszero(old_stack: &[u128], new_stack: &[u128]) -> (Vec<u128>, Vec<u128>) {
+        let mut evaluations = vec![0; 4];
+        let mut aux = vec![0; 1];
+        super::enforce_iszero(&mut evaluations, &mut aux, old_stack, new_stack, 1);
+        return (evaluations, aux);
+    }
+}
@@ -80,4 +80,48 @@ pub fn enforce_cswap2(result: &mut [u128], aux: &mut [u128], old_stack: &[u128],
 
     // make sure the condition was a binary value
     aux.agg_constraint(0, op_flag, is_binary(condition));
-}
\ No newline at end of file
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::{ field, is_binary };
+
+    #[test]
+    fn op_choose() {
+        // condition = 1 selects x, condition = 0 selects y
+        for &(condition, selected) in [(1, 3), (0, 5)].iter() {
+            let old_stack = [3, 5, condition, 7, 0];
+            let new_stack = [selected, 7, 0, 0, 0];
+            let mut evaluations = vec![0; 5];
+            let mut aux = vec![0; 2];
+            super::enforce_choose(&mut evaluations, &mut aux, &old_stack, &new_stack, 1);
+            assert_eq!(vec![0, 0, 0, 0, 0], evaluations);
+            assert_eq!(vec![0, 0], aux);
+        }
+
+        // selecting the wrong value does not satisfy the constraint
+        let old_stack = [3, 5, 1, 7, 0];
+        let new_stack = [5, 7, 0, 0, 0];
+        let mut evaluations = vec![0; 5];
+        let mut aux = vec![0; 2];
+        super::enforce_choose(&mut evaluations, &mut aux, &old_stack, &new_stack, 1);
+        assert_ne!(0, evaluations[0]);
+    }
+
+    #[test]
+    fn op_choose_non_binary() {
+        // with condition = 2, the result 2*x - y satisfies the selection constraint, but the
+        // condition is not binary and so the auxiliary constraint is not satisfied
+        let old_stack = [3, 5, 2, 7, 0];
+        let new_stack = [1, 7, 0, 0, 0];
+        let mut evaluations = vec![0; 5];
+        let mut aux = vec![0; 2];
+        super::enforce_choose(&mut evaluations, &mut aux, &old_stack, &new_stack, 1);
+        assert_eq!(vec![0, 0, 0, 0, 0], evaluations);
+        assert_eq!(vec![is_binary(2), 0], aux);
+        assert_ne!(field::ZERO, aux[0]);
+    }
+}
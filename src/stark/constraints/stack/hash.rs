@@ -33,4 +33,73 @@ pub fn enforce_rescr(result: &mut [u128], old_stack: &[u128], new_stack: &[u128]
 
     // make sure the rest of the stack didn't change
     enforce_stack_copy(result, old_stack, new_stack, HASH_STATE_WIDTH, op_flag);
-}
\ No newline at end of file
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ utils::hasher, HASH_NUM_ROUNDS, HASH_DIGEST_SIZE };
+    use super::{ field, HASH_STATE_WIDTH };
+
+    #[test]
+    fn rescr_rounds() {
+        // the hash state holds [1, 2]; registers below the state must stay the same
+        let mut stack = vec![field::ZERO; HASH_STATE_WIDTH + 2];
+        init_state(&mut stack[..HASH_STATE_WIDTH], &[1, 2]);
+        stack[HASH_STATE_WIDTH] = 7;
+        stack[HASH_STATE_WIDTH + 1] = 9;
+
+        for round in 0..HASH_NUM_ROUNDS {
+            let mut next = stack.clone();
+            hasher::apply_round(&mut next[..HASH_STATE_WIDTH], round);
+
+            let mut evaluations = vec![field::ZERO; stack.len()];
+            super::enforce_rescr(&mut evaluations, &stack, &next, &get_ark(round), 1);
+            assert_eq!(vec![field::ZERO; stack.len()], evaluations, "round {} is not valid", round);
+
+            stack = next;
+        }
+
+        assert_eq!(hasher::hash(&[1, 2]), read_digest(&stack[..HASH_STATE_WIDTH]));
+        assert_eq!([7, 9], stack[HASH_STATE_WIDTH..]);
+    }
+
+    #[test]
+    fn rescr_invalid_transition() {
+        let mut stack = vec![field::ZERO; HASH_STATE_WIDTH + 2];
+        init_state(&mut stack[..HASH_STATE_WIDTH], &[1, 2]);
+
+        // the state is advanced by a round, but a register below the state changes as well
+        let mut next = stack.clone();
+        hasher::apply_round(&mut next[..HASH_STATE_WIDTH], 0);
+        next[HASH_STATE_WIDTH] = 1;
+
+        let mut evaluations = vec![field::ZERO; stack.len()];
+        super::enforce_rescr(&mut evaluations, &stack, &next, &get_ark(0), 1);
+        assert!(evaluations[..HASH_STATE_WIDTH].iter().all(|&v| v == field::ZERO));
+        assert!(evaluations[HASH_STATE_WIDTH] != field::ZERO);
+    }
+
+    // HELPER FUNCTIONS
+    // --------------------------------------------------------------------------------------------
+
+    /// Puts the first block absorbed by hasher::hash() for `values` into the `state`; the state
+    /// is reversed in the same way as by hasher::digest().
+    fn init_state(state: &mut [u128], values: &[u128]) {
+        state[0] = values.len() as u128;
+        state[2..(2 + values.len())].copy_from_slice(values);
+        state.reverse();
+    }
+
+    fn read_digest(state: &[u128]) -> Vec<u128> {
+        let mut state = state.to_vec();
+        state.reverse();
+        return state[..HASH_DIGEST_SIZE].to_vec();
+    }
+
+    fn get_ark(round: usize) -> Vec<u128> {
+        return hasher::ARK.iter().map(|constants| constants[round]).collect();
+    }
+}
@@ -1,4 +1,4 @@
-use super::{ enforce_right_shift };
+use super::{ are_equal, EvaluationResult, enforce_right_shift, enforce_left_shift };
 
 /// Enforces constraints for PUSH operation. The constraints on the first element of the stack
 /// are enforced in the Decoder where the value pushed onto the stack is injected into sponge
@@ -20,4 +20,21 @@ pub fn enforce_read(result: &mut [u128], old_stack: &[u128], new_stack: &[u128],
 pub fn enforce_read2(result: &mut [u128], old_stack: &[u128], new_stack: &[u128], op_flag: u128)
 {
     enforce_right_shift(result, old_stack, new_stack, 2, op_flag);
-}
\ No newline at end of file
+}
+
+/// Enforces constraints for CLK operation. The first element of the new stack must be equal to
+/// the value of the operation counter at the next step; the old stack is shifted right by 1
+/// element.
+pub fn enforce_clk(result: &mut [u128], old_stack: &[u128], new_stack: &[u128], op_counter: u128, op_flag: u128)
+{
+    result.agg_constraint(0, op_flag, are_equal(new_stack[0], op_counter));
+    enforce_right_shift(result, old_stack, new_stack, 1, op_flag);
+}
+
+/// Enforces constraints for WRITE operation. The value written to the output tape is the first
+/// element of the old stack; it is bound to the tape by point constraints (if the tape is public),
+/// and thus, the stack is simply shifted left by 1 element.
+pub fn enforce_write(result: &mut [u128], old_stack: &[u128], new_stack: &[u128], op_flag: u128)
+{
+    enforce_left_shift(result, old_stack, new_stack, 1, 1, op_flag);
+}
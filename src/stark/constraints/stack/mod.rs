@@ -1,9 +1,10 @@
+use std::sync::{ Arc, Mutex };
 use crate::{
     math::{ field, polynom },
     processor::OpCode,
-    stark::TraceState,
+    stark::{ TraceState, EvalStep, Constraint, MAX_CONSTRAINT_DEGREE },
     utils::hasher::ARK,
-    BASE_CYCLE_LENGTH, HASH_STATE_WIDTH
+    BASE_CYCLE_LENGTH, HASH_STATE_WIDTH, MAX_STACK_DEPTH, MIN_STACK_DEPTH
 };
 use super::utils::{
     are_equal, is_zero, is_binary, binary_not, extend_constants, EvaluationResult,
@@ -11,7 +12,7 @@ use super::utils::{
 };
 
 mod input;
-use input::{ enforce_push, enforce_read, enforce_read2 };
+use input::{ enforce_push, enforce_read, enforce_read2, enforce_clk, enforce_write };
 
 mod arithmetic;
 use arithmetic::{
@@ -26,7 +27,9 @@ use manipulation::{
 };
 
 mod comparison;
-use comparison::{ enforce_assert, enforce_asserteq, enforce_eq, enforce_cmp, enforce_binacc };
+use comparison::{
+    enforce_assert, enforce_asserteq, enforce_eq, enforce_iszero, enforce_cmp, enforce_binacc, enforce_bitdec,
+};
 
 mod conditional;
 use conditional::{ enforce_choose, enforce_choose2, enforce_cswap2 };
@@ -40,36 +43,99 @@ pub const NUM_AUX_CONSTRAINTS: usize = 2;
 const AUX_CONSTRAINT_DEGREES: [usize; NUM_AUX_CONSTRAINTS] = [7, 7];
 const STACK_TRANSITION_DEGREE: usize = 7; // degree for all stack register transition constraints
 
+// constraints of every operation (except RESCR, see below) multiply the operation flag into the
+// constraint of every stack register; this lists the numbers of multiplications performed by
+// constraints of each operation on top of that: multiplications needed to compute the result of
+// the operation and to evaluate auxiliary constraints, and the number of top stack registers
+// which the operation leaves unconstrained (and thus, takes no multiplications for)
+const OP_MUL_COUNTS: [(OpCode, u64, u64); 35] = [
+    (OpCode::Assert,    1, 0),  (OpCode::AssertEq,  1, 0),
+    (OpCode::Read,      0, 1),  (OpCode::Read2,     0, 2),  (OpCode::Clk,       0, 0),
+    (OpCode::Write,     0, 0),
+    (OpCode::Dup,       0, 0),  (OpCode::Dup2,      0, 0),  (OpCode::Dup4,      0, 0),
+    (OpCode::Pad2,      0, 0),  (OpCode::Drop,      0, 0),  (OpCode::Drop4,     0, 0),
+    (OpCode::Swap,      0, 0),  (OpCode::Swap2,     0, 0),  (OpCode::Swap4,     0, 0),
+    (OpCode::Roll4,     0, 0),  (OpCode::Roll8,     0, 0),
+    (OpCode::Add,       0, 0),  (OpCode::Mul,       1, 0),  (OpCode::Inv,       1, 0),
+    (OpCode::Neg,       0, 0),  (OpCode::Not,       2, 0),  (OpCode::And,       5, 0),
+    (OpCode::Or,        5, 0),
+    (OpCode::Eq,        3, 0),  (OpCode::IsZero,    3, 0),  (OpCode::BinAcc,    3, 0),
+    (OpCode::BitDec,    2, 0),
+    (OpCode::Choose,    4, 0),  (OpCode::Choose2,   6, 0),  (OpCode::CSwap2,   10, 0),
+    (OpCode::Push,      0, 1),  (OpCode::Cmp,      10, 0),
+    (OpCode::Begin,     0, 0),  (OpCode::Noop,      0, 0),
+];
+
+// number of multiplications performed when evaluating hash round constraints: 4 for every
+// S-Box (x^3) and HASH_STATE_WIDTH for every row of the MDS matrix applied to each half of the
+// round, and a multiplication by RESCR flag for every stack register
+const HASH_ROUND_MUL_COUNT: u64 = 2 * (4 * HASH_STATE_WIDTH + HASH_STATE_WIDTH * HASH_STATE_WIDTH) as u64;
+
 // TYPES AND INTERFACES
 // ================================================================================================
 pub struct Stack {
     trace_length        : usize,
-    cycle_length        : usize,
-    ark_values          : Vec<[u128; 2 * HASH_STATE_WIDTH]>,
-    ark_polys           : Vec<Vec<u128>>,
+    ark                 : Arc<ExtendedArk>,
     constraint_degrees  : Vec<usize>,
 }
 
+/// Hash round constraint for a single register of user stack; this is the same constraint as
+/// the one evaluated by `Stack::evaluate_hash()` for the register, but exposed through the
+/// `Constraint` interface so that it can be composed together with arbitrary other constraints.
+/// Since the constraint is evaluated at x coordinates, it is slower than the built-in one.
+///
+/// Hash round constraints of all registers are computed together; to avoid doing this once per
+/// register, evaluators created by `HashEvaluator::all()` share the evaluations of the last step
+/// they were invoked for, and so the constraints of all registers cost a single evaluation.
+pub struct HashEvaluator {
+    trace_length        : usize,
+    ark                 : Arc<ExtendedArk>,
+    register            : usize,
+    cache               : Arc<Mutex<HashStepCache>>,
+}
+
+/// Hash round evaluations for all registers of user stack at a single step, together with the
+/// inputs they were computed from.
+struct HashStepCache {
+    x                   : u128,
+    rescr_flag          : u128,
+    depth               : usize,
+    old_stack           : [u128; MAX_STACK_DEPTH],
+    new_stack           : [u128; MAX_STACK_DEPTH],
+    evaluations         : [u128; MAX_STACK_DEPTH],
+}
+
+/// Rescue round constants extended by a specific extension factor. Extending the constants
+/// is relatively expensive, and the result depends only on the trace length, the extension
+/// factor, and the domain offset; thus, the same constants can be shared by many stack
+/// constraint evaluators.
+pub struct ExtendedArk {
+    cycle_length        : usize,
+    values              : Vec<[u128; 2 * HASH_STATE_WIDTH]>,
+    polys               : Vec<Vec<u128>>,
+}
+
 // STACK CONSTRAINT EVALUATOR IMPLEMENTATION
 // ================================================================================================
 impl Stack {
 
-    pub fn new(trace_length: usize, extension_factor: usize, stack_depth: usize) -> Stack 
+    /// Creates a stack constraint evaluator for the evaluation domain of an execution trace of
+    /// `trace_length` steps extended by `extension_factor` and shifted by `domain_offset`.
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128, stack_depth: usize) -> Stack 
+    {
+        let ark = Arc::new(ExtendedArk::new(trace_length, extension_factor, domain_offset));
+        return Stack::with_ark(trace_length, stack_depth, ark);
+    }
+
+    /// Creates a stack constraint evaluator using previously extended round constants.
+    pub fn with_ark(trace_length: usize, stack_depth: usize, ark: Arc<ExtendedArk>) -> Stack
     {
         // build an array of constraint degrees for the stack
         let mut degrees = Vec::from(&AUX_CONSTRAINT_DEGREES[..]);
         degrees.resize(stack_depth + NUM_AUX_CONSTRAINTS, STACK_TRANSITION_DEGREE);
 
-        // determine extended cycle length
-        let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
-
-        // extend rounds constants by the specified extension factor
-        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor);
-        let ark_values = transpose_ark_constants(ark_evaluations, cycle_length);
-
         return Stack {
-            trace_length, cycle_length,
-            ark_values, ark_polys,
+            trace_length, ark,
             constraint_degrees: degrees,
         };
     }
@@ -78,37 +144,179 @@ impl Stack {
         return &self.constraint_degrees;
     }
 
+    /// Returns the number of multiplications performed by evaluate() at a single step, not
+    /// counting hash round constraints; see hash_mul_count().
+    pub fn mul_count(&self) -> u64 {
+        let register_count = self.register_count() as u64;
+        return OP_MUL_COUNTS.iter()
+            .map(|&(_, op_count, unconstrained)| op_count + register_count - unconstrained)
+            .sum();
+    }
+
+    /// Returns the number of multiplications needed to evaluate hash round constraints at a
+    /// single step.
+    pub fn hash_mul_count(&self) -> u64 {
+        return HASH_ROUND_MUL_COUNT + self.register_count() as u64;
+    }
+
+    /// Returns the number of stack registers constraints are evaluated for; these include
+    /// registers padding the stack to the minimum depth.
+    fn register_count(&self) -> usize {
+        return std::cmp::max(self.constraint_degrees.len() - NUM_AUX_CONSTRAINTS, MIN_STACK_DEPTH);
+    }
+
     // EVALUATOR FUNCTIONS
     // --------------------------------------------------------------------------------------------
 
     /// Evaluates stack transition constraints at the specified step of the evaluation domain and
     /// saves the evaluations into `result`.
-    pub fn evaluate(&self, current: &TraceState, next: &TraceState, step: usize, result: &mut [u128])
+    pub fn evaluate(&self, current: &TraceState, next: &TraceState, step: EvalStep, result: &mut [u128])
     {
         // determine round constants at the specified step
-        let ark = self.ark_values[step % self.cycle_length];
+        let ark = self.ark.values[step.0 % self.ark.cycle_length];
 
         // evaluate transition constraints for the stack
         enforce_constraints(current, next, &ark, result);
     }
 
+    /// Evaluates only hash round constraints at the specified step of the evaluation domain; the
+    /// evaluations are saved into the same positions of `result` as by evaluate(), and are zeros
+    /// at steps where RESCR flag is zero.
+    pub fn evaluate_hash(&self, current: &TraceState, next: &TraceState, step: EvalStep, result: &mut [u128])
+    {
+        let ark = self.ark.values[step.0 % self.ark.cycle_length];
+        let (_, result) = result.split_at_mut(NUM_AUX_CONSTRAINTS);
+
+        let old_stack = current.user_stack();
+        let mut evaluations = [field::ZERO; MAX_STACK_DEPTH];
+        let evaluations = &mut evaluations[..old_stack.len()];
+
+        let rescr_flag = current.hd_op_flags()[OpCode::RescR.hd_index()];
+        enforce_rescr(evaluations, old_stack, next.user_stack(), &ark, rescr_flag);
+        result.copy_from_slice(&evaluations[..result.len()]);
+    }
+
     /// Evaluates stack transition constraints at the specified x coordinate and saves the
     /// evaluations into `result`. Unlike the function above, this function can evaluate constraints
     /// at any out-of-domain point, but it is much slower than the previous function.
     pub fn evaluate_at(&self, current: &TraceState, next: &TraceState, x: u128, result: &mut [u128])
     {
+        // determine round constants at the specified x coordinate
+        let ark = self.ark.eval_at(self.trace_length, x);
+
+        // evaluate transition constraints for the stack
+        enforce_constraints(current, next, &ark, result);
+    }
+}
+
+// HASH CONSTRAINT IMPLEMENTATION
+// ================================================================================================
+impl HashEvaluator {
+
+    /// Returns hash round constraints for all `stack_depth` registers of user stack of an
+    /// execution trace of `trace_length` steps; the constraints share round constants.
+    pub fn all(trace_length: usize, stack_depth: usize) -> Vec<HashEvaluator> {
+        assert!(stack_depth <= MAX_STACK_DEPTH,
+            "stack depth cannot exceed {}, but was {}", MAX_STACK_DEPTH, stack_depth);
+        let ark = Arc::new(ExtendedArk::new(trace_length, MAX_CONSTRAINT_DEGREE, field::ONE));
+        let cache = Arc::new(Mutex::new(HashStepCache::new()));
+        return (0..stack_depth).map(|register| HashEvaluator {
+            trace_length: trace_length,
+            ark         : ark.clone(),
+            register    : register,
+            cache       : cache.clone(),
+        }).collect();
+    }
+}
+
+impl Constraint for HashEvaluator {
+
+    fn degree(&self) -> usize {
+        return STACK_TRANSITION_DEGREE;
+    }
+
+    fn evaluate(&self, current: &TraceState, next: &TraceState, x: u128) -> u128 {
+        let old_stack = current.user_stack();
+        let new_stack = next.user_stack();
+        let rescr_flag = current.hd_op_flags()[OpCode::RescR.hd_index()];
+
+        let mut cache = self.cache.lock().unwrap();
+        if !cache.contains(x, rescr_flag, old_stack, new_stack) {
+            let ark = self.ark.eval_at(self.trace_length, x);
+            cache.update(x, rescr_flag, old_stack, new_stack);
+            let depth = cache.depth;
+            enforce_rescr(&mut cache.evaluations[..depth], old_stack, new_stack, &ark, rescr_flag);
+        }
+        return cache.evaluations[self.register];
+    }
+}
+
+impl HashStepCache {
+
+    fn new() -> HashStepCache {
+        return HashStepCache {
+            x           : field::ZERO,
+            rescr_flag  : field::ZERO,
+            depth       : 0,
+            old_stack   : [field::ZERO; MAX_STACK_DEPTH],
+            new_stack   : [field::ZERO; MAX_STACK_DEPTH],
+            evaluations : [field::ZERO; MAX_STACK_DEPTH],
+        };
+    }
+
+    /// Returns true if evaluations in the cache were computed from the specified inputs; an
+    /// empty cache contains no evaluations.
+    fn contains(&self, x: u128, rescr_flag: u128, old_stack: &[u128], new_stack: &[u128]) -> bool {
+        return self.depth > 0
+            && self.x == x
+            && self.rescr_flag == rescr_flag
+            && &self.old_stack[..self.depth] == old_stack
+            && &self.new_stack[..self.depth] == new_stack;
+    }
+
+    /// Records the inputs of a new step and resets evaluations to zeros.
+    fn update(&mut self, x: u128, rescr_flag: u128, old_stack: &[u128], new_stack: &[u128]) {
+        let depth = old_stack.len();
+        self.x = x;
+        self.rescr_flag = rescr_flag;
+        self.depth = depth;
+        self.old_stack[..depth].copy_from_slice(old_stack);
+        self.new_stack[..depth].copy_from_slice(new_stack);
+        self.evaluations = [field::ZERO; MAX_STACK_DEPTH];
+    }
+}
+
+// EXTENDED ROUND CONSTANTS IMPLEMENTATION
+// ================================================================================================
+impl ExtendedArk {
+
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128) -> ExtendedArk {
+        // determine extended cycle length
+        let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
+
+        // extend rounds constants by the specified extension factor
+        let (polys, evaluations) = extend_constants(&ARK, trace_length, extension_factor, domain_offset);
+        let values = transpose_ark_constants(evaluations, cycle_length);
+
+        return ExtendedArk { cycle_length, values, polys };
+    }
+
+    pub fn extension_factor(&self) -> usize {
+        return self.cycle_length / BASE_CYCLE_LENGTH;
+    }
+
+    /// Evaluates round constants at the specified x coordinate of an execution trace of
+    /// `trace_length` steps; x can be any point in the field.
+    fn eval_at(&self, trace_length: usize, x: u128) -> [u128; 2 * HASH_STATE_WIDTH] {
         // map x to the corresponding coordinate in constant cycles
-        let num_cycles = (self.trace_length / BASE_CYCLE_LENGTH) as u128;
+        let num_cycles = (trace_length / BASE_CYCLE_LENGTH) as u128;
         let x = field::exp(x, num_cycles);
 
-        // determine round constants at the specified x coordinate
         let mut ark = [field::ZERO; 2 * HASH_STATE_WIDTH];
         for i in 0..ark.len() {
-            ark[i] = polynom::eval(&self.ark_polys[i], x);
+            ark[i] = polynom::eval(&self.polys[i], x);
         }
-
-        // evaluate transition constraints for the stack
-        enforce_constraints(current, next, &ark, result);
+        return ark;
     }
 }
 
@@ -123,62 +331,68 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
     let old_stack = current.user_stack();
     let new_stack = next.user_stack();
 
-    // initialize a vector to hold stack constraint evaluations; this is needed because
+    // initialize a buffer to hold stack constraint evaluations; this is needed because
     // constraint evaluator functions assume that the stack is at least 8 items deep; while
-    // it may actually be smaller than that
-    let mut evaluations = vec![field::ZERO; old_stack.len()];
+    // it may actually be smaller than that. The buffer is allocated on the stack since this
+    // function is invoked for every step of the evaluation domain.
+    let mut evaluations = [field::ZERO; MAX_STACK_DEPTH];
+    let evaluations = &mut evaluations[..old_stack.len()];
 
     // 1 ----- enforce constraints for low-degree operations --------------------------------------
     let ld_flags = current.ld_op_flags();
 
     // assertion operations
-    enforce_assert  (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Assert.ld_index()]);
-    enforce_asserteq(&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::AssertEq.ld_index()]);
+    enforce_assert  (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Assert.ld_index()]);
+    enforce_asserteq(evaluations, aux, old_stack, new_stack, ld_flags[OpCode::AssertEq.ld_index()]);
 
     // input operations
-    enforce_read    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Read.ld_index()]);
-    enforce_read2   (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Read2.ld_index()]);
+    enforce_read    (evaluations,      old_stack, new_stack, ld_flags[OpCode::Read.ld_index()]);
+    enforce_read2   (evaluations,      old_stack, new_stack, ld_flags[OpCode::Read2.ld_index()]);
+    enforce_clk     (evaluations,      old_stack, new_stack, next.op_counter(), ld_flags[OpCode::Clk.ld_index()]);
+    enforce_write   (evaluations,      old_stack, new_stack, ld_flags[OpCode::Write.ld_index()]);
 
     // stack manipulation operations
-    enforce_dup     (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup.ld_index()]);
-    enforce_dup2    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup2.ld_index()]);
-    enforce_dup4    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup4.ld_index()]);
-    enforce_pad2    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Pad2.ld_index()]);
+    enforce_dup     (evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup.ld_index()]);
+    enforce_dup2    (evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup2.ld_index()]);
+    enforce_dup4    (evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup4.ld_index()]);
+    enforce_pad2    (evaluations,      old_stack, new_stack, ld_flags[OpCode::Pad2.ld_index()]);
 
-    enforce_drop    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Drop.ld_index()]);
-    enforce_drop4   (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Drop4.ld_index()]);
+    enforce_drop    (evaluations,      old_stack, new_stack, ld_flags[OpCode::Drop.ld_index()]);
+    enforce_drop4   (evaluations,      old_stack, new_stack, ld_flags[OpCode::Drop4.ld_index()]);
     
-    enforce_swap    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Swap.ld_index()]);
-    enforce_swap2   (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Swap2.ld_index()]);
-    enforce_swap4   (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Swap4.ld_index()]);
+    enforce_swap    (evaluations,      old_stack, new_stack, ld_flags[OpCode::Swap.ld_index()]);
+    enforce_swap2   (evaluations,      old_stack, new_stack, ld_flags[OpCode::Swap2.ld_index()]);
+    enforce_swap4   (evaluations,      old_stack, new_stack, ld_flags[OpCode::Swap4.ld_index()]);
 
-    enforce_roll4   (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Roll4.ld_index()]);
-    enforce_roll8   (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Roll8.ld_index()]);
+    enforce_roll4   (evaluations,      old_stack, new_stack, ld_flags[OpCode::Roll4.ld_index()]);
+    enforce_roll8   (evaluations,      old_stack, new_stack, ld_flags[OpCode::Roll8.ld_index()]);
 
     // arithmetic and boolean operations
-    enforce_add     (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Add.ld_index()]);
-    enforce_mul     (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Mul.ld_index()]);
-    enforce_inv     (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Inv.ld_index()]);
-    enforce_neg     (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Neg.ld_index()]);
-    enforce_not     (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Not.ld_index()]);
-    enforce_and     (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::And.ld_index()]);
-    enforce_or      (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Or.ld_index()]);
+    enforce_add     (evaluations,      old_stack, new_stack, ld_flags[OpCode::Add.ld_index()]);
+    enforce_mul     (evaluations,      old_stack, new_stack, ld_flags[OpCode::Mul.ld_index()]);
+    enforce_inv     (evaluations,      old_stack, new_stack, ld_flags[OpCode::Inv.ld_index()]);
+    enforce_neg     (evaluations,      old_stack, new_stack, ld_flags[OpCode::Neg.ld_index()]);
+    enforce_not     (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Not.ld_index()]);
+    enforce_and     (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::And.ld_index()]);
+    enforce_or      (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Or.ld_index()]);
     
     // comparison operations
-    enforce_eq      (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Eq.ld_index()]);
-    enforce_binacc  (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::BinAcc.ld_index()]);
+    enforce_eq      (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Eq.ld_index()]);
+    enforce_iszero  (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::IsZero.ld_index()]);
+    enforce_binacc  (evaluations,      old_stack, new_stack, ld_flags[OpCode::BinAcc.ld_index()]);
+    enforce_bitdec  (evaluations,      old_stack, new_stack, ld_flags[OpCode::BitDec.ld_index()]);
 
     // conditional selection operations
-    enforce_choose  (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Choose.ld_index()]);
-    enforce_choose2 (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Choose2.ld_index()]);
-    enforce_cswap2  (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::CSwap2.ld_index()]);
+    enforce_choose  (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Choose.ld_index()]);
+    enforce_choose2 (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Choose2.ld_index()]);
+    enforce_cswap2  (evaluations, aux, old_stack, new_stack, ld_flags[OpCode::CSwap2.ld_index()]);
 
     // 2 ----- enforce constraints for high-degree operations --------------------------------------
     let hd_flags = current.hd_op_flags();
 
-    enforce_push    (&mut evaluations,      old_stack, new_stack,      hd_flags[OpCode::Push.hd_index() ]);
-    enforce_cmp     (&mut evaluations,      old_stack, new_stack,      hd_flags[OpCode::Cmp.hd_index()  ]);
-    enforce_rescr   (&mut evaluations,      old_stack, new_stack, ark, hd_flags[OpCode::RescR.hd_index()]);
+    enforce_push    (evaluations,      old_stack, new_stack,      hd_flags[OpCode::Push.hd_index() ]);
+    enforce_cmp     (evaluations,      old_stack, new_stack,      hd_flags[OpCode::Cmp.hd_index()  ]);
+    enforce_rescr   (evaluations,      old_stack, new_stack, ark, hd_flags[OpCode::RescR.hd_index()]);
 
     // 3 ----- enforce constraints for composite operations ---------------------------------------
 
@@ -187,8 +401,8 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
     // this results in flag degree of 7 for each operation, but since both operations enforce the
     // same constraints (the stack doesn't change), higher degree terms cancel out, and we
     // end up with overall constraint degree of (6 + 1 = 7) for both operations.
-    enforce_stack_copy(&mut evaluations, old_stack, new_stack, 0, current.begin_flag());
-    enforce_stack_copy(&mut evaluations, old_stack, new_stack, 0, current.noop_flag());
+    enforce_stack_copy(evaluations, old_stack, new_stack, 0, current.begin_flag());
+    enforce_stack_copy(evaluations, old_stack, new_stack, 0, current.noop_flag());
     
     // 4 ----- copy evaluations into the result ---------------------------------------------------
     result.copy_from_slice(&evaluations[..result.len()]);
@@ -204,4 +418,124 @@ fn transpose_ark_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Ve
         }
     }
     return values;
-}
\ No newline at end of file
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::{ field, fft }, processor, programs::assembly, stark::{ TraceState, TraceTable, LdeStep }, OpCode, ProgramInputs };
+    use super::{ Stack, enforce_constraints, NUM_AUX_CONSTRAINTS, OP_MUL_COUNTS };
+    use super::{ input::*, arithmetic::*, manipulation::*, comparison::*, conditional::*, enforce_stack_copy };
+
+    const TRACE_LENGTH: usize = 16;
+    const EXTENSION_FACTOR: usize = 8;
+
+    #[test]
+    fn mul_counts() {
+        for &stack_depth in [8, 13].iter() {
+            let width = TraceState::compute_decoder_width(0, 0) + stack_depth;
+            let current = TraceState::from_vec(0, 0, stack_depth, &field::rand_vector(width));
+            let next = TraceState::from_vec(0, 0, stack_depth, &field::rand_vector(width));
+            let (old_stack, new_stack) = (current.user_stack(), next.user_stack());
+
+            // constraints of every operation take the listed number of multiplications
+            for &(op_code, op_count, unconstrained) in OP_MUL_COUNTS.iter() {
+                let mut evaluations = vec![field::ZERO; stack_depth];
+                let mut aux = vec![field::ZERO; NUM_AUX_CONSTRAINTS];
+                let count = field::mul_count();
+                enforce_op(op_code, &mut evaluations, &mut aux, old_stack, new_stack, next.op_counter(), 11);
+                assert_eq!(op_count + (stack_depth as u64) - unconstrained, field::mul_count() - count,
+                    "multiplication count did not match for {}", op_code);
+            }
+
+            // and together with hash round constraints, they add up to the counts reported by the stack
+            let stack = Stack::new(TRACE_LENGTH, EXTENSION_FACTOR, field::ONE, stack_depth);
+            current.cf_op_flags();
+            let mut evaluations = vec![field::ZERO; stack_depth + NUM_AUX_CONSTRAINTS];
+            let count = field::mul_count();
+            enforce_constraints(&current, &next, &stack.ark.values[3], &mut evaluations);
+            assert_eq!(stack.mul_count() + stack.hash_mul_count(), field::mul_count() - count);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "extension factor must be a power of 2, but was 3")]
+    fn extend_ark_invalid_extension_factor() {
+        super::ExtendedArk::new(TRACE_LENGTH, 3, field::ONE);
+    }
+
+    #[test]
+    fn rescr_flag_between_trace_steps() {
+        let program = assembly::compile("begin push.3 push.5 add push.7 mul dup mul end").unwrap();
+        let inputs = ProgramInputs::none();
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &inputs);
+        let trace_length = trace[0].len();
+        let extension_factor = 16;
+
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, extension_factor);
+        let lde_root = field::get_root_of_unity(trace.domain_size());
+        let trace_root = field::get_root_of_unity(trace_length);
+        trace.extend(
+            &fft::get_twiddles(lde_root, trace.domain_size()),
+            &fft::get_inv_twiddles(trace_root, trace_length), 1);
+
+        // the program has no hash operations, so RESCR flag is zero at all trace steps, but
+        // not between them (the LDE domain does not include any of the trace steps)
+        let (ctx_depth, loop_depth, stack_depth) = (ctx_depth, loop_depth, trace.stack_depth());
+        for i in 0..trace_length {
+            let x = field::exp(trace_root, i as u128);
+            let state = TraceState::from_vec(ctx_depth, loop_depth, stack_depth, &trace.eval_polys_at(x));
+            assert_eq!(field::ZERO, state.hd_op_flags()[OpCode::RescR.hd_index()], "RESCR flag is not zero at trace step {}", i);
+        }
+
+        let mut state = TraceState::new(ctx_depth, loop_depth, stack_depth);
+        let rescr_flags: Vec<u128> = (0..trace.domain_size()).map(|i| {
+            trace.fill_state(&mut state, LdeStep(i));
+            state.hd_op_flags()[OpCode::RescR.hd_index()]
+        }).collect();
+        assert!(rescr_flags.iter().all(|&flag| flag != field::ZERO));
+    }
+
+    fn enforce_op(op_code: OpCode, result: &mut [u128], aux: &mut [u128], old: &[u128], new: &[u128], op_counter: u128, flag: u128) {
+        match op_code {
+            OpCode::Assert      => enforce_assert(result, aux, old, new, flag),
+            OpCode::AssertEq    => enforce_asserteq(result, aux, old, new, flag),
+            OpCode::Read        => enforce_read(result, old, new, flag),
+            OpCode::Read2       => enforce_read2(result, old, new, flag),
+            OpCode::Clk         => enforce_clk(result, old, new, op_counter, flag),
+            OpCode::Write       => enforce_write(result, old, new, flag),
+            OpCode::Dup         => enforce_dup(result, old, new, flag),
+            OpCode::Dup2        => enforce_dup2(result, old, new, flag),
+            OpCode::Dup4        => enforce_dup4(result, old, new, flag),
+            OpCode::Pad2        => enforce_pad2(result, old, new, flag),
+            OpCode::Drop        => enforce_drop(result, old, new, flag),
+            OpCode::Drop4       => enforce_drop4(result, old, new, flag),
+            OpCode::Swap        => enforce_swap(result, old, new, flag),
+            OpCode::Swap2       => enforce_swap2(result, old, new, flag),
+            OpCode::Swap4       => enforce_swap4(result, old, new, flag),
+            OpCode::Roll4       => enforce_roll4(result, old, new, flag),
+            OpCode::Roll8       => enforce_roll8(result, old, new, flag),
+            OpCode::Add         => enforce_add(result, old, new, flag),
+            OpCode::Mul         => enforce_mul(result, old, new, flag),
+            OpCode::Inv         => enforce_inv(result, old, new, flag),
+            OpCode::Neg         => enforce_neg(result, old, new, flag),
+            OpCode::Not         => enforce_not(result, aux, old, new, flag),
+            OpCode::And         => enforce_and(result, aux, old, new, flag),
+            OpCode::Or          => enforce_or(result, aux, old, new, flag),
+            OpCode::Eq          => enforce_eq(result, aux, old, new, flag),
+            OpCode::IsZero      => enforce_iszero(result, aux, old, new, flag),
+            OpCode::BinAcc      => enforce_binacc(result, old, new, flag),
+            OpCode::BitDec      => enforce_bitdec(result, old, new, flag),
+            OpCode::Choose      => enforce_choose(result, aux, old, new, flag),
+            OpCode::Choose2     => enforce_choose2(result, aux, old, new, flag),
+            OpCode::CSwap2      => enforce_cswap2(result, aux, old, new, flag),
+            OpCode::Push        => enforce_push(result, old, new, flag),
+            OpCode::Cmp         => enforce_cmp(result, old, new, flag),
+            OpCode::Begin       => enforce_stack_copy(result, old, new, 0, flag),
+            OpCode::Noop        => enforce_stack_copy(result, old, new, 0, flag),
+            _ => panic!("no stack constraints for {}", op_code),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+use crate::{ math::field, OpCode, stark::{ TraceState, OutputTape } };
+use super::{ AuxTrace, AuxFrame };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Degree of the write counter transition constraint: the constraint is linear in the counter
+/// register and is multiplied by the flag of WRITE operation, which is a product of 5 op bits.
+const WRITE_COUNTER_DEGREE: usize = 5;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Auxiliary registers which bind a public output tape to the execution trace: in addition to
+/// the registers of the `inner` auxiliary trace (if any), the last register counts WRITE
+/// operations executed by the program. The counter starts at 0 and must be equal to the number
+/// of values on the tape at the last step; together with the constraints which enforce WRITE
+/// operation at every step recorded on the tape, this ensures that the tape contains every
+/// value written by the program, and not a subset of them.
+pub struct TapeCounter {
+    inner       : Option<Arc<dyn AuxTrace>>,
+    num_writes  : usize,
+}
+
+// TAPE COUNTER IMPLEMENTATION
+// ================================================================================================
+impl TapeCounter {
+
+    /// Returns auxiliary registers which include the registers of `inner` auxiliary trace (if
+    /// any) and a counter of WRITE operations which must end at the number of values on `tape`.
+    pub fn new(inner: Option<&Arc<dyn AuxTrace>>, tape: &OutputTape) -> TapeCounter {
+        return TapeCounter { inner: inner.cloned(), num_writes: tape.values.len() };
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+    fn inner_width(&self) -> usize {
+        return self.inner.as_ref().map_or(0, |inner| inner.width());
+    }
+}
+
+impl AuxTrace for TapeCounter {
+
+    fn width(&self) -> usize {
+        return self.inner_width() + 1;
+    }
+
+    fn num_challenges(&self) -> usize {
+        return self.inner.as_ref().map_or(0, |inner| inner.num_challenges());
+    }
+
+    fn build_registers(&self, trace: &[TraceState], challenges: &[u128]) -> Vec<Vec<u128>> {
+        let mut result = match &self.inner {
+            Some(inner) => inner.build_registers(trace, challenges),
+            None => Vec::new(),
+        };
+
+        let mut counter = Vec::with_capacity(trace.len());
+        counter.push(field::ZERO);
+        for state in trace[..(trace.len() - 1)].iter() {
+            let last = counter[counter.len() - 1];
+            counter.push(field::add(last, get_write_flag(state)));
+        }
+        result.push(counter);
+
+        return result;
+    }
+
+    fn constraint_degrees(&self) -> Vec<usize> {
+        let mut result = match &self.inner {
+            Some(inner) => inner.constraint_degrees(),
+            None => Vec::new(),
+        };
+        result.push(WRITE_COUNTER_DEGREE);
+        return result;
+    }
+
+    fn evaluate(&self, frame: &AuxFrame, challenges: &[u128], x: u128, result: &mut [u128]) {
+        let width = self.inner_width();
+        let (inner_result, result) = result.split_at_mut(result.len() - 1);
+
+        if let Some(inner) = &self.inner {
+            let inner_frame = AuxFrame {
+                current     : frame.current,
+                next        : frame.next,
+                aux_current : &frame.aux_current[..width],
+                aux_next    : &frame.aux_next[..width],
+            };
+            inner.evaluate(&inner_frame, challenges, x, inner_result);
+        }
+
+        // the counter must be incremented by 1 at every WRITE operation, and must remain the
+        // same otherwise
+        let expected = field::add(frame.aux_current[width], get_write_flag(frame.current));
+        result[0] = field::sub(frame.aux_next[width], expected);
+    }
+
+    fn boundary_values(&self, challenges: &[u128]) -> (Vec<u128>, Vec<u128>) {
+        let (mut i_values, mut f_values) = match &self.inner {
+            Some(inner) => inner.boundary_values(challenges),
+            None => (Vec::new(), Vec::new()),
+        };
+        i_values.push(field::ZERO);
+        f_values.push(self.num_writes as u128);
+        return (i_values, f_values);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn get_write_flag(state: &TraceState) -> u128 {
+    return state.ld_op_flags()[OpCode::Write.ld_index()];
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, processor, programs::assembly, ProgramInputs };
+    use crate::stark::{ TraceTable, TraceState, TraceStep, AuxTrace, AuxFrame, OutputTape };
+    use super::TapeCounter;
+
+    #[test]
+    fn count_writes() {
+        let program = assembly::compile("begin push.3 dup write push.5 write mul end").unwrap();
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &ProgramInputs::from_public(&[2]));
+        let trace = TraceTable::new(trace, ctx_depth, loop_depth, 32);
+        let states: Vec<TraceState> = (0..trace.unextended_length()).map(|i| trace.get_state(TraceStep(i))).collect();
+        let tape = trace.get_output_tape();
+        assert_eq!(2, tape.values.len());
+
+        // the counter ends at the number of written values, and satisfies transition constraints
+        let counter = TapeCounter::new(None, &tape);
+        let registers = counter.build_registers(&states, &[]);
+        assert_eq!(1, registers.len());
+        let (i_values, f_values) = counter.boundary_values(&[]);
+        assert_eq!(i_values[0], registers[0][0]);
+        assert_eq!(f_values[0], registers[0][states.len() - 1]);
+
+        let mut result = [field::ZERO];
+        for i in 0..(states.len() - 1) {
+            let frame = AuxFrame {
+                current     : &states[i],
+                next        : &states[i + 1],
+                aux_current : &registers[0][i..(i + 1)],
+                aux_next    : &registers[0][(i + 1)..(i + 2)],
+            };
+            counter.evaluate(&frame, &[], field::ONE, &mut result);
+            assert_eq!(field::ZERO, result[0]);
+        }
+
+        // a tape which omits one of the written values does not satisfy the boundary constraint
+        let partial = OutputTape { steps: tape.steps[..1].to_vec(), values: tape.values[..1].to_vec() };
+        let (_, f_values) = TapeCounter::new(None, &partial).boundary_values(&[]);
+        assert_ne!(f_values[0], registers[0][states.len() - 1]);
+    }
+}
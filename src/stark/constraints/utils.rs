@@ -1,6 +1,7 @@
 use crate::math::{ field, polynom, fft };
 use crate::utils::{ filled_vector };
 use crate::{ BASE_CYCLE_LENGTH };
+use crate::stark::MAX_CONSTRAINT_DEGREE;
 
 // BASIC CONSTRAINTS OPERATORS
 // ================================================================================================
@@ -84,8 +85,22 @@ impl EvaluationResult for Vec<u128> {
 
 // CONSTANT INTERPOLATION AND EXTENSIONS
 // ================================================================================================
-pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_factor: usize) -> (Vec<Vec<u128>>, Vec<Vec<u128>>)
+/// Interpolates cyclic `constants` into polynomials and evaluates them over a cycle of the
+/// evaluation domain extended by `extension_factor`. The evaluation domain of an execution trace
+/// of `trace_length` steps is shifted by `domain_offset`; since constant polynomials are
+/// evaluated at x^(trace_length / BASE_CYCLE_LENGTH), the cycle is shifted by the offset raised
+/// to the same power.
+pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], trace_length: usize, extension_factor: usize, domain_offset: u128) -> (Vec<Vec<u128>>, Vec<Vec<u128>>)
 {
+    // constants are extended via FFT, and must be defined over the entire evaluation domain
+    assert!(extension_factor.is_power_of_two(),
+        "extension factor must be a power of 2, but was {}", extension_factor);
+    assert!(extension_factor >= MAX_CONSTRAINT_DEGREE,
+        "extension factor cannot be smaller than {}, but was {}", MAX_CONSTRAINT_DEGREE, extension_factor);
+    assert!(trace_length.is_power_of_two() && trace_length >= BASE_CYCLE_LENGTH,
+        "trace length must be a power of 2 greater than or equal to {}, but was {}", BASE_CYCLE_LENGTH, trace_length);
+    let cycle_offset = field::exp(domain_offset, (trace_length / BASE_CYCLE_LENGTH) as u128);
+
     let root = field::get_root_of_unity(BASE_CYCLE_LENGTH);
     let inv_twiddles = fft::get_inv_twiddles(root, BASE_CYCLE_LENGTH);
 
@@ -104,6 +119,7 @@ pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_facto
         polys.push(extended_constant.clone());
 
         unsafe { extended_constant.set_len(extended_constant.capacity()); }
+        polynom::scale_argument(&mut extended_constant[..BASE_CYCLE_LENGTH], cycle_offset);
         polynom::eval_fft_twiddles(&mut extended_constant, &twiddles, true);
 
         evaluations.push(extended_constant);
@@ -117,6 +133,27 @@ pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_facto
 #[cfg(test)]
 mod tests {
 
+    use crate::{ math::field, utils::hasher::{ self, ARK } };
+
+    #[test]
+    fn ark_representations() {
+        let trace_length = 64;
+        let extension_factor = 32;
+        for &domain_offset in [field::ONE, field::GENERATOR].iter() {
+            let (polys, evaluations) = super::extend_constants(&ARK, trace_length, extension_factor, domain_offset);
+            let cycle_offset = field::exp(domain_offset, (trace_length / super::BASE_CYCLE_LENGTH) as u128);
+
+            assert_eq!(evaluations, hasher::ark_to_evaluations(&polys, extension_factor, cycle_offset));
+            assert_eq!(polys, hasher::ark_to_polys(&evaluations, cycle_offset));
+
+            // evaluations over the cycle of the trace domain are the constants themselves
+            if domain_offset == field::ONE {
+                let ark = hasher::ark_to_evaluations(&polys, 1, cycle_offset);
+                assert_eq!(ARK.iter().map(|row| row.to_vec()).collect::<Vec<_>>(), ark);
+            }
+        }
+    }
+
     #[test]
     fn enforce_left_shift() {
 
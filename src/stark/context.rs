@@ -0,0 +1,137 @@
+use std::{ cmp, thread };
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::math::{ field, fft };
+use super::{ constraints::ExtendedArk, prover::{ lde_domain, lde_domain_generator }, MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Traces shorter than this are proven in a single thread by auto_threads(), since for small
+/// traces the overhead of spawning threads outweighs the time saved.
+pub const MIN_PARALLEL_TRACE_LENGTH: usize = 4096;
+
+/// Minimum number of trace steps processed by each thread chosen by auto_threads().
+const MIN_STEPS_PER_THREAD: usize = 2048;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Caches values which depend only on trace length and extension factor (e.g. FFT twiddles and
+/// extended round constants) so that they can be re-used across many proofs.
+///
+/// The context also specifies the number of threads used by the prover. Only trace extension
+/// and building of the trace Merkle tree are distributed across threads; work in these stages
+/// is split into fixed batches whose results are combined in order, and thus, proofs generated
+/// with any number of threads are byte-for-byte identical. Constraint evaluation and FRI
+/// layer construction are always performed in a single thread.
+pub struct ProverContext {
+    domains     : HashMap<(usize, usize), Arc<LdeDomain>>,
+    stack_ark   : HashMap<usize, Arc<ExtendedArk>>,
+    num_threads : usize,
+}
+
+/// Low-degree extension domain together with twiddles needed to extend execution traces
+/// of a given length.
+pub struct LdeDomain {
+    pub values          : Vec<u128>,    // all elements of the LDE domain
+    pub twiddles        : Vec<u128>,    // twiddles for FFT evaluation over the LDE domain
+    pub inv_twiddles    : Vec<u128>,    // inverse twiddles for FFT interpolation over trace domain
+}
+
+// PROVER CONTEXT IMPLEMENTATION
+// ================================================================================================
+impl ProverContext {
+
+    /// Returns a new single-threaded context with no cached values.
+    pub fn new() -> ProverContext {
+        return ProverContext::with_threads(1);
+    }
+
+    /// Returns a new context with no cached values which distributes proof generation across
+    /// `num_threads` threads; `num_threads` must be a power of 2. Work is never split into more
+    /// batches than there are trace registers or steps in the extended trace, and thus, contexts
+    /// with more threads than that can still be used to prove small traces.
+    pub fn with_threads(num_threads: usize) -> ProverContext {
+        assert!(num_threads.is_power_of_two(), "number of threads must be a power of 2, but was {}", num_threads);
+        return ProverContext {
+            domains     : HashMap::new(),
+            stack_ark   : HashMap::new(),
+            num_threads,
+        };
+    }
+
+    /// Returns a new context with no cached values which uses a number of threads suitable for
+    /// proving execution traces of `trace_length` steps: traces shorter than
+    /// MIN_PARALLEL_TRACE_LENGTH are proven in a single thread; for longer traces, the number
+    /// of threads grows with trace length up to the number of threads available on this
+    /// machine. Proofs do not depend on the number of threads.
+    pub fn auto_threads(trace_length: usize) -> ProverContext {
+        if trace_length < MIN_PARALLEL_TRACE_LENGTH {
+            return ProverContext::new();
+        }
+
+        let available = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let num_threads = cmp::min(available, trace_length / MIN_STEPS_PER_THREAD);
+
+        // round the number of threads down to a power of 2
+        return ProverContext::with_threads(1 << (usize::BITS - 1 - num_threads.leading_zeros()));
+    }
+
+    /// Returns the number of threads used for proof generation.
+    pub fn num_threads(&self) -> usize {
+        return self.num_threads;
+    }
+
+    /// Returns LDE domain for the specified trace length and extension factor; the domain is
+    /// built on the first request and is cached for subsequent requests.
+    pub fn get_lde_domain(&mut self, trace_length: usize, extension_factor: usize) -> Arc<LdeDomain> {
+        let domain = self.domains.entry((trace_length, extension_factor)).or_insert_with(|| {
+            Arc::new(LdeDomain::new(trace_length, extension_factor))
+        });
+        return domain.clone();
+    }
+
+    /// Returns Rescue round constants extended by MAX_CONSTRAINT_DEGREE over the evaluation
+    /// domain of an execution trace of the specified length for use by stack constraint
+    /// evaluator; the constants are extended on the first request and are cached for subsequent
+    /// requests.
+    pub fn get_stack_ark(&mut self, trace_length: usize) -> Arc<ExtendedArk> {
+        let ark = self.stack_ark.entry(trace_length).or_insert_with(|| {
+            Arc::new(ExtendedArk::new(trace_length, MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET))
+        });
+        return ark.clone();
+    }
+
+    /// Returns the number of distinct LDE domains cached in this context.
+    pub fn domain_count(&self) -> usize {
+        return self.domains.len();
+    }
+}
+
+impl Default for ProverContext {
+    fn default() -> ProverContext {
+        return ProverContext::new();
+    }
+}
+
+// LDE DOMAIN IMPLEMENTATION
+// ================================================================================================
+impl LdeDomain {
+
+    pub fn new(trace_length: usize, extension_factor: usize) -> LdeDomain {
+        let domain_size = trace_length * extension_factor;
+
+        // build LDE domain and LDE twiddles (for FFT evaluation over LDE domain); the domain is
+        // a coset of the subgroup, and the twiddles are for the subgroup itself: polynomials are
+        // evaluated over the coset by scaling their coefficients before running FFT
+        let values = lde_domain(trace_length, extension_factor);
+        let twiddles = fft::get_twiddles(lde_domain_generator(trace_length, extension_factor), domain_size);
+
+        // build inverse twiddles needed for FFT interpolation of trace registers
+        let trace_root = field::get_root_of_unity(trace_length);
+        let inv_twiddles = fft::get_inv_twiddles(trace_root, trace_length);
+
+        return LdeDomain { values, twiddles, inv_twiddles };
+    }
+}
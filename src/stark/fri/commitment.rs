@@ -0,0 +1,194 @@
+use serde::{ Serialize, Deserialize };
+use crate::crypto::{ MerkleTree };
+use crate::math::{ field, quartic };
+use crate::stark::{ ProofOptions, VerifyError, utils::{ compute_query_positions, Transcript } };
+
+use super::{ FriProof, reduce, build_proof, verify, utils, verifier::{ get_column_values, validate_proof_shape } };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A commitment to evaluations of a polynomial over a multiplicative subgroup of size
+/// `domain_size`, together with the degree bound the polynomial is claimed to satisfy. `root`
+/// is the root of the Merkle tree built from the evaluations (the first FRI layer).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriCommitment {
+    pub root        : [u8; 32],
+    pub domain_size : usize,
+    pub max_degree  : usize,
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Commits to the provided `evaluations` and generates a FRI proof that the evaluations are of
+/// a polynomial of degree at most `max_degree`. The evaluations must be in the order of the
+/// power series of the root of unity of order `evaluations.len()`. Query positions are derived
+/// from the roots of all FRI layers.
+///
+/// The degree of the polynomial is not checked; thus, a proof for a polynomial which does not
+/// satisfy the degree bound can be generated, but it will not pass verification.
+pub fn prove_fri(evaluations: &[u128], max_degree: usize, options: &ProofOptions) -> (FriCommitment, FriProof)
+{
+    let domain_size = evaluations.len();
+    assert!(domain_size.is_power_of_two(), "number of evaluations must be a power of 2");
+
+    let domain_root = field::get_root_of_unity(domain_size);
+    let domain = field::get_power_series(domain_root, domain_size);
+    let (trees, values) = reduce(evaluations, &domain, max_degree, options);
+
+    let roots: Vec<[u8; 32]> = trees.iter().map(|tree| *tree.root()).collect();
+    let commitment = FriCommitment { root: roots[0], domain_size, max_degree };
+
+    let seed = get_query_seed(&roots, options);
+    let positions = compute_query_positions(&seed, domain_size, options);
+    let proof = build_proof(trees, values, &positions);
+
+    return (commitment, proof);
+}
+
+/// Verifies that `proof` is a valid FRI proof for the committed polynomial; that is, that the
+/// polynomial committed to by `commitment` has degree of at most `commitment.max_degree`.
+/// Unlike STARK verification, only the low-degree test is performed: values of the polynomial
+/// at the queried positions are taken from the first layer of the proof.
+pub fn verify_fri(commitment: &FriCommitment, proof: &FriProof, options: &ProofOptions) -> Result<(), VerifyError>
+{
+    let domain_size = commitment.domain_size;
+    if !domain_size.is_power_of_two() || domain_size < 4 {
+        return Err(invalid_proof(format!("domain size {} is not a power of 2 greater than 2", domain_size)));
+    }
+    if domain_size - domain_size / options.extension_factor() < options.num_queries() {
+        return Err(invalid_proof(format!("domain of size {} is too small for {} queries",
+            domain_size, options.num_queries())));
+    }
+
+    // make sure the structure of the proof matches the committed domain before any of its
+    // values are used
+    validate_proof_shape(proof, domain_size).map_err(invalid_proof)?;
+
+    // the remainder is sent in full; make sure it matches its commitment since query
+    // positions are derived from it
+    let rem_rows = quartic::transpose(&proof.rem_values, 1);
+    let rem_tree = MerkleTree::new(utils::hash_values(&rem_rows, options.hash_fn()), options.hash_fn());
+    if *rem_tree.root() != proof.rem_root {
+        return Err(invalid_proof(String::from("remainder values do not match remainder root")));
+    }
+
+    // make sure the proof is for the committed polynomial over the committed domain
+    let mut roots: Vec<[u8; 32]> = proof.layers.iter().map(|layer| layer.root).collect();
+    roots.push(proof.rem_root);
+    if roots[0] != commitment.root {
+        return Err(invalid_proof(String::from("proof does not match the committed polynomial")));
+    }
+
+    // read values of the polynomial at queried positions; values from the first layer are
+    // authenticated against the commitment during FRI verification
+    let seed = get_query_seed(&roots, options);
+    let positions = compute_query_positions(&seed, domain_size, options);
+    let evaluations = match proof.layers.first() {
+        Some(layer) => {
+            let augmented_positions = utils::get_augmented_positions(&positions, domain_size);
+            if layer.values.len() != augmented_positions.len() {
+                return Err(invalid_proof(format!("expected {} rows at layer 0, but received {}",
+                    augmented_positions.len(), layer.values.len())));
+            }
+            get_column_values(&layer.values, &positions, &augmented_positions, domain_size)
+        },
+        None => positions.iter().map(|&p| proof.rem_values[p]).collect(),
+    };
+
+    return match verify(proof, &evaluations, &positions, domain_size, commitment.max_degree, field::ONE, options) {
+        Ok(_) => Ok(()),
+        Err(msg) => Err(invalid_proof(format!("verification of low-degree proof failed: {}", msg))),
+    };
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Derives a seed for query positions from roots of all FRI layers.
+fn get_query_seed(roots: &[[u8; 32]], options: &ProofOptions) -> [u8; 32] {
+    let mut transcript = Transcript::new(options.transcript_fn());
+    transcript.absorb_commitments(&roots.concat());
+    return transcript.state();
+}
+
+fn invalid_proof(message: String) -> VerifyError {
+    return VerifyError::InvalidProof(message);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::math::{ field, polynom };
+    use crate::stark::{ ProofOptions, VerifyError };
+
+    #[test]
+    fn verify_fri() {
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
+
+        // degree 63 polynomial evaluated over a domain of 1024 points
+        let evaluations = build_poly_evaluations(1024, 63);
+        let (commitment, proof) = super::prove_fri(&evaluations, 63, &options);
+        assert_eq!(Ok(()), super::verify_fri(&commitment, &proof, &options));
+
+        // the proof is not valid for a different commitment
+        let mut other = commitment;
+        other.root[0] ^= 1;
+        let err = super::verify_fri(&other, &proof, &options).unwrap_err();
+        assert_eq!(VerifyError::InvalidProof(String::from("proof does not match the committed polynomial")), err);
+    }
+
+    #[test]
+    fn verify_fri_high_degree() {
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
+
+        // the polynomial has degree 127, but the commitment claims degree 63
+        let evaluations = build_poly_evaluations(1024, 127);
+        let (commitment, proof) = super::prove_fri(&evaluations, 63, &options);
+
+        let err = super::verify_fri(&commitment, &proof, &options).unwrap_err();
+        let expected = "verification of low-degree proof failed: remainder is not a valid degree 15 polynomial";
+        assert_eq!(VerifyError::InvalidProof(String::from(expected)), err);
+    }
+
+    #[test]
+    fn verify_fri_malformed() {
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
+        let evaluations = build_poly_evaluations(1024, 63);
+        let (commitment, proof) = super::prove_fri(&evaluations, 63, &options);
+
+        // remainder values which cannot be split into rows of 4 values
+        let mut bad_proof = proof.clone();
+        bad_proof.rem_values.pop();
+        let err = super::verify_fri(&commitment, &bad_proof, &options).unwrap_err();
+        assert_eq!(VerifyError::InvalidProof(String::from("remainder must consist of at least 8 values and a multiple of 4, but was 255")), err);
+
+        // a remainder of a single row cannot be put into a Merkle tree
+        let mut bad_proof = proof.clone();
+        bad_proof.rem_values.truncate(4);
+        let err = super::verify_fri(&commitment, &bad_proof, &options).unwrap_err();
+        assert_eq!(VerifyError::InvalidProof(String::from("remainder must consist of at least 8 values and a multiple of 4, but was 4")), err);
+
+        // a layer depth which would overflow the domain size
+        let mut bad_proof = proof.clone();
+        bad_proof.layers[0].depth = 200;
+        let err = super::verify_fri(&commitment, &bad_proof, &options).unwrap_err();
+        assert_eq!(VerifyError::InvalidProof(String::from("expected layer 0 for domain of size 1024, but depth was 200")), err);
+
+        // a layer with fewer rows than queried positions
+        let mut bad_proof = proof.clone();
+        bad_proof.layers[0].values.truncate(1);
+        let err = super::verify_fri(&commitment, &bad_proof, &options).unwrap_err();
+        assert_eq!(VerifyError::InvalidProof(String::from("authentication paths at layer 0 are malformed")), err);
+    }
+
+    fn build_poly_evaluations(domain_size: usize, degree: usize) -> Vec<u128> {
+        let mut evaluations = field::rand_vector(degree + 1);
+        evaluations.resize(domain_size, 0);
+        polynom::eval_fft(&mut evaluations, true);
+        return evaluations;
+    }
+}
@@ -3,14 +3,16 @@ use serde::{ Serialize, Deserialize };
 // RE-EXPORTS
 // ================================================================================================
 mod utils;
+pub use utils::{ get_layer_degrees, get_folding_challenges };
 
 mod prover;
-pub use prover::{ reduce, build_proof };
+pub use prover::{ reduce, build_proof, fold_layer };
 
 mod verifier;
-pub use verifier::{ verify };
+pub use verifier::{ verify, FriVerifier };
 
-const MAX_REMAINDER_LENGTH: usize = 256;
+mod commitment;
+pub use commitment::{ FriCommitment, prove_fri, verify_fri };
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -33,7 +35,7 @@ pub struct FriLayer {
 // ================================================================================================
 #[cfg(test)]
 mod tests {
-    use crate::math::{ field, polynom };
+    use crate::math::{ field, polynom, quartic };
     use crate::stark::{ ProofOptions, utils::compute_query_positions };
 
     #[test]
@@ -42,18 +44,18 @@ mod tests {
         let domain_size: usize = 512;
         let root = field::get_root_of_unity(domain_size);
         let domain = field::get_power_series(root, domain_size);
-        let options = ProofOptions::default();
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
 
         let evaluations = build_random_poly_evaluations(domain_size, degree);
 
         // generate proof
-        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &options);
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
         let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
         let proof = super::build_proof(fri_trees, fri_values, &positions);
 
         // verify proof
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
         assert_eq!(Ok(true), result);
     }
 
@@ -63,39 +65,218 @@ mod tests {
         let domain_size: usize = 512;
         let root = field::get_root_of_unity(domain_size);
         let domain = field::get_power_series(root, domain_size);
-        let options = ProofOptions::default();
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
 
         // degree too low 1
         let evaluations = build_random_poly_evaluations(domain_size, degree);
-        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &options);
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
         let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
         let proof = super::build_proof(fri_trees, fri_values, &positions);
 
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree - 1, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree - 1, field::ONE, &options);
         let err_msg = format!("remainder is not a valid degree {} polynomial", 14);
         assert_eq!(Err(err_msg), result);
 
         // degree too low 2
         let evaluations = build_random_poly_evaluations(domain_size, degree + 1);
-        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &options);
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
         let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
         let proof = super::build_proof(fri_trees, fri_values, &positions);
 
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
         let err_msg = format!("remainder is not a valid degree {} polynomial", 15);
         assert_eq!(Err(err_msg), result);
 
         // invalid evaluations
         let sampled_evaluations = sampled_evaluations[1..].to_vec();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
         let err_msg = format!("evaluations did not match column value at depth 0");
         assert_eq!(Err(err_msg), result);
     }
 
+    #[test]
+    fn prove_verify_coset() {
+        let degree: usize = 255;
+        let domain_size: usize = 4096;
+        let offset = field::GENERATOR;
+        let root = field::get_root_of_unity(domain_size);
+        let domain: Vec<u128> = field::get_power_series(root, domain_size).into_iter()
+            .map(|x| field::mul(x, offset)).collect();
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
+
+        // evaluate a random polynomial over the coset
+        let mut evaluations = field::rand_vector(degree + 1);
+        evaluations.resize(domain_size, 0);
+        polynom::scale_argument(&mut evaluations, offset);
+        polynom::eval_fft(&mut evaluations, true);
+
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
+        let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
+        let proof = super::build_proof(fri_trees, fri_values, &positions);
+
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, offset, &options);
+        assert_eq!(Ok(true), result);
+
+        // the same proof does not verify against the subgroup
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn folding_challenges_chain() {
+        let degree: usize = 1023;
+        let domain_size: usize = 16384;
+        let root = field::get_root_of_unity(domain_size);
+        let domain = field::get_power_series(root, domain_size);
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
+
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
+        let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
+        let mut proof = super::build_proof(fri_trees, fri_values, &positions);
+        assert_eq!(3, proof.layers.len());
+
+        let roots: Vec<[u8; 32]> = proof.layers.iter().map(|layer| layer.root).collect();
+        let challenges = super::get_folding_challenges(&roots, options.transcript_fn());
+
+        // corrupting a commitment to the middle layer changes challenges for all subsequent layers
+        let mut bad_roots = roots.clone();
+        bad_roots[1][0] ^= 1;
+        let bad_challenges = super::get_folding_challenges(&bad_roots, options.transcript_fn());
+        assert_eq!(challenges[0], bad_challenges[0]);
+        assert!(challenges[1] != bad_challenges[1]);
+        assert!(challenges[2] != bad_challenges[2]);
+
+        // and the proof is rejected
+        proof.layers[1].root = bad_roots[1];
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn fold_layer() {
+        let degree: usize = 255;
+        let domain_size: usize = 4096;
+        let root = field::get_root_of_unity(domain_size);
+        let domain = field::get_power_series(root, domain_size);
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
+
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
+        let roots: Vec<[u8; 32]> = fri_trees.iter().map(|tree| *tree.root()).collect();
+        let challenges = super::get_folding_challenges(&roots, options.transcript_fn());
+
+        // folding the first layer manually results in the second layer built by the prover
+        let folded = super::fold_layer(&evaluations, challenges[0], &domain);
+        assert_eq!(domain_size / 4, folded.len());
+        assert_eq!(fri_values[1], quartic::transpose(&folded, 1));
+
+        // the folded polynomial has 4 times smaller degree over 4th powers of the domain
+        let next_root = field::exp(root, 4);
+        let next_domain = field::get_power_series(next_root, domain_size / 4);
+        let poly = polynom::interpolate(&next_domain, &folded);
+        assert!(poly[(degree + 1) / 4..].iter().all(|&c| c == field::ZERO));
+
+        // folding the second layer manually results in the remainder
+        let folded = super::fold_layer(&folded, challenges[1], &next_domain);
+        assert_eq!(fri_values[2], quartic::transpose(&folded, 1));
+    }
+
+    #[test]
+    fn log_layer_degrees() {
+        let degree: usize = 255;
+        let domain_size: usize = 4096;
+        let root = field::get_root_of_unity(domain_size);
+        let domain = field::get_power_series(root, domain_size);
+        let options = ProofOptions::default().with_fri_max_remainder_degree(15).unwrap();
+
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
+        let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
+        let proof = super::build_proof(fri_trees, fri_values, &positions);
+
+        // degree is reduced by a factor of 4 at every layer
+        assert_eq!(vec![255, 63, 15], super::get_layer_degrees(degree, proof.layers.len()));
+
+        // capture messages logged during verification
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Debug);
+        LOG.with(|log| log.borrow_mut().clear());
+
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
+        assert_eq!(Ok(true), result);
+
+        let expected = vec![
+            String::from("FRI layer 0: degree bound 255"),
+            String::from("FRI layer 1: degree bound 63"),
+            String::from("FRI remainder: degree bound 15 (16 coefficients)"),
+        ];
+        LOG.with(|log| assert_eq!(expected, *log.borrow()));
+    }
+
+    #[test]
+    fn max_remainder_degree() {
+        let degree: usize = 255;
+        let domain_size: usize = 4096;
+        let root = field::get_root_of_unity(domain_size);
+        let domain = field::get_power_series(root, domain_size);
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+
+        // the polynomial is folded until the remainder degree is at most the specified degree
+        for &(max_remainder_degree, num_layers) in [(255, 0), (63, 1), (15, 2), (0, 4)].iter() {
+            let options = ProofOptions::default().with_fri_max_remainder_degree(max_remainder_degree).unwrap();
+            let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
+            let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
+            let proof = super::build_proof(fri_trees, fri_values, &positions);
+            assert_eq!(num_layers, proof.layers.len());
+
+            let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+            let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
+            assert_eq!(Ok(true), result);
+        }
+
+        // proofs with remainders of higher degree are rejected
+        let options = ProofOptions::default();
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, degree, &options);
+        let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
+        let proof = super::build_proof(fri_trees, fri_values, &positions);
+
+        let options = options.with_fri_max_remainder_degree(15).unwrap();
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify(&proof, &sampled_evaluations, &positions, domain_size, degree, field::ONE, &options);
+        let err_msg = format!("remainder degree bound 63 is greater than the maximum remainder degree 15");
+        assert_eq!(Err(err_msg), result);
+    }
+
     // TODO: add more tests
 
+    // messages are captured per thread so that concurrently running tests don't interfere
+    thread_local! {
+        static LOG: std::cell::RefCell<Vec<String>> = std::cell::RefCell::new(Vec::new());
+    }
+
+    struct TestLogger;
+    static LOGGER: TestLogger = TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            return true;
+        }
+
+        fn log(&self, record: &log::Record) {
+            if record.target().ends_with("fri::verifier") {
+                LOG.with(|log| log.borrow_mut().push(format!("{}", record.args())));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
     fn build_random_poly_evaluations(domain_size: usize, degree: usize) -> Vec<u128> {
         let mut evaluations = field::rand_vector(degree + 1);
         evaluations.resize(domain_size, 0);
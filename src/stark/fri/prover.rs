@@ -3,12 +3,16 @@ use crate::math::{ field, quartic };
 use crate::crypto::{ MerkleTree };
 use crate::stark::{ ProofOptions };
 
-use super::{ FriProof, FriLayer, utils, MAX_REMAINDER_LENGTH};
+use super::{ FriProof, FriLayer, utils };
 
 // PROVER FUNCTIONS
 // ================================================================================================
 
-pub fn reduce(evaluations: &[u128], domain: &[u128], options: &ProofOptions) -> (Vec<MerkleTree>, Vec<Vec<[u128; 4]>>) {
+/// Folds `evaluations` of a polynomial of degree at most `max_degree` over `domain` into FRI
+/// layers until the degree of the remaining polynomial is at most the maximum remainder degree
+/// specified by `options`. The domain may be a coset of a subgroup; the domain of each next
+/// layer consists of 4th powers of elements of the previous domain.
+pub fn reduce(evaluations: &[u128], domain: &[u128], max_degree: usize, options: &ProofOptions) -> (Vec<MerkleTree>, Vec<Vec<[u128; 4]>>) {
     let mut tree_results: Vec<MerkleTree> = Vec::new();
     let mut value_results: Vec<Vec<[u128; 4]>> = Vec::new();
 
@@ -16,18 +20,17 @@ pub fn reduce(evaluations: &[u128], domain: &[u128], options: &ProofOptions) ->
     let mut p_values = quartic::transpose(evaluations, 1);
     let hashed_values = utils::hash_values(&p_values, options.hash_fn());
     let mut p_tree = MerkleTree::new(hashed_values, options.hash_fn());
+    let mut challenges = utils::FoldingChallenges::new(options.transcript_fn());
 
     // reduce the degree by 4 at each iteration until the remaining polynomial is small enough
-    while p_tree.leaves().len() * 4 > MAX_REMAINDER_LENGTH {
+    // (the next layer must still consist of at least 4 values)
+    let mut max_degree_plus_1 = max_degree + 1;
+    let mut domain = domain.to_vec();
+    while max_degree_plus_1 > options.fri_max_remainder_degree() + 1 && p_values.len() >= 4 {
 
-        // build polynomials from each row of the polynomial value matrix
-        let depth = tree_results.len() as u32;
-        let xs = quartic::transpose(domain, usize::pow(4, depth));
-        let polys = quartic::interpolate_batch(&xs, &p_values);
-
-        // select a pseudo-random x coordinate and evaluate each row polynomial at that x
-        let special_x = field::prng(*p_tree.root());
-        let column = quartic::evaluate_batch(&polys, special_x);
+        // select a pseudo-random x coordinate and fold the layer at that x
+        let special_x = challenges.draw(p_tree.root());
+        let column = fold_rows(&p_values, special_x, &domain);
 
         // break the column in a polynomial value matrix for the next layer
         let mut c_values = quartic::transpose(&column, 1);
@@ -43,6 +46,12 @@ pub fn reduce(evaluations: &[u128], domain: &[u128], options: &ProofOptions) ->
         // add p_tree and p_values from this loop (which is now under c_tree and c_values) to the result
         tree_results.push(c_tree);
         value_results.push(c_values);
+        max_degree_plus_1 = max_degree_plus_1 / 4;
+
+        // x coordinates of the next layer are 4th powers of x coordinates of this layer; since
+        // x^4 is the same for all x in a row, powers of the first quarter of the domain suffice
+        domain.truncate(domain.len() / 4);
+        domain.iter_mut().for_each(|x| *x = field::exp(*x, 4));
     }
 
     // add the tree at the last layer (the remainder)
@@ -52,6 +61,20 @@ pub fn reduce(evaluations: &[u128], domain: &[u128], options: &ProofOptions) ->
     return (tree_results, value_results);
 }
 
+/// Folds `evaluations` of a polynomial over `domain` into a single FRI layer: evaluations are
+/// split into rows of 4 values at x coordinates x * g^(n/4 * j) for j in 0..4 (n is the size of
+/// the domain); for every row, a degree 3 polynomial is interpolated and evaluated at `alpha`.
+/// The result is evaluations of a polynomial of 4 times smaller degree over the domain of 4th
+/// powers of the first quarter of `domain`, and is the same as the next layer built by reduce().
+pub fn fold_layer(evaluations: &[u128], alpha: u128, domain: &[u128]) -> Vec<u128> {
+    assert!(evaluations.len() == domain.len(),
+        "expected {} evaluations, but received {}", domain.len(), evaluations.len());
+    assert!(domain.len() >= 4 && domain.len().is_power_of_two(),
+        "domain size must be a power of 2 greater than 3, but was {}", domain.len());
+
+    return fold_rows(&quartic::transpose(evaluations, 1), alpha, domain);
+}
+
 pub fn build_proof(trees: Vec<MerkleTree>, values: Vec<Vec<[u128; 4]>>, positions: &[usize]) -> FriProof {
     let mut positions = positions.to_vec();
     let mut domain_size = trees[0].leaves().len() * 4;
@@ -93,4 +116,19 @@ pub fn build_proof(trees: Vec<MerkleTree>, values: Vec<Vec<[u128; 4]>>, position
     }
 
     return FriProof { layers, rem_root: *last_tree.root(), rem_values: remainder };
-}
\ No newline at end of file
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Same as fold_layer(), but takes evaluations which have already been transposed into rows of
+/// the polynomial value matrix; this lets reduce() fold the matrix it has already committed to
+/// without keeping another copy of the evaluations.
+fn fold_rows(values: &[[u128; 4]], alpha: u128, domain: &[u128]) -> Vec<u128> {
+    // build polynomials from each row of the polynomial value matrix
+    let xs = quartic::transpose(domain, 1);
+    let polys = quartic::interpolate_batch(&xs, values);
+
+    // evaluate each row polynomial at alpha
+    return quartic::evaluate_batch(&polys, alpha);
+}
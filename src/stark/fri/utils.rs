@@ -1,5 +1,6 @@
 use crate::crypto::{ HashFunction };
 use crate::utils::{ uninit_vector, as_bytes };
+use crate::stark::utils::Transcript;
 
 pub fn get_augmented_positions(positions: &[usize], column_length: usize) -> Vec<usize> {
     let row_length = column_length / 4;
@@ -19,4 +20,51 @@ pub fn hash_values(values: &Vec<[u128; 4]>, hash: HashFunction) -> Vec<[u8; 32]>
         hash(as_bytes(&values[i]), &mut result[i]);
     }
     return result;
-}
\ No newline at end of file
+}
+
+/// Returns degree bounds of the polynomial at each FRI layer, followed by the degree bound of
+/// the remainder; the degree of the polynomial is reduced by a factor of 4 at each layer.
+pub fn get_layer_degrees(max_degree: usize, num_layers: usize) -> Vec<usize> {
+    let mut result = Vec::with_capacity(num_layers + 1);
+    let mut max_degree_plus_1 = max_degree + 1;
+    for _ in 0..(num_layers + 1) {
+        result.push(max_degree_plus_1 - 1);
+        max_degree_plus_1 = max_degree_plus_1 / 4;
+    }
+    return result;
+}
+
+/// Returns pseudo-random x coordinates at which row polynomials of FRI layers committed to by
+/// `roots` are evaluated to build the next layers; see `FoldingChallenges` for details.
+pub fn get_folding_challenges(roots: &[[u8; 32]], hash_fn: HashFunction) -> Vec<u128> {
+    let mut challenges = FoldingChallenges::new(hash_fn);
+    return roots.iter().map(|root| challenges.draw(root)).collect();
+}
+
+// FOLDING CHALLENGES
+// ================================================================================================
+
+/// Draws folding challenges for FRI layers one layer at a time. The challenge for a layer is
+/// drawn from a transcript after the commitment to that layer has been absorbed into it; thus,
+/// the challenge depends on commitments to all preceding layers, and changing a commitment to
+/// any layer changes challenges for all subsequent layers.
+pub struct FoldingChallenges {
+    transcript  : Transcript,
+    previous    : Option<u128>,
+}
+
+impl FoldingChallenges {
+
+    pub fn new(hash_fn: HashFunction) -> FoldingChallenges {
+        return FoldingChallenges { transcript: Transcript::new(hash_fn), previous: None };
+    }
+
+    /// Absorbs the `root` of the next FRI layer and returns the challenge for this layer.
+    pub fn draw(&mut self, root: &[u8; 32]) -> u128 {
+        self.transcript.absorb_commitments(root);
+        let challenge = self.transcript.draw_field_elements(1)[0];
+        debug_assert!(self.previous != Some(challenge), "folding challenge was re-used across FRI layers");
+        self.previous = Some(challenge);
+        return challenge;
+    }
+}
@@ -1,44 +1,103 @@
 use std::mem;
+use log::debug;
 use crate::math::{ field, polynom, quartic };
 use crate::crypto::{ MerkleTree, BatchMerkleProof };
 use crate::stark::{ ProofOptions };
 
 use super::{ FriProof, FriLayer, utils };
 
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Verifies a FRI proof one layer at a time; this allows the verification work to be split into
+/// bounded steps. Once all layers have been verified via verify_layer(), the remainder of the
+/// proof is verified via verify_remainder().
+pub struct FriVerifier {
+    quartic_roots       : [u128; 4],
+    domain_root         : u128,
+    domain_offset       : u128,
+    domain_size         : usize,
+    max_degree_plus_1   : usize,
+    positions           : Vec<usize>,
+    evaluations         : Vec<u128>,
+    challenges          : utils::FoldingChallenges,
+    depth               : usize,
+}
+
 // VERIFIER
 // ================================================================================================
 
+/// Verifies that `evaluations` at the specified `positions` are of a polynomial of degree at
+/// most `max_degree`; the evaluation domain is the subgroup of size `domain_size` shifted by
+/// `domain_offset` (ONE for the subgroup itself).
 pub fn verify(
     proof       : &FriProof,
     evaluations : &[u128],
     positions   : &[usize],
+    domain_size : usize,
     max_degree  : usize,
+    domain_offset: u128,
     options     : &ProofOptions) -> Result<bool, String>
 {
+    let mut verifier = FriVerifier::new(proof, evaluations, positions, domain_size, max_degree, domain_offset, options)?;
 
-    let domain_size = usize::pow(2, proof.layers[0].depth as u32) * 4;
-    let domain_root = field::get_root_of_unity(domain_size);
+    // 1 ----- verify the recursive components of the FRI proof -----------------------------------
+    for layer in proof.layers.iter() {
+        verifier.verify_layer(layer, options)?;
+    }
 
-    // powers of the given root of unity 1, p, p^2, p^3 such that p^4 = 1
-    let quartic_roots = [
-        1u128,
-        field::exp(domain_root, (domain_size / 4) as u128),
-        field::exp(domain_root, (domain_size / 2) as u128),
-        field::exp(domain_root, (domain_size * 3 / 4) as u128),
-    ];
+    // 2 ----- verify the remainder of the FRI proof ----------------------------------------------
+    return verifier.verify_remainder(proof, options);
+}
 
-    // 1 ----- verify the recursive components of the FRI proof -----------------------------------
-    let mut domain_root = domain_root;
-    let mut domain_size = domain_size;
-    let mut max_degree_plus_1 = max_degree + 1;
-    let mut positions = positions.to_vec();
-    let mut evaluations = evaluations.to_vec();
+// FRI VERIFIER IMPLEMENTATION
+// ================================================================================================
+impl FriVerifier {
+
+    /// Returns a verifier for the `proof` of `evaluations` at the specified `positions`; the
+    /// parameters are the same as for verify(). An error is returned if the structure of the
+    /// proof does not match the domain of `domain_size` values.
+    pub fn new(proof: &FriProof, evaluations: &[u128], positions: &[usize], domain_size: usize, max_degree: usize, domain_offset: u128, options: &ProofOptions) -> Result<FriVerifier, String> {
+
+        // the domain size comes from the verifier, and the proof must fold exactly this domain;
+        // this is checked before any of the proof values are used
+        validate_proof_shape(proof, domain_size)?;
+        let domain_root = field::get_root_of_unity(domain_size);
+
+        // powers of the given root of unity 1, p, p^2, p^3 such that p^4 = 1
+        let quartic_roots = [
+            1u128,
+            field::exp(domain_root, (domain_size / 4) as u128),
+            field::exp(domain_root, (domain_size / 2) as u128),
+            field::exp(domain_root, (domain_size * 3 / 4) as u128),
+        ];
+
+        return Ok(FriVerifier {
+            quartic_roots,
+            domain_root,
+            domain_offset,
+            domain_size,
+            max_degree_plus_1   : max_degree + 1,
+            positions           : positions.to_vec(),
+            evaluations         : evaluations.to_vec(),
+            challenges          : utils::FoldingChallenges::new(options.transcript_fn()),
+            depth               : 0,
+        });
+    }
 
-    for (depth, layer) in proof.layers.iter().enumerate() {
+    /// Verifies the next `layer` of the proof; layers must be verified in the order in which
+    /// they appear in the proof.
+    pub fn verify_layer(&mut self, layer: &FriLayer, options: &ProofOptions) -> Result<(), String> {
+        let depth = self.depth;
+        debug!("FRI layer {}: degree bound {}", depth, self.max_degree_plus_1 - 1);
 
-        let mut augmented_positions = utils::get_augmented_positions(&positions, domain_size);
-        let column_values = get_column_values(&layer.values, &positions, &augmented_positions, domain_size);
-        if evaluations != column_values {
+        let mut augmented_positions = utils::get_augmented_positions(&self.positions, self.domain_size);
+        if layer.values.len() != augmented_positions.len() {
+            return Err(format!("expected {} rows at layer {}, but received {}",
+                augmented_positions.len(), depth, layer.values.len()));
+        }
+        let column_values = get_column_values(&layer.values, &self.positions, &augmented_positions, self.domain_size);
+        if self.evaluations != column_values {
             return Err(format!("evaluations did not match column value at depth {}", depth));
         }
 
@@ -51,12 +110,12 @@ pub fn verify(
         // build a set of x for each row polynomial
         let mut xs = Vec::with_capacity(augmented_positions.len());
         for &i in augmented_positions.iter() {
-            let xe = field::exp(domain_root, i as u128);
+            let xe = field::mul(field::exp(self.domain_root, i as u128), self.domain_offset);
             xs.push([
-                field::mul(quartic_roots[0], xe),
-                field::mul(quartic_roots[1], xe),
-                field::mul(quartic_roots[2], xe),
-                field::mul(quartic_roots[3], xe)
+                field::mul(self.quartic_roots[0], xe),
+                field::mul(self.quartic_roots[1], xe),
+                field::mul(self.quartic_roots[2], xe),
+                field::mul(self.quartic_roots[3], xe)
             ]);
         }
 
@@ -64,31 +123,46 @@ pub fn verify(
         let row_polys = quartic::interpolate_batch(&xs, &layer.values);
 
         // calculate the pseudo-random x coordinate
-        let special_x = field::prng(layer.root);
+        let special_x = self.challenges.draw(&layer.root);
 
         // check that when the polynomials are evaluated at x, the result is equal to the corresponding column value
-        evaluations = quartic::evaluate_batch(&row_polys, special_x);
+        self.evaluations = quartic::evaluate_batch(&row_polys, special_x);
 
-        // update variables for the next iteration of the loop
-        domain_root = field::exp(domain_root, 4);
-        max_degree_plus_1 = max_degree_plus_1 / 4;
-        domain_size = domain_size / 4;
-        mem::swap(&mut positions, &mut augmented_positions);
+        // update variables for the next layer
+        self.domain_root = field::exp(self.domain_root, 4);
+        self.domain_offset = field::exp(self.domain_offset, 4);
+        self.max_degree_plus_1 = self.max_degree_plus_1 / 4;
+        self.domain_size = self.domain_size / 4;
+        mem::swap(&mut self.positions, &mut augmented_positions);
+        self.depth += 1;
+
+        return Ok(());
     }
 
-    // 2 ----- verify the remainder of the FRI proof ----------------------------------------------
-    
-    for (&position, evaluation) in positions.iter().zip(evaluations) {
-        if proof.rem_values[position] != evaluation {
-            return Err(String::from("remainder values are inconsistent with values of the last column"));
+    /// Verifies the remainder of the `proof`; this must be called after all layers of the proof
+    /// have been verified.
+    pub fn verify_remainder(&self, proof: &FriProof, options: &ProofOptions) -> Result<bool, String> {
+        debug!("FRI remainder: degree bound {} ({} coefficients)", self.max_degree_plus_1 - 1, self.max_degree_plus_1);
+        debug_assert!(self.depth == proof.layers.len(), "not all FRI layers have been verified");
+
+        // make sure the prover did not stop folding before the remainder became small enough
+        if self.max_degree_plus_1 > options.fri_max_remainder_degree() + 1 {
+            return Err(format!("remainder degree bound {} is greater than the maximum remainder degree {}",
+                self.max_degree_plus_1 - 1, options.fri_max_remainder_degree()));
+        }
+
+        for (&position, &evaluation) in self.positions.iter().zip(self.evaluations.iter()) {
+            if proof.rem_values[position] != evaluation {
+                return Err(String::from("remainder values are inconsistent with values of the last column"));
+            }
         }
-    }
 
-    // make sure the remainder values satisfy the degree
-    return verify_remainder(&proof.rem_values, max_degree_plus_1, domain_root, options.extension_factor());
+        // make sure the remainder values satisfy the degree
+        return verify_remainder(&proof.rem_values, self.max_degree_plus_1, self.domain_root, self.domain_offset, options.extension_factor());
+    }
 }
 
-fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u128, extension_factor: usize) -> Result<bool, String> {
+fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u128, domain_offset: u128, extension_factor: usize) -> Result<bool, String> {
     if max_degree_plus_1 > remainder.len() {
         return Err(String::from("remainder degree is greater than number of remainder values"));
     }
@@ -102,7 +176,8 @@ fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u
     }
 
     // pick a subset of points from the remainder and interpolate them into a polynomial
-    let domain = field::get_power_series(domain_root, remainder.len());
+    let mut domain = field::get_power_series(domain_root, remainder.len());
+    domain.iter_mut().for_each(|x| *x = field::mul(*x, domain_offset));
     let mut xs = Vec::with_capacity(max_degree_plus_1);
     let mut ys = Vec::with_capacity(max_degree_plus_1);
     for i in 0..max_degree_plus_1 {
@@ -125,7 +200,43 @@ fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn get_column_values(values: &Vec<[u128; 4]>, positions: &[usize], augmented_positions: &[usize], column_length: usize) -> Vec<u128> {
+
+/// Makes sure that every layer of the `proof` has the depth implied by the domain of `domain_size`
+/// values, that authentication paths are no longer than the depth of their layer, and that the
+/// remainder consists of at least 2 rows of 4 values and covers the domain left after the last
+/// layer.
+pub fn validate_proof_shape(proof: &FriProof, domain_size: usize) -> Result<(), String> {
+    if !domain_size.is_power_of_two() {
+        return Err(format!("domain size {} is not a power of 2", domain_size));
+    }
+
+    let rem_length = proof.rem_values.len();
+    if rem_length % 4 != 0 || rem_length < 8 {
+        return Err(format!("remainder must consist of at least 8 values and a multiple of 4, but was {}",
+            rem_length));
+    }
+
+    // each layer reduces the domain by a factor of 4, and a layer of n rows has depth log2(n)
+    let mut layer_domain_size = domain_size;
+    for (i, layer) in proof.layers.iter().enumerate() {
+        let expected_depth = (layer_domain_size / 4).trailing_zeros();
+        if layer_domain_size < 8 || layer.depth as u32 != expected_depth {
+            return Err(format!("expected layer {} for domain of size {}, but depth was {}",
+                i, layer_domain_size, layer.depth));
+        }
+        if layer.nodes.len() > layer.values.len() || layer.nodes.iter().any(|path| path.len() > layer.depth as usize) {
+            return Err(format!("authentication paths at layer {} are malformed", i));
+        }
+        layer_domain_size = layer_domain_size / 4;
+    }
+
+    if rem_length != layer_domain_size {
+        return Err(format!("expected remainder of {} values, but was {}", layer_domain_size, rem_length));
+    }
+    return Ok(());
+}
+
+pub fn get_column_values(values: &Vec<[u128; 4]>, positions: &[usize], augmented_positions: &[usize], column_length: usize) -> Vec<u128> {
     let row_length = column_length / 4;
 
     let mut result = Vec::new();
@@ -164,16 +275,16 @@ mod tests {
         polynom::eval_fft(&mut remainder, true);
 
         // check against exact degree
-        let result = super::verify_remainder(&remainder, degree_plus_1, root, extension_factor);
+        let result = super::verify_remainder(&remainder, degree_plus_1, root, field::ONE, extension_factor);
         assert_eq!(Ok(true), result);
 
         // check against higher degree
-        let result = super::verify_remainder(&remainder, degree_plus_1 + 1, root, extension_factor);
+        let result = super::verify_remainder(&remainder, degree_plus_1 + 1, root, field::ONE, extension_factor);
         assert_eq!(Ok(true), result);
 
         // check against lower degree
         let degree_plus_1 = degree_plus_1 - 1;
-        let result = super::verify_remainder(&remainder, degree_plus_1, root, extension_factor);
+        let result = super::verify_remainder(&remainder, degree_plus_1, root, field::ONE, extension_factor);
         let err_msg = format!("remainder is not a valid degree {} polynomial", degree_plus_1 - 1);
         assert_eq!(Err(err_msg), result);
     }
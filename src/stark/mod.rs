@@ -2,25 +2,52 @@ mod trace;
 mod constraints;
 mod options;
 mod prover;
+mod context;
 mod verifier;
 mod proof;
 mod fri;
 mod utils;
+mod verification_key;
 
-pub use trace::{ TraceTable, TraceState };
+pub use trace::{ TraceTable, TraceState, TraceStep, LdeStep, EvalStep, OP_FLAGS_MUL_COUNT };
 
 pub use constraints::{
     ConstraintEvaluator,
-    ConstraintTable,
-    ConstraintPoly };
+    HashEvaluator,
+    compose_constraints,
+    compose_constraints_chunked,
+    composition_buffer_size,
+    DEFAULT_COMPOSITION_CHUNK_SIZE,
+    Constraint,
+    CustomConstraints,
+    AuxTrace,
+    AuxFrame,
+    MAX_CUSTOM_CONSTRAINTS,
+    MAX_AUX_REGISTERS,
+    MAX_AUX_CHALLENGES,
+    ConstraintGroup,
+    ConstraintViolation,
+    check_constraint_group };
+
+#[cfg(feature = "testing")]
+pub use constraints::{ StackEvaluator };
 
 pub use utils::{
     ConstraintCoefficients,
     CompositionCoefficients };
 
-pub use options::ProofOptions;
-pub use proof::{ StarkProof, DeepValues };
-pub use prover::{ prove };
-pub use verifier::{ verify };
+pub use options::{ ProofOptions, OptionsError };
+pub use proof::{ StarkProof, ProofHeader, OutputTape, DeepValues, AuxTraceProof, VerifierCost, ProofVersion, ProofError, PROOF_VERSION, PROOF_HEADER_SIZE };
+pub use prover::{
+    prove, checkpoint, resume, ProverCheckpoint, composition_coefficients, constraint_mul_count, max_constraint_degree, lde_domain, lde_domain_generator, lde_domain_offset, estimate_time, get_mul_throughput, ProverError };
+pub use context::{ ProverContext, MIN_PARALLEL_TRACE_LENGTH };
+pub use verifier::{
+    verify, verify_with_constraints, verify_expecting, verify_batch_same_program, verify_with_tape, check_input_commitment, verify_from_reader, VerifyPolicy, DEFAULT_MAX_PROOF_SIZE, VerifierSession, VerifierStatus, VerifyError, Challenges };
+pub use verification_key::{ VerificationKey, verify_with_key };
+pub use fri::{ FriProof, FriLayer, FriCommitment, prove_fri, verify_fri, fold_layer as fold_fri_layer };
+
+const MAX_CONSTRAINT_DEGREE : usize = 8;
 
-const MAX_CONSTRAINT_DEGREE : usize = 8;
\ No newline at end of file
+/// Offset by which the low-degree extension domain is shifted off the multiplicative subgroup;
+/// thus, the low-degree extension domain is disjoint from the trace domain.
+const DOMAIN_OFFSET : u128 = crate::math::field::GENERATOR;
\ No newline at end of file
@@ -1,5 +1,7 @@
 use serde::{ Serialize, Deserialize };
 use crate::crypto::{ HashFunction, hash };
+use crate::math::field;
+use crate::DEFAULT_STEP_LIMIT;
 use super::MAX_CONSTRAINT_DEGREE;
 
 // CONSTANTS
@@ -7,19 +9,59 @@ use super::MAX_CONSTRAINT_DEGREE;
 const DEFAULT_EXTENSION_FACTOR: u8 = (MAX_CONSTRAINT_DEGREE * 4) as u8;
 const DEFAULT_NUM_QUERIES     : u8 = 50;
 const DEFAULT_GRINDING_FACTOR : u8 = 20;
+const DEFAULT_REMAINDER_DEGREE: u16 = 63;
+
+/// Maximum degree of the FRI remainder; larger remainders would make the verifier interpolate
+/// polynomials of very high degree.
+const MAX_REMAINDER_DEGREE    : usize = 1023;
+
+// bounds on the values of the fields; same bounds are enforced by ProofOptions::new()
+const MIN_EXTENSION_FACTOR    : usize = 16;
+const MAX_EXTENSION_FACTOR    : usize = 256;
+const MAX_NUM_QUERIES         : usize = 128;
+const MAX_GRINDING_FACTOR     : usize = 32;
 
 // TYPES AND INTERFACES
 // ================================================================================================
 
-// TODO: validate field values on de-serialization
+// field values are not validated on de-serialization; use validate_fields() to make sure
+// options received from an untrusted source are within the supported ranges
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ProofOptions {
     extension_factor    : u8,   // stored as power of 2
     num_queries         : u8,
     grinding_factor     : u8,
+    remainder_degree    : u16,
 
     #[serde(with = "hash_fn_serialization")]
     hash_fn: HashFunction,
+
+    #[serde(with = "hash_fn_serialization")]
+    transcript_fn: HashFunction,
+
+    // only affects proof generation, and thus, is not serialized
+    #[serde(skip)]
+    max_memory_bytes: Option<usize>,
+
+    // only affects program execution, and thus, is not serialized
+    #[serde(skip, default = "default_step_limit")]
+    execution_step_limit: usize,
+
+    // the verifier learns whether the tape is public from the proof, and thus, this is not
+    // serialized either
+    #[serde(skip)]
+    public_tape: bool,
+
+    // the verifier learns whether the proof commits to public inputs from the proof as well
+    #[serde(skip)]
+    input_commitment: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionsError {
+    DomainTooLarge { trace_length: usize, extension_factor: usize },
+    InvalidRemainderDegree { degree: usize, max_degree: usize },
+    ValueOutOfRange { field: &'static str, value: usize, min: usize, max: usize },
 }
 
 // PROOF OPTIONS IMPLEMENTATION
@@ -33,22 +75,87 @@ impl ProofOptions {
         hash_fn          : HashFunction) -> ProofOptions
     {
         assert!(extension_factor.is_power_of_two(), "extension_factor must be a power of 2");
-        assert!(extension_factor >= 16, "extension_factor cannot be smaller than 16");
-        assert!(extension_factor <= 256, "extension_factor cannot be greater than 256");
+        assert!(extension_factor >= MIN_EXTENSION_FACTOR,
+            "extension_factor cannot be smaller than {}", MIN_EXTENSION_FACTOR);
+        assert!(extension_factor <= MAX_EXTENSION_FACTOR,
+            "extension_factor cannot be greater than {}", MAX_EXTENSION_FACTOR);
 
         assert!(num_queries > 0, "num_queries must be greater than 0");
-        assert!(num_queries <= 128, "num_queries cannot be greater than 128");
+        assert!(num_queries <= MAX_NUM_QUERIES, "num_queries cannot be greater than {}", MAX_NUM_QUERIES);
 
-        assert!(grinding_factor <= 32, "grinding factor cannot be greater than 32");
+        assert!(grinding_factor as usize <= MAX_GRINDING_FACTOR,
+            "grinding factor cannot be greater than {}", MAX_GRINDING_FACTOR);
 
         return ProofOptions {
             extension_factor    : extension_factor.trailing_zeros() as u8,
             num_queries         : num_queries as u8,
             grinding_factor     : grinding_factor as u8,
-            hash_fn
+            remainder_degree    : DEFAULT_REMAINDER_DEGREE,
+            hash_fn,
+            transcript_fn       : hash::blake3,
+            max_memory_bytes    : None,
+            execution_step_limit: DEFAULT_STEP_LIMIT,
+            public_tape         : false,
+            input_commitment    : false,
         };
     }
 
+    /// Returns a copy of these options which uses `transcript_fn` to derive Fiat-Shamir
+    /// randomness from proof commitments; supported functions are blake3, rescue, and poseidon.
+    pub fn with_transcript_fn(mut self, transcript_fn: HashFunction) -> ProofOptions {
+        assert!(hash_fn_serialization::is_supported(transcript_fn),
+            "transcript hash function must be one of: blake3, rescue, poseidon");
+        self.transcript_fn = transcript_fn;
+        return self;
+    }
+
+    /// Returns a copy of these options with which FRI layers are folded until the degree of the
+    /// remaining polynomial is at most `degree`; the remainder is then sent in full. Higher
+    /// degrees result in smaller proofs, but require more work from the verifier. An error is
+    /// returned if `degree` is greater than 1023.
+    pub fn with_fri_max_remainder_degree(mut self, degree: usize) -> Result<ProofOptions, OptionsError> {
+        if degree > MAX_REMAINDER_DEGREE {
+            return Err(OptionsError::InvalidRemainderDegree { degree, max_degree: MAX_REMAINDER_DEGREE });
+        }
+        self.remainder_degree = degree as u16;
+        return Ok(self);
+    }
+
+    /// Returns a copy of these options which limits memory usage of the prover to roughly
+    /// `max_memory_bytes`; if the prover estimates that generating a proof would require more
+    /// memory than that, it fails before allocating the memory.
+    pub fn with_max_memory(mut self, max_memory_bytes: usize) -> ProofOptions {
+        self.max_memory_bytes = Some(max_memory_bytes);
+        return self;
+    }
+
+    /// Returns a copy of these options with which program execution is aborted as soon as the
+    /// program executes more than `limit` steps; by default, the limit is DEFAULT_STEP_LIMIT.
+    /// This should be used to bound execution time of untrusted programs.
+    pub fn with_execution_step_limit(mut self, limit: usize) -> ProofOptions {
+        assert!(limit > 0, "execution step limit must be greater than 0");
+        self.execution_step_limit = limit;
+        return self;
+    }
+
+    /// Returns a copy of these options which makes the output tape public: values written to
+    /// the tape by WRITE operations are included into the proof, and the proof attests that
+    /// these values, and no other values, were written by the program at the specified steps.
+    pub fn with_public_tape(mut self) -> ProofOptions {
+        self.public_tape = true;
+        return self;
+    }
+
+    /// Returns a copy of these options which includes a commitment to public inputs into the
+    /// proof: the commitment is the Rescue hash of the inputs (see ProgramInputs::commitment()),
+    /// and the verifier re-hashes the inputs it is given and rejects the proof if the hashes
+    /// differ. The commitment is absorbed into the seeds of composition coefficients and query
+    /// positions, and thus, cannot be removed or replaced without invalidating the proof.
+    pub fn with_input_commitment(mut self) -> ProofOptions {
+        self.input_commitment = true;
+        return self;
+    }
+
     pub fn extension_factor(&self) -> usize {
         return 1 << (self.extension_factor as usize)
     }
@@ -61,10 +168,78 @@ impl ProofOptions {
         return self.grinding_factor as u32;
     }
 
+    pub fn fri_max_remainder_degree(&self) -> usize {
+        return self.remainder_degree as usize;
+    }
+
     pub fn hash_fn(&self) -> HashFunction {
         return self.hash_fn;
     }
 
+    pub fn transcript_fn(&self) -> HashFunction {
+        return self.transcript_fn;
+    }
+
+    pub fn max_memory_bytes(&self) -> Option<usize> {
+        return self.max_memory_bytes;
+    }
+
+    pub fn execution_step_limit(&self) -> usize {
+        return self.execution_step_limit;
+    }
+
+    pub fn public_tape(&self) -> bool {
+        return self.public_tape;
+    }
+
+    pub fn input_commitment(&self) -> bool {
+        return self.input_commitment;
+    }
+
+    /// Returns true if proofs generated with these options are verified in the same way as
+    /// proofs generated with `other` options; options which affect only proof generation (e.g.
+    /// memory budget) are ignored.
+    pub fn is_equivalent_to(&self, other: &ProofOptions) -> bool {
+        return self.extension_factor == other.extension_factor
+            && self.num_queries == other.num_queries
+            && self.grinding_factor == other.grinding_factor
+            && self.remainder_degree == other.remainder_degree
+            && hash_fn_serialization::is_same_hash_fn(self.hash_fn, other.hash_fn)
+            && hash_fn_serialization::is_same_hash_fn(self.transcript_fn, other.transcript_fn);
+    }
+
+    /// Makes sure that values of all fields are within the ranges accepted by ProofOptions::new()
+    /// and with_fri_max_remainder_degree(); options which were de-serialized from an untrusted
+    /// source must be validated before any of their accessors are used, since, for example,
+    /// extension_factor() overflows for out-of-range values.
+    pub fn validate_fields(&self) -> Result<(), OptionsError> {
+        let ranges = [
+            ("extension factor (log2)", self.extension_factor as usize,
+                MIN_EXTENSION_FACTOR.trailing_zeros() as usize, MAX_EXTENSION_FACTOR.trailing_zeros() as usize),
+            ("number of queries", self.num_queries as usize, 1, MAX_NUM_QUERIES),
+            ("grinding factor", self.grinding_factor as usize, 0, MAX_GRINDING_FACTOR),
+            ("FRI remainder degree", self.remainder_degree as usize, 0, MAX_REMAINDER_DEGREE),
+        ];
+        for &(field, value, min, max) in ranges.iter() {
+            if value < min || value > max {
+                return Err(OptionsError::ValueOutOfRange { field, value, min, max });
+            }
+        }
+        return Ok(());
+    }
+
+    /// Checks whether a proof for an execution trace of the specified length can be generated
+    /// with these options; that is, whether the LDE domain of the trace fits into the largest
+    /// multiplicative subgroup of the field of size 2^k.
+    pub fn validate(&self, trace_length: usize) -> Result<(), OptionsError> {
+        let extension_factor = self.extension_factor();
+        let domain_size = trace_length.checked_mul(extension_factor).map(|size| size.checked_next_power_of_two());
+        return match domain_size {
+            Some(Some(size)) if size.trailing_zeros() <= field::TWO_ADICITY => Ok(()),
+            _ => Err(OptionsError::DomainTooLarge { trace_length, extension_factor }),
+        };
+    }
+
     pub fn security_level(&self, optimistic: bool) -> u32 {
         let one_over_rho = (self.extension_factor() / MAX_CONSTRAINT_DEGREE) as u32;
         let security_factor = 31 - one_over_rho.leading_zeros(); // same as log2(one_over_rho)
@@ -86,10 +261,36 @@ impl Default for ProofOptions {
             extension_factor: DEFAULT_EXTENSION_FACTOR.trailing_zeros() as u8,
             num_queries     : DEFAULT_NUM_QUERIES,
             grinding_factor : DEFAULT_GRINDING_FACTOR,
+            remainder_degree: DEFAULT_REMAINDER_DEGREE,
             hash_fn         : hash::blake3,
+            transcript_fn   : hash::blake3,
+            max_memory_bytes: None,
+            execution_step_limit: DEFAULT_STEP_LIMIT,
+            public_tape     : false,
+            input_commitment: false,
+        };
+    }
+
+}
+
+impl std::fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            OptionsError::DomainTooLarge { trace_length, extension_factor } => write!(f,
+                "trace of length {} extended by {} does not fit into a domain of size 2^{} (two-adicity of the field)",
+                trace_length, extension_factor, field::TWO_ADICITY),
+            OptionsError::InvalidRemainderDegree { degree, max_degree } => write!(f,
+                "FRI remainder degree {} is invalid; value must be between 0 and {}", degree, max_degree),
+            OptionsError::ValueOutOfRange { field, value, min, max } => write!(f,
+                "{} {} is invalid; value must be between {} and {}", field, value, min, max),
         };
     }
+}
 
+// HELPER FUNCTIONS
+// ================================================================================================
+fn default_step_limit() -> usize {
+    return DEFAULT_STEP_LIMIT;
 }
 
 // HASH FUNCTION SERIALIZATION / DE-SERIALIZATION
@@ -103,19 +304,127 @@ mod hash_fn_serialization {
     where
         S: Serializer
     {
-        match *hf as usize {
-            f if f == hash::blake3 as usize => s.serialize_u8(0),
-            _ => Err(ser::Error::custom("unsupported hash function"))?
+        match get_hash_fn_id(*hf) {
+            Some(id) => s.serialize_u8(id),
+            None => Err(ser::Error::custom("unsupported hash function"))?
         }
     }
-    
+
     pub fn deserialize<'de, D>(deserializer: D) -> Result<HashFunction, D::Error>
     where
         D: Deserializer<'de>
     {
-        match Deserialize::deserialize(deserializer)? {
-            0u8 => Ok(hash::blake3),
-            _ => Err(de::Error::custom("unsupported hash function"))
+        let id: u8 = Deserialize::deserialize(deserializer)?;
+        match SUPPORTED_HASH_FNS.get(id as usize) {
+            Some(&hf) => Ok(hf),
+            None => Err(de::Error::custom("unsupported hash function"))
+        }
+    }
+
+    pub fn is_supported(hf: HashFunction) -> bool {
+        return get_hash_fn_id(hf).is_some();
+    }
+
+    /// Returns the serialized ID of the specified hash function, or None if the function is not
+    /// supported. Functions are identified by their digest of a fixed input rather than by their
+    /// addresses, since the same function can have different addresses in different codegen
+    /// units, and different functions can share an address.
+    pub fn get_hash_fn_id(hf: HashFunction) -> Option<u8> {
+        let fingerprint = get_fingerprint(hf);
+        return SUPPORTED_HASH_FNS.iter()
+            .position(|&supported| get_fingerprint(supported) == fingerprint)
+            .map(|id| id as u8);
+    }
+
+    /// Returns true if both hash functions have the same ID; functions which are not supported
+    /// (and thus, have no ID) are compared by their digests of a fixed input.
+    pub fn is_same_hash_fn(hf1: HashFunction, hf2: HashFunction) -> bool {
+        return match (get_hash_fn_id(hf1), get_hash_fn_id(hf2)) {
+            (Some(id1), Some(id2)) => id1 == id2,
+            (None, None) => get_fingerprint(hf1) == get_fingerprint(hf2),
+            _ => false,
+        };
+    }
+
+    /// Supported hash functions, indexed by their serialized IDs.
+    const SUPPORTED_HASH_FNS: [HashFunction; 3] = [hash::blake3, hash::rescue, hash::poseidon];
+
+    fn get_fingerprint(hf: HashFunction) -> [u8; 32] {
+        let mut values = [0u8; 32];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = i as u8 + 1;
         }
+        let mut result = [0u8; 32];
+        hf(&values, &mut result);
+        return result;
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::{ ProofOptions, OptionsError };
+
+    #[test]
+    fn validate() {
+        let options = ProofOptions::default();
+        assert_eq!(Ok(()), options.validate(1 << 30));
+        assert_eq!(Ok(()), options.validate(1 << 35));
+
+        // 2^36 steps extended by 32 require a domain of size 2^41
+        let err = options.validate(1 << 36).unwrap_err();
+        assert_eq!(OptionsError::DomainTooLarge { trace_length: 1 << 36, extension_factor: 32 }, err);
+        assert_eq!("trace of length 68719476736 extended by 32 does not fit into a domain of size 2^40 (two-adicity of the field)",
+            err.to_string());
+
+        // overflows are caught too
+        assert!(options.validate(usize::MAX).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn validate_fields() {
+        assert_eq!(Ok(()), ProofOptions::default().validate_fields());
+        assert_eq!(Ok(()), ProofOptions::new(256, 128, 32, crate::crypto::hash::rescue).validate_fields());
+
+        // extension factor is stored as a power of 2, and 2^200 would overflow extension_factor()
+        let mut options = ProofOptions::default();
+        options.extension_factor = 200;
+        let err = options.validate_fields().unwrap_err();
+        assert_eq!(OptionsError::ValueOutOfRange { field: "extension factor (log2)", value: 200, min: 4, max: 8 }, err);
+        assert_eq!("extension factor (log2) 200 is invalid; value must be between 4 and 8", err.to_string());
+
+        let mut options = ProofOptions::default();
+        options.num_queries = 0;
+        assert!(options.validate_fields().is_err());
+
+        let mut options = ProofOptions::default();
+        options.grinding_factor = 33;
+        assert!(options.validate_fields().is_err());
+
+        let mut options = ProofOptions::default();
+        options.remainder_degree = 1024;
+        assert!(options.validate_fields().is_err());
+    }
+    #[test]
+    fn hash_fn_ids() {
+        use crate::crypto::hash;
+        use super::hash_fn_serialization::{ get_hash_fn_id, is_same_hash_fn };
+
+        assert_eq!(Some(0), get_hash_fn_id(hash::blake3));
+        assert_eq!(Some(1), get_hash_fn_id(hash::rescue));
+        assert_eq!(Some(2), get_hash_fn_id(hash::poseidon));
+        assert_eq!(None, get_hash_fn_id(hash::sha3));
+        assert!(is_same_hash_fn(hash::sha3, hash::sha3));
+        assert!(!is_same_hash_fn(hash::sha3, hash::blake3));
+
+        // options are compared by hash function IDs, and the IDs survive serialization
+        let options = ProofOptions::new(32, 48, 0, hash::rescue).with_transcript_fn(hash::poseidon);
+        let bytes = bincode::serialize(&options).unwrap();
+        let deserialized: ProofOptions = bincode::deserialize(&bytes).unwrap();
+        assert!(options.is_equivalent_to(&deserialized));
+        assert!(!options.is_equivalent_to(&ProofOptions::new(32, 48, 0, hash::blake3).with_transcript_fn(hash::poseidon)));
+        assert!(!options.is_equivalent_to(&ProofOptions::new(32, 48, 0, hash::rescue)));
+    }
+}
@@ -1,7 +1,62 @@
+use std::io::{ self, Read };
+use std::fmt::Write;
 use serde::{ Serialize, Deserialize };
+use bincode::Options;
 use crate::crypto::{ BatchMerkleProof };
-use crate::stark::{ fri::FriProof, TraceState, ProofOptions };
+use crate::stark::{ fri::FriProof, TraceState, ProofOptions, Challenges, verifier, utils::get_composition_degree };
 use crate::utils::{ uninit_vector, as_bytes };
+use crate::math::field;
+use crate::{ HASH_DIGEST_SIZE, MIN_TRACE_LENGTH, MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_STACK_DEPTH };
+
+// CONSTANTS
+// ================================================================================================
+
+// rough number of field operations needed to evaluate all transition and boundary constraints
+// at the DEEP point, per trace register
+const CONSTRAINT_OPS_PER_REGISTER: usize = 64;
+
+// number of field operations needed to interpolate a degree 3 polynomial from 4 points (using
+// quartic::interpolate_batch) and evaluate it at a single point
+const QUARTIC_FOLD_OPS: usize = 48;
+
+// number of field operations needed to compute the x coordinate of a queried position in the
+// evaluation domain: an exponentiation of the domain root and a multiplication by the offset
+const QUERY_POINT_OPS: usize = 2;
+
+// number of field operations per register needed to compose a queried row: each of the quotients
+// (T(x) - T(z)) / (x - z) and (T(x) - T(z * g)) / (x - z * g) takes 2 subtractions and a division,
+// and is then multiplied by a coefficient and added to the composition
+const DEEP_OPS_PER_REGISTER: usize = 2 * 5;
+
+// number of field operations needed to adjust the degree of the trace composition of a queried
+// row: an exponentiation to compute x^p, 3 multiplications by x^p and degree coefficients, and
+// an addition
+const DEEP_DEGREE_ADJUSTMENT_OPS: usize = 5;
+
+// number of field operations needed to compute the quotient (C(x) - C(z)) / (x - z) of constraint
+// evaluations at a queried position (2 subtractions and a division), to multiply it by a
+// coefficient, and to add it to the trace composition
+const DEEP_CONSTRAINT_OPS: usize = 5;
+
+// number of field operations needed to compute x coordinates of a queried row of a FRI layer:
+// the x coordinate of the position is multiplied by each of the 4 quartic roots of unity
+const FRI_ROW_POINT_OPS: usize = QUERY_POINT_OPS + 4;
+
+// number of field operations needed to interpolate the FRI remainder, per pair of interpolated
+// points (Lagrange interpolation), and to evaluate the remainder at a point, per coefficient
+// (Horner's method: a multiplication and an addition)
+const INTERPOLATION_OPS_PER_PAIR: usize = 3;
+const EVALUATION_OPS_PER_COEFFICIENT: usize = 2;
+
+/// Version of the proof format produced by this prover. Proofs with the same major version are
+/// compatible: the major version must be incremented whenever a change to the proof layout or
+/// to the verification procedure would cause existing proofs to be rejected, while minor
+/// version is incremented for all other changes.
+pub const PROOF_VERSION: ProofVersion = ProofVersion { major: 12, minor: 0 };
+
+/// Size of the header which starts every serialized proof: proof version (2 bytes), trace root
+/// (32 bytes), trace info (8 bytes), and proof options (7 bytes).
+pub const PROOF_HEADER_SIZE: usize = 49;
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -9,22 +64,81 @@ use crate::utils::{ uninit_vector, as_bytes };
 // TODO: custom serialization should reduce size by 5% - 10%
 #[derive(Clone, Serialize, Deserialize)]
 pub struct StarkProof {
+    version             : ProofVersion,
     trace_root          : [u8; 32],
     trace_info          : TraceInfo,
+    options             : ProofOptions,
+    inputs_hash         : Option<[u128; HASH_DIGEST_SIZE]>,
+    outputs             : Vec<u128>,
+    tape                : Option<OutputTape>,
     trace_nodes         : Vec<Vec<[u8; 32]>>,
     trace_evaluations   : Vec<Vec<u128>>,
+    aux_trace           : Option<AuxTraceProof>,
     constraint_root     : [u8; 32],
     constraint_proof    : BatchMerkleProof,
     deep_values         : DeepValues,
     degree_proof        : FriProof,
     pow_nonce           : u64,
-    options             : ProofOptions
+}
+
+/// Parameters of a proof which precede all other values in a serialized proof; the header has
+/// a fixed size, and thus, it can be read via StarkProof::peek_header() without parsing the
+/// rest of the proof.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProofHeader {
+    version             : ProofVersion,
+    trace_root          : [u8; 32],
+    trace_info          : TraceInfo,
+    options             : ProofOptions,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofVersion {
+    pub major   : u8,
+    pub minor   : u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProofError {
+    VersionMismatch { found: ProofVersion, expected: ProofVersion },
+    UnexpectedEof,
+    MalformedProof(String),
+    ProofTooLarge { limit: u64 },
+    InsufficientSecurity { found: u32, required: u32 },
+    InvalidProof(String),
+}
+
+/// An estimate of the amount of work the verifier needs to do to verify a proof. The estimate
+/// is an upper bound derived from proof parameters only.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VerifierCost {
+    pub hash_invocations    : usize,
+    pub field_ops           : usize,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DeepValues {
     pub trace_at_z1     : Vec<u128>,
     pub trace_at_z2     : Vec<u128>,
+    pub aux_at_z1       : Vec<u128>,
+    pub aux_at_z2       : Vec<u128>,
+}
+
+/// Commitment to auxiliary trace registers together with the values of these registers at the
+/// queried positions; Merkle authentication paths for the queried positions are in `nodes`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuxTraceProof {
+    pub root            : [u8; 32],
+    pub nodes           : Vec<Vec<[u8; 32]>>,
+    pub evaluations     : Vec<Vec<u128>>,
+}
+
+/// Values written to the output tape by WRITE operations, together with the steps of the
+/// execution trace at which they were written; the steps are in ascending order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputTape {
+    pub steps           : Vec<u32>,
+    pub values          : Vec<u128>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -43,6 +157,7 @@ impl StarkProof {
         trace_root          : &[u8; 32],
         trace_proof         : BatchMerkleProof,
         trace_evaluations   : Vec<Vec<u128>>,
+        aux_trace           : Option<AuxTraceProof>,
         constraint_root     : &[u8; 32],
         constraint_proof    : BatchMerkleProof,
         deep_values         : DeepValues,
@@ -52,6 +167,9 @@ impl StarkProof {
         ctx_depth           : usize,
         loop_depth          : usize,
         stack_depth         : usize,
+        outputs             : &[u128],
+        tape                : Option<OutputTape>,
+        inputs_hash         : Option<[u128; HASH_DIGEST_SIZE]>,
         options             : &ProofOptions ) -> StarkProof
     {
         let trace_info = TraceInfo {
@@ -63,19 +181,135 @@ impl StarkProof {
         };
 
         return StarkProof {
+            version             : PROOF_VERSION,
             trace_root          : *trace_root,
             trace_info          : trace_info,
+            options             : options.clone(),
+            inputs_hash         : inputs_hash,
+            outputs             : outputs.to_vec(),
+            tape                : tape,
             trace_nodes         : trace_proof.nodes,
             trace_evaluations   : trace_evaluations,
+            aux_trace           : aux_trace,
             constraint_root     : *constraint_root,
             constraint_proof    : constraint_proof,
             deep_values         : deep_values,
             degree_proof        : degree_proof,
             pow_nonce           : pow_nonce,
-            options             : options.clone()
         };
     }
 
+    /// Parses a proof serialized with bincode from the `reader`; values are read from the stream
+    /// as they are parsed, and at most `max_size` bytes are read (if specified). Truncated
+    /// streams result in UnexpectedEof error, and proofs with header fields outside of the
+    /// supported ranges result in MalformedProof error.
+    pub fn read_from<R: Read>(reader: R, max_size: Option<u64>) -> Result<StarkProof, ProofError> {
+        let options = bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes();
+        let result: Result<StarkProof, _> = match max_size {
+            Some(limit) => options.with_limit(limit).deserialize_from(reader),
+            None => options.deserialize_from(reader),
+        };
+
+        return match result {
+            Ok(proof) => {
+                proof.check_header()?;
+                Ok(proof)
+            },
+            Err(err) => Err(match *err {
+                bincode::ErrorKind::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => ProofError::UnexpectedEof,
+                bincode::ErrorKind::SizeLimit => ProofError::ProofTooLarge { limit: max_size.unwrap_or(u64::MAX) },
+                err => ProofError::MalformedProof(err.to_string()),
+            }),
+        };
+    }
+
+    /// Reads the header of a proof serialized with bincode from the start of `bytes` without
+    /// parsing the rest of the proof; bytes after the first PROOF_HEADER_SIZE bytes are ignored.
+    /// An error is returned if `bytes` are too short to contain a header, if the proof was
+    /// generated with an incompatible version of the proof format, or if any of the header
+    /// fields is outside of the supported range.
+    pub fn peek_header(bytes: &[u8]) -> Result<ProofHeader, ProofError> {
+        if bytes.len() < PROOF_HEADER_SIZE {
+            return Err(ProofError::UnexpectedEof);
+        }
+
+        // the layout of the header may differ across major versions, and thus, the version
+        // is checked before the rest of the header is parsed
+        let options = bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes();
+        let version: ProofVersion = options.deserialize(bytes)
+            .map_err(|err| ProofError::MalformedProof(err.to_string()))?;
+        if version.major != PROOF_VERSION.major {
+            return Err(ProofError::VersionMismatch { found: version, expected: PROOF_VERSION });
+        }
+
+        let header: ProofHeader = options.deserialize(&bytes[..PROOF_HEADER_SIZE])
+            .map_err(|err| ProofError::MalformedProof(err.to_string()))?;
+        validate_header(&header.trace_info, &header.options)?;
+        return Ok(header);
+    }
+
+    /// Returns the header of this proof; this is the same header as the one returned by
+    /// peek_header() for the serialized proof.
+    pub fn header(&self) -> ProofHeader {
+        return ProofHeader {
+            version     : self.version,
+            trace_root  : self.trace_root,
+            trace_info  : self.trace_info.clone(),
+            options     : self.options.clone(),
+        };
+    }
+
+    pub fn version(&self) -> ProofVersion {
+        return self.version;
+    }
+
+    /// Returns an error if this proof was generated by a prover with an incompatible version
+    /// of the proof format (i.e. different major version).
+    pub fn check_version(&self) -> Result<(), ProofError> {
+        if self.version.major != PROOF_VERSION.major {
+            return Err(ProofError::VersionMismatch { found: self.version, expected: PROOF_VERSION });
+        }
+        return Ok(());
+    }
+
+    /// Returns an error if this proof was generated with an incompatible version of the proof
+    /// format, if any of the fields which describe the trace and proof options is outside of the
+    /// supported range, or if trace values in the proof do not match the trace width implied by
+    /// these fields; accessors such as domain_size() and get_state_at_z1() overflow or panic for
+    /// such proofs, and thus, proofs from untrusted sources must be checked first.
+    pub fn check_header(&self) -> Result<(), ProofError> {
+        self.check_version()?;
+        validate_header(&self.trace_info, &self.options)?;
+        return validate_trace_widths(self);
+    }
+
+    /// Returns outputs claimed by the prover; these are not authenticated until the proof is
+    /// verified against them.
+    pub fn outputs(&self) -> &[u128] {
+        return &self.outputs;
+    }
+
+    /// Returns Fiat-Shamir challenges which the verifier derives from this proof for the
+    /// specified public `inputs` and outputs claimed by the prover; these are the same values
+    /// which are used during verification. Coefficients for custom constraints are not included.
+    pub fn derive_challenges(&self, inputs: &[u128]) -> Result<Challenges, String> {
+        return verifier::derive_challenges(self, inputs);
+    }
+
+    /// Returns the commitment to public inputs included into the proof, or None if the proof
+    /// was generated without input commitment; the verifier checks that the commitment is the
+    /// hash of the inputs the proof is verified against.
+    pub fn inputs_hash(&self) -> Option<&[u128; HASH_DIGEST_SIZE]> {
+        return self.inputs_hash.as_ref();
+    }
+
+    /// Returns the output tape claimed by the prover, or None if the tape was not made public
+    /// when the proof was generated; similar to outputs, the tape is not authenticated until
+    /// the proof is verified.
+    pub fn tape(&self) -> Option<&OutputTape> {
+        return self.tape.as_ref();
+    }
+
     pub fn trace_root(&self) -> &[u8; 32] {
         return &self.trace_root;
     }
@@ -103,6 +337,39 @@ impl StarkProof {
          };
     }
 
+    /// Returns the root of auxiliary trace commitment, or None if the proof does not commit to
+    /// auxiliary registers.
+    pub fn aux_root(&self) -> Option<&[u8; 32]> {
+        return self.aux_trace.as_ref().map(|aux_trace| &aux_trace.root);
+    }
+
+    /// Returns Merkle proof for auxiliary registers at the queried positions, or None if the
+    /// proof does not commit to auxiliary registers.
+    pub fn aux_proof(&self) -> Option<BatchMerkleProof> {
+        let aux_trace = self.aux_trace.as_ref()?;
+
+        let hash = self.options.hash_fn();
+        let mut hashed_rows = uninit_vector::<[u8; 32]>(aux_trace.evaluations.len());
+        for i in 0..aux_trace.evaluations.len() {
+            hash(as_bytes(&aux_trace.evaluations[i]), &mut hashed_rows[i]);
+        }
+
+        return Some(BatchMerkleProof {
+            nodes   : aux_trace.nodes.clone(),
+            values  : hashed_rows,
+            depth   : self.trace_info.domain_depth,
+        });
+    }
+
+    /// Returns values of auxiliary registers at the queried positions; the result is empty if
+    /// the proof does not commit to auxiliary registers.
+    pub fn aux_evaluations(&self) -> &[Vec<u128>] {
+        return match &self.aux_trace {
+            Some(aux_trace) => &aux_trace.evaluations,
+            None => &[],
+        };
+    }
+
     pub fn constraint_root(&self) -> &[u8; 32] {
         return &self.constraint_root;
     }
@@ -145,6 +412,99 @@ impl StarkProof {
         return self.trace_info.op_count as u128;
     }
 
+    // PROOF STRUCTURE
+    // -------------------------------------------------------------------------------------------
+
+    /// Renders the structure of this proof as a human-readable tree: trace and constraint
+    /// commitments, auxiliary trace commitment (if any), values of the out-of-domain frame,
+    /// FRI layers with their roots and domain sizes, and proof-of-work nonce. Query positions
+    /// are not included since they are derived from public inputs; use derive_challenges() to
+    /// get them.
+    pub fn describe(&self) -> String {
+        let mut result = String::new();
+        let domain_size = self.domain_size();
+        let trace_width = self.deep_values.trace_at_z1.len();
+
+        let _ = writeln!(result, "StarkProof (version {})", self.version);
+        let _ = writeln!(result, "├─ trace: {} steps, {} registers, LDE domain of {} steps",
+            self.trace_length(), trace_width, domain_size);
+        let _ = writeln!(result, "│  ├─ root: {}", hex::encode(self.trace_root));
+        let _ = writeln!(result, "│  └─ op count: {}", self.op_count());
+        if let Some(aux_trace) = &self.aux_trace {
+            let _ = writeln!(result, "├─ auxiliary trace: {} registers", self.deep_values.aux_at_z1.len());
+            let _ = writeln!(result, "│  └─ root: {}", hex::encode(aux_trace.root));
+        }
+        let _ = writeln!(result, "├─ constraints");
+        let _ = writeln!(result, "│  └─ root: {}", hex::encode(self.constraint_root));
+        let _ = writeln!(result, "├─ ood frame");
+        let _ = writeln!(result, "│  ├─ current: {:?}", self.deep_values.trace_at_z1);
+        let _ = writeln!(result, "│  └─ next: {:?}", self.deep_values.trace_at_z2);
+        let _ = writeln!(result, "├─ queries: {}", self.trace_evaluations.len());
+
+        let fri_layers = &self.degree_proof.layers;
+        let _ = writeln!(result, "├─ fri: {} layers", fri_layers.len());
+        let mut layer_size = domain_size;
+        for (i, layer) in fri_layers.iter().enumerate() {
+            let _ = writeln!(result, "│  ├─ layer {}: {} values, root: {}", i, layer_size, hex::encode(layer.root));
+            layer_size = layer_size / 4;
+        }
+        let _ = writeln!(result, "│  └─ remainder: {} values, root: {}",
+            self.degree_proof.rem_values.len(), hex::encode(self.degree_proof.rem_root));
+        let _ = write!(result, "└─ pow nonce: {}", self.pow_nonce);
+
+        return result;
+    }
+
+    // VERIFIER COST
+    // -------------------------------------------------------------------------------------------
+
+    /// Estimates the number of hash function invocations and field operations the verifier
+    /// will perform to verify this proof. The estimate does not grow linearly with trace length:
+    /// the number of queries is fixed by proof options, and trace length affects only the depth
+    /// of Merkle authentication paths and the number of FRI layers (i.e. logarithmically).
+    /// Returns an error if the header of the proof is malformed; see check_header().
+    pub fn verifier_work_estimate(&self) -> Result<VerifierCost, ProofError> {
+        self.check_header()?;
+
+        let num_queries = self.options.num_queries();
+        let domain_depth = self.trace_info.domain_depth as usize;
+        let trace_width = self.deep_values.trace_at_z1.len();
+        let fri_layers = &self.degree_proof.layers;
+
+        // hashing: Fiat-Shamir transcript over roots of FRI layers and of the remainder, and
+        // proof-of-work
+        let mut hash_invocations = (fri_layers.len() + 1) + 1;
+        // trace Merkle proof: hash each queried trace row and its authentication path
+        hash_invocations += num_queries * (domain_depth + 1);
+        // constraint Merkle proof: leaves hold 2 evaluations and do not need to be hashed
+        hash_invocations += num_queries * (domain_depth - 1);
+        // FRI layers: hash each queried row of 4 values and its authentication path
+        for layer in fri_layers.iter() {
+            hash_invocations += num_queries * (layer.depth as usize + 1);
+        }
+
+        // field operations: evaluating constraints at the DEEP point
+        let mut field_ops = trace_width * CONSTRAINT_OPS_PER_REGISTER;
+        // DEEP composition of trace registers and constraint evaluations for each query; the x
+        // coordinate is computed separately for trace registers and constraint evaluations
+        let deep_ops = trace_width * DEEP_OPS_PER_REGISTER + DEEP_DEGREE_ADJUSTMENT_OPS + DEEP_CONSTRAINT_OPS;
+        field_ops += num_queries * (deep_ops + 2 * QUERY_POINT_OPS);
+        // FRI layers: fold each queried row into the next layer
+        field_ops += num_queries * fri_layers.len() * (QUARTIC_FOLD_OPS + FRI_ROW_POINT_OPS);
+
+        // FRI remainder: interpolate max_degree + 1 points and evaluate at the remaining ones
+        let mut max_degree_plus_1 = get_composition_degree(self.trace_length()) + 1;
+        for _ in fri_layers.iter() {
+            max_degree_plus_1 = max_degree_plus_1 / 4;
+        }
+        let remainder_length = self.degree_proof.rem_values.len();
+        let num_points = remainder_length - remainder_length / self.options.extension_factor();
+        field_ops += max_degree_plus_1 * max_degree_plus_1 * INTERPOLATION_OPS_PER_PAIR;
+        field_ops += num_points.saturating_sub(max_degree_plus_1) * max_degree_plus_1 * EVALUATION_OPS_PER_COEFFICIENT;
+
+        return Ok(VerifierCost { hash_invocations, field_ops });
+    }
+
     // DEEP VALUES
     // -------------------------------------------------------------------------------------------
     pub fn get_state_at_z1(&self) -> TraceState {
@@ -162,4 +522,423 @@ impl StarkProof {
             self.stack_depth(),
             &self.deep_values.trace_at_z2);
     }
-}
\ No newline at end of file
+
+    pub fn get_aux_at_z1(&self) -> &[u128] {
+        return &self.deep_values.aux_at_z1;
+    }
+
+    pub fn get_aux_at_z2(&self) -> &[u128] {
+        return &self.deep_values.aux_at_z2;
+    }
+}
+
+// PROOF VERSION IMPLEMENTATION
+// ================================================================================================
+impl std::fmt::Display for ProofVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return write!(f, "{}.{}", self.major, self.minor);
+    }
+}
+
+// PROOF HEADER IMPLEMENTATION
+// ================================================================================================
+impl ProofHeader {
+
+    pub fn version(&self) -> ProofVersion {
+        return self.version;
+    }
+
+    pub fn trace_root(&self) -> &[u8; 32] {
+        return &self.trace_root;
+    }
+
+    pub fn options(&self) -> &ProofOptions {
+        return &self.options;
+    }
+
+    pub fn trace_length(&self) -> usize {
+        return self.domain_size() / self.options.extension_factor();
+    }
+
+    pub fn domain_size(&self) -> usize {
+        return usize::pow(2, self.trace_info.domain_depth as u32);
+    }
+
+    pub fn extension_factor(&self) -> usize {
+        return self.options.extension_factor();
+    }
+
+    pub fn num_queries(&self) -> usize {
+        return self.options.num_queries();
+    }
+
+    pub fn ctx_depth(&self) -> usize {
+        return self.trace_info.ctx_depth as usize;
+    }
+
+    pub fn loop_depth(&self) -> usize {
+        return self.trace_info.loop_depth as usize;
+    }
+
+    pub fn stack_depth(&self) -> usize {
+        return self.trace_info.stack_depth as usize;
+    }
+}
+
+impl std::fmt::Display for ProofError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            ProofError::VersionMismatch { found, expected } => write!(f,
+                "proof version {} is not supported; supported versions are {}.x",
+                found, expected.major),
+            ProofError::UnexpectedEof => write!(f, "proof stream ended unexpectedly"),
+            ProofError::MalformedProof(err) => write!(f, "proof is malformed: {}", err),
+            ProofError::ProofTooLarge { limit } => write!(f,
+                "proof is larger than the limit of {} bytes", limit),
+            ProofError::InsufficientSecurity { found, required } => write!(f,
+                "proof provides {} bits of security, but at least {} bits are required", found, required),
+            ProofError::InvalidProof(err) => write!(f, "{}", err),
+        };
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Makes sure every field of the proof header is within the range which the prover could have
+/// produced: domain depth must fit a trace of at least MIN_TRACE_LENGTH steps extended by the
+/// extension factor into the field, and depths of the trace must not exceed their maximums.
+fn validate_header(trace_info: &TraceInfo, options: &ProofOptions) -> Result<(), ProofError> {
+    options.validate_fields().map_err(|err| ProofError::MalformedProof(err.to_string()))?;
+
+    let min_domain_depth = MIN_TRACE_LENGTH.trailing_zeros() + options.extension_factor().trailing_zeros();
+    let domain_depth = trace_info.domain_depth as u32;
+    if domain_depth < min_domain_depth || domain_depth > field::TWO_ADICITY {
+        return Err(ProofError::MalformedProof(format!("domain depth {} is invalid; value must be between {} and {}",
+            domain_depth, min_domain_depth, field::TWO_ADICITY)));
+    }
+
+    let depths = [
+        ("context depth", trace_info.ctx_depth as usize, MAX_CONTEXT_DEPTH),
+        ("loop depth", trace_info.loop_depth as usize, MAX_LOOP_DEPTH),
+        ("stack depth", trace_info.stack_depth as usize, MAX_STACK_DEPTH),
+    ];
+    for &(name, depth, max_depth) in depths.iter() {
+        if depth > max_depth {
+            return Err(ProofError::MalformedProof(format!("{} {} is invalid; value cannot exceed {}",
+                name, depth, max_depth)));
+        }
+    }
+
+    // the operation counter cannot be incremented more times than there are steps in the trace
+    let trace_length = 1usize << (domain_depth - options.extension_factor().trailing_zeros());
+    if trace_info.op_count as usize > trace_length {
+        return Err(ProofError::MalformedProof(format!("operation count {} exceeds trace length {}",
+            trace_info.op_count, trace_length)));
+    }
+
+    return Ok(());
+}
+
+/// Makes sure that values of trace registers at both DEEP points and at every queried position
+/// have the width of the trace described by the header: the decoder width for the context and
+/// loop depths, plus the stack depth.
+fn validate_trace_widths(proof: &StarkProof) -> Result<(), ProofError> {
+    let width = TraceState::compute_decoder_width(proof.ctx_depth(), proof.loop_depth()) + proof.stack_depth();
+    let deep_values = &proof.deep_values;
+    if deep_values.trace_at_z1.len() != width || deep_values.trace_at_z2.len() != width {
+        return Err(ProofError::MalformedProof(format!("values of the trace at DEEP points must contain {} registers",
+            width)));
+    }
+    if proof.trace_evaluations.iter().any(|row| row.len() != width) {
+        return Err(ProofError::MalformedProof(format!("trace values at queried positions must contain {} registers",
+            width)));
+    }
+    return Ok(());
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ crypto::hash, ProofOptions, ProgramInputs, assembly };
+    use super::{ StarkProof, ProofVersion, ProofError, PROOF_VERSION, PROOF_HEADER_SIZE };
+
+    #[test]
+    fn check_version() {
+        let program = assembly::compile("begin push.3 add end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let (outputs, mut proof) = crate::execute(&program, &inputs, 1, &ProofOptions::default());
+        assert_eq!(PROOF_VERSION, proof.version());
+
+        // a proof with a different minor version is accepted
+        proof.version = ProofVersion { major: PROOF_VERSION.major, minor: PROOF_VERSION.minor + 1 };
+        assert_eq!(Ok(()), proof.check_version());
+        let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+
+        // a proof with a different major version is rejected
+        let found = ProofVersion { major: PROOF_VERSION.major + 1, minor: 0 };
+        proof.version = found;
+        let err = ProofError::VersionMismatch { found, expected: PROOF_VERSION };
+        assert_eq!(Err(err.clone()), proof.check_version());
+        let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Err(err.to_string()), result);
+        assert_eq!(Err(String::from("proof version 13.0 is not supported; supported versions are 12.x")), result);
+    }
+
+    #[test]
+    fn peek_header() {
+        let program = assembly::compile("begin push.3 add end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let options = ProofOptions::new(32, 40, 0, hash::blake3);
+        let (_, proof) = crate::execute(&program, &inputs, 1, &options);
+        let bytes = bincode::serialize(&proof).unwrap();
+
+        // the header is read from the start of the serialized proof
+        let header = StarkProof::peek_header(&bytes).unwrap();
+        let expected = StarkProof::read_from(&bytes[..], None).unwrap().header();
+        assert_eq!(expected.version(), header.version());
+        assert_eq!(expected.trace_root(), header.trace_root());
+        assert_eq!(expected.trace_length(), header.trace_length());
+        assert_eq!(expected.domain_size(), header.domain_size());
+        assert_eq!((expected.ctx_depth(), expected.loop_depth(), expected.stack_depth()),
+            (header.ctx_depth(), header.loop_depth(), header.stack_depth()));
+        assert!(expected.options().is_equivalent_to(header.options()));
+        assert_eq!((proof.trace_length(), 32, 40), (header.trace_length(), header.extension_factor(), header.num_queries()));
+        assert_eq!(bincode::serialize(&header).unwrap(), &bytes[..PROOF_HEADER_SIZE]);
+
+        // only the header is needed
+        assert!(StarkProof::peek_header(&bytes[..PROOF_HEADER_SIZE]).is_ok());
+        let result = StarkProof::peek_header(&bytes[..(PROOF_HEADER_SIZE - 1)]);
+        assert!(matches!(result, Err(ProofError::UnexpectedEof)));
+
+        // headers of proofs with incompatible versions are rejected
+        let mut other = bytes.clone();
+        other[0] = PROOF_VERSION.major + 1;
+        let result = StarkProof::peek_header(&other);
+        assert!(matches!(result, Err(ProofError::VersionMismatch { .. })));
+    }
+
+    #[test]
+    fn check_header() {
+        let program = assembly::compile("begin push.3 add end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let (outputs, proof) = crate::execute(&program, &inputs, 1, &ProofOptions::default());
+        assert_eq!(Ok(()), proof.check_header());
+
+        // the header starts with version (2 bytes) and trace root (32 bytes), followed by trace
+        // info and proof options; out-of-range values are rejected both when the header is
+        // peeked at and when the whole proof is read
+        let bytes = bincode::serialize(&proof).unwrap();
+        let (domain_depth, stack_depth, extension_factor) = (34, 37, 42);
+        for &(offset, value) in [(domain_depth, 200u8), (domain_depth, 0), (stack_depth, 200), (extension_factor, 200)].iter() {
+            let mut malformed = bytes.clone();
+            malformed[offset] = value;
+            let result = StarkProof::peek_header(&malformed);
+            assert!(matches!(result, Err(ProofError::MalformedProof(_))), "{:?}", result.map(|_| ()));
+            let result = StarkProof::read_from(&malformed[..], None);
+            assert!(matches!(result, Err(ProofError::MalformedProof(_))));
+        }
+
+        // a proof with an out-of-range field is rejected by the verifier instead of panicking
+        let mut malformed = proof.clone();
+        malformed.trace_info.domain_depth = 200;
+        let err = malformed.check_header().unwrap_err();
+        assert_eq!("proof is malformed: domain depth 200 is invalid; value must be between 9 and 40", err.to_string());
+        let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &malformed);
+        assert_eq!(Err(err.to_string()), result);
+
+        let mut malformed = proof.clone();
+        malformed.trace_info.stack_depth = (crate::MAX_STACK_DEPTH + 1) as u8;
+        let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &malformed);
+        assert_eq!(Err(String::from("proof is malformed: stack depth 33 is invalid; value cannot exceed 32")), result);
+    }
+
+    #[test]
+    fn tamper_trace_depths() {
+        let program = assembly::compile("begin push.3 add end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let (outputs, proof) = crate::execute(&program, &inputs, 1, &ProofOptions::default());
+        let bytes = bincode::serialize(&proof).unwrap();
+
+        // context, loop, and stack depths follow domain depth in the header; a flipped bit in
+        // any of them leaves the value in range, but changes the width of the trace
+        let (ctx_depth, loop_depth, stack_depth) = (35, 36, 37);
+        for &offset in [ctx_depth, loop_depth, stack_depth].iter() {
+            let mut malformed = bytes.clone();
+            malformed[offset] ^= 1;
+            let result = StarkProof::read_from(&malformed[..], None);
+            assert!(matches!(result, Err(ProofError::MalformedProof(_))), "{:?}", result.map(|_| ()));
+
+            let tampered: StarkProof = bincode::deserialize(&malformed).unwrap();
+            let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &tampered);
+            assert!(result.unwrap_err().contains("values of the trace at DEEP points must contain"));
+        }
+
+        // rows of trace values must have the same width as well
+        let mut tampered = proof.clone();
+        tampered.trace_evaluations[0].pop();
+        let err = tampered.check_header().unwrap_err();
+        assert!(err.to_string().contains("trace values at queried positions must contain"));
+
+        // and there must be exactly one row per queried position
+        let mut tampered = proof.clone();
+        tampered.trace_evaluations.pop();
+        let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &tampered);
+        assert!(result.unwrap_err().starts_with("proof must contain trace values at"));
+    }
+
+    #[test]
+    fn verifier_work_estimate() {
+        let program = assembly::compile("begin push.3 add end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let (_, proof) = crate::execute(&program, &inputs, 1, &ProofOptions::default());
+        assert!(proof.verifier_work_estimate().is_ok());
+
+        // domain depths outside of the supported range are rejected instead of overflowing
+        for &depth in [0, 1, 200].iter() {
+            let mut tampered = proof.clone();
+            tampered.trace_info.domain_depth = depth;
+            let result = tampered.verifier_work_estimate();
+            assert!(matches!(result, Err(ProofError::MalformedProof(_))), "{:?}", result);
+        }
+
+        // so are proofs with malformed trace values
+        let mut tampered = proof.clone();
+        tampered.deep_values.trace_at_z1.clear();
+        assert!(matches!(tampered.verifier_work_estimate(), Err(ProofError::MalformedProof(_))));
+    }
+
+    #[test]
+    fn tamper_fri_layer_depth() {
+        let program = assembly::compile("begin push.3 add end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let (outputs, proof) = crate::execute(&program, &inputs, 1, &ProofOptions::default());
+        assert!(!proof.degree_proof.layers.is_empty());
+
+        // layer depths are not a part of the Fiat-Shamir transcript, and thus, they must match
+        // the domain of the proof; depths which would overflow the domain size are rejected as
+        // well as depths which would fold a smaller domain
+        let expected = (proof.domain_size() / 4).trailing_zeros() as u8;
+        for &depth in [64u8, 200, expected - 1, expected + 1].iter() {
+            let mut tampered = proof.clone();
+            tampered.degree_proof.layers[0].depth = depth;
+            let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &tampered);
+            let err = result.unwrap_err();
+            assert!(err.starts_with("verification of low-degree proof failed: expected layer"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn tamper_output_tape() {
+        let program = assembly::compile("begin push.3 dup write push.5 write mul end").unwrap();
+        let inputs = ProgramInputs::from_public(&[2]);
+        let options = ProofOptions::new(32, 50, 0, hash::blake3).with_public_tape();
+        let (outputs, proof) = crate::execute_with_outputs(&program, &inputs, 1, &options);
+        assert_eq!(&[6], outputs.stack());
+        assert_eq!(&[3, 5], outputs.tape());
+
+        let tape = proof.tape().unwrap();
+        assert_eq!(2, tape.steps.len());
+        let result = crate::verify_with_outputs(program.hash(), &[2], &outputs, &proof);
+        assert_eq!(Ok(true), result);
+
+        // a proof claiming a different value is rejected
+        let mut tampered = proof.clone();
+        tampered.tape.as_mut().unwrap().values[1] = 6;
+        let result = crate::verify(program.hash(), &[2], outputs.stack(), &tampered);
+        assert_ne!(Ok(true), result);
+
+        // and so is a proof claiming that a value was written at a different step
+        let mut tampered = proof.clone();
+        tampered.tape.as_mut().unwrap().steps[1] += 1;
+        let result = crate::verify(program.hash(), &[2], outputs.stack(), &tampered);
+        assert_ne!(Ok(true), result);
+
+        // steps must be strictly increasing
+        let mut tampered = proof.clone();
+        tampered.tape.as_mut().unwrap().steps[1] = tape.steps[0];
+        let result = crate::verify(program.hash(), &[2], outputs.stack(), &tampered);
+        assert_eq!(Err(format!("output tape step {} at position 1 is invalid", tape.steps[0])), result);
+    }
+
+    #[test]
+    fn tamper_inputs_hash() {
+        let program = assembly::compile("begin add push.3 mul end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1, 2]);
+        let options = ProofOptions::default().with_input_commitment();
+        let (outputs, proof, vk) = crate::execute_with_key(&program, &inputs, 1, &options);
+        assert!(vk.input_commitment());
+        assert_eq!(Ok(outputs.clone()), crate::verify_with_key(&vk, &[1, 2], &proof));
+
+        // the commitment is a part of the Fiat-Shamir transcript: stripping it from the proof
+        // changes query positions even for a verifier which does not require it
+        let mut stripped = proof.clone();
+        stripped.inputs_hash = None;
+        let result = crate::verify(program.hash(), &[1, 2], &outputs, &stripped);
+        assert_eq!(Err(String::from("seed proof-of-work verification failed")), result);
+
+        // a verifier which requires the commitment rejects it before that
+        let result = crate::verify_with_key(&vk, &[1, 2], &stripped);
+        assert_eq!(Err(String::from("proof does not commit to public inputs")), result);
+
+        // replacing the commitment with a commitment to other inputs is detected as well
+        let mut forged = proof.clone();
+        forged.inputs_hash = Some(crate::commit_to_inputs(&[1, 3]));
+        let result = crate::verify(program.hash(), &[1, 3], &outputs, &forged);
+        assert_eq!(Err(String::from("seed proof-of-work verification failed")), result);
+
+        // a proof generated without the commitment is rejected by a key which expects it
+        let (_, proof) = crate::execute(&program, &inputs, 1, &ProofOptions::default());
+        let result = crate::verify_with_key(&vk, &[1, 2], &proof);
+        assert_eq!(Err(String::from("proof does not commit to public inputs")), result);
+    }
+
+    #[test]
+    fn check_input_commitment() {
+        let program = assembly::compile("begin push.3 add end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let options = ProofOptions::default().with_input_commitment();
+        let (outputs, proof) = crate::execute(&program, &inputs, 1, &options);
+        assert!(crate::check_input_commitment(&[1], &proof));
+
+        // the proof does not commit to different or non-canonical public inputs
+        assert!(!crate::check_input_commitment(&[2], &proof));
+        assert!(!crate::check_input_commitment(&[1, 0], &proof));
+        assert!(!crate::check_input_commitment(&[1 + crate::math::field::MODULUS], &proof));
+
+        // the check does not depend on proof-of-work: it is not affected by the nonce, and a
+        // proof with a tampered trace commitment passes it, but is rejected by full verification
+        let mut tampered = proof.clone();
+        tampered.pow_nonce += 1;
+        tampered.trace_root[0] ^= 1;
+        assert!(crate::check_input_commitment(&[1], &tampered));
+        assert!(crate::verify(program.hash(), &[1], &outputs, &tampered).is_err());
+
+        // proofs which do not commit to public inputs never pass the check
+        let (_, proof) = crate::execute(&program, &inputs, 1, &ProofOptions::default());
+        assert!(!crate::check_input_commitment(&[1], &proof));
+    }
+
+    #[test]
+    fn describe() {
+        let program = assembly::compile("begin push.3 add dup mul end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let options = ProofOptions::default();
+        let (_, proof) = crate::execute(&program, &inputs, 1, &options);
+        let description = proof.describe();
+
+        // the description lists every FRI layer and the number of queries
+        let num_layers = proof.degree_proof().layers.len();
+        assert!(description.contains(&format!("fri: {} layers", num_layers)));
+        assert_eq!(num_layers, description.matches("─ layer ").count());
+        assert!(description.contains(&format!("queries: {}", options.num_queries())));
+
+        // as well as the commitments and the proof-of-work nonce
+        assert!(description.contains(&hex::encode(proof.trace_root())));
+        assert!(description.contains(&hex::encode(proof.constraint_root())));
+        assert!(description.ends_with(&format!("pow nonce: {}", proof.pow_nonce())));
+    }
+}
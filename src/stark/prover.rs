@@ -1,30 +1,140 @@
-use std::time::Instant;
+use std::sync::{ Once, atomic::{ AtomicU64, Ordering } };
+use std::time::{ Duration, Instant };
 use log::debug;
+use serde::{ Serialize, Deserialize };
 use crate::{
-    math::{ field, polynom, fft },
-    crypto::MerkleTree,
+    math::{ field, polynom },
+    crypto::{ MerkleTree, HashFunction },
+    processor::ExecutionError,
+    programs::commit_to_inputs,
+    HASH_DIGEST_SIZE,
 };
 use super::{
-    ProofOptions, StarkProof, CompositionCoefficients, DeepValues, fri, utils,
-    trace::{ TraceTable, TraceState },
-    constraints::{ ConstraintTable, ConstraintPoly },
-    MAX_CONSTRAINT_DEGREE,
+    ProofOptions, OptionsError, ProverContext, StarkProof, OutputTape, CompositionCoefficients, DeepValues, AuxTraceProof, fri, utils,
+    trace::{ TraceTable, AuxTable, TraceState, TraceStep, LdeStep },
+    constraints::{ ConstraintTable, ConstraintPoly, ConstraintEvaluator, CustomConstraints, AuxEvaluator, draw_aux_challenges },
+    MAX_CONSTRAINT_DEGREE, DOMAIN_OFFSET,
 };
 
+// CONSTANTS
+// ================================================================================================
+
+// approximate costs of operations other than field multiplications, expressed in the number of
+// field multiplications; these are used only to estimate proving time
+const HASH_COST: usize = 8;
+const HASH_COST_PER_ELEMENT: usize = 2;
+const CONSTRAINT_COST_PER_REGISTER: usize = 32;
+const COMPOSITION_COST_PER_REGISTER: usize = 6;
+const FRI_COST_PER_ELEMENT: usize = 8;
+
+/// Number of multiplications performed to measure field multiplication throughput.
+const CALIBRATION_ROUNDS: usize = 1 << 16;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProverError {
+    MemoryBudgetExceeded { estimated: usize, budget: usize },
+    ExecutionFailed(String),
+    ExecutionAborted(ExecutionError),
+    InvalidOptions(OptionsError),
+    InvalidTrace(String),
+    TooManyOutputs { requested: usize, max: usize },
+    CheckpointOptionsMismatch,
+}
+
+/// State of the prover after the execution trace has been extended and committed to; this is
+/// the most expensive deterministic part of proof generation. A checkpoint can be serialized,
+/// and proof generation can be resumed from it via resume().
+#[derive(Serialize, Deserialize)]
+pub struct ProverCheckpoint {
+    trace       : TraceTable,
+    trace_tree  : MerkleTree,
+    inputs      : Vec<u128>,
+    outputs     : Vec<u128>,
+    options     : ProofOptions,
+}
+
+/// Auxiliary trace registers extended to the LDE domain, the Merkle tree built from them, and
+/// the challenges from which they were built.
+struct AuxCommitment {
+    table       : AuxTable,
+    tree        : MerkleTree,
+    challenges  : Vec<u128>,
+}
+
+/// States of the execution trace at an out-of-domain point z (`current`) and at z * g (`next`),
+/// where g is the generator of the trace domain; these are the states against which constraints
+/// are evaluated at z. Like ood_frame(), this is internal to the prover and is not exported
+/// from the crate.
+#[derive(Debug, PartialEq)]
+pub(crate) struct OodFrame {
+    pub current : TraceState,
+    pub next    : TraceState,
+}
+
 // PROVER FUNCTION
 // ================================================================================================
 
-pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options: &ProofOptions) -> StarkProof {
+/// Generates a STARK proof for the specified `trace`; in addition to built-in constraints, the
+/// trace must satisfy all `custom` constraints. `context` is used to look up (and cache) values
+/// which can be re-used across multiple proofs for traces of the same length.
+pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> Result<StarkProof, ProverError> {
+    let trace_tree = commit_trace(trace, options, context)?;
+    return prove_committed(trace, &trace_tree, inputs, outputs, custom, options, context);
+}
+
+/// Extends the specified `trace` and commits to it; the returned checkpoint can be used to
+/// complete proof generation via resume(). The proof generated from a checkpoint is the same
+/// as the proof generated by prove() with the same parameters.
+pub fn checkpoint(mut trace: TraceTable, inputs: &[u128], outputs: &[u128], options: &ProofOptions, context: &mut ProverContext) -> Result<ProverCheckpoint, ProverError> {
+    let trace_tree = commit_trace(&mut trace, options, context)?;
+    return Ok(ProverCheckpoint {
+        trace, trace_tree,
+        inputs  : inputs.to_vec(),
+        outputs : outputs.to_vec(),
+        options : options.clone(),
+    });
+}
+
+/// Completes generation of a proof from the specified `checkpoint`; `options` must be equivalent
+/// to the options with which the checkpoint was created (see ProofOptions::is_equivalent_to()),
+/// otherwise an error is returned.
+pub fn resume(checkpoint: &ProverCheckpoint, custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> Result<StarkProof, ProverError> {
+    if !checkpoint.options.is_equivalent_to(options) {
+        return Err(ProverError::CheckpointOptionsMismatch);
+    }
+    let ProverCheckpoint { trace, trace_tree, inputs, outputs, .. } = checkpoint;
+    return prove_committed(trace, trace_tree, inputs, outputs, custom, options, context);
+}
+
+/// Extends the execution trace to the LDE domain and builds a Merkle tree from the extended
+/// trace; this covers steps 1 and 2 of proof generation.
+fn commit_trace(trace: &mut TraceTable, options: &ProofOptions, context: &mut ProverContext) -> Result<MerkleTree, ProverError> {
+    // make sure the LDE domain of the trace can be built in the field
+    options.validate(trace.unextended_length()).map_err(ProverError::InvalidOptions)?;
+
+    // make sure the proof can be generated within the memory budget
+    if let Some(budget) = options.max_memory_bytes() {
+        let estimated = estimate_memory(trace.unextended_length(), trace.register_count(), trace.extension_factor());
+        if estimated > budget {
+            return Err(ProverError::MemoryBudgetExceeded { estimated, budget });
+        }
+    }
+
+    // in debug mode, make sure constraint evaluators produce expected results on a known
+    // transition; this catches accidental changes to hash function parameters and the like
+    #[cfg(debug_assertions)]
+    super::constraints::self_test();
+
     // 1 ----- extend execution trace -------------------------------------------------------------
     let now = Instant::now();
 
-    // build LDE domain and LDE twiddles (for FFT evaluation over LDE domain)
-    let lde_root = field::get_root_of_unity(trace.domain_size());
-    let lde_domain = field::get_power_series(lde_root, trace.domain_size());
-    let lde_twiddles = twiddles_from_domain(&lde_domain);
+    // get LDE domain and LDE twiddles (for FFT evaluation over LDE domain)
+    let domain = context.get_lde_domain(trace.unextended_length(), trace.extension_factor());
 
     // extend the execution trace registers to LDE domain
-    trace.extend(&lde_twiddles);
+    trace.extend(&domain.twiddles, &domain.inv_twiddles, context.num_threads());
     debug!("Extended execution trace from {} to {} steps in {} ms",
         trace.unextended_length(),
         trace.domain_size(), 
@@ -32,88 +142,69 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
 
     // 2 ----- build Merkle tree from the extended execution trace ------------------------------------
     let now = Instant::now();
-    let trace_tree = trace.build_merkle_tree(options.hash_fn());
+    let trace_tree = trace.build_merkle_tree(options.hash_fn(), context.num_threads());
     debug!("Built trace Merkle tree in {} ms", 
         now.elapsed().as_millis());
 
-    // 3 ----- evaluate constraints ---------------------------------------------------------------
-    let now = Instant::now();
-    
-    // initialize constraint evaluation table
-    let mut constraints = ConstraintTable::new(&trace, trace_tree.root(), inputs, outputs);
-    
-    // allocate space to hold current and next states for constraint evaluations
-    let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
-    let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
-
-    // we don't need to evaluate constraints over the entire extended execution trace; we need
-    // to evaluate them over the domain extended to match max constraint degree - thus, we can
-    // skip most trace states for the purposes of constraint evaluation.
-    let stride = trace.extension_factor() / MAX_CONSTRAINT_DEGREE;
-    for i in (0..trace.domain_size()).step_by(stride) {
-        // TODO: this loop should be parallelized and also potentially optimized to avoid copying
-        // next state from the trace table twice
-
-        // copy current and next states from the trace table; next state may wrap around the
-        // execution trace (close to the end of the trace)
-        trace.fill_state(&mut current, i);
-        trace.fill_state(&mut next, (i + trace.extension_factor()) % trace.domain_size());
-
-        // evaluate the constraints
-        constraints.evaluate(&current, &next, lde_domain[i], i / stride);
-    }
-
-    debug!("Evaluated {} constraints over domain of {} elements in {} ms",
-        constraints.constraint_count(),
-        constraints.evaluation_domain_size(),
-        now.elapsed().as_millis());
-
-    // 4 ----- convert constraint evaluations into a polynomial -----------------------------------
-    let now = Instant::now();
-    let constraint_poly = constraints.combine_polys();
-    debug!("Converted constraint evaluations into a single polynomial of degree {} in {} ms",
-        constraint_poly.degree(),
-        now.elapsed().as_millis());
-
-    // 5 ----- build Merkle tree from constraint polynomial evaluations ---------------------------
-    let now = Instant::now();
-    
-    // evaluate constraint polynomial over the evaluation domain
-    let constraint_evaluations = constraint_poly.eval(&lde_twiddles);
-
-    // put evaluations into a Merkle tree; 4 evaluations per leaf
-    let constraint_evaluations = evaluations_to_leaves(constraint_evaluations);
-    let constraint_tree = MerkleTree::new(constraint_evaluations, options.hash_fn());
-    debug!("Evaluated constraint polynomial and built constraint Merkle tree in {} ms",
-        now.elapsed().as_millis());
+    return Ok(trace_tree);
+}
 
-    // 6 ----- build and evaluate deep composition polynomial -------------------------------------
+/// Generates a proof for the extended `trace` committed to by `trace_tree`; this covers steps
+/// 3 through 7 of proof generation.
+fn prove_committed(trace: &TraceTable, trace_tree: &MerkleTree, inputs: &[u128], outputs: &[u128], custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> Result<StarkProof, ProverError> {
+    let domain = context.get_lde_domain(trace.unextended_length(), trace.extension_factor());
+    let lde_domain = &domain.values;
+    let lde_twiddles = &domain.twiddles;
+
+    // 3 ----- evaluate constraints and commit to the constraint polynomial ---------------------
+    // if custom constraints define auxiliary registers, the registers are built and committed
+    // to first, and their constraints are evaluated together with all other constraints; a
+    // public output tape is bound to the trace via an auxiliary register as well
+    let tape = get_public_tape(trace, options);
+    let custom = &custom.with_tape(tape.as_ref());
+    let aux = commit_aux_trace(trace, trace_tree.root(), custom, options, context);
+    let aux_evaluator = build_aux_evaluator(trace, aux.as_ref(), custom, options);
+    let constraints = ConstraintTable::new(trace, trace_tree.root(), options.transcript_fn(), inputs, outputs, tape.as_ref(), custom, aux_evaluator, context.get_stack_ark(trace.unextended_length()));
+    let aux_table = aux.as_ref().map(|aux| &aux.table);
+    let (constraint_tree, constraint_poly) = commit_composition(trace, aux_table, constraints, context, options);
+
+    // 4 ----- build and evaluate deep composition polynomial -------------------------------------
     let now = Instant::now();
 
-    // combine trace and constraint polynomials into the final deep composition polynomial
-    let seed = constraint_tree.root();
-    let (composition_poly, deep_values) = build_composition_poly(&trace, constraint_poly, seed);
+    // combine trace and constraint polynomials into the final deep composition polynomial;
+    // the commitment to public inputs (if any) is bound to the composition coefficients
+    let inputs_hash = get_inputs_hash(inputs, options);
+    let seed = utils::get_composition_seed(constraint_tree.root(), inputs_hash.as_ref(), options.transcript_fn());
+    let (composition_poly, deep_values) = build_composition_poly(trace, aux_table, constraint_poly, &seed, options.transcript_fn());
 
     // evaluate the composition polynomial over LDE domain
     let mut composed_evaluations = composition_poly;
     debug_assert!(composed_evaluations.capacity() == lde_domain.len(), "invalid composition polynomial capacity");
     unsafe { composed_evaluations.set_len(composed_evaluations.capacity()); }
-    polynom::eval_fft_twiddles(&mut composed_evaluations, &lde_twiddles, true);
+    polynom::scale_argument(&mut composed_evaluations, DOMAIN_OFFSET);
+    polynom::eval_fft_twiddles(&mut composed_evaluations, lde_twiddles, true);
 
     debug!("Built composition polynomial and evaluated it over domain of {} elements in {} ms",
         composed_evaluations.len(),
         now.elapsed().as_millis());
 
-    // 7 ----- compute FRI layers for the composition polynomial ----------------------------------
+    // 5 ----- compute FRI layers for the composition polynomial ----------------------------------
     let now = Instant::now();
     let composition_degree = utils::get_composition_degree(trace.unextended_length());
     debug_assert!(composition_degree == polynom::infer_degree(&composed_evaluations));
-    let (fri_trees, fri_values) = fri::reduce(&composed_evaluations, &lde_domain, options);
+    let (fri_trees, fri_values) = fri::reduce(&composed_evaluations, lde_domain, composition_degree, options);
     debug!("Computed {} FRI layers from composition polynomial evaluations in {} ms",
     fri_trees.len(),
         now.elapsed().as_millis());
+    let layer_degrees = fri::get_layer_degrees(composition_degree, fri_trees.len() - 1);
+    for (depth, degree) in layer_degrees[..(fri_trees.len() - 1)].iter().enumerate() {
+        debug!("FRI layer {}: degree bound {}", depth, degree);
+    }
+    debug!("FRI remainder: degree bound {} ({} coefficients)",
+        layer_degrees[fri_trees.len() - 1],
+        layer_degrees[fri_trees.len() - 1] + 1);
 
-    // 8 ----- determine query positions -----------------------------------------------------------
+    // 6 ----- determine query positions -----------------------------------------------------------
     let now = Instant::now();
 
     // combine all FRI layer roots into a single vector
@@ -122,9 +213,18 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
         tree.root().iter().for_each(|&v| fri_roots.push(v));
     }
 
-    // derive a seed from the combined roots
-    let mut seed = [0u8; 32];
-    options.hash_fn()(&fri_roots, &mut seed);
+    // derive a seed from public inputs, outputs, commitment to the inputs, and the combined roots
+    let mut transcript = utils::Transcript::new(options.transcript_fn());
+    transcript.absorb_public_inputs(inputs);
+    transcript.absorb_public_inputs(outputs);
+    if let Some(tape) = &tape {
+        transcript.absorb_public_inputs(&tape.values);
+    }
+    if let Some(inputs_hash) = &inputs_hash {
+        transcript.absorb_public_inputs(inputs_hash);
+    }
+    transcript.absorb_commitments(&fri_roots);
+    let seed = transcript.state();
 
     // apply proof-of-work to get a new seed
     let (seed, pow_nonce) = utils::find_pow_nonce(seed, &options);
@@ -136,7 +236,7 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
         hex::encode(seed),
         now.elapsed().as_millis());
 
-    // 9 ----- build proof object -----------------------------------------------------------------
+    // 7 ----- build proof object -----------------------------------------------------------------
     let now = Instant::now();
 
     // generate FRI proof
@@ -145,6 +245,13 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
     // built a list of trace evaluations at queried positions
     let trace_evaluations = trace.get_register_values_at(&positions);
 
+    // build a list of auxiliary register evaluations at queried positions (if any)
+    let aux_trace = aux.map(|aux| AuxTraceProof {
+        root        : *aux.tree.root(),
+        nodes       : aux.tree.prove_batch(&positions).nodes,
+        evaluations : aux.table.get_register_values_at(&positions),
+    });
+
     // build a list of constraint positions
     let constraint_positions = utils::map_trace_to_constraint_positions(&positions);
 
@@ -153,6 +260,7 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
         trace_tree.root(),
         trace_tree.prove_batch(&positions),
         trace_evaluations,
+        aux_trace,
         constraint_tree.root(),
         constraint_tree.prove_batch(&constraint_positions),
         deep_values,
@@ -162,20 +270,279 @@ pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options:
         trace.ctx_depth(),
         trace.loop_depth(),
         trace.stack_depth(),
+        outputs,
+        tape,
+        inputs_hash,
         &options);
 
     debug!("Built proof object in {} ms", now.elapsed().as_millis());
-    return proof;
+    return Ok(proof);
 }
 
-// HELPER FUNCTIONS
-// ================================================================================================
-fn twiddles_from_domain(domain: &[u128]) -> Vec<u128> {
-    let mut twiddles = domain[..(domain.len() / 2)].to_vec();
-    fft::permute(&mut twiddles);
-    return twiddles;
+/// Evaluates `constraints` over the constraint evaluation domain of the extended `trace` (and
+/// of the extended `aux` table, if the constraints include constraints of auxiliary registers),
+/// combines the evaluations into a single constraint polynomial, and commits to the evaluations
+/// of this polynomial over the LDE domain. The root of the returned tree is the commitment
+/// included into the proof; random coefficients for combining constraints are drawn from the
+/// trace root with which the `constraints` table was created. ConstraintTable is not exported
+/// from the crate, and so this function is internal to the prover as well.
+pub(crate) fn commit_composition(trace: &TraceTable, aux: Option<&AuxTable>, mut constraints: ConstraintTable, context: &mut ProverContext, options: &ProofOptions) -> (MerkleTree, ConstraintPoly) {
+    let domain = context.get_lde_domain(trace.unextended_length(), trace.extension_factor());
+    let lde_domain = &domain.values;
+    let lde_twiddles = &domain.twiddles;
+
+    // 1 ----- evaluate constraints ---------------------------------------------------------------
+    let now = Instant::now();
+
+    // allocate space to hold current and next states for constraint evaluations
+    let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+    let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+    let aux_width = aux.map_or(0, |aux| aux.width());
+    let mut aux_current = vec![field::ZERO; aux_width];
+    let mut aux_next = vec![field::ZERO; aux_width];
+
+    // we don't need to evaluate constraints over the entire extended execution trace; we need
+    // to evaluate them over the domain extended to match max constraint degree - thus, we can
+    // skip most trace states for the purposes of constraint evaluation. unlike in
+    // compose_constraints_chunked(), the loop is not split into chunks: the table has to keep
+    // evaluations over the entire domain to interpolate them, and thus, chunking would not
+    // reduce memory (see constraints/README.md).
+    let stride = trace.extension_factor() / MAX_CONSTRAINT_DEGREE;
+    for i in (0..trace.domain_size()).step_by(stride) {
+        // TODO: this loop should be parallelized and also potentially optimized to avoid copying
+        // next state from the trace table twice
+
+        // copy current and next states from the trace table; next state may wrap around the
+        // execution trace (close to the end of the trace)
+        let step = LdeStep(i);
+        let next_step = LdeStep((i + trace.extension_factor()) % trace.domain_size());
+        trace.fill_state(&mut current, step);
+        trace.fill_state(&mut next, next_step);
+        if let Some(aux) = aux {
+            aux.fill_row(&mut aux_current, step);
+            aux.fill_row(&mut aux_next, next_step);
+        }
+
+        // evaluate the constraints
+        constraints.evaluate(&current, &next, &aux_current, &aux_next, lde_domain[i], step.to_eval(trace.extension_factor()));
+    }
+
+    debug!("Evaluated {} constraints over domain of {} elements in {} ms",
+        constraints.constraint_count(),
+        constraints.evaluation_domain_size(),
+        now.elapsed().as_millis());
+
+    // 2 ----- convert constraint evaluations into a polynomial -----------------------------------
+    let now = Instant::now();
+    let constraint_poly = constraints.combine_polys();
+    debug!("Converted constraint evaluations into a single polynomial of degree {} in {} ms",
+        constraint_poly.degree(),
+        now.elapsed().as_millis());
+
+    // 3 ----- build Merkle tree from constraint polynomial evaluations ---------------------------
+    let now = Instant::now();
+    
+    // evaluate constraint polynomial over the evaluation domain
+    let constraint_evaluations = constraint_poly.eval(lde_twiddles);
+
+    // put evaluations into a Merkle tree; 4 evaluations per leaf
+    let constraint_evaluations = evaluations_to_leaves(constraint_evaluations);
+    let constraint_tree = MerkleTree::new(constraint_evaluations, options.hash_fn());
+    debug!("Evaluated constraint polynomial and built constraint Merkle tree in {} ms",
+        now.elapsed().as_millis());
+
+    return (constraint_tree, constraint_poly);
+}
+
+/// Evaluates constraints over the `trace` and returns coefficients of the resulting constraint
+/// polynomial (in the order of increasing powers of x) without generating a proof. The trace is
+/// extended if it has not been extended yet. Constraints are combined using the same random
+/// coefficients as during proof generation, and thus, the polynomial matches the one committed
+/// to in the proof; its degree must not exceed max_constraint_degree().
+pub fn composition_coefficients(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> Vec<u128> {
+    if !trace.is_extended() {
+        let domain = context.get_lde_domain(trace.unextended_length(), trace.extension_factor());
+        trace.extend(&domain.twiddles, &domain.inv_twiddles, context.num_threads());
+    }
+    let trace_tree = trace.build_merkle_tree(options.hash_fn(), context.num_threads());
+
+    let tape = get_public_tape(trace, options);
+    let custom = &custom.with_tape(tape.as_ref());
+    let aux = commit_aux_trace(trace, trace_tree.root(), custom, options, context);
+    let aux_evaluator = build_aux_evaluator(trace, aux.as_ref(), custom, options);
+    let constraints = ConstraintTable::new(trace, trace_tree.root(), options.transcript_fn(), inputs, outputs, tape.as_ref(), custom, aux_evaluator, context.get_stack_ark(trace.unextended_length()));
+    let (_, constraint_poly) = commit_composition(trace, aux.as_ref().map(|aux| &aux.table), constraints, context, options);
+    return constraint_poly.into_coefficients();
+}
+
+/// Returns the number of field multiplications performed when evaluating constraints over the
+/// constraint evaluation domain of the `trace`; see ConstraintEvaluator::mul_count() for details.
+pub fn constraint_mul_count(trace: &TraceTable, inputs: &[u128], outputs: &[u128], custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> u64 {
+    // the root of the trace tree affects only the random coefficients, and not the count
+    let tape = get_public_tape(trace, options);
+    let evaluator = ConstraintEvaluator::from_trace(trace, &[0; 32], options.transcript_fn(), inputs, outputs, tape.as_ref(), custom, context.get_stack_ark(trace.unextended_length()));
+    return evaluator.mul_count(trace.unextended_length(), MAX_CONSTRAINT_DEGREE);
+}
+
+/// Returns the maximum degree of the constraint polynomial for an execution trace of the
+/// specified length; this is one more than the degree of the composition polynomial since
+/// the composition polynomial is divided by (x - z).
+pub fn max_constraint_degree(trace_length: usize) -> usize {
+    return utils::get_composition_degree(trace_length) + 1;
+}
+
+/// Returns the generator of the low-degree extension domain for an execution trace of the
+/// specified length; this is the root of unity of order trace_length * extension_factor.
+pub fn lde_domain_generator(trace_length: usize, extension_factor: usize) -> u128 {
+    assert!(trace_length.is_power_of_two(), "trace length must be a power of 2");
+    assert!(extension_factor.is_power_of_two(), "extension factor must be a power of 2");
+    return field::get_root_of_unity(trace_length * extension_factor);
+}
+
+/// Returns the offset of the low-degree extension domain; the domain is the coset of the
+/// subgroup generated by `lde_domain_generator()` shifted by this offset, and thus, is disjoint
+/// from the trace domain.
+pub fn lde_domain_offset() -> u128 {
+    return DOMAIN_OFFSET;
+}
+
+/// Returns elements of the low-degree extension domain in the order used by the prover: the
+/// i-th element is the x coordinate of the i-th step of the extended trace, and is the point
+/// at which constraints are evaluated at this step. The i-th element is equal to
+/// offset * g^i, where g is the generator and offset is the offset of the domain.
+pub fn lde_domain(trace_length: usize, extension_factor: usize) -> Vec<u128> {
+    let g = lde_domain_generator(trace_length, extension_factor);
+    let mut domain = field::get_power_series(g, trace_length * extension_factor);
+    domain.iter_mut().for_each(|x| *x = field::mul(*x, DOMAIN_OFFSET));
+    return domain;
+}
+
+/// Evaluates polynomials of the extended `trace` at the out-of-domain point `z` and at z * g.
+pub(crate) fn ood_frame(trace: &TraceTable, z: u128) -> OodFrame {
+    let g = field::get_root_of_unity(trace.unextended_length());
+    let next_z = field::mul(z, g);
+
+    let (ctx_depth, loop_depth, stack_depth) = (trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
+    return OodFrame {
+        current : TraceState::from_vec(ctx_depth, loop_depth, stack_depth, &trace.eval_polys_at(z)),
+        next    : TraceState::from_vec(ctx_depth, loop_depth, stack_depth, &trace.eval_polys_at(next_z)),
+    };
 }
 
+/// Estimates the number of bytes needed to generate a proof for an execution trace with the
+/// specified dimensions. Only the largest allocations are accounted for: LDE domain, extended
+/// execution trace, Merkle trees, and constraint and composition polynomial evaluations.
+pub fn estimate_memory(trace_length: usize, register_count: usize, extension_factor: usize) -> usize {
+    let domain_size = trace_length * extension_factor;
+    let element_size = std::mem::size_of::<u128>();
+    let node_size = std::mem::size_of::<[u8; 32]>();
+
+    // LDE domain and twiddles
+    let domain = (domain_size + domain_size / 2 + trace_length / 2) * element_size;
+
+    // extended registers and register polynomials
+    let trace = register_count * (domain_size + trace_length) * element_size;
+
+    // trace Merkle tree: one leaf per step and the same number of internal nodes
+    let trace_tree = 2 * domain_size * node_size;
+
+    // constraint evaluation table (3 evaluation vectors) and constraint Merkle tree built from
+    // the evaluations of the constraint polynomial (2 evaluations per leaf)
+    let constraints = 3 * trace_length * MAX_CONSTRAINT_DEGREE * element_size
+        + domain_size * element_size + (domain_size / 2) * node_size;
+
+    // evaluations of the composition polynomial
+    let composition = domain_size * element_size;
+
+    return domain + trace + trace_tree + constraints + composition;
+}
+
+/// Estimates the time needed to generate a proof for an execution trace with the specified
+/// dimensions given field multiplication throughput (`muls_per_second`). Every stage of
+/// the prover is modeled as an equivalent number of field multiplications: FFTs dominate trace
+/// extension and constraint interpolation, while hashing, constraint evaluation, and FRI are
+/// approximated as a fixed number of multiplications per element.
+pub fn estimate_time(trace_length: usize, register_count: usize, options: &ProofOptions, muls_per_second: f64) -> Duration {
+    let extension_factor = options.extension_factor();
+    let domain_size = trace_length * extension_factor;
+    let constraint_length = trace_length * MAX_CONSTRAINT_DEGREE;
+
+    // interpolation of trace registers and their evaluation over the LDE domain
+    let extension = register_count * (fft_cost(trace_length) + fft_cost(domain_size));
+
+    // hashing of the extended trace and of the constraint evaluations; grinding takes 2^g
+    // hashes on average
+    let hashing = (register_count + 2) * domain_size * HASH_COST_PER_ELEMENT
+        + usize::pow(2, options.grinding_factor()) * HASH_COST;
+
+    // evaluation of constraints, and interpolation of the constraint polynomial and its
+    // evaluation over the LDE domain
+    let constraints = register_count * constraint_length * CONSTRAINT_COST_PER_REGISTER
+        + fft_cost(constraint_length) + fft_cost(domain_size);
+
+    // building of the composition polynomial and its low-degree proof
+    let composition = register_count * domain_size * COMPOSITION_COST_PER_REGISTER + fft_cost(domain_size);
+    let fri = domain_size * FRI_COST_PER_ELEMENT;
+
+    let num_muls = extension + hashing + constraints + composition + fri;
+    return Duration::from_secs_f64(num_muls as f64 / muls_per_second);
+}
+
+/// Returns the number of field multiplications this machine performs per second. The throughput
+/// is measured on the first call and is cached for subsequent calls.
+pub fn get_mul_throughput() -> f64 {
+    // the throughput is stored as bits of an f64 value
+    static CALIBRATION: Once = Once::new();
+    static MUL_THROUGHPUT: AtomicU64 = AtomicU64::new(0);
+
+    CALIBRATION.call_once(|| {
+        let mut x = field::rand();
+        let y = field::rand();
+        let now = Instant::now();
+        for _ in 0..CALIBRATION_ROUNDS {
+            x = field::mul(x, y);
+        }
+        // the result is made opaque to the compiler before the timer is read, so that the
+        // multiplications are neither optimized away nor moved out of the timed region
+        std::hint::black_box(x);
+        let elapsed = now.elapsed();
+
+        // a coarse timer may report zero elapsed time; the throughput must stay finite
+        let elapsed = std::cmp::max(elapsed, Duration::from_nanos(1));
+        let throughput = CALIBRATION_ROUNDS as f64 / elapsed.as_secs_f64();
+        MUL_THROUGHPUT.store(throughput.to_bits(), Ordering::Relaxed);
+    });
+    return f64::from_bits(MUL_THROUGHPUT.load(Ordering::Relaxed));
+}
+
+impl ProverCheckpoint {
+
+    /// Returns the outputs of the program for which the checkpoint was created.
+    pub fn outputs(&self) -> &[u128] {
+        return &self.outputs;
+    }
+}
+
+impl std::fmt::Display for ProverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            ProverError::MemoryBudgetExceeded { estimated, budget } => write!(f,
+                "estimated prover memory usage of {} bytes exceeds the budget of {} bytes",
+                estimated, budget),
+            ProverError::ExecutionFailed(message) => write!(f, "execution failed: {}", message),
+            ProverError::ExecutionAborted(err) => write!(f, "execution aborted: {}", err),
+            ProverError::InvalidOptions(err) => write!(f, "invalid proof options: {}", err),
+            ProverError::InvalidTrace(message) => write!(f, "invalid execution trace: {}", message),
+            ProverError::TooManyOutputs { requested, max } => write!(f,
+                "cannot produce more than {} outputs, but requested {}", max, requested),
+            ProverError::CheckpointOptionsMismatch => write!(f,
+                "proof options do not match the options with which the checkpoint was created"),
+        };
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
 /// Re-interpret vector of 16-byte values as a vector of 32-byte arrays
 fn evaluations_to_leaves(evaluations: Vec<u128>) -> Vec<[u8; 32]> {
     assert!(evaluations.len() % 2 == 0, "number of values must be divisible by 2");
@@ -186,16 +553,250 @@ fn evaluations_to_leaves(evaluations: Vec<u128>) -> Vec<[u8; 32]> {
     return unsafe { Vec::from_raw_parts(p as *mut [u8; 32], len, cap) };
 }
 
-fn build_composition_poly(trace: &TraceTable, constraint_poly: ConstraintPoly, seed: &[u8; 32]) -> (Vec<u128>, DeepValues) {
-    // pseudo-randomly selection deep point z and coefficients for the composition
-    let z = field::prng(*seed);
-    let coefficients = CompositionCoefficients::new(*seed);
+fn build_composition_poly(trace: &TraceTable, aux: Option<&AuxTable>, constraint_poly: ConstraintPoly, seed: &[u8; 32], transcript_fn: HashFunction) -> (Vec<u128>, DeepValues) {
+    // squeeze deep point z and coefficients for the composition from the seed
+    let z = utils::draw_deep_point(seed, transcript_fn);
+    let coefficients = CompositionCoefficients::new(*seed, transcript_fn);
+
+    // evaluate trace polynomials at the deep point z and z * g
+    let frame = ood_frame(trace, z);
+    let (s1, s2) = (frame.current.to_vec(), frame.next.to_vec());
+
+    // evaluate auxiliary register polynomials (if any) at the same points
+    let (a1, a2) = match aux {
+        Some(aux) => {
+            let next_z = field::mul(z, field::get_root_of_unity(trace.unextended_length()));
+            (aux.eval_polys_at(z), aux.eval_polys_at(next_z))
+        },
+        None => (Vec::new(), Vec::new()),
+    };
 
     // divide out deep point from trace polynomials and merge them into a single polynomial
-    let (mut result, s1, s2) = trace.get_composition_poly(z, &coefficients);
+    let aux = aux.map(|aux| (aux, &a1[..], &a2[..]));
+    let mut result = trace.get_composition_poly(z, &s1, &s2, aux, &coefficients);
 
     // divide out deep point from constraint polynomial and merge it into the result
     constraint_poly.merge_into(&mut result, z, &coefficients);
 
-    return (result, DeepValues { trace_at_z1: s1, trace_at_z2: s2 });
-}
\ No newline at end of file
+    return (result, DeepValues { trace_at_z1: s1, trace_at_z2: s2, aux_at_z1: a1, aux_at_z2: a2 });
+}
+
+/// Builds auxiliary registers defined by `custom` constraints (if any) from challenges drawn
+/// from the `trace_root`, extends the registers to the LDE domain, and commits to them.
+fn commit_aux_trace(trace: &TraceTable, trace_root: &[u8; 32], custom: &CustomConstraints, options: &ProofOptions, context: &mut ProverContext) -> Option<AuxCommitment> {
+    let aux_trace = custom.aux_trace()?;
+    let now = Instant::now();
+
+    // build auxiliary registers from the states of the un-extended execution trace
+    let challenges = draw_aux_challenges(trace_root, aux_trace.num_challenges(), options.transcript_fn());
+    let states: Vec<TraceState> = (0..trace.unextended_length()).map(|i| trace.get_state(TraceStep(i))).collect();
+    let registers = aux_trace.build_registers(&states, &challenges);
+    assert!(registers.len() == aux_trace.width(),
+        "expected {} auxiliary registers, but {} were built", aux_trace.width(), registers.len());
+
+    // if we are in debug mode, make sure boundary constraints of auxiliary registers are satisfied
+    if cfg!(debug_assertions) {
+        let (i_values, f_values) = aux_trace.boundary_values(&challenges);
+        let last_step = trace.unextended_length() - 1;
+        assert!(registers.iter().zip(i_values).all(|(r, v)| r[0] == v),
+            "auxiliary boundary constraints at the first step were not satisfied");
+        assert!(registers.iter().zip(f_values).all(|(r, v)| r[last_step] == v),
+            "auxiliary boundary constraints at the last step were not satisfied");
+    }
+
+    // extend auxiliary registers and commit to them
+    let domain = context.get_lde_domain(trace.unextended_length(), trace.extension_factor());
+    let mut table = AuxTable::new(registers, trace.unextended_length(), trace.extension_factor());
+    table.extend(&domain.twiddles, &domain.inv_twiddles, context.num_threads());
+    let tree = table.build_merkle_tree(options.hash_fn(), context.num_threads());
+    debug!("Built and committed to {} auxiliary registers in {} ms",
+        table.width(),
+        now.elapsed().as_millis());
+
+    return Some(AuxCommitment { table, tree, challenges });
+}
+
+/// Returns an evaluator for constraints of auxiliary registers committed to by `aux`, or None
+/// if auxiliary registers are not defined.
+fn build_aux_evaluator(trace: &TraceTable, aux: Option<&AuxCommitment>, custom: &CustomConstraints, options: &ProofOptions) -> Option<AuxEvaluator> {
+    let aux_trace = custom.aux_trace()?;
+    let aux = aux?;
+    return Some(AuxEvaluator::new(aux_trace, &aux.challenges, aux.tree.root(), options.transcript_fn(), trace.unextended_length()));
+}
+
+/// Returns the output tape of the `trace` if the tape is to be made public, and None otherwise.
+fn get_public_tape(trace: &TraceTable, options: &ProofOptions) -> Option<OutputTape> {
+    if !options.public_tape() { return None; }
+    return Some(trace.get_output_tape());
+}
+
+/// Returns the commitment to public `inputs` if the commitment is to be included into the
+/// proof, and None otherwise.
+fn get_inputs_hash(inputs: &[u128], options: &ProofOptions) -> Option<[u128; HASH_DIGEST_SIZE]> {
+    if !options.input_commitment() { return None; }
+    return Some(commit_to_inputs(inputs));
+}
+
+/// Returns the number of field multiplications in an FFT over `n` values.
+fn fft_cost(n: usize) -> usize {
+    return n / 2 * (n.trailing_zeros() as usize);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::crypto::{ hash::blake3, MerkleTree };
+    use crate::{ math::{ field, polynom }, processor, programs::assembly, ProgramInputs, ProofOptions };
+    use crate::stark::{ TraceTable, utils, ProverContext, CustomConstraints, constraints::{ ConstraintTable, ConstraintPoly } };
+
+    #[test]
+    fn ood_frame() {
+        let program = assembly::compile("begin push.3 add dup mul end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let options = ProofOptions::default();
+
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &inputs);
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+        let outputs = trace.get_last_state().user_stack()[..1].to_vec();
+        let custom = CustomConstraints::new();
+        let proof = super::prove(&mut trace, &[1], &outputs, &custom, &options, &mut ProverContext::new()).unwrap();
+
+        // the frame matches the one committed to in the proof and consumed by the verifier
+        let seed = utils::get_composition_seed(proof.constraint_root(), proof.inputs_hash(), options.transcript_fn());
+        let z = utils::draw_deep_point(&seed, options.transcript_fn());
+        let frame = super::ood_frame(&trace, z);
+        assert_eq!(proof.get_state_at_z1(), frame.current);
+        assert_eq!(proof.get_state_at_z2(), frame.next);
+
+        // next state is the trace evaluated at z * g
+        let g = field::get_root_of_unity(trace.unextended_length());
+        assert_eq!(trace.eval_polys_at(field::mul(z, g)), frame.next.to_vec());
+    }
+
+    #[test]
+    fn commit_composition() {
+        let program = assembly::compile("begin push.3 add dup mul end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let options = ProofOptions::default();
+
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &inputs);
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+        let outputs = trace.get_last_state().user_stack()[..1].to_vec();
+        let custom = CustomConstraints::new();
+        let mut context = ProverContext::new();
+        let proof = super::prove(&mut trace, &[1], &outputs, &custom, &options, &mut context).unwrap();
+
+        // commit to the constraint polynomial of the (already extended) trace using the same
+        // trace root; the commitment matches the one in the proof
+        let constraints = ConstraintTable::new(&trace, proof.trace_root(), options.transcript_fn(), &[1], &outputs, None, &custom, None, context.get_stack_ark(trace.unextended_length()));
+        let (tree, _) = super::commit_composition(&trace, None, constraints, &mut context, &options);
+        assert_eq!(proof.constraint_root(), tree.root());
+    }
+
+    #[test]
+    fn composition_coefficients() {
+        let program = assembly::compile("begin push.3 add dup mul push.7 hash.2 read.a mul end").unwrap();
+        let inputs = ProgramInputs::new(&[1], &[5], &[]);
+        let options = ProofOptions::default();
+
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &inputs);
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+        let outputs = trace.get_last_state().user_stack()[..1].to_vec();
+        let custom = CustomConstraints::new();
+        let coefficients = super::composition_coefficients(&mut trace, &[1], &outputs, &custom, &options, &mut ProverContext::new());
+
+        // the polynomial does not exceed the degree bound
+        let max_degree = super::max_constraint_degree(trace.unextended_length());
+        assert!(polynom::degree_of(&coefficients) <= max_degree);
+
+        // and it is the polynomial committed to in the proof
+        let (trace, _, _) = processor::execute(&program, &inputs);
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+        let mut context = ProverContext::new();
+        let proof = super::prove(&mut trace, &[1], &outputs, &custom, &options, &mut context).unwrap();
+
+        let domain = context.get_lde_domain(trace.unextended_length(), trace.extension_factor());
+        let evaluations = ConstraintPoly::new(coefficients).eval(&domain.twiddles);
+        let tree = MerkleTree::new(super::evaluations_to_leaves(evaluations), options.hash_fn());
+        assert_eq!(proof.constraint_root(), tree.root());
+    }
+
+    #[test]
+    fn lde_domain() {
+        let program = assembly::compile("begin push.3 add dup mul push.7 hash.2 end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let options = ProofOptions::default();
+
+        let (trace, ctx_depth, loop_depth) = processor::execute(&program, &inputs);
+        let mut trace = TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+        let outputs = trace.get_last_state().user_stack()[..1].to_vec();
+        let mut context = ProverContext::new();
+        super::prove(&mut trace, &[1], &outputs, &CustomConstraints::new(), &options, &mut context).unwrap();
+
+        // the domain is the one used by the prover
+        let domain = super::lde_domain(trace.unextended_length(), trace.extension_factor());
+        assert_eq!(context.get_lde_domain(trace.unextended_length(), trace.extension_factor()).values, domain);
+        let g = super::lde_domain_generator(trace.unextended_length(), trace.extension_factor());
+        let offset = super::lde_domain_offset();
+        assert_eq!(offset, domain[0]);
+        assert_eq!(field::mul(g, offset), domain[1]);
+
+        // trace polynomials evaluated at domain elements are values of the extended trace
+        let positions: Vec<usize> = (0..domain.len()).step_by(37).collect();
+        let values = trace.get_register_values_at(&positions);
+        for (&i, row) in positions.iter().zip(values.iter()) {
+            for (j, &value) in row.iter().enumerate() {
+                assert_eq!(value, polynom::eval(trace.get_register_poly(j), domain[i]));
+            }
+        }
+    }
+
+    #[test]
+    fn lde_domain_disjoint_from_trace_domain() {
+        let program = assembly::compile("begin push.3 add dup mul push.7 hash.2 end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1]);
+        let options = ProofOptions::default();
+
+        let (registers, ctx_depth, loop_depth) = processor::execute(&program, &inputs);
+        let mut trace = TraceTable::new(registers, ctx_depth, loop_depth, options.extension_factor());
+        let outputs = trace.get_last_state().user_stack()[..1].to_vec();
+        let proof = super::prove(&mut trace, &[1], &outputs, &CustomConstraints::new(), &options, &mut ProverContext::new()).unwrap();
+        assert_eq!(Ok(true), crate::verify(program.hash(), &[1], &outputs, &proof));
+
+        // the LDE domain is disjoint from the trace domain
+        let trace_domain = field::get_power_series(field::get_root_of_unity(trace.unextended_length()), trace.unextended_length());
+        let domain = super::lde_domain(trace.unextended_length(), trace.extension_factor());
+        assert!(domain.iter().all(|x| !trace_domain.contains(x)));
+    }
+
+    #[test]
+    fn estimate_time() {
+        // grinding time does not depend on trace length, so it is excluded
+        let options = ProofOptions::new(32, 48, 0, blake3);
+        let throughput = 1e8;
+
+        // the estimate grows with trace length roughly linearly
+        let mut previous = super::estimate_time(16, 20, &options, throughput);
+        for trace_length in [32, 64, 128, 256, 512, 1024].iter() {
+            let estimate = super::estimate_time(*trace_length, 20, &options, throughput);
+            let ratio = estimate.as_secs_f64() / previous.as_secs_f64();
+            assert!(ratio > 1.9 && ratio < 2.3, "unexpected ratio {} for trace length {}", ratio, trace_length);
+            previous = estimate;
+        }
+
+        // and so does the estimate with the number of registers
+        let estimate1 = super::estimate_time(1024, 20, &options, throughput);
+        let estimate2 = super::estimate_time(1024, 40, &options, throughput);
+        assert!(estimate2 > estimate1 && estimate2 < estimate1 * 2);
+    }
+
+    #[test]
+    fn mul_throughput() {
+        // the throughput is measured once, and is always a finite positive number
+        let throughput = super::get_mul_throughput();
+        assert!(throughput.is_finite() && throughput > 0.0);
+        assert_eq!(throughput, super::get_mul_throughput());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::math::polynom;
+use crate::crypto::{ MerkleTree, HashFunction };
+use super::{ LdeStep, trace_table::{ extend_registers, hash_registers } };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Auxiliary trace registers built after the execution trace has been committed to; the
+/// registers are extended and committed to separately from the registers of the execution trace.
+pub struct AuxTable {
+    registers       : Vec<Vec<u128>>,
+    polys           : Vec<Vec<u128>>,
+    trace_length    : usize,
+    extension_factor: usize,
+}
+
+// AUX TABLE IMPLEMENTATION
+// ================================================================================================
+impl AuxTable {
+
+    /// Returns a table constructed from the specified auxiliary register traces; every register
+    /// must have `trace_length` values.
+    pub fn new(registers: Vec<Vec<u128>>, trace_length: usize, extension_factor: usize) -> AuxTable {
+        assert!(registers.len() > 0, "auxiliary trace must consist of at least one register");
+        for register in registers.iter() {
+            assert!(register.len() == trace_length,
+                "auxiliary registers must have {} values, but a register had {} values", trace_length, register.len());
+        }
+
+        let polys = Vec::with_capacity(registers.len());
+        return AuxTable { registers, polys, trace_length, extension_factor };
+    }
+
+    /// Returns the number of auxiliary registers.
+    pub fn width(&self) -> usize {
+        return self.registers.len();
+    }
+
+    /// Returns the number of steps in the extended table.
+    pub fn domain_size(&self) -> usize {
+        return self.trace_length * self.extension_factor;
+    }
+
+    /// Returns `true` if the table has been extended.
+    pub fn is_extended(&self) -> bool {
+        return self.registers[0].len() > self.trace_length;
+    }
+
+    /// Extends all registers of the table to the LDE domain in the same way as registers of
+    /// the execution trace are extended.
+    pub fn extend(&mut self, twiddles: &[u128], inv_twiddles: &[u128], num_threads: usize) {
+        assert!(!self.is_extended(), "auxiliary table has already been extended");
+        assert!(twiddles.len() * 2 == self.domain_size(), "invalid number of twiddles");
+        assert!(inv_twiddles.len() * 2 == self.trace_length, "invalid number of inverse twiddles");
+        assert!(num_threads > 0, "number of threads must be greater than 0");
+
+        let domain_size = self.domain_size();
+        extend_registers(&mut self.registers, &mut self.polys, domain_size, twiddles, inv_twiddles, num_threads);
+    }
+
+    /// Puts the extended table into a Merkle tree such that values of all registers at a given
+    /// step of the LDE domain become a single leaf.
+    pub fn build_merkle_tree(&self, hash: HashFunction, num_threads: usize) -> MerkleTree {
+        debug_assert!(self.is_extended(), "auxiliary table has not been extended yet");
+        return hash_registers(&self.registers, hash, num_threads);
+    }
+
+    /// Copies values of all registers at the specified `step` of the LDE domain into `row`.
+    pub fn fill_row(&self, row: &mut [u128], step: LdeStep) {
+        debug_assert!(self.is_extended(), "auxiliary table has not been extended yet");
+        for (value, register) in row.iter_mut().zip(self.registers.iter()) {
+            *value = register[step.0];
+        }
+    }
+
+    /// Returns values of all registers at the specified `positions`.
+    pub fn get_register_values_at(&self, positions: &[usize]) -> Vec<Vec<u128>> {
+        return positions.iter().map(|&i| self.registers.iter().map(|r| r[i]).collect()).collect();
+    }
+
+    /// Evaluates register polynomials at the specified point `z`.
+    pub fn eval_polys_at(&self, z: u128) -> Vec<u128> {
+        assert!(self.is_extended(), "auxiliary table has not been extended yet");
+        return self.polys.iter().map(|poly| polynom::eval(poly, z)).collect();
+    }
+
+    /// Returns polynomials of all registers; can be called only after the table has been extended.
+    pub fn polys(&self) -> &[Vec<u128>] {
+        return &self.polys;
+    }
+}
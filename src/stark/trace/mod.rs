@@ -1,5 +1,9 @@
 mod trace_state;
 mod trace_table;
+mod aux_table;
+mod trace_step;
 
-pub use trace_state::TraceState;
-pub use trace_table::TraceTable;
\ No newline at end of file
+pub use trace_state::{ TraceState, OP_FLAGS_MUL_COUNT };
+pub use trace_table::TraceTable;
+pub use aux_table::AuxTable;
+pub use trace_step::{ TraceStep, LdeStep, EvalStep };
\ No newline at end of file
@@ -1,4 +1,4 @@
-use std::{ fmt, cmp };
+use std::{ fmt, cmp, cell::Cell };
 use crate::{
     math::field,
     OpCode,
@@ -15,6 +15,11 @@ use crate::{
 const NUM_OP_BITS: usize = NUM_CF_OP_BITS + NUM_LD_OP_BITS + NUM_HD_OP_BITS;
 const NUM_STATIC_DECODER_REGISTERS: usize = 1 + SPONGE_WIDTH + NUM_OP_BITS; // 1 is for op_counter
 
+/// Number of multiplications needed to compute operation flags of a single state: 12 for
+/// control flow flags, 60 for low-degree flags, 4 for high-degree flags, 2 for BEGIN and NOOP
+/// flags, and 2 for adjusting PUSH and ASSERT flags.
+pub const OP_FLAGS_MUL_COUNT: u64 = 80;
+
 // TYPES AND INTERFACES
 // ================================================================================================
 #[derive(PartialEq)]
@@ -32,12 +37,13 @@ pub struct TraceState {
     loop_depth  : usize,
     stack_depth : usize,
 
-    cf_op_flags : [u128; NUM_CF_OPS],
-    ld_op_flags : [u128; NUM_LD_OPS],
-    hd_op_flags : [u128; NUM_HD_OPS],
-    begin_flag  : u128,
-    noop_flag   : u128,
-    op_flags_set: bool,
+    // operation flags are computed lazily from op bits when they are first accessed
+    cf_op_flags : Cell<[u128; NUM_CF_OPS]>,
+    ld_op_flags : Cell<[u128; NUM_LD_OPS]>,
+    hd_op_flags : Cell<[u128; NUM_HD_OPS]>,
+    begin_flag  : Cell<u128>,
+    noop_flag   : Cell<u128>,
+    op_flags_set: Cell<bool>,
 }
 
 // TRACE STATE IMPLEMENTATION
@@ -61,12 +67,12 @@ impl TraceState {
             ctx_depth   : ctx_depth,
             loop_depth  : loop_depth,
             stack_depth : stack_depth,
-            cf_op_flags : [0; NUM_CF_OPS],
-            ld_op_flags : [0; NUM_LD_OPS],
-            hd_op_flags : [0; NUM_HD_OPS],
-            begin_flag  : 0,
-            noop_flag   : 0,
-            op_flags_set: false,
+            cf_op_flags : Cell::new([0; NUM_CF_OPS]),
+            ld_op_flags : Cell::new([0; NUM_LD_OPS]),
+            hd_op_flags : Cell::new([0; NUM_HD_OPS]),
+            begin_flag  : Cell::new(0),
+            noop_flag   : Cell::new(0),
+            op_flags_set: Cell::new(false),
         };
     }
 
@@ -102,12 +108,12 @@ impl TraceState {
             cf_op_bits, ld_op_bits, hd_op_bits,
             ctx_stack, loop_stack, user_stack,
             ctx_depth, loop_depth, stack_depth,
-            cf_op_flags : [0; NUM_CF_OPS],
-            ld_op_flags : [0; NUM_LD_OPS],
-            hd_op_flags : [0; NUM_HD_OPS],
-            begin_flag  : 0,
-            noop_flag   : 0,
-            op_flags_set: false,
+            cf_op_flags : Cell::new([0; NUM_CF_OPS]),
+            ld_op_flags : Cell::new([0; NUM_LD_OPS]),
+            hd_op_flags : Cell::new([0; NUM_HD_OPS]),
+            begin_flag  : Cell::new(0),
+            noop_flag   : Cell::new(0),
+            op_flags_set: Cell::new(false),
         };
     }
 
@@ -177,46 +183,34 @@ impl TraceState {
         self.cf_op_bits.copy_from_slice(&bits[..3]);
         self.ld_op_bits.copy_from_slice(&bits[3..8]);
         self.hd_op_bits.copy_from_slice(&bits[8..]);
+        self.op_flags_set.set(false);
     }
 
     // OP FLAGS
     // --------------------------------------------------------------------------------------------
     pub fn cf_op_flags(&self) -> [u128; NUM_CF_OPS] {
-        if !self.op_flags_set {
-            unsafe {
-                let mutable_self = &mut *(self as *const _ as *mut TraceState);
-                mutable_self.set_op_flags();
-            }
-        }
-        return self.cf_op_flags;
+        self.set_op_flags();
+        return self.cf_op_flags.get();
     }
 
     pub fn ld_op_flags(&self) -> [u128; NUM_LD_OPS] {
-        if !self.op_flags_set {
-            unsafe {
-                let mutable_self = &mut *(self as *const _ as *mut TraceState);
-                mutable_self.set_op_flags();
-            }
-        }
-        return self.ld_op_flags;
+        self.set_op_flags();
+        return self.ld_op_flags.get();
     }
 
     pub fn hd_op_flags(&self) -> [u128; NUM_HD_OPS] {
-        if !self.op_flags_set {
-            unsafe {
-                let mutable_self = &mut *(self as *const _ as *mut TraceState);
-                mutable_self.set_op_flags();
-            }
-        }
-        return self.hd_op_flags;
+        self.set_op_flags();
+        return self.hd_op_flags.get();
     }
 
     pub fn begin_flag(&self) -> u128 {
-        return self.begin_flag;
+        self.set_op_flags();
+        return self.begin_flag.get();
     }
 
     pub fn noop_flag(&self) -> u128 {
-        return self.noop_flag;
+        self.set_op_flags();
+        return self.noop_flag.get();
     }
 
     // STACKS
@@ -273,80 +267,92 @@ impl TraceState {
             self.user_stack[i] = trace[j][step];
         }
         
-        self.op_flags_set = false;
+        self.op_flags_set.set(false);
     }
 
     // HELPER METHODS
     // --------------------------------------------------------------------------------------------
-    fn set_op_flags(&mut self) {
+    /// Computes operation flags from op bits of the state, unless they have already been
+    /// computed since op bits were last updated.
+    fn set_op_flags(&self) {
+        if self.op_flags_set.get() { return; }
+
+        let mut cf_op_flags = [field::ZERO; NUM_CF_OPS];
+        let mut ld_op_flags = [field::ZERO; NUM_LD_OPS];
+        let mut hd_op_flags = [field::ZERO; NUM_HD_OPS];
 
         // set control flow flags
         let not_0 = binary_not(self.cf_op_bits[0]);
         let not_1 = binary_not(self.cf_op_bits[1]);
-        self.cf_op_flags[0] = field::mul(not_0, not_1);
-        self.cf_op_flags[1] = field::mul(self.cf_op_bits[0], not_1);
-        self.cf_op_flags[2] = field::mul(not_0, self.cf_op_bits[1]);
-        self.cf_op_flags[3] = field::mul(self.cf_op_bits[0], self.cf_op_bits[1]);
-        self.cf_op_flags.copy_within(0..4, 4);
+        cf_op_flags[0] = field::mul(not_0, not_1);
+        cf_op_flags[1] = field::mul(self.cf_op_bits[0], not_1);
+        cf_op_flags[2] = field::mul(not_0, self.cf_op_bits[1]);
+        cf_op_flags[3] = field::mul(self.cf_op_bits[0], self.cf_op_bits[1]);
+        cf_op_flags.copy_within(0..4, 4);
 
         let not_2 = binary_not(self.cf_op_bits[2]);
-        for i in 0..4 { self.cf_op_flags[i] = field::mul(self.cf_op_flags[i], not_2); }
-        for i in 4..8 { self.cf_op_flags[i] = field::mul(self.cf_op_flags[i], self.cf_op_bits[2]); }
+        for i in 0..4 { cf_op_flags[i] = field::mul(cf_op_flags[i], not_2); }
+        for i in 4..8 { cf_op_flags[i] = field::mul(cf_op_flags[i], self.cf_op_bits[2]); }
 
         // set low-degree operation flags
         let not_0 = binary_not(self.ld_op_bits[0]);
         let not_1 = binary_not(self.ld_op_bits[1]);
-        self.ld_op_flags[0] = field::mul(not_0, not_1);
-        self.ld_op_flags[1] = field::mul(self.ld_op_bits[0], not_1);
-        self.ld_op_flags[2] = field::mul(not_0, self.cf_op_bits[1]);
-        self.ld_op_flags[3] = field::mul(self.ld_op_bits[0], self.ld_op_bits[1]);
-        self.ld_op_flags.copy_within(0..4, 4);
+        ld_op_flags[0] = field::mul(not_0, not_1);
+        ld_op_flags[1] = field::mul(self.ld_op_bits[0], not_1);
+        ld_op_flags[2] = field::mul(not_0, self.cf_op_bits[1]);
+        ld_op_flags[3] = field::mul(self.ld_op_bits[0], self.ld_op_bits[1]);
+        ld_op_flags.copy_within(0..4, 4);
 
         let not_2 = binary_not(self.ld_op_bits[2]);
-        for i in 0..4 { self.ld_op_flags[i] = field::mul(self.ld_op_flags[i], not_2); }
-        for i in 4..8 { self.ld_op_flags[i] = field::mul(self.ld_op_flags[i], self.ld_op_bits[2]); }
-        self.ld_op_flags.copy_within(0..8, 8);
+        for i in 0..4 { ld_op_flags[i] = field::mul(ld_op_flags[i], not_2); }
+        for i in 4..8 { ld_op_flags[i] = field::mul(ld_op_flags[i], self.ld_op_bits[2]); }
+        ld_op_flags.copy_within(0..8, 8);
 
         let not_3 = binary_not(self.ld_op_bits[3]);
-        for i in 0..8  { self.ld_op_flags[i] = field::mul(self.ld_op_flags[i], not_3); }
-        for i in 8..16 { self.ld_op_flags[i] = field::mul(self.ld_op_flags[i], self.ld_op_bits[3]); }
-        self.ld_op_flags.copy_within(0..16, 16);
+        for i in 0..8  { ld_op_flags[i] = field::mul(ld_op_flags[i], not_3); }
+        for i in 8..16 { ld_op_flags[i] = field::mul(ld_op_flags[i], self.ld_op_bits[3]); }
+        ld_op_flags.copy_within(0..16, 16);
 
         let not_4 = binary_not(self.ld_op_bits[4]);
-        for i in 0..16  { self.ld_op_flags[i] = field::mul(self.ld_op_flags[i], not_4); }
-        for i in 16..32 { self.ld_op_flags[i] = field::mul(self.ld_op_flags[i], self.ld_op_bits[4]); }
+        for i in 0..16  { ld_op_flags[i] = field::mul(ld_op_flags[i], not_4); }
+        for i in 16..32 { ld_op_flags[i] = field::mul(ld_op_flags[i], self.ld_op_bits[4]); }
 
         // set high-degree operation flags
         let not_0 = binary_not(self.hd_op_bits[0]);
         let not_1 = binary_not(self.hd_op_bits[1]);
-        self.hd_op_flags[0] = field::mul(not_0, not_1);
-        self.hd_op_flags[1] = field::mul(self.hd_op_bits[0], not_1);
-        self.hd_op_flags[2] = field::mul(not_0, self.hd_op_bits[1]);
-        self.hd_op_flags[3] = field::mul(self.hd_op_bits[0], self.hd_op_bits[1]);
+        hd_op_flags[0] = field::mul(not_0, not_1);
+        hd_op_flags[1] = field::mul(self.hd_op_bits[0], not_1);
+        hd_op_flags[2] = field::mul(not_0, self.hd_op_bits[1]);
+        hd_op_flags[3] = field::mul(self.hd_op_bits[0], self.hd_op_bits[1]);
 
         // compute flag for BEGIN operation which is just 0000000; the below is equivalent
         // to multiplying binary inverses of all op bits together.
-        self.begin_flag = field::mul(
-            self.ld_op_flags[OpCode::Begin.ld_index()], 
-            self.hd_op_flags[OpCode::Begin.hd_index()]);
+        let begin_flag = field::mul(
+            ld_op_flags[OpCode::Begin.ld_index()], 
+            hd_op_flags[OpCode::Begin.hd_index()]);
 
         // compute flag for NOOP operation which is just 1111111; the below is equivalent to
         // multiplying all op bits together.
-        self.noop_flag = field::mul(
-            self.ld_op_flags[OpCode::Noop.ld_index()], 
-            self.hd_op_flags[OpCode::Noop.hd_index()]);
+        let noop_flag = field::mul(
+            ld_op_flags[OpCode::Noop.ld_index()], 
+            hd_op_flags[OpCode::Noop.hd_index()]);
 
         // we need to make special adjustments for PUSH and ASSERT op flags so that they
         // don't coincide with BEGIN operation; we do this by multiplying each flag by a
         // single op_bit from another op bank; this increases degree of each flag by 1
         debug_assert!(OpCode::Push.hd_index() == 0, "PUSH index is not 0!");
-        self.hd_op_flags[0] = field::mul(self.hd_op_flags[0], self.ld_op_bits[0]);
+        hd_op_flags[0] = field::mul(hd_op_flags[0], self.ld_op_bits[0]);
 
         debug_assert!(OpCode::Assert.ld_index() == 0, "ASSERT index is not 0!");
-        self.ld_op_flags[0] = field::mul(self.ld_op_flags[0], self.hd_op_bits[0]);
-
-        // mark flags as set
-        self.op_flags_set = true;
+        ld_op_flags[0] = field::mul(ld_op_flags[0], self.hd_op_bits[0]);
+
+        // save the flags and mark them as set
+        self.cf_op_flags.set(cf_op_flags);
+        self.ld_op_flags.set(ld_op_flags);
+        self.hd_op_flags.set(hd_op_flags);
+        self.begin_flag.set(begin_flag);
+        self.noop_flag.set(noop_flag);
+        self.op_flags_set.set(true);
     }
 }
 
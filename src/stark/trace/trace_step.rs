@@ -0,0 +1,71 @@
+use crate::stark::MAX_CONSTRAINT_DEGREE;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Index of a step in the execution trace domain; the domain contains one step per executed
+/// operation (padded to a power of 2).
+///
+/// Steps of different domains are distinct types, and thus, cannot be mixed up:
+///
+/// ```compile_fail,E0308
+/// use distaff::{ TraceStep, LdeStep };
+///
+/// fn read_lde_state(_step: LdeStep) { }
+/// read_lde_state(TraceStep(3));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TraceStep(pub usize);
+
+/// Index of a step in the low-degree extension domain; the domain is larger than the execution
+/// trace domain by the extension factor specified in proof options, and trace step i maps to
+/// LDE step i * extension_factor. Since the LDE domain is shifted off the trace domain, the x
+/// coordinate of the LDE step is the x coordinate of the trace step multiplied by the offset.
+///
+/// ```
+/// use distaff::{ TraceStep, LdeStep, EvalStep };
+///
+/// let step = TraceStep(3).to_lde(32);
+/// assert_eq!(LdeStep(96), step);
+/// assert_eq!(EvalStep(24), step.to_eval(32));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LdeStep(pub usize);
+
+/// Index of a step in the constraint evaluation domain; the domain is larger than the execution
+/// trace domain by MAX_CONSTRAINT_DEGREE, and is a subset of the low-degree extension domain.
+///
+/// ```compile_fail,E0308
+/// use distaff::{ LdeStep, EvalStep };
+///
+/// fn evaluate_constraints(_step: EvalStep) { }
+/// evaluate_constraints(LdeStep(64));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EvalStep(pub usize);
+
+// STEP IMPLEMENTATIONS
+// ================================================================================================
+impl TraceStep {
+
+    /// Returns the step of the low-degree extension domain which corresponds to this step.
+    pub fn to_lde(self, extension_factor: usize) -> LdeStep {
+        return LdeStep(self.0 * extension_factor);
+    }
+
+    /// Returns the step of the constraint evaluation domain which corresponds to this step.
+    pub fn to_eval(self) -> EvalStep {
+        return EvalStep(self.0 * MAX_CONSTRAINT_DEGREE);
+    }
+}
+
+impl LdeStep {
+
+    /// Returns the step of the constraint evaluation domain which corresponds to this step;
+    /// panics if this step is not in the constraint evaluation domain.
+    pub fn to_eval(self, extension_factor: usize) -> EvalStep {
+        let stride = extension_factor / MAX_CONSTRAINT_DEGREE;
+        assert!(self.0 % stride == 0, "LDE step {} is not in the constraint evaluation domain", self.0);
+        return EvalStep(self.0 / stride);
+    }
+}
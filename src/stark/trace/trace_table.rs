@@ -1,14 +1,21 @@
-use crate::math::{ field, fft, polynom, parallel };
+use std::cmp;
+use crossbeam_utils::thread;
+use serde::{ Serialize, Deserialize };
+use crate::math::{ field, polynom, parallel };
 use crate::crypto::{ MerkleTree, HashFunction };
-use crate::stark::{ CompositionCoefficients, utils };
+use crate::stark::{ CompositionCoefficients, OutputTape, utils, DOMAIN_OFFSET };
+use crate::processor::OpCode;
 use crate::utils::{ uninit_vector, filled_vector, as_bytes };
-use super::{ TraceState };
+use crate::MAX_REGISTER_COUNT;
+use super::{ TraceState, TraceStep, LdeStep, AuxTable };
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Serialize, Deserialize)]
 pub struct TraceTable {
     registers       : Vec<Vec<u128>>,
     polys           : Vec<Vec<u128>>,
+    trace           : Vec<Vec<u128>>,   // un-extended registers; retained once the table is extended
     ctx_depth       : usize,
     loop_depth      : usize,
     stack_depth     : usize,
@@ -52,32 +59,47 @@ impl TraceTable {
         let polys = Vec::with_capacity(registers.len());
         return TraceTable {
             registers, polys,
+            trace: Vec::new(),
             ctx_depth, loop_depth, stack_depth,
             trace_length, extension_factor
         };
     }
 
-    /// Returns state of the trace table at the specified `step`.
-    pub fn get_state(&self, step: usize) -> TraceState {
+    /// Returns state of the trace table at the specified `step`; the LDE domain does not
+    /// contain steps of the execution trace, and thus, once the trace table has been extended,
+    /// the state is read from a copy of the un-extended registers.
+    pub fn get_state(&self, step: TraceStep) -> TraceState {
+        let registers = if self.is_extended() { &self.trace } else { &self.registers };
         let mut result = TraceState::new(self.ctx_depth, self.loop_depth, self.stack_depth);
-        self.fill_state(&mut result, step);
+        result.update_from_trace(registers, step.0);
         return result;
     }
 
     /// Returns state of the trace table at the last step.
     pub fn get_last_state(&self) -> TraceState {
-        let last_step = if self.is_extended() {
-            self.domain_size() - self.extension_factor()
-        }
-        else {
-            self.unextended_length() - 1
-        };
-        return self.get_state(last_step);
+        return self.get_state(TraceStep(self.unextended_length() - 1));
+    }
+
+    /// Copies state of the extended trace table at the specified `step` of the LDE domain to
+    /// the passed in `state` object.
+    pub fn fill_state(&self, state: &mut TraceState, step: LdeStep) {
+        debug_assert!(self.is_extended(), "trace table has not been extended yet");
+        state.update_from_trace(&self.registers, step.0);
     }
 
-    /// Copies trace table state at the specified `step` to the passed in `state` object.
-    pub fn fill_state(&self, state: &mut TraceState, step: usize) {
-        state.update_from_trace(&self.registers, step);
+    /// Returns values written to the output tape by WRITE operations; a value written at step
+    /// i is the value at the top of the stack at step i.
+    pub fn get_output_tape(&self) -> OutputTape {
+        let write_op = OpCode::Write.to_u8() as u128;
+        let mut tape = OutputTape { steps: Vec::new(), values: Vec::new() };
+        for step in 0..(self.trace_length - 1) {
+            let state = self.get_state(TraceStep(step));
+            if state.cf_op_bits().iter().all(|&bit| bit == field::ZERO) && state.op_code() == write_op {
+                tape.steps.push(step as u32);
+                tape.values.push(state.user_stack()[0]);
+            }
+        }
+        return tape;
     }
 
     /// Returns the number of states in the un-extended trace table.
@@ -139,49 +161,28 @@ impl TraceTable {
     }
 
     /// Extends all registers of the trace table by the `extension_factor` specified during
-    /// trace table construction. A trace table can be extended only once.
-    pub fn extend(&mut self, twiddles: &[u128]) {
+    /// trace table construction. A trace table can be extended only once. `twiddles` are used
+    /// for FFT evaluation over the LDE domain, and `inv_twiddles` are used for FFT interpolation
+    /// over the un-extended trace domain. Registers are distributed across `num_threads`
+    /// threads; each register is extended independently, so the result does not depend on
+    /// the number of threads.
+    pub fn extend(&mut self, twiddles: &[u128], inv_twiddles: &[u128], num_threads: usize) {
         assert!(!self.is_extended(), "trace table has already been extended");
         assert!(twiddles.len() * 2 == self.domain_size(), "invalid number of twiddles");
+        assert!(inv_twiddles.len() * 2 == self.unextended_length(), "invalid number of inverse twiddles");
+        assert!(num_threads > 0, "number of threads must be greater than 0");
 
-        // build inverse twiddles needed for FFT interpolation
-        let root = field::get_root_of_unity(self.unextended_length());
-        let inv_twiddles = fft::get_inv_twiddles(root, self.unextended_length());
-        
-        // move register traces into polys
-        std::mem::swap(&mut self.registers, &mut self.polys);
-
-        // extend all registers
         let domain_size = self.domain_size();
-        for poly in self.polys.iter_mut() {
-
-            // interpolate register trace into a polynomial
-            polynom::interpolate_fft_twiddles(poly, &inv_twiddles, true);
-            
-            // allocate space to hold extended evaluations and copy the polynomial into it
-            let mut register = vec![field::ZERO; domain_size];
-            register[..poly.len()].copy_from_slice(&poly);
-            
-            // evaluate the polynomial over extended domain
-            polynom::eval_fft_twiddles(&mut register, &twiddles, true);
-            self.registers.push(register);
-        }
+        self.trace = self.registers.clone();
+        extend_registers(&mut self.registers, &mut self.polys, domain_size, twiddles, inv_twiddles, num_threads);
     }
 
     /// Puts the trace table into a Merkle tree such that each state of the table becomes
     /// a distinct leaf in the tree; all registers at a given step are hashed together to
-    /// form a single leaf value.
-    pub fn build_merkle_tree(&self, hash: HashFunction) -> MerkleTree {
-        let mut trace_state = vec![field::ZERO; self.register_count()];
-        let mut hashed_states = uninit_vector::<[u8; 32]>(self.domain_size());
-        // TODO: this loop should be parallelized
-        for i in 0..self.domain_size() {
-            for j in 0..trace_state.len() {
-                trace_state[j] = self.registers[j][i];
-            }
-            hash(as_bytes(&trace_state), &mut hashed_states[i]);
-        }
-        return MerkleTree::new(hashed_states, hash);
+    /// form a single leaf value. Steps are hashed in `num_threads` contiguous batches, and
+    /// thus, the tree does not depend on the number of threads.
+    pub fn build_merkle_tree(&self, hash: HashFunction, num_threads: usize) -> MerkleTree {
+        return hash_registers(&self.registers, hash, num_threads);
     }
 
     /// Evaluates trace polynomials at the specified point `z`; can be called only after
@@ -198,12 +199,15 @@ impl TraceTable {
 
     /// Combines trace polynomials for all registers into a single composition polynomial.
     /// The combination is done as follows:
-    /// 1. First, state of trace registers at deep points z and z * g are computed;
-    /// 2. Then, polynomials T1_i(x) = (T_i(x) - T_i(z)) / (x - z) and 
+    /// 1. First, polynomials T1_i(x) = (T_i(x) - T_i(z)) / (x - z) and 
     /// T2_i(x) = (T_i(x) - T_i(z * g)) / (x - z * g) are computed for all i and combined
     /// together into a single polynomial using a pseudo-random linear combination;
-    /// 3. Then the degree of the polynomial is adjusted to match the specified degree
-    pub fn get_composition_poly(&self, z: u128, cc: &CompositionCoefficients) -> (Vec<u128>, Vec<u128>, Vec<u128>) {
+    /// 2. Then the degree of the polynomial is adjusted to match the specified degree.
+    /// 
+    /// `trace_state1` and `trace_state2` are the states of trace registers at deep points z
+    /// and z * g respectively; if `aux` is provided, it must contain auxiliary registers
+    /// together with their values at the same deep points.
+    pub fn get_composition_poly(&self, z: u128, trace_state1: &[u128], trace_state2: &[u128], aux: Option<(&AuxTable, &[u128], &[u128])>, cc: &CompositionCoefficients) -> Vec<u128> {
 
         let trace_length = self.unextended_length();
         assert!(self.is_extended(), "trace table has not been extended yet");
@@ -211,26 +215,17 @@ impl TraceTable {
         let g = field::get_root_of_unity(trace_length);
         let next_z = field::mul(z, g);
 
-        // compute state of registers at deep points z and z * g
-        let trace_state1 = self.eval_polys_at(z);
-        let trace_state2 = self.eval_polys_at(next_z);
-
         let mut t1_composition = vec![field::ZERO; trace_length];
         let mut t2_composition = vec![field::ZERO; trace_length];
 
         // combine trace polynomials into 2 composition polynomials T1(x) and T2(x)
-        for i in 0..self.polys.len() {
-            // compute T1(x) = (T(x) - T(z)), multiply it by a pseudo-random coefficient,
-            // and add the result into composition polynomial
-            parallel::mul_acc(&mut t1_composition, &self.polys[i], cc.trace1[i], 1);
-            let adjusted_tz = field::mul(trace_state1[i], cc.trace1[i]);
-            t1_composition[0] = field::sub(t1_composition[0], adjusted_tz);
-
-            // compute T2(x) = (T(x) - T(z * g)), multiply it by a pseudo-random
-            // coefficient, and add the result into composition polynomial
-            parallel::mul_acc(&mut t2_composition, &self.polys[i], cc.trace2[i], 1);
-            let adjusted_tz = field::mul(trace_state2[i], cc.trace2[i]);
-            t2_composition[0] = field::sub(t2_composition[0], adjusted_tz);
+        add_deep_terms(&mut t1_composition, &mut t2_composition, &self.polys,
+            trace_state1, trace_state2, &cc.trace1, &cc.trace2);
+
+        // auxiliary registers use coefficients which follow the coefficients of main registers
+        if let Some((aux, aux_state1, aux_state2)) = aux {
+            add_deep_terms(&mut t1_composition, &mut t2_composition, aux.polys(),
+                aux_state1, aux_state2, &cc.trace1[MAX_REGISTER_COUNT..], &cc.trace2[MAX_REGISTER_COUNT..]);
         }
 
         // divide the two composition polynomials by (x - z) and (x - z * g)
@@ -257,7 +252,86 @@ impl TraceTable {
             cc.t2_degree,
             1);
         
-        return (composition_poly, trace_state1, trace_state2);
+        return composition_poly;
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Interpolates `registers` into polynomials, moves the polynomials into `polys`, and replaces
+/// `registers` with evaluations of the polynomials over the LDE domain of `domain_size` elements;
+/// the LDE domain is a coset of the subgroup defined by `twiddles` shifted by DOMAIN_OFFSET.
+pub fn extend_registers(registers: &mut Vec<Vec<u128>>, polys: &mut Vec<Vec<u128>>, domain_size: usize, twiddles: &[u128], inv_twiddles: &[u128], num_threads: usize) {
+    // move register traces into polys
+    std::mem::swap(registers, polys);
+    *registers = vec![Vec::new(); polys.len()];
+
+    // extend batches of registers in separate threads; there is no need for more threads
+    // than there are registers
+    let num_threads = cmp::max(1, cmp::min(num_threads, polys.len()));
+    let batch_size = (polys.len() + num_threads - 1) / num_threads;
+    let batches = polys.chunks_mut(batch_size).zip(registers.chunks_mut(batch_size));
+    thread::scope(|s| {
+        for (polys, registers) in batches {
+            s.spawn(move |_| {
+                for (poly, register) in polys.iter_mut().zip(registers.iter_mut()) {
+
+                    // interpolate register trace into a polynomial
+                    polynom::interpolate_fft_twiddles(poly, inv_twiddles, true);
+
+                    // allocate space to hold extended evaluations and copy the polynomial into it
+                    *register = vec![field::ZERO; domain_size];
+                    register[..poly.len()].copy_from_slice(&poly);
+
+                    // evaluate the polynomial over extended domain
+                    polynom::scale_argument(&mut register[..poly.len()], DOMAIN_OFFSET);
+                    polynom::eval_fft_twiddles(register, &twiddles, true);
+                }
+            });
+        }
+    }).unwrap();
+}
+
+/// Builds a Merkle tree from extended `registers` such that values of all registers at a given
+/// step are hashed together to form a single leaf.
+pub fn hash_registers(registers: &[Vec<u128>], hash: HashFunction, num_threads: usize) -> MerkleTree {
+    let domain_size = registers[0].len();
+
+    // there is no need for more threads than there are steps; both values are powers of 2,
+    // and thus, the domain splits into batches of equal size
+    let num_threads = cmp::min(num_threads, domain_size);
+    assert!(domain_size % num_threads == 0, "domain size must be divisible by number of threads");
+    let batch_size = domain_size / num_threads;
+
+    // hash batches of trace states in separate threads
+    let mut hashed_states = uninit_vector::<[u8; 32]>(domain_size);
+    thread::scope(|s| {
+        for (i, batch) in hashed_states.chunks_mut(batch_size).enumerate() {
+            s.spawn(move |_| {
+                let mut trace_state = vec![field::ZERO; registers.len()];
+                for (k, hashed_state) in batch.iter_mut().enumerate() {
+                    let step = i * batch_size + k;
+                    for j in 0..trace_state.len() {
+                        trace_state[j] = registers[j][step];
+                    }
+                    hash(as_bytes(&trace_state), hashed_state);
+                }
+            });
+        }
+    }).unwrap();
+    return MerkleTree::new(hashed_states, hash);
+}
+
+/// Computes T1(x) = (T(x) - T(z)) and T2(x) = (T(x) - T(z * g)) for all `polys`, multiplies them
+/// by pseudo-random coefficients `cc1` and `cc2`, and adds the results into `t1` and `t2`.
+fn add_deep_terms(t1: &mut [u128], t2: &mut [u128], polys: &[Vec<u128>], state1: &[u128], state2: &[u128], cc1: &[u128], cc2: &[u128]) {
+    for i in 0..polys.len() {
+        parallel::mul_acc(t1, &polys[i], cc1[i], 1);
+        t1[0] = field::sub(t1[0], field::mul(state1[i], cc1[i]));
+
+        parallel::mul_acc(t2, &polys[i], cc2[i], 1);
+        t2[0] = field::sub(t2[0], field::mul(state2[i], cc2[i]));
     }
 }
 
@@ -273,7 +347,7 @@ mod tests {
         crypto::hash::blake3,
         programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Group } },
         processor::{ execute, OpCode },
-        stark::{ TraceTable, CompositionCoefficients, utils::get_composition_degree }
+        stark::{ TraceTable, TraceStep, CompositionCoefficients, utils::{ get_composition_degree, draw_deep_point } }
     };
     
     const EXT_FACTOR: usize = 32;
@@ -282,36 +356,69 @@ mod tests {
     fn eval_polys_at() {
         let mut trace = build_trace_table();
         let lde_root = field::get_root_of_unity(trace.domain_size());
-        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()));
+        let trace_root = field::get_root_of_unity(trace.unextended_length());
+        trace.extend(
+            &fft::get_twiddles(lde_root, trace.domain_size()),
+            &fft::get_inv_twiddles(trace_root, trace.unextended_length()), 1);
 
         let g = field::get_root_of_unity(trace.unextended_length());
 
         let v1 = trace.eval_polys_at(g);
-        let s1 = trace.get_state(1 * EXT_FACTOR);
+        let s1 = trace.get_state(TraceStep(1));
         assert_eq!(v1, s1.to_vec());
 
         let v2 = trace.eval_polys_at(field::exp(g, 2));
-        let s2 = trace.get_state(2 * EXT_FACTOR);
+        let s2 = trace.get_state(TraceStep(2));
         assert_eq!(v2, s2.to_vec());
     }
 
+    #[test]
+    fn extend_with_excess_threads() {
+        let mut trace = build_trace_table();
+        let lde_root = field::get_root_of_unity(trace.domain_size());
+        let trace_root = field::get_root_of_unity(trace.unextended_length());
+        let twiddles = fft::get_twiddles(lde_root, trace.domain_size());
+        let inv_twiddles = fft::get_inv_twiddles(trace_root, trace.unextended_length());
+
+        // more threads than there are registers or steps in the extended trace
+        let num_threads = trace.domain_size() * 2;
+        assert!(num_threads > trace.register_count());
+
+        let mut expected = build_trace_table();
+        expected.extend(&twiddles, &inv_twiddles, 1);
+        trace.extend(&twiddles, &inv_twiddles, num_threads);
+        for i in 0..trace.register_count() {
+            assert_eq!(expected.get_register_poly(i), trace.get_register_poly(i));
+        }
+
+        let expected_tree = expected.build_merkle_tree(blake3, 1);
+        let tree = trace.build_merkle_tree(blake3, num_threads);
+        assert_eq!(expected_tree.root(), tree.root());
+    }
+
     #[test]
     fn get_composition_poly() {
 
         let mut trace = build_trace_table();
         let lde_root = field::get_root_of_unity(trace.domain_size());
-        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()));
+        let trace_root = field::get_root_of_unity(trace.unextended_length());
+        trace.extend(
+            &fft::get_twiddles(lde_root, trace.domain_size()),
+            &fft::get_inv_twiddles(trace_root, trace.unextended_length()), 1);
 
         // compute trace composition polynomial
-        let t_tree = trace.build_merkle_tree(blake3);
-        let z = field::prng(*t_tree.root());
-        let cc = CompositionCoefficients::new(*t_tree.root());
+        let t_tree = trace.build_merkle_tree(blake3, 1);
+        let z = draw_deep_point(t_tree.root(), blake3);
+        let cc = CompositionCoefficients::new(*t_tree.root(), blake3);
         let target_degree =  get_composition_degree(trace.unextended_length());
 
         let g = field::get_root_of_unity(trace.unextended_length());
         let zg = field::mul(z, g);
 
-        let (composition_poly, ..) = trace.get_composition_poly(z, &cc);
+        let tz = trace.eval_polys_at(z);
+        let tzg = trace.eval_polys_at(zg);
+
+        let composition_poly = trace.get_composition_poly(z, &tz, &tzg, None, &cc);
         let mut actual_evaluations = composition_poly.clone();
         polynom::eval_fft(&mut actual_evaluations, true);
         assert_eq!(target_degree, polynom::infer_degree(&actual_evaluations));
@@ -323,9 +430,6 @@ mod tests {
 
         let mut expected_evaluations = vec![0; domain_size];
 
-        let tz = trace.eval_polys_at(z);
-        let tzg = trace.eval_polys_at(zg);
-
         for i in 0..trace.register_count() {
             // add T1(x) to expected evaluations
             let mut trace_poly = trace.get_register_poly(i).to_vec();
@@ -1,5 +1,6 @@
 use std::{ ops::Range };
 use crate::{
+    crypto::HashFunction,
     math::field,
     utils::RangeSlider,
     MAX_REGISTER_COUNT, MAX_PUBLIC_INPUTS,
@@ -7,8 +8,9 @@ use crate::{
     MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_STACK_DEPTH,
     MIN_CONTEXT_DEPTH, MIN_LOOP_DEPTH, MIN_STACK_DEPTH,
     NUM_CF_OP_BITS, NUM_LD_OP_BITS, NUM_HD_OP_BITS,
-    stark::constraints::{ NUM_STATIC_DECODER_CONSTRAINTS, NUM_AUX_STACK_CONSTRAINTS },
+    stark::constraints::{ NUM_STATIC_DECODER_CONSTRAINTS, NUM_AUX_STACK_CONSTRAINTS, MAX_CUSTOM_CONSTRAINTS },
 };
+use super::draw_field_elements;
 
 // CONSTANTS
 // ================================================================================================
@@ -22,7 +24,7 @@ const NUM_BOUNDARY_CONSTRAINTS: usize =
     + MAX_LOOP_DEPTH
     + MAX_USER_STACK_IO_CONSTRAINTS;
 
-const NUM_TRANSITION_CONSTRAINTS: usize =
+const NUM_BUILTIN_TRANSITION_CONSTRAINTS: usize =
     0 
     + NUM_STATIC_DECODER_CONSTRAINTS
     + MAX_CONTEXT_DEPTH
@@ -30,16 +32,28 @@ const NUM_TRANSITION_CONSTRAINTS: usize =
     + MAX_STACK_DEPTH
     + NUM_AUX_STACK_CONSTRAINTS;
 
-const NUM_CONSTRAINTS: usize = NUM_TRANSITION_CONSTRAINTS + 2 * NUM_BOUNDARY_CONSTRAINTS;
+// coefficients for custom constraints come after coefficients for all built-in constraints so
+// that adding custom constraints does not affect coefficients of built-in constraints
+const NUM_TRANSITION_CONSTRAINTS: usize = NUM_BUILTIN_TRANSITION_CONSTRAINTS + MAX_CUSTOM_CONSTRAINTS;
+
+// output tape constraints: op_bits are set to WRITE, and the top of the stack is set to the
+// written value; coefficients for these come last so that they do not affect coefficients of
+// other constraints
+const NUM_TAPE_CONSTRAINTS: usize = NUM_OP_BITS + 1;
+
+const NUM_CONSTRAINTS: usize = NUM_TRANSITION_CONSTRAINTS + 2 * NUM_BOUNDARY_CONSTRAINTS + NUM_TAPE_CONSTRAINTS;
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Debug, Clone, PartialEq)]
 pub struct ConstraintCoefficients {
     pub i_boundary  : BoundaryCoefficients,
     pub f_boundary  : BoundaryCoefficients,
     pub transition  : Vec<u128>,
+    pub tape        : TapeCoefficients,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct BoundaryCoefficients {
     pub op_counter  : [u128; 2],
     pub sponge      : [u128; SPONGE_WIDTH * 2],
@@ -49,6 +63,13 @@ pub struct BoundaryCoefficients {
     pub user_stack  : [u128; MAX_USER_STACK_IO_CONSTRAINTS * 2],
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapeCoefficients {
+    pub op_bits     : [u128; NUM_OP_BITS * 2],
+    pub value       : [u128; 2],
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CompositionCoefficients {
     pub trace1      : [u128; 2 * MAX_REGISTER_COUNT],
     pub trace2      : [u128; 2 * MAX_REGISTER_COUNT],
@@ -60,28 +81,31 @@ pub struct CompositionCoefficients {
 // IMPLEMENTATIONS
 // ================================================================================================
 impl ConstraintCoefficients {
-    pub fn new(seed: [u8; 32], ctx_depth: usize, loop_depth: usize, stack_depth: usize) -> ConstraintCoefficients {
+    pub fn new(seed: [u8; 32], hash_fn: HashFunction, ctx_depth: usize, loop_depth: usize, stack_depth: usize, custom_num: usize) -> ConstraintCoefficients {
 
-        // generate a pseudo-random list of coefficients
-        let coefficients = field::prng_vector(seed, 2 * NUM_CONSTRAINTS);
+        // squeeze a list of coefficients from the seed
+        let coefficients = draw_field_elements(&seed, 2 * NUM_CONSTRAINTS, hash_fn);
 
         // copy coefficients for boundary constraints
         let (i_boundary, i) = build_boundary_coefficients(&coefficients);
         let (f_boundary, i) = build_boundary_coefficients(&coefficients[i..]);
 
         // copy coefficients for transition constraints
-        let transition = build_transition_coefficients(&coefficients[i..], ctx_depth, loop_depth, stack_depth);
+        let transition = build_transition_coefficients(&coefficients[i..], ctx_depth, loop_depth, stack_depth, custom_num);
+
+        // copy coefficients for output tape constraints
+        let tape = build_tape_coefficients(&coefficients[(2 * NUM_CONSTRAINTS - 2 * NUM_TAPE_CONSTRAINTS)..]);
 
-        return ConstraintCoefficients { i_boundary, f_boundary, transition };
+        return ConstraintCoefficients { i_boundary, f_boundary, transition, tape };
     }
 }
 
 impl CompositionCoefficients {
-    pub fn new(seed: [u8; 32]) -> CompositionCoefficients {
-        // generate a pseudo-random list of coefficients
-        let coefficients = field::prng_vector(seed, 1 + 4 * MAX_REGISTER_COUNT + 3);
+    pub fn new(seed: [u8; 32], hash_fn: HashFunction) -> CompositionCoefficients {
+        // squeeze a list of coefficients from the seed
+        let coefficients = draw_field_elements(&seed, 1 + 4 * MAX_REGISTER_COUNT + 3, hash_fn);
 
-        // skip the first value because it is used up by deep point z
+        // skip the first value because it is used up by deep point z; see draw_deep_point()
         let start_index = 1;
 
         // copy coefficients to their respective segments
@@ -137,7 +161,20 @@ fn build_boundary_coefficients(coefficients: &[u128]) -> (BoundaryCoefficients,
     return (result, range.end);
 }
 
-fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_depth: usize, stack_depth: usize) -> Vec<u128>{
+fn build_tape_coefficients(coefficients: &[u128]) -> TapeCoefficients
+{
+    let mut result = TapeCoefficients {
+        op_bits     : [0; NUM_OP_BITS * 2],
+        value       : [0; 2],
+    };
+
+    result.op_bits.copy_from_slice(&coefficients[..(NUM_OP_BITS * 2)]);
+    result.value.copy_from_slice(&coefficients[(NUM_OP_BITS * 2)..]);
+
+    return result;
+}
+
+fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_depth: usize, stack_depth: usize, custom_num: usize) -> Vec<u128>{
 
     let ctx_depth = std::cmp::max(ctx_depth, MIN_CONTEXT_DEPTH);
     let loop_depth = std::cmp::max(loop_depth, MIN_LOOP_DEPTH);
@@ -148,7 +185,8 @@ fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_d
         + ctx_depth
         + loop_depth
         + stack_depth
-        + NUM_AUX_STACK_CONSTRAINTS;
+        + NUM_AUX_STACK_CONSTRAINTS
+        + custom_num;
 
     // we need 2 coefficients per constraint
     let mut result = vec![0; num_constraints * 2];
@@ -160,6 +198,7 @@ fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_d
     // 3. loop stack constraints - the number depends on the actual loop depth
     // 4. aux stack constraints
     // 5. user stack constraints - the number depends on the actual stack depth
+    // 6. custom constraints - the number depends on the number of registered constraints
 
     let mut s_range = new_range(0, NUM_STATIC_DECODER_CONSTRAINTS * 2);
     let mut t_range = new_range(0, NUM_STATIC_DECODER_CONSTRAINTS * 2);
@@ -181,6 +220,10 @@ fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_d
     t_range = t_range.slide(stack_depth * 2);
     result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
 
+    s_range = new_range(NUM_BUILTIN_TRANSITION_CONSTRAINTS * 2, custom_num * 2);
+    t_range = t_range.slide(custom_num * 2);
+    result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
+
     return result;
 }
 
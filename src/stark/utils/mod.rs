@@ -1,5 +1,6 @@
 use rand::prelude::*;
 use rand::distributions::Uniform;
+use crate::{ crypto::HashFunction, HASH_DIGEST_SIZE };
 use super::{ ProofOptions, MAX_CONSTRAINT_DEGREE };
 
 // RE-EXPORTS
@@ -10,6 +11,9 @@ pub use coefficients::{ ConstraintCoefficients, CompositionCoefficients };
 mod proof_of_work;
 pub use proof_of_work::{ find_pow_nonce, verify_pow_nonce };
 
+mod transcript;
+pub use transcript::{ Transcript, draw_field_elements };
+
 pub fn get_composition_degree(trace_length: usize) -> usize {
     return (MAX_CONSTRAINT_DEGREE - 1) * trace_length - 1;
 }
@@ -50,4 +54,26 @@ pub fn map_trace_to_constraint_positions(positions: &[usize]) -> Vec<usize> {
         if !result.contains(&cp) { result.push(cp); }
     }
     return result;
-}
\ No newline at end of file
+}
+
+/// Returns the seed from which coefficients of the composition polynomial are drawn: if the
+/// proof commits to public inputs, the commitment is absorbed together with the root of the
+/// constraint tree; otherwise, the root of the constraint tree is the seed.
+pub fn get_composition_seed(constraint_root: &[u8; 32], inputs_hash: Option<&[u128; HASH_DIGEST_SIZE]>, transcript_fn: HashFunction) -> [u8; 32] {
+    return match inputs_hash {
+        Some(inputs_hash) => {
+            let mut transcript = Transcript::new(transcript_fn);
+            transcript.absorb_commitments(constraint_root);
+            transcript.absorb_public_inputs(inputs_hash);
+            transcript.state()
+        },
+        None => *constraint_root,
+    };
+}
+
+/// Returns the out-of-domain point z at which trace and constraint polynomials are evaluated;
+/// the point is the first field element squeezed from the composition `seed`, and the remaining
+/// elements are used as composition coefficients.
+pub fn draw_deep_point(seed: &[u8; 32], transcript_fn: HashFunction) -> u128 {
+    return draw_field_elements(seed, 1, transcript_fn)[0];
+}
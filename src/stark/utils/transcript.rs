@@ -0,0 +1,173 @@
+use crate::{ crypto::HashFunction, math::field };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Hashes values which the prover and the verifier need to agree on into a single 32-byte
+/// seed. Values are absorbed in 32-byte chunks, one chunk at a time, so that hash functions
+/// which accept at most 64 bytes of input (e.g. Rescue, Poseidon) can be used.
+pub struct Transcript {
+    state   : [u8; 32],
+    hash_fn : HashFunction,
+}
+
+// TRANSCRIPT IMPLEMENTATION
+// ================================================================================================
+impl Transcript {
+
+    pub fn new(hash_fn: HashFunction) -> Transcript {
+        return Transcript { state: [0u8; 32], hash_fn };
+    }
+
+    /// Absorbs a sequence of 32-byte commitments (e.g. Merkle tree roots) into the transcript.
+    pub fn absorb_commitments(&mut self, commitments: &[u8]) {
+        debug_assert!(commitments.len() % 32 == 0, "commitments must be a multiple of 32 bytes");
+        for commitment in commitments.chunks(32) {
+            self.absorb_chunk(commitment);
+        }
+    }
+
+    /// Absorbs a sequence of public inputs into the transcript. The number of inputs is
+    /// absorbed first (as 8-byte little-endian integer), followed by the inputs themselves
+    /// (as 16-byte little-endian integers, 2 inputs per chunk, with the last chunk padded with
+    /// zeros). Thus, an empty sequence still updates the state of the transcript.
+    pub fn absorb_public_inputs(&mut self, inputs: &[u128]) {
+        let mut chunk = [0u8; 32];
+        chunk[..8].copy_from_slice(&(inputs.len() as u64).to_le_bytes());
+        self.absorb_chunk(&chunk);
+
+        for values in inputs.chunks(2) {
+            let mut chunk = [0u8; 32];
+            for (i, value) in values.iter().enumerate() {
+                chunk[(i * 16)..(i * 16 + 16)].copy_from_slice(&value.to_le_bytes());
+            }
+            self.absorb_chunk(&chunk);
+        }
+    }
+
+    /// Returns the current state of the transcript.
+    pub fn state(&self) -> [u8; 32] {
+        return self.state;
+    }
+
+    /// Squeezes `count` field elements from the current state of the transcript; the state
+    /// itself is not modified.
+    pub fn draw_field_elements(&self, count: usize) -> Vec<u128> {
+        return draw_field_elements(&self.state, count, self.hash_fn);
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+    fn absorb_chunk(&mut self, chunk: &[u8]) {
+        let mut buffer = [0u8; 64];
+        buffer[..32].copy_from_slice(&self.state);
+        buffer[32..].copy_from_slice(chunk);
+        (self.hash_fn)(&buffer, &mut self.state);
+    }
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Squeezes `count` field elements from the `seed` using the specified hash function. The i-th
+/// block of output is computed as hash(seed || i), where i is a 32-byte little-endian counter,
+/// and each block is split into two 16-byte little-endian values which are reduced into the
+/// field.
+pub fn draw_field_elements(seed: &[u8; 32], count: usize, hash_fn: HashFunction) -> Vec<u128> {
+    let mut result = Vec::with_capacity(count + 1);
+    let mut buffer = [0u8; 64];
+    buffer[..32].copy_from_slice(seed);
+
+    let mut block = [0u8; 32];
+    let mut counter = 0u64;
+    while result.len() < count {
+        buffer[32..40].copy_from_slice(&counter.to_le_bytes());
+        hash_fn(&buffer, &mut block);
+        for chunk in block.chunks(16) {
+            let mut value = [0u8; 16];
+            value.copy_from_slice(chunk);
+            result.push(field::reduce(u128::from_le_bytes(value)));
+        }
+        counter += 1;
+    }
+    result.truncate(count);
+
+    return result;
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ crypto::hash, math::field };
+    use super::{ Transcript, draw_field_elements };
+
+    #[test]
+    fn absorb_public_inputs() {
+        let absorb = |inputs: &[u128]| {
+            let mut transcript = Transcript::new(hash::blake3);
+            transcript.absorb_public_inputs(inputs);
+            transcript.state()
+        };
+
+        // absorption is deterministic
+        assert_eq!(absorb(&[1, 2, 3]), absorb(&[1, 2, 3]));
+
+        // reordering inputs changes the state
+        assert_ne!(absorb(&[1, 2, 3]), absorb(&[2, 1, 3]));
+        assert_ne!(absorb(&[1, 2, 3]), absorb(&[1, 3, 2]));
+
+        // padding of the last chunk is not confused with a zero input
+        assert_ne!(absorb(&[1, 2, 3]), absorb(&[1, 2, 3, 0]));
+
+        // zero inputs still produce a well-defined state
+        assert_eq!(absorb(&[]), absorb(&[]));
+        assert_ne!([0u8; 32], absorb(&[]));
+        assert_ne!(absorb(&[]), absorb(&[0]));
+    }
+
+    #[test]
+    fn absorb_commitments() {
+        let mut transcript1 = Transcript::new(hash::blake3);
+        transcript1.absorb_commitments(&[1u8; 64]);
+
+        let mut transcript2 = Transcript::new(hash::blake3);
+        transcript2.absorb_commitments(&[1u8; 32]);
+        transcript2.absorb_commitments(&[1u8; 32]);
+        assert_eq!(transcript1.state(), transcript2.state());
+    }
+
+    #[test]
+    fn draw_elements() {
+        let seed = [7u8; 32];
+
+        // drawing is deterministic, and shorter draws are prefixes of longer ones
+        let elements = draw_field_elements(&seed, 5, hash::blake3);
+        assert_eq!(5, elements.len());
+        assert_eq!(elements, draw_field_elements(&seed, 5, hash::blake3));
+        assert_eq!(elements[..3], draw_field_elements(&seed, 3, hash::blake3)[..]);
+        assert!(elements.iter().all(|&e| e < field::MODULUS));
+
+        // elements are squeezed from hash(seed || counter)
+        let mut buffer = [0u8; 64];
+        buffer[..32].copy_from_slice(&seed);
+        buffer[32] = 1;
+        let mut block = [0u8; 32];
+        hash::blake3(&buffer, &mut block);
+        let mut value = [0u8; 16];
+        value.copy_from_slice(&block[..16]);
+        assert_eq!(field::reduce(u128::from_le_bytes(value)), elements[2]);
+
+        // elements depend on the seed and on the hash function
+        assert_ne!(elements, draw_field_elements(&[8u8; 32], 5, hash::blake3));
+        assert_ne!(elements, draw_field_elements(&seed, 5, hash::sha3));
+
+        // drawing from a transcript does not change its state
+        let mut transcript = Transcript::new(hash::blake3);
+        transcript.absorb_commitments(&seed);
+        let state = transcript.state();
+        assert_eq!(draw_field_elements(&state, 2, hash::blake3), transcript.draw_field_elements(2));
+        assert_eq!(state, transcript.state());
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{ Serialize, Deserialize };
+use crate::math::field;
+use super::{ StarkProof, ProofOptions, VerifierSession };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Values which the verifier needs to know before receiving a proof: the hash of the program,
+/// the options with which proofs are generated, and the modulus of the field over which
+/// execution traces are defined. A proof is verified against a key only if it was generated
+/// with the options specified by the key; if these options include a commitment to public
+/// inputs, proofs without the commitment are rejected.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VerificationKey {
+    program_hash    : [u8; 32],
+    options         : ProofOptions,
+    modulus         : u128,
+    input_commitment: bool,
+}
+
+// VERIFICATION KEY IMPLEMENTATION
+// ================================================================================================
+impl VerificationKey {
+
+    /// Returns a key for proofs of execution of a program with the specified `program_hash`
+    /// generated with the specified `options`.
+    pub fn new(program_hash: &[u8; 32], options: &ProofOptions) -> VerificationKey {
+        return VerificationKey {
+            program_hash    : *program_hash,
+            options         : options.clone(),
+            modulus         : field::MODULUS,
+            input_commitment: options.input_commitment(),
+        };
+    }
+
+    pub fn program_hash(&self) -> &[u8; 32] {
+        return &self.program_hash;
+    }
+
+    pub fn options(&self) -> &ProofOptions {
+        return &self.options;
+    }
+
+    pub fn modulus(&self) -> u128 {
+        return self.modulus;
+    }
+
+    pub fn input_commitment(&self) -> bool {
+        return self.input_commitment;
+    }
+}
+
+// VERIFIER
+// ================================================================================================
+
+/// Verifies the `proof` against the key `vk` and the specified public `inputs`, and returns the
+/// outputs claimed by the prover if the proof is valid.
+pub fn verify_with_key(vk: &VerificationKey, inputs: &[u128], proof: &StarkProof) -> Result<Vec<u128>, String>
+{
+    if vk.modulus != field::MODULUS {
+        return Err(format!("verification key is for a field with modulus {}, but this verifier uses modulus {}",
+            vk.modulus, field::MODULUS));
+    }
+    if !proof.options().is_equivalent_to(&vk.options) {
+        return Err(String::from("proof was generated with options which do not match the verification key"));
+    }
+
+    let mut session = VerifierSession::new(&vk.program_hash, inputs, proof.outputs(), proof);
+    if vk.input_commitment {
+        session = session.require_input_commitment();
+    }
+    return match session.finish() {
+        Ok(true) => Ok(proof.outputs().to_vec()),
+        Ok(false) => Err(String::from("proof verification failed")),
+        Err(err) => Err(err),
+    };
+}
@@ -1,84 +1,531 @@
+use std::io::Read;
 use crate::{
     math::field,
     crypto::{ MerkleTree },
-    MIN_TRACE_LENGTH
+    programs::commit_to_inputs,
+    MIN_TRACE_LENGTH, MAX_REGISTER_COUNT
 };
-use super::{ StarkProof, TraceState, ConstraintEvaluator, CompositionCoefficients, fri, utils };
+use super::{
+    StarkProof, ProofError, OutputTape, ConstraintEvaluator, CustomConstraints, ConstraintCoefficients,
+    CompositionCoefficients, fri, utils, DOMAIN_OFFSET,
+    constraints::{ AuxEvaluator, AuxFrame, draw_aux_challenges } };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Default limit on the size of a proof read by verify_from_reader(). With the largest options
+/// accepted by ProofOptions (128 queries, extension factor 256, FRI remainder degree 1023) and
+/// a trace of 2^32 steps and 128 registers, a proof is about 8 MB: the FRI remainder accounts for
+/// 1024 * 256 * 16 bytes = 4 MB, and trace values, Merkle paths and FRI layers for the rest.
+pub const DEFAULT_MAX_PROOF_SIZE: u64 = 16 * 1024 * 1024;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Verifies a STARK proof in bounded steps. Every call to `step()` performs up to the specified
+/// number of work units. Stages which process all queries at once (verification of a batch
+/// Merkle proof, or of a single FRI layer) cost one unit per query; all other stages (e.g.
+/// evaluation of constraints at the DEEP point, or computing composition value for a single
+/// query) cost one unit each.
+pub struct VerifierSession<'a> {
+    proof           : &'a StarkProof,
+    program_hash    : [u8; 32],
+    inputs          : Vec<u128>,
+    outputs         : Vec<u128>,
+    custom          : CustomConstraints,
+    stage           : Stage,
+    t_positions     : Vec<usize>,
+    c_positions     : Vec<usize>,
+    c_evaluations   : Vec<u128>,
+    z               : u128,
+    evaluation_at_z : u128,
+    coefficients    : Option<CompositionCoefficients>,
+    evaluations     : Vec<u128>,
+    fri_verifier    : Option<fri::FriVerifier>,
+    require_commit  : bool,
+}
+
+/// Fiat-Shamir challenges derived by the verifier from proof commitments and public inputs:
+/// `constraints` are coefficients for combining built-in constraints (drawn from the trace root),
+/// `z` and `composition` are the DEEP point and coefficients for the composition polynomial
+/// (drawn from the constraint root), `fri_alphas` are x coordinates at which FRI layers are
+/// folded (drawn from layer roots), and `query_positions` are the queried LDE positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenges {
+    pub constraints     : ConstraintCoefficients,
+    pub z               : u128,
+    pub composition     : CompositionCoefficients,
+    pub fri_alphas      : Vec<u128>,
+    pub query_positions : Vec<usize>,
+}
+
+/// Requirements which a proof read by verify_from_reader() must meet before it is verified:
+/// `min_security_level` is the minimum (conservative) security level of the proof options in
+/// bits, `max_proof_size` limits the number of bytes read from the stream, and if
+/// `require_input_commitment` is set, the proof must commit to public inputs. By default, only
+/// the size of the proof is limited (to DEFAULT_MAX_PROOF_SIZE bytes), so that parsing a proof
+/// from an untrusted stream cannot exhaust memory of the verifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyPolicy {
+    pub min_security_level      : u32,
+    pub max_proof_size          : Option<u64>,
+    pub require_input_commitment: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifierStatus {
+    InProgress,
+    Done(Result<bool, String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    InvalidProof(String),
+    OutputCountMismatch { expected: usize, found: usize },
+    OutputMismatch { index: usize, expected: u128, found: u128 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stage {
+    QueryPositions,
+    TraceMerkleProof,
+    AuxMerkleProof,
+    ConstraintMerkleProof,
+    DeepEvaluation,
+    Composition(usize),
+    FriLayer(usize),
+    FriRemainder,
+    Done(Result<bool, String>),
+}
 
 // VERIFIER FUNCTION
 // ================================================================================================
 
 pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
 {
-    let options = proof.options();
-    let hash_fn = options.hash_fn();
+    return verify_with_constraints(program_hash, inputs, outputs, proof, &CustomConstraints::new());
+}
 
-    // 1 ----- Verify proof of work and determine query positions ---------------------------------
-    let degree_proof = proof.degree_proof();
-    let mut fri_roots: Vec<u8> = Vec::new();
-    for layer in degree_proof.layers.iter() {
-        layer.root.iter().for_each(|&v| fri_roots.push(v));
+/// Same as verify(), but also checks that the execution trace satisfies `custom` constraints;
+/// these must be the same constraints which were used to generate the proof.
+pub fn verify_with_constraints(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof, custom: &CustomConstraints) -> Result<bool, String>
+{
+    return VerifierSession::with_constraints(program_hash, inputs, outputs, proof, custom).finish();
+}
+
+/// Verifies the proof against outputs claimed by the prover, and then makes sure these outputs
+/// are exactly the `expected_outputs`; if the proof is valid but attests to different outputs,
+/// the first mismatching output is reported.
+pub fn verify_expecting(program_hash: &[u8; 32], inputs: &[u128], expected_outputs: &[u128], proof: &StarkProof) -> Result<(), VerifyError>
+{
+    let outputs = proof.outputs();
+    match verify(program_hash, inputs, outputs, proof) {
+        Ok(true) => (),
+        Ok(false) => return Err(VerifyError::InvalidProof(String::from("proof verification failed"))),
+        Err(err) => return Err(VerifyError::InvalidProof(err)),
     }
-    degree_proof.rem_root.iter().for_each(|&v| fri_roots.push(v));
 
-    let mut seed = [0u8; 32];
-    hash_fn(&fri_roots, &mut seed);
-    let seed = match utils::verify_pow_nonce(seed, proof.pow_nonce(), &options) {
-        Ok(seed) => seed,
-        Err(msg) => return Err(msg)
+    for (index, (&expected, &found)) in expected_outputs.iter().zip(outputs).enumerate() {
+        if expected != found {
+            return Err(VerifyError::OutputMismatch { index, expected, found });
+        }
+    }
+
+    if expected_outputs.len() != outputs.len() {
+        return Err(VerifyError::OutputCountMismatch { expected: expected_outputs.len(), found: outputs.len() });
+    }
+
+    return Ok(());
+}
+
+/// Verifies a batch of proofs of a program with the same `program_hash`; every item of the batch
+/// consists of public inputs, outputs, and a proof of execution for these values. Every proof is
+/// verified in full, and verification stops at the first proof which is not valid; the index of
+/// this proof is returned together with the reason it was rejected.
+pub fn verify_batch_same_program(program_hash: &[u8; 32], items: &[(&[u128], &[u128], &StarkProof)]) -> Result<(), (usize, VerifyError)>
+{
+    for (index, &(inputs, outputs, proof)) in items.iter().enumerate() {
+        match verify(program_hash, inputs, outputs, proof) {
+            Ok(true) => (),
+            Ok(false) => return Err((index, VerifyError::InvalidProof(String::from("proof verification failed")))),
+            Err(err) => return Err((index, VerifyError::InvalidProof(err))),
+        }
+    }
+    return Ok(());
+}
+
+/// Verifies the proof against the `outputs`, and makes sure the proof attests that exactly the
+/// values in `tape` were written to the output tape; the tape must have been made public when
+/// the proof was generated.
+pub fn verify_with_tape(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], tape: &[u128], proof: &StarkProof) -> Result<bool, String>
+{
+    match proof.tape() {
+        Some(proof_tape) if proof_tape.values == tape => (),
+        Some(_) => return Err(String::from("output tape does not match the tape attested to by the proof")),
+        None => return Err(String::from("output tape was not made public in the proof")),
+    }
+    return verify(program_hash, inputs, outputs, proof);
+}
+
+/// Reads a proof from the `reader` and verifies it against the outputs claimed by the prover;
+/// the proof is parsed as it is read from the stream, and is verified only if it meets the
+/// requirements of the `policy`. If the proof is valid, the outputs it attests to are returned.
+pub fn verify_from_reader<R: Read>(program_hash: &[u8; 32], inputs: &[u128], reader: R, policy: &VerifyPolicy) -> Result<Vec<u128>, ProofError>
+{
+    let proof = StarkProof::read_from(reader, policy.max_proof_size)?;
+
+    let security_level = proof.options().security_level(false);
+    if security_level < policy.min_security_level {
+        return Err(ProofError::InsufficientSecurity { found: security_level, required: policy.min_security_level });
+    }
+
+    let mut session = VerifierSession::new(program_hash, inputs, proof.outputs(), &proof);
+    if policy.require_input_commitment {
+        session = session.require_input_commitment();
+    }
+    return match session.finish() {
+        Ok(true) => Ok(proof.outputs().to_vec()),
+        Ok(false) => Err(ProofError::InvalidProof(String::from("proof verification failed"))),
+        Err(err) => Err(ProofError::InvalidProof(err)),
     };
+}
+
+/// Checks that the proof carries a commitment to the specified public `inputs` (see
+/// ProofOptions::with_input_commitment()); this does not verify the proof in any way and is
+/// not a soundness check. The commitment is deterministic and is absorbed into the Fiat-Shamir
+/// transcript, and thus, if this function returns false, verify() rejects the proof as well;
+/// this can be used to cheaply route or filter out proofs before verifying them in full. Proofs
+/// which do not commit to public inputs, or `inputs` which are not valid field elements, are
+/// always rejected.
+pub fn check_input_commitment(inputs: &[u128], proof: &StarkProof) -> bool
+{
+    if validate_field_elements(inputs, "public input").is_err() || proof.check_header().is_err() {
+        return false;
+    }
+    return validate_inputs_hash(proof, inputs, true).is_ok();
+}
 
-    let t_positions = utils::compute_query_positions(&seed, proof.domain_size(), options);
-    let c_positions = utils::map_trace_to_constraint_positions(&t_positions);
+// VERIFY POLICY IMPLEMENTATION
+// ================================================================================================
+impl Default for VerifyPolicy {
 
-    // 2 ----- Verify number of operations in the program -----------------------------------------
-    if proof.op_count() < MIN_TRACE_LENGTH as u128 {
-        return Err(String::from("Verification of minimum operation count failed"));
+    fn default() -> VerifyPolicy {
+        return VerifyPolicy {
+            min_security_level      : 0,
+            max_proof_size          : Some(DEFAULT_MAX_PROOF_SIZE),
+            require_input_commitment: false,
+        };
     }
+}
 
-    // 3 ----- Verify trace and constraint Merkle proofs ------------------------------------------
-    if !MerkleTree::verify_batch(proof.trace_root(), &t_positions, &proof.trace_proof(), hash_fn) {
-        return Err(String::from("verification of trace Merkle proof failed"));
+// VERIFIER SESSION IMPLEMENTATION
+// ================================================================================================
+impl <'a> VerifierSession<'a> {
+
+    pub fn new(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &'a StarkProof) -> VerifierSession<'a> {
+        return VerifierSession::with_constraints(program_hash, inputs, outputs, proof, &CustomConstraints::new());
     }
 
-    if !MerkleTree::verify_batch(proof.constraint_root(), &c_positions, &proof.constraint_proof(), hash_fn) {
-        return Err(String::from("verification of constraint Merkle proof failed"));
+    /// Returns a new session which, in addition to built-in constraints, checks that the
+    /// execution trace satisfies `custom` constraints. Verification fails if any of `inputs` or
+    /// `outputs` is not a valid field element: the claim must be canonical to be unambiguous.
+    pub fn with_constraints(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &'a StarkProof, custom: &CustomConstraints) -> VerifierSession<'a> {
+        return VerifierSession {
+            proof,
+            program_hash    : *program_hash,
+            inputs          : inputs.to_vec(),
+            outputs         : outputs.to_vec(),
+            custom          : custom.with_tape(proof.tape()),
+            stage           : Stage::QueryPositions,
+            t_positions     : Vec::new(),
+            c_positions     : Vec::new(),
+            c_evaluations   : Vec::new(),
+            z               : field::ZERO,
+            evaluation_at_z : field::ZERO,
+            coefficients    : None,
+            evaluations     : Vec::new(),
+            fri_verifier    : None,
+            require_commit  : false,
+        };
     }
 
-    // 4 ----- Compute constraint evaluations at DEEP point z -------------------------------------
-    // derive DEEP point z from the root of the constraint tree
-    let z = field::prng(*proof.constraint_root());
+    /// Returns this session with which verification fails unless the proof commits to public
+    /// inputs; this should be used whenever the verifier expects proofs to carry the commitment,
+    /// since, otherwise, a prover could strip the commitment from the proof.
+    pub fn require_input_commitment(mut self) -> VerifierSession<'a> {
+        self.require_commit = true;
+        return self;
+    }
 
-    // evaluate constraints at z
-    let constraint_evaluation_at_z = evaluate_constraints(
-        ConstraintEvaluator::from_proof(proof, program_hash, inputs, outputs),
-        proof.get_state_at_z1(),
-        proof.get_state_at_z2(),
-        z
-    );
+    /// Performs all remaining verification work and returns the result of verification.
+    pub fn finish(&mut self) -> Result<bool, String> {
+        return match self.step(usize::MAX) {
+            VerifierStatus::Done(result) => result,
+            VerifierStatus::InProgress => unreachable!("verification did not complete"),
+        };
+    }
 
-    // 5 ----- Compute composition polynomial evaluations -----------------------------------------
-    // derive coefficient for linear combination from the root of constraint tree
-    let coefficients = CompositionCoefficients::new(*proof.constraint_root());
+    /// Performs up to `budget` units of verification work; once verification is complete,
+    /// the result is returned in `VerifierStatus::Done`. A stage is never split between steps:
+    /// if the next stage costs more than `budget` units, it is performed only when it is the
+    /// first stage of a step, so every step with a non-zero budget makes progress. Calling this
+    /// function after verification is complete does no work and returns the same result again.
+    pub fn step(&mut self, budget: usize) -> VerifierStatus {
+        let mut work = 0usize;
+        while work < budget {
+            if let Stage::Done(_) = self.stage { break; }
+            let cost = self.get_stage_cost();
+            if work > 0 && work.saturating_add(cost) > budget { break; }
 
-    // compute composition values separately for trace and constraints, and then add them together
-    let t_composition = compose_registers(&proof, &t_positions, z, &coefficients);
-    let c_composition = compose_constraints(&proof, &t_positions, &c_positions, z, constraint_evaluation_at_z, &coefficients);
-    let evaluations = t_composition.iter().zip(c_composition).map(|(&t, c)| field::add(t, c)).collect::<Vec<u128>>();
-    
-    // 6 ----- Verify low-degree proof -------------------------------------------------------------
-    let max_degree = utils::get_composition_degree(proof.trace_length());
-    return match fri::verify(&degree_proof, &evaluations, &t_positions, max_degree, options) {
-        Ok(result) => Ok(result),
-        Err(msg) => Err(format!("verification of low-degree proof failed: {}", msg))
+            self.stage = match self.advance() {
+                Ok(stage) => stage,
+                Err(msg) => Stage::Done(Err(msg)),
+            };
+            work = work.saturating_add(cost);
+        }
+
+        return match &self.stage {
+            Stage::Done(result) => VerifierStatus::Done(result.clone()),
+            _ => VerifierStatus::InProgress,
+        };
+    }
+
+    /// Executes a single unit of work and returns the stage to be executed next.
+    fn advance(&mut self) -> Result<Stage, String> {
+        let proof = self.proof;
+        let options = proof.options();
+        let hash_fn = options.hash_fn();
+
+        match self.stage {
+            // 1 ----- Verify proof of work and determine query positions -------------------------
+            Stage::QueryPositions => {
+                proof.check_header().map_err(|err| err.to_string())?;
+                validate_field_elements(&self.inputs, "public input")?;
+                validate_field_elements(&self.outputs, "output")?;
+                validate_inputs_hash(proof, &self.inputs, self.require_commit)?;
+
+                self.t_positions = get_query_positions(proof, &self.inputs, &self.outputs)?;
+                self.c_positions = utils::map_trace_to_constraint_positions(&self.t_positions);
+                if proof.trace_evaluations().len() != self.t_positions.len() {
+                    return Err(format!("proof must contain trace values at {} queried positions, but contains {}",
+                        self.t_positions.len(), proof.trace_evaluations().len()));
+                }
+
+                // 2 ----- Verify number of operations in the program -----------------------------
+                if proof.op_count() < MIN_TRACE_LENGTH as u128 {
+                    return Err(String::from("Verification of minimum operation count failed"));
+                }
+
+                return Ok(Stage::TraceMerkleProof);
+            },
+            // 3 ----- Verify trace and constraint Merkle proofs ----------------------------------
+            Stage::TraceMerkleProof => {
+                if !MerkleTree::verify_batch(proof.trace_root(), &self.t_positions, &proof.trace_proof(), hash_fn) {
+                    return Err(String::from("verification of trace Merkle proof failed"));
+                }
+                return Ok(Stage::AuxMerkleProof);
+            },
+            Stage::AuxMerkleProof => {
+                // auxiliary registers must be committed to if and only if they are defined by
+                // custom constraints or the output tape is public
+                match (self.custom.aux_trace(), proof.aux_proof()) {
+                    (Some(aux_trace), Some(aux_proof)) => {
+                        validate_aux_widths(proof, aux_trace.width())?;
+                        if !MerkleTree::verify_batch(proof.aux_root().unwrap(), &self.t_positions, &aux_proof, hash_fn) {
+                            return Err(String::from("verification of auxiliary trace Merkle proof failed"));
+                        }
+                    },
+                    (Some(_), None) => return Err(String::from("proof does not commit to auxiliary trace registers")),
+                    (None, Some(_)) => return Err(String::from("proof commits to auxiliary trace registers which are not defined by custom constraints")),
+                    (None, None) => (),
+                }
+                return Ok(Stage::ConstraintMerkleProof);
+            },
+            Stage::ConstraintMerkleProof => {
+                if !MerkleTree::verify_batch(proof.constraint_root(), &self.c_positions, &proof.constraint_proof(), hash_fn) {
+                    return Err(String::from("verification of constraint Merkle proof failed"));
+                }
+                return Ok(Stage::DeepEvaluation);
+            },
+            // 4 ----- Compute constraint evaluations at DEEP point z -----------------------------
+            Stage::DeepEvaluation => {
+                // derive DEEP point z from the root of the constraint tree and the commitment
+                // to public inputs (if any)
+                let seed = get_composition_seed(proof);
+                self.z = utils::draw_deep_point(&seed, options.transcript_fn());
+
+                // evaluate constraints at z; challenges for auxiliary registers are drawn from
+                // the trace root in the same way as by the prover
+                let aux_evaluator = self.custom.aux_trace().map(|aux_trace| {
+                    let challenges = draw_aux_challenges(proof.trace_root(), aux_trace.num_challenges(), options.transcript_fn());
+                    AuxEvaluator::new(aux_trace, &challenges, proof.aux_root().unwrap(), options.transcript_fn(), proof.trace_length())
+                });
+                self.evaluation_at_z = evaluate_constraints(
+                    ConstraintEvaluator::from_proof(proof, &self.program_hash, &self.inputs, &self.outputs, &self.custom),
+                    aux_evaluator,
+                    proof,
+                    self.z
+                );
+
+                // derive coefficients for linear combination from the same seed as z
+                self.coefficients = Some(CompositionCoefficients::new(seed, options.transcript_fn()));
+                self.c_evaluations = get_constraint_evaluations(proof, &self.t_positions, &self.c_positions);
+                self.evaluations = Vec::with_capacity(self.t_positions.len());
+
+                return Ok(Stage::Composition(0));
+            },
+            // 5 ----- Compute composition polynomial evaluations ---------------------------------
+            Stage::Composition(i) => {
+                let coefficients = self.coefficients.as_ref().unwrap();
+
+                // compute composition values separately for trace and constraints, and then
+                // add them together; this is done one query at a time
+                let positions = &self.t_positions[i..(i + 1)];
+                let (trace_at_z1, trace_at_z2) = (proof.get_state_at_z1().to_vec(), proof.get_state_at_z2().to_vec());
+                let mut t_composition = compose_registers(&proof.trace_evaluations()[i..(i + 1)],
+                    (&trace_at_z1, &trace_at_z2), 0, proof, positions, self.z, coefficients);
+                if self.custom.aux_trace().is_some() {
+                    let aux_at_z = (proof.get_aux_at_z1(), proof.get_aux_at_z2());
+                    let a_composition = compose_registers(&proof.aux_evaluations()[i..(i + 1)],
+                        aux_at_z, MAX_REGISTER_COUNT, proof, positions, self.z, coefficients);
+                    t_composition[0] = field::add(t_composition[0], a_composition[0]);
+                }
+                let c_composition = compose_constraints(&self.c_evaluations[i..(i + 1)],
+                    proof, positions, self.z, self.evaluation_at_z, coefficients);
+                self.evaluations.push(field::add(t_composition[0], c_composition[0]));
+
+                if i + 1 < self.t_positions.len() {
+                    return Ok(Stage::Composition(i + 1));
+                }
+
+                let max_degree = utils::get_composition_degree(proof.trace_length());
+                let fri_verifier = fri::FriVerifier::new(proof.degree_proof(), &self.evaluations,
+                    &self.t_positions, proof.domain_size(), max_degree, DOMAIN_OFFSET, options)
+                    .map_err(|msg| format!("verification of low-degree proof failed: {}", msg))?;
+                self.fri_verifier = Some(fri_verifier);
+                return Ok(get_fri_stage(proof, 0));
+            },
+            // 6 ----- Verify low-degree proof one FRI layer at a time ----------------------------
+            Stage::FriLayer(depth) => {
+                let layer = &proof.degree_proof().layers[depth];
+                self.fri_verifier.as_mut().unwrap().verify_layer(layer, options)
+                    .map_err(|msg| format!("verification of low-degree proof failed: {}", msg))?;
+                return Ok(get_fri_stage(proof, depth + 1));
+            },
+            Stage::FriRemainder => {
+                return match self.fri_verifier.as_ref().unwrap().verify_remainder(proof.degree_proof(), options) {
+                    Ok(result) => Ok(Stage::Done(Ok(result))),
+                    Err(msg) => Err(format!("verification of low-degree proof failed: {}", msg))
+                };
+            },
+            Stage::Done(_) => unreachable!("verification is already complete"),
+        }
+    }
+
+    /// Returns the number of work units performed by the next stage.
+    fn get_stage_cost(&self) -> usize {
+        return match self.stage {
+            Stage::TraceMerkleProof | Stage::FriLayer(_) => self.t_positions.len(),
+            Stage::AuxMerkleProof if self.custom.aux_trace().is_some() => self.t_positions.len(),
+            Stage::ConstraintMerkleProof => self.c_positions.len(),
+            _ => 1,
+        };
     }
 }
 
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return match self {
+            VerifyError::InvalidProof(err) => write!(f, "{}", err),
+            VerifyError::OutputCountMismatch { expected, found } => write!(f,
+                "expected {} outputs, but the proof attests to {} outputs", expected, found),
+            VerifyError::OutputMismatch { index, expected, found } => write!(f,
+                "expected output {} to be {}, but the proof attests to {}", index, expected, found),
+        };
+    }
+}
+
+// CHALLENGES
+// ================================================================================================
+
+/// Derives Fiat-Shamir challenges for the `proof` in the same way as they are derived during
+/// verification against the outputs claimed by the prover; an error is returned if query
+/// positions cannot be derived (e.g. because the proof-of-work nonce is invalid).
+pub fn derive_challenges(proof: &StarkProof, inputs: &[u128]) -> Result<Challenges, String> {
+    proof.check_header().map_err(|err| err.to_string())?;
+    let query_positions = get_query_positions(proof, inputs, proof.outputs())?;
+    let transcript_fn = proof.options().transcript_fn();
+    let constraints = ConstraintCoefficients::new(*proof.trace_root(), transcript_fn,
+        proof.ctx_depth(), proof.loop_depth(), proof.stack_depth(), 0);
+    let fri_roots: Vec<[u8; 32]> = proof.degree_proof().layers.iter().map(|layer| layer.root).collect();
+    let fri_alphas = fri::get_folding_challenges(&fri_roots, transcript_fn);
+    let seed = get_composition_seed(proof);
+
+    return Ok(Challenges {
+        constraints,
+        z           : utils::draw_deep_point(&seed, transcript_fn),
+        composition : CompositionCoefficients::new(seed, transcript_fn),
+        fri_alphas,
+        query_positions,
+    });
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
-fn evaluate_constraints(evaluator: ConstraintEvaluator, state1: TraceState, state2: TraceState, x: u128) -> u128 {
-    let (i_value, f_value) = evaluator.evaluate_boundaries(&state1, x);
-    let t_value = evaluator.evaluate_transition_at(&state1, &state2, x);
+
+/// Returns the stage which verifies the FRI layer at the specified `depth`, or the remainder if
+/// all layers of the degree proof have been verified.
+fn get_fri_stage(proof: &StarkProof, depth: usize) -> Stage {
+    return if depth < proof.degree_proof().layers.len() { Stage::FriLayer(depth) } else { Stage::FriRemainder };
+}
+
+/// Derives query positions from public inputs, outputs, output tape, commitment to public
+/// inputs, and FRI layer roots; the seed for query positions is accepted only if it satisfies
+/// proof-of-work requirements.
+fn get_query_positions(proof: &StarkProof, inputs: &[u128], outputs: &[u128]) -> Result<Vec<usize>, String> {
+    let options = proof.options();
+
+    let degree_proof = proof.degree_proof();
+    let mut fri_roots: Vec<u8> = Vec::new();
+    for layer in degree_proof.layers.iter() {
+        layer.root.iter().for_each(|&v| fri_roots.push(v));
+    }
+    degree_proof.rem_root.iter().for_each(|&v| fri_roots.push(v));
+
+    let mut transcript = utils::Transcript::new(options.transcript_fn());
+    transcript.absorb_public_inputs(inputs);
+    transcript.absorb_public_inputs(outputs);
+    if let Some(tape) = proof.tape() {
+        validate_tape(tape, proof.trace_length())?;
+        transcript.absorb_public_inputs(&tape.values);
+    }
+    if let Some(inputs_hash) = proof.inputs_hash() {
+        transcript.absorb_public_inputs(inputs_hash);
+    }
+    transcript.absorb_commitments(&fri_roots);
+    let seed = transcript.state();
+    let seed = utils::verify_pow_nonce(seed, proof.pow_nonce(), &options)?;
+
+    return Ok(utils::compute_query_positions(&seed, proof.domain_size(), options));
+}
+
+fn evaluate_constraints(evaluator: ConstraintEvaluator, aux_evaluator: Option<AuxEvaluator>, proof: &StarkProof, x: u128) -> u128 {
+    let state1 = proof.get_state_at_z1();
+    let state2 = proof.get_state_at_z2();
+    let (mut i_value, mut f_value) = evaluator.evaluate_boundaries(&state1, x);
+    let mut t_value = evaluator.evaluate_transition_at(&state1, &state2, x);
+
+    // constraints of auxiliary registers have the same divisors as boundary and transition
+    // constraints of the execution trace
+    if let Some(aux_evaluator) = aux_evaluator {
+        let (aux_current, aux_next) = (proof.get_aux_at_z1(), proof.get_aux_at_z2());
+        let frame = AuxFrame { current: &state1, next: &state2, aux_current, aux_next };
+        let (aux_i_value, aux_f_value) = aux_evaluator.evaluate_boundaries(aux_current, x);
+        i_value = field::add(i_value, aux_i_value);
+        f_value = field::add(f_value, aux_f_value);
+        let mut evaluations = vec![field::ZERO; aux_evaluator.transition_constraint_count()];
+        t_value = field::add(t_value, aux_evaluator.evaluate_transition_at(&frame, x, &mut evaluations));
+    }
 
     // Z(x) = x - 1
     let z = field::sub(x, field::ONE);
@@ -92,36 +539,99 @@ fn evaluate_constraints(evaluator: ConstraintEvaluator, state1: TraceState, stat
     let z = field::div(field::sub(field::exp(x, evaluator.trace_length() as u128), field::ONE), z);
     result = field::add(result, field::div(t_value, z));
 
+    // Z(x) = (x - x_0) * (x - x_1) * ... for all steps at which values were written to the tape
+    if evaluator.has_tape_constraints() {
+        let w_value = evaluator.evaluate_tape(&state1, x);
+        let z = evaluator.get_tape_points().iter().fold(field::ONE, |z, &p| field::mul(z, field::sub(x, p)));
+        result = field::add(result, field::div(w_value, z));
+    }
+
     return result;
 }
 
-fn compose_registers(proof: &StarkProof, positions: &[usize], z: u128, cc: &CompositionCoefficients) -> Vec<u128>
-{    
+/// Makes sure that all `values` are valid field elements; values coming from the verifier's
+/// claim are not reduced since this would make the claim malleable.
+fn validate_field_elements(values: &[u128], name: &str) -> Result<(), String> {
+    for (i, &value) in values.iter().enumerate() {
+        if value >= field::MODULUS {
+            return Err(format!("{} {} is not a valid field element: {}", name, i, value));
+        }
+    }
+    return Ok(());
+}
+
+/// Makes sure that the commitment to public inputs in the proof (if any) is the hash of `inputs`;
+/// if the commitment is `required`, proofs which do not contain it are rejected.
+fn validate_inputs_hash(proof: &StarkProof, inputs: &[u128], required: bool) -> Result<(), String> {
+    return match proof.inputs_hash() {
+        Some(inputs_hash) if *inputs_hash != commit_to_inputs(inputs) =>
+            Err(String::from("public inputs do not match the input commitment in the proof")),
+        None if required => Err(String::from("proof does not commit to public inputs")),
+        _ => Ok(()),
+    };
+}
+
+/// Returns the seed for composition coefficients of the `proof`; the seed is derived in the
+/// same way as by the prover.
+fn get_composition_seed(proof: &StarkProof) -> [u8; 32] {
+    return utils::get_composition_seed(proof.constraint_root(), proof.inputs_hash(), proof.options().transcript_fn());
+}
+
+/// Makes sure the output tape claimed by the prover is well-formed: every written value must
+/// have a step associated with it, and the steps must be strictly increasing and must precede
+/// the last step of the execution trace.
+fn validate_tape(tape: &OutputTape, trace_length: usize) -> Result<(), String> {
+    if tape.steps.len() != tape.values.len() {
+        return Err(format!("output tape contains {} values, but {} steps", tape.values.len(), tape.steps.len()));
+    }
+    for i in 0..tape.steps.len() {
+        let step = tape.steps[i] as usize;
+        if step >= trace_length - 1 || (i > 0 && step <= tape.steps[i - 1] as usize) {
+            return Err(format!("output tape step {} at position {} is invalid", step, i));
+        }
+    }
+    return Ok(());
+}
+
+/// Makes sure the proof contains values of `width` auxiliary registers at every queried position
+/// and at both DEEP points.
+fn validate_aux_widths(proof: &StarkProof, width: usize) -> Result<(), String> {
+    let mut rows = proof.aux_evaluations().iter().map(|row| row.len());
+    if proof.get_aux_at_z1().len() != width || proof.get_aux_at_z2().len() != width || rows.any(|len| len != width) {
+        return Err(format!("proof must contain values of {} auxiliary registers", width));
+    }
+    return Ok(());
+}
+
+/// Computes DEEP composition of register `evaluations` at the specified `positions`; values of
+/// the registers at z and z * g are in `values_at_z`, and coefficients for the registers start
+/// at `cc_offset`.
+fn compose_registers(evaluations: &[Vec<u128>], values_at_z: (&[u128], &[u128]), cc_offset: usize, proof: &StarkProof, positions: &[usize], z: u128, cc: &CompositionCoefficients) -> Vec<u128>
+{
     let lde_root = field::get_root_of_unity(proof.domain_size());
     let trace_root = field::get_root_of_unity(proof.trace_length());
     let next_z = field::mul(z, trace_root);
 
-    let trace_at_z1 = proof.get_state_at_z1().to_vec();
-    let trace_at_z2 = proof.get_state_at_z2().to_vec();
-    let evaluations = proof.trace_evaluations();
+    let (trace_at_z1, trace_at_z2) = values_at_z;
+    let (cc_trace1, cc_trace2) = (&cc.trace1[cc_offset..], &cc.trace2[cc_offset..]);
 
     let incremental_degree = utils::get_incremental_trace_degree(proof.trace_length()) as u128;
 
     let mut result = Vec::with_capacity(evaluations.len());
     for (registers, &position) in evaluations.into_iter().zip(positions) {
-        let x = field::exp(lde_root, position as u128);
+        let x = field::mul(field::exp(lde_root, position as u128), DOMAIN_OFFSET);
         
         let mut composition = field::ZERO;
         for (i, &value) in registers.iter().enumerate() {
             // compute T1(x) = (T(x) - T(z)) / (x - z)
             let t1 = field::div(field::sub(value, trace_at_z1[i]), field::sub(x, z));
             // multiply it by a pseudo-random coefficient, and combine with result
-            composition = field::add(composition, field::mul(t1, cc.trace1[i]));
+            composition = field::add(composition, field::mul(t1, cc_trace1[i]));
 
             // compute T2(x) = (T(x) - T(z * g)) / (x - z * g)
             let t2 = field::div(field::sub(value, trace_at_z2[i]), field::sub(x, next_z));
             // multiply it by a pseudo-random coefficient, and combine with result
-            composition = field::add(composition, field::mul(t2, cc.trace2[i]));
+            composition = field::add(composition, field::mul(t2, cc_trace2[i]));
         }
 
         // raise the degree to match composition degree
@@ -135,8 +645,8 @@ fn compose_registers(proof: &StarkProof, positions: &[usize], z: u128, cc: &Comp
     return result;
 }
 
-fn compose_constraints(proof: &StarkProof, t_positions: &[usize], c_positions: &[usize], z: u128, evaluation_at_z: u128, cc: &CompositionCoefficients) -> Vec<u128> {
-    // build constraint evaluation values from the leaves of constraint Merkle proof
+/// Builds constraint evaluation values from the leaves of constraint Merkle proof.
+fn get_constraint_evaluations(proof: &StarkProof, t_positions: &[usize], c_positions: &[usize]) -> Vec<u128> {
     let mut evaluations: Vec<u128> = Vec::with_capacity(t_positions.len());
     let leaves = proof.constraint_proof().values;
     for &position in t_positions.iter() {
@@ -145,13 +655,16 @@ fn compose_constraints(proof: &StarkProof, t_positions: &[usize], c_positions: &
         let element_bytes = &leaves[leaf_idx][element_start..(element_start + 16)];
         evaluations.push(field::from_bytes(element_bytes));
     }
+    return evaluations;
+}
 
+fn compose_constraints(evaluations: &[u128], proof: &StarkProof, t_positions: &[usize], z: u128, evaluation_at_z: u128, cc: &CompositionCoefficients) -> Vec<u128> {
     let lde_root = field::get_root_of_unity(proof.domain_size());
 
     // divide out deep point from the evaluations
     let mut result = Vec::with_capacity(evaluations.len());
-    for (evaluation, &position) in evaluations.into_iter().zip(t_positions) {
-        let x = field::exp(lde_root, position as u128);
+    for (&evaluation, &position) in evaluations.iter().zip(t_positions) {
+        let x = field::mul(field::exp(lde_root, position as u128), DOMAIN_OFFSET);
 
         // compute C(x) = (P(x) - P(z)) / (x - z)
         let composition = field::div(field::sub(evaluation, evaluation_at_z), field::sub(x, z));
@@ -0,0 +1,286 @@
+use std::any::Any;
+use std::sync::mpsc::{ self, Sender };
+use std::thread::{ self, JoinHandle };
+use crate::{
+    math::field,
+    processor::{ self, Tape, InputCallback },
+    CustomConstraints, Program, ProofOptions, ProverContext, ProverError, StarkProof, InputError, ExecutionError,
+    MAX_OUTPUTS, MAX_PUBLIC_INPUTS, DEFAULT_STEP_LIMIT, OP_COUNTER_IDX,
+};
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+type ExecutionResult = Result<(Vec<Vec<u128>>, usize, usize), String>;
+
+/// Executes a program while its secret inputs are still arriving. The program starts executing
+/// as soon as the executor is created; whenever the program reads a value which has not been
+/// pushed yet, execution waits until the value arrives. Once all inputs have been pushed,
+/// finalize() completes the execution trace and generates a proof of execution.
+pub struct StreamingExecutor {
+    program         : Program,
+    public_inputs   : Vec<u128>,
+    tape_a          : Sender<u128>,
+    tape_b          : Sender<u128>,
+    execution       : JoinHandle<ExecutionResult>,
+}
+
+// STREAMING EXECUTOR IMPLEMENTATION
+// ================================================================================================
+impl StreamingExecutor {
+
+    /// Starts executing the `program` with the stack initialized to `public_inputs`; secret
+    /// inputs are pushed into the executor via push_a() and push_b() methods. Execution is
+    /// aborted if the program does not terminate within DEFAULT_STEP_LIMIT steps; the execution
+    /// step limit of the options passed to finalize() is applied as well.
+    ///
+    /// Same as for ProgramInputs, values which are not valid field elements are reduced by the
+    /// field modulus, and an error is returned if there are more than MAX_PUBLIC_INPUTS inputs.
+    pub fn new(program: &Program, public_inputs: &[u128]) -> Result<StreamingExecutor, InputError> {
+        return StreamingExecutor::with_step_limit(program, public_inputs, DEFAULT_STEP_LIMIT);
+    }
+
+    /// Same as new(), but execution is aborted as soon as the program executes more than
+    /// `step_limit` steps; this should be set to the execution step limit of the options with
+    /// which the proof is to be generated.
+    pub fn with_step_limit(program: &Program, public_inputs: &[u128], step_limit: usize) -> Result<StreamingExecutor, InputError> {
+        if public_inputs.len() > MAX_PUBLIC_INPUTS {
+            return Err(InputError::InitialStackTooLarge { len: public_inputs.len(), max: MAX_PUBLIC_INPUTS });
+        }
+        let public_inputs: Vec<u128> = public_inputs.iter().map(|&value| field::reduce(value)).collect();
+
+        let (tape_a, receiver_a) = mpsc::channel();
+        let (tape_b, receiver_b) = mpsc::channel();
+
+        // the tapes block until the next value is pushed, or until the executor is finalized
+        let callback_a: InputCallback = Box::new(move || receiver_a.recv().ok());
+        let callback_b: InputCallback = Box::new(move || receiver_b.recv().ok());
+
+        let execution = {
+            let program = program.clone();
+            let public_inputs = public_inputs.clone();
+            thread::spawn(move || processor::execute_with_tapes(&program, &public_inputs,
+                Tape::from_callback(callback_a), Tape::from_callback(callback_b), step_limit))
+        };
+
+        return Ok(StreamingExecutor {
+            program         : program.clone(),
+            public_inputs,
+            tape_a, tape_b, execution,
+        });
+    }
+
+    /// Appends `value` to the end of secret input tape A; the value is reduced by the field
+    /// modulus. Returns an error if the program has already terminated and cannot consume any
+    /// more inputs.
+    pub fn push_a(&self, value: u128) -> Result<(), String> {
+        return self.tape_a.send(field::reduce(value))
+            .map_err(|_| String::from("cannot push input onto tape A: program execution has terminated"));
+    }
+
+    /// Appends `value` to the end of secret input tape B; the value is reduced by the field
+    /// modulus. Returns an error if the program has already terminated and cannot consume any
+    /// more inputs.
+    pub fn push_b(&self, value: u128) -> Result<(), String> {
+        return self.tape_b.send(field::reduce(value))
+            .map_err(|_| String::from("cannot push input onto tape B: program execution has terminated"));
+    }
+
+    /// Closes the input tapes, waits for the program to terminate, and returns the top
+    /// `num_outputs` values of the stack together with a STARK-based proof of execution.
+    ///
+    /// An error is returned if more than MAX_OUTPUTS outputs are requested, if the program fails
+    /// (e.g. because it tries to read more inputs than were pushed), if some of the pushed
+    /// inputs were not consumed by the program, or if the program executed more operations than
+    /// allowed by the execution step limit of the `options`.
+    pub fn finalize(self, num_outputs: usize, options: &ProofOptions) -> Result<(Vec<u128>, StarkProof), ProverError> {
+        // dropping the senders signals to the tapes that no more values will arrive
+        let StreamingExecutor { program, public_inputs, tape_a, tape_b, execution } = self;
+        drop(tape_a);
+        drop(tape_b);
+        if num_outputs > MAX_OUTPUTS {
+            return Err(ProverError::TooManyOutputs { requested: num_outputs, max: MAX_OUTPUTS });
+        }
+
+        let (trace, ctx_depth, loop_depth) = match execution.join() {
+            Ok(Ok(result)) => result,
+            Ok(Err(message)) => return Err(ProverError::ExecutionFailed(message)),
+            Err(err) => return Err(ProverError::ExecutionFailed(get_panic_message(err))),
+        };
+
+        // the executor may have been created with a larger step limit than the one in options
+        let step_limit = options.execution_step_limit();
+        let op_count = *trace[OP_COUNTER_IDX].last().unwrap_or(&field::ZERO);
+        if op_count > step_limit as u128 {
+            let err = ExecutionError::StepLimitExceeded { limit: step_limit };
+            return Err(ProverError::ExecutionFailed(err.to_string()));
+        }
+
+        let (outputs, proof) = crate::prove_trace(&program, trace, ctx_depth, loop_depth, &public_inputs,
+            num_outputs, &CustomConstraints::new(), options, &mut ProverContext::new())?;
+        return Ok((outputs.stack().to_vec(), proof));
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn get_panic_message(err: Box<dyn Any + Send>) -> String {
+    if let Some(message) = err.downcast_ref::<String>() {
+        return message.clone();
+    }
+    return match err.downcast_ref::<&str>() {
+        Some(&message) => String::from(message),
+        None => String::from("unknown error"),
+    };
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::sync::mpsc;
+    use std::thread;
+    use crate::{
+        math::field, processor::{ self, Tape }, assembly, ProgramInputs, ProofOptions, ProverError, InputError,
+        MAX_OUTPUTS, MAX_PUBLIC_INPUTS };
+    use super::StreamingExecutor;
+
+    const SOURCE: &str = "
+    begin
+        read.a read.ab mul add
+        read.a eq not
+        push.5 read.a mul add
+    end";
+
+    #[test]
+    fn streaming_trace_matches_upfront_trace() {
+        let program = assembly::compile(SOURCE).unwrap();
+        let (tape_a, tape_b) = (vec![3, 4, 7, 11], vec![5]);
+
+        let inputs = ProgramInputs::new(&[1], &tape_a, &tape_b);
+        let expected = processor::execute(&program, &inputs);
+
+        // push inputs one at a time from a separate thread
+        let (sender_a, receiver_a) = mpsc::channel();
+        let (sender_b, receiver_b) = mpsc::channel();
+        let feeder = thread::spawn(move || {
+            for (i, &value) in tape_a.iter().enumerate() {
+                sender_a.send(value).unwrap();
+                if let Some(&value) = tape_b.get(i) {
+                    sender_b.send(value).unwrap();
+                }
+                thread::yield_now();
+            }
+        });
+
+        let tape_a = Tape::from_callback(Box::new(move || receiver_a.recv().ok()));
+        let tape_b = Tape::from_callback(Box::new(move || receiver_b.recv().ok()));
+        let streamed = processor::execute_with_tapes(&program, &[1], tape_a, tape_b, crate::DEFAULT_STEP_LIMIT).unwrap();
+        feeder.join().unwrap();
+
+        assert_eq!(expected, streamed);
+    }
+
+    #[test]
+    fn streaming_executor() {
+        let program = assembly::compile(SOURCE).unwrap();
+        let options = ProofOptions::default();
+
+        let executor = StreamingExecutor::new(&program, &[1]).unwrap();
+        for &value in [3, 4, 7, 11].iter() {
+            executor.push_a(value).unwrap();
+        }
+        executor.push_b(5).unwrap();
+        let (outputs, proof) = executor.finalize(1, &options).unwrap();
+
+        let inputs = ProgramInputs::new(&[1], &[3, 4, 7, 11], &[5]);
+        assert_eq!(crate::simulate(&program, &inputs, 1), outputs);
+        assert_eq!(Ok(true), crate::verify(program.hash(), &[1], &outputs, &proof));
+    }
+
+    #[test]
+    fn finalize_with_pending_inputs() {
+        let program = assembly::compile(SOURCE).unwrap();
+        let options = ProofOptions::default();
+
+        // an extra value is pushed onto tape A
+        let executor = StreamingExecutor::new(&program, &[1]).unwrap();
+        for &value in [3, 4, 7, 11, 13].iter() {
+            executor.push_a(value).unwrap();
+        }
+        executor.push_b(5).unwrap();
+        match executor.finalize(1, &options) {
+            Err(ProverError::ExecutionFailed(message)) => assert!(message.contains("still pending")),
+            _ => panic!("expected execution failure"),
+        }
+
+        // too few values are pushed onto tape A
+        let executor = StreamingExecutor::new(&program, &[1]).unwrap();
+        executor.push_a(3).unwrap();
+        executor.push_b(5).unwrap();
+        match executor.finalize(1, &options) {
+            Err(ProverError::ExecutionFailed(message)) => assert!(message.starts_with("attempt to read from empty tape A")),
+            _ => panic!("expected execution failure"),
+        }
+    }
+
+    #[test]
+    fn finalize_with_step_limit() {
+        // the loop never exits because every iteration leaves 1 at the top of the stack
+        let program = assembly::compile("begin push.1 while.true push.1 end end").unwrap();
+        let options = ProofOptions::default().with_execution_step_limit(4096);
+
+        let executor = StreamingExecutor::with_step_limit(&program, &[], options.execution_step_limit()).unwrap();
+        match executor.finalize(1, &options) {
+            Err(ProverError::ExecutionFailed(message)) => assert_eq!("program execution exceeded the limit of 4096 steps", message),
+            _ => panic!("expected execution failure"),
+        }
+
+        // the limit of the options is applied even if the executor was created without it
+        let program = assembly::compile("begin repeat.5000 push.3 drop end end").unwrap();
+        let executor = StreamingExecutor::new(&program, &[]).unwrap();
+        match executor.finalize(1, &options) {
+            Err(ProverError::ExecutionFailed(message)) => assert_eq!("program execution exceeded the limit of 4096 steps", message),
+            _ => panic!("expected execution failure"),
+        }
+
+        // a program which terminates within the limit is proven as usual
+        let program = assembly::compile("begin repeat.64 push.3 drop end end").unwrap();
+        let executor = StreamingExecutor::new(&program, &[]).unwrap();
+        let (outputs, proof) = executor.finalize(1, &options).unwrap();
+        assert_eq!(Ok(true), crate::verify(program.hash(), &[], &outputs, &proof));
+    }
+
+    #[test]
+    fn non_canonical_inputs() {
+        let program = assembly::compile(SOURCE).unwrap();
+        let options = ProofOptions::default();
+
+        // values which are not valid field elements are reduced in the same way as by
+        // ProgramInputs, and the proof attests to canonical outputs
+        let executor = StreamingExecutor::new(&program, &[field::MODULUS + 1]).unwrap();
+        for &value in [field::MODULUS + 3, 4, 7, 11].iter() {
+            executor.push_a(value).unwrap();
+        }
+        executor.push_b(field::MODULUS + 5).unwrap();
+        let (outputs, proof) = executor.finalize(1, &options).unwrap();
+
+        let inputs = ProgramInputs::new(&[1], &[3, 4, 7, 11], &[5]);
+        assert_eq!(crate::simulate(&program, &inputs, 1), outputs);
+        assert_eq!(Ok(true), crate::verify(program.hash(), &[1], &outputs, &proof));
+    }
+
+    #[test]
+    fn too_many_inputs_or_outputs() {
+        let program = assembly::compile(SOURCE).unwrap();
+
+        let public_inputs = vec![1; MAX_PUBLIC_INPUTS + 1];
+        let result = StreamingExecutor::new(&program, &public_inputs);
+        assert_eq!(Some(InputError::InitialStackTooLarge { len: MAX_PUBLIC_INPUTS + 1, max: MAX_PUBLIC_INPUTS }), result.err());
+
+        let executor = StreamingExecutor::new(&program, &[1]).unwrap();
+        let result = executor.finalize(MAX_OUTPUTS + 1, &ProofOptions::default());
+        assert_eq!(Some(ProverError::TooManyOutputs { requested: MAX_OUTPUTS + 1, max: MAX_OUTPUTS }), result.err());
+    }
+}
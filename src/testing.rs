@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use rand::{ Rng, SeedableRng, rngs::StdRng };
+use crate::{
+    math::field, stark::{ StackEvaluator, TraceState, EvalStep, lde_domain_offset },
+    blocks::ProgramBlock, Program, ProgramInputs, OpCode, assembly, MAX_STACK_DEPTH,
+};
+
+// CONSTANTS
+// ================================================================================================
+const MAX_PUBLIC_INPUTS: usize = 4;
+const MAX_PROGRAM_DEPTH: usize = MAX_STACK_DEPTH / 2;
+const FIXTURE_STACK_DEPTH: usize = 8;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Stack constraint evaluator together with a pair of random trace states; this can be used
+/// to benchmark and cross-check `evaluate()` and `evaluate_at()` evaluation paths.
+pub struct StackEvaluatorFixture {
+    evaluator   : StackEvaluator,
+    current     : TraceState,
+    next        : TraceState,
+    result      : Vec<u128>,
+    domain_root : u128,
+    domain_offset: u128,
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Generates a random program of no more than `max_ops` instructions together with inputs
+/// against which the program can be executed successfully. The program and the inputs are
+/// fully determined by the `seed`.
+///
+/// To make sure the program is valid, stack values are tracked during generation; thus,
+/// instructions which would underflow the stack, divide by zero, or apply boolean operations
+/// to non-binary values are never emitted.
+pub fn random_program(seed: u64, max_ops: usize) -> (Program, ProgramInputs) {
+    assert!(max_ops > 0, "max_ops must be greater than 0");
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // build random public inputs; the stack is modeled so that the last value is at the top
+    let num_public_inputs = rng.gen_range(0, MAX_PUBLIC_INPUTS + 1);
+    let public_inputs: Vec<u128> = (0..num_public_inputs).map(|_| random_value(&mut rng)).collect();
+    let mut stack: Vec<u128> = public_inputs.iter().rev().cloned().collect();
+    let mut tape_a = Vec::new();
+
+    let num_ops = rng.gen_range(1, max_ops + 1);
+    let mut ops = Vec::with_capacity(num_ops);
+    while ops.len() < num_ops {
+        if let Some(op) = random_op(&mut rng, &mut stack, &mut tape_a) {
+            ops.push(op);
+        }
+    }
+
+    let source = format!("begin {} end", ops.join(" "));
+    let program = assembly::compile(&source)
+        .unwrap_or_else(|err| panic!("failed to compile random program: {}", err.message()));
+    let inputs = ProgramInputs::new(&public_inputs, &tape_a, &[]);
+
+    return (program, inputs);
+}
+
+/// Counts how many times each operation appears in the Span blocks of the specified programs;
+/// operations which do not appear in any of the programs are not included in the result.
+///
+/// Operations are counted statically: operations of a loop body are counted once regardless
+/// of the number of iterations, and flow control operations implied by program structure
+/// (e.g. TEND or WRAP) are not counted.
+pub fn coverage(programs: &[Program]) -> HashMap<OpCode, usize> {
+    let mut counts = HashMap::new();
+    for program in programs.iter() {
+        count_ops(program.root().body(), &mut counts);
+    }
+    return counts;
+}
+
+// STACK EVALUATOR FIXTURE IMPLEMENTATION
+// ================================================================================================
+impl StackEvaluatorFixture {
+
+    /// Returns a new fixture for traces of the specified length extended by the specified
+    /// extension factor; trace states are generated randomly from the `seed`.
+    pub fn new(trace_length: usize, extension_factor: usize, seed: u64) -> StackEvaluatorFixture {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let width = TraceState::compute_decoder_width(0, 0) + FIXTURE_STACK_DEPTH;
+
+        let current: Vec<u128> = (0..width).map(|_| random_value(&mut rng)).collect();
+        let next: Vec<u128> = (0..width).map(|_| random_value(&mut rng)).collect();
+
+        let domain_offset = lde_domain_offset();
+        let evaluator = StackEvaluator::new(trace_length, extension_factor, domain_offset, FIXTURE_STACK_DEPTH);
+        let result = vec![field::ZERO; evaluator.constraint_degrees().len()];
+
+        return StackEvaluatorFixture {
+            evaluator, result,
+            current     : TraceState::from_vec(0, 0, FIXTURE_STACK_DEPTH, &current),
+            next        : TraceState::from_vec(0, 0, FIXTURE_STACK_DEPTH, &next),
+            domain_root : field::get_root_of_unity(trace_length * extension_factor),
+            domain_offset,
+        };
+    }
+
+    /// Same as new(), but op bits of the current state are set to the binary encoding of
+    /// `op_code`; this mimics a step of the execution trace at which `op_code` is executed.
+    pub fn with_op_code(trace_length: usize, extension_factor: usize, op_code: OpCode, seed: u64) -> StackEvaluatorFixture {
+        let mut fixture = StackEvaluatorFixture::new(trace_length, extension_factor, seed);
+        let op_code = op_code as u128;
+        let mut op_bits = [field::ZERO; 10];
+        for i in 0..7 {
+            op_bits[3 + i] = (op_code >> i) & 1;
+        }
+        fixture.current.set_op_bits(op_bits);
+        return fixture;
+    }
+
+    /// Returns x coordinate in the evaluation domain corresponding to the specified step.
+    pub fn domain_point(&self, step: usize) -> u128 {
+        return field::mul(field::exp(self.domain_root, step as u128), self.domain_offset);
+    }
+
+    /// Evaluates stack constraints at the specified step of the evaluation domain.
+    pub fn evaluate(&mut self, step: usize) -> &[u128] {
+        // evaluations are aggregated into the result, and thus, it must be reset first
+        self.result.iter_mut().for_each(|v| *v = field::ZERO);
+        self.evaluator.evaluate(&self.current, &self.next, EvalStep(step), &mut self.result);
+        return &self.result;
+    }
+
+    /// Evaluates stack constraints at the specified x coordinate.
+    pub fn evaluate_at(&mut self, x: u128) -> &[u128] {
+        self.result.iter_mut().for_each(|v| *v = field::ZERO);
+        self.evaluator.evaluate_at(&self.current, &self.next, x, &mut self.result);
+        return &self.result;
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Adds operations in Span blocks of the `blocks` (and all of their descendants) to `counts`.
+fn count_ops(blocks: &[ProgramBlock], counts: &mut HashMap<OpCode, usize>) {
+    for block in blocks.iter() {
+        match block {
+            ProgramBlock::Span(block) => {
+                for step in 0..block.length() {
+                    let (op_code, _) = block.get_op(step);
+                    *counts.entry(op_code).or_insert(0) += 1;
+                }
+            },
+            ProgramBlock::Group(block) => count_ops(block.body(), counts),
+            ProgramBlock::Switch(block) => {
+                count_ops(block.true_branch(), counts);
+                count_ops(block.false_branch(), counts);
+            },
+            ProgramBlock::Loop(block) => count_ops(block.body(), counts),
+        }
+    }
+}
+
+/// Tries to append a random instruction to the program; if the selected instruction cannot be
+/// executed against the current state of the stack, None is returned.
+fn random_op(rng: &mut StdRng, stack: &mut Vec<u128>, tape_a: &mut Vec<u128>) -> Option<String> {
+    let depth = stack.len();
+    let op = match rng.gen_range(0, 14) {
+        0 if depth < MAX_PROGRAM_DEPTH => {
+            let value = random_value(rng);
+            stack.push(value);
+            format!("push.{}", value)
+        },
+        1 if depth < MAX_PROGRAM_DEPTH => {
+            let value = random_value(rng);
+            tape_a.push(value);
+            stack.push(value);
+            String::from("read.a")
+        },
+        2 if depth >= 1 && depth < MAX_PROGRAM_DEPTH => {
+            stack.push(stack[depth - 1]);
+            String::from("dup")
+        },
+        3 if depth >= 2 && depth < MAX_PROGRAM_DEPTH - 1 => {
+            stack.push(stack[depth - 2]);
+            stack.push(stack[depth - 1]);
+            String::from("dup.2")
+        },
+        4 if depth >= 2 => {
+            stack.swap(depth - 1, depth - 2);
+            String::from("swap")
+        },
+        5 if depth >= 1 => {
+            stack.pop();
+            String::from("drop")
+        },
+        6 if depth >= 2 => {
+            let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+            stack.push(field::add(a, b));
+            String::from("add")
+        },
+        7 if depth >= 2 => {
+            let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+            stack.push(field::sub(a, b));
+            String::from("sub")
+        },
+        8 if depth >= 2 => {
+            let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+            stack.push(field::mul(a, b));
+            String::from("mul")
+        },
+        9 if depth >= 2 && stack[depth - 1] != field::ZERO => {
+            let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+            stack.push(field::div(a, b));
+            String::from("div")
+        },
+        10 if depth >= 1 => {
+            stack[depth - 1] = field::neg(stack[depth - 1]);
+            String::from("neg")
+        },
+        11 if depth >= 1 && stack[depth - 1] != field::ZERO => {
+            stack[depth - 1] = field::inv(stack[depth - 1]);
+            String::from("inv")
+        },
+        12 if depth >= 2 => {
+            let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+            stack.push(if a == b { field::ONE } else { field::ZERO });
+            String::from("eq")
+        },
+        13 if depth >= 1 && is_binary(stack[depth - 1]) => {
+            stack[depth - 1] = field::sub(field::ONE, stack[depth - 1]);
+            String::from("not")
+        },
+        _ => return None,
+    };
+
+    return Some(op);
+}
+
+/// Returns a random field element; small values are generated more frequently to make
+/// sure equality comparisons and boolean operations get exercised.
+fn random_value(rng: &mut StdRng) -> u128 {
+    return match rng.gen_range(0, 4) {
+        0 => rng.gen_range(0, 2),
+        1 => rng.gen_range(0, 16),
+        _ => rng.gen_range(0, field::MODULUS),
+    };
+}
+
+fn is_binary(value: u128) -> bool {
+    return value == field::ZERO || value == field::ONE;
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+    use proptest::prelude::*;
+    use crate::{ ProofOptions };
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(50))]
+
+        #[test]
+        fn prove_verify_random_programs(seed in any::<u64>()) {
+            let (program, inputs) = super::random_program(seed, 32);
+
+            let options = ProofOptions::default();
+            let (outputs, proof) = crate::execute(&program, &inputs, 4, &options);
+            let result = crate::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+            prop_assert_eq!(Ok(true), result);
+        }
+    }
+
+    #[test]
+    fn stack_evaluator_fixture() {
+        let mut fixture = super::StackEvaluatorFixture::new(32, 8, 1);
+        for &step in [0, 13, 100, 255].iter() {
+            let expected = fixture.evaluate(step).to_vec();
+            let x = fixture.domain_point(step);
+            assert_eq!(expected, fixture.evaluate_at(x));
+        }
+    }
+
+    #[test]
+    fn stack_evaluator_fixture_with_op_code() {
+        // evaluation at domain steps skips hash rounds when RESCR flag is zero; evaluation at
+        // out-of-domain points never does, and thus, both evaluations must match for all ops
+        use crate::OpCode;
+        for &op_code in [OpCode::Noop, OpCode::Add, OpCode::Push, OpCode::RescR].iter() {
+            let mut fixture = super::StackEvaluatorFixture::with_op_code(32, 8, op_code, 1);
+            for &step in [0, 3, 8, 13, 24, 77, 255].iter() {
+                let expected = fixture.evaluate(step).to_vec();
+                let x = fixture.domain_point(step);
+                assert_eq!(expected, fixture.evaluate_at(x));
+            }
+        }
+    }
+
+    #[test]
+    fn coverage() {
+        use crate::OpCode;
+
+        let programs = vec![
+            crate::assembly::compile("begin push.1 push.2 add end").unwrap(),
+            crate::assembly::compile("begin push.3 hash.2 end").unwrap(),
+        ];
+        let counts = super::coverage(&programs);
+
+        let expected: HashMap<OpCode, usize> = [
+            (OpCode::Begin, 2), (OpCode::Noop, 43), (OpCode::Push, 3), (OpCode::Add, 1),
+            (OpCode::Pad2, 2), (OpCode::RescR, 10), (OpCode::Drop4, 1),
+        ].iter().cloned().collect();
+        assert_eq!(expected, counts);
+
+        // operations in all branches and loop bodies are counted
+        let program = crate::assembly::compile("begin read if.true add else mul end while.true dup end end").unwrap();
+        let counts = super::coverage(&[program]);
+        assert_eq!(Some(&1), counts.get(&OpCode::Mul));
+        assert_eq!(Some(&1), counts.get(&OpCode::Not));
+        assert_eq!(Some(&3), counts.get(&OpCode::Assert));
+        assert_eq!(None, counts.get(&OpCode::RescR));
+    }
+
+    #[test]
+    fn random_program_is_deterministic() {
+        let (program1, inputs1) = super::random_program(42, 32);
+        let (program2, inputs2) = super::random_program(42, 32);
+        assert_eq!(program1.hash(), program2.hash());
+        assert_eq!(inputs1.get_public_inputs(), inputs2.get_public_inputs());
+        assert_eq!(inputs1.get_secret_inputs(), inputs2.get_secret_inputs());
+    }
+}
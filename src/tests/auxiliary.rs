@@ -0,0 +1,156 @@
+use crate::{ AuxTrace, AuxFrame, CustomConstraints, ProofOptions, ProgramInputs, TraceState, math::field, assembly };
+use super::super::{ execute_with_constraints, verify_with_constraints, verify };
+
+#[test]
+fn permutation_argument() {
+    let custom = CustomConstraints::new().with_aux_trace(PermutationCheck(0, 1));
+    let options = ProofOptions::default();
+
+    // the top two stack registers hold (a, b) for 2 steps, (b, a) for 2 steps, and (b, b) for
+    // all other steps; thus, values of the registers are permutations of each other
+    let program = assembly::compile("begin swap noop dup end").unwrap();
+    let inputs = ProgramInputs::from_public(&[3, 5]);
+    let (outputs, proof) = execute_with_constraints(&program, &inputs, 2, &custom, &options);
+    assert_eq!(vec![5, 5], outputs);
+    assert!(proof.aux_root().is_some());
+
+    let result = verify_with_constraints(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &custom);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn permutation_argument_fail() {
+    let custom = CustomConstraints::new().with_aux_trace(PermutationCheck(0, 1));
+    let options = ProofOptions::default();
+
+    let program = assembly::compile("begin swap noop dup end").unwrap();
+    let inputs = ProgramInputs::from_public(&[3, 5]);
+    let (outputs, proof) = execute_with_constraints(&program, &inputs, 2, &custom, &options);
+
+    // values of the 1st and the 3rd registers are not permutations of each other
+    let other = CustomConstraints::new().with_aux_trace(PermutationCheck(0, 2));
+    let result = verify_with_constraints(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &other);
+    assert!(result != Ok(true));
+
+    // the proof cannot be verified without auxiliary registers
+    let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    let expected = "proof commits to auxiliary trace registers which are not defined by custom constraints";
+    assert_eq!(Err(String::from(expected)), result);
+}
+
+#[test]
+fn permutation_argument_with_public_tape() {
+    // a register is always a permutation of itself
+    let custom = CustomConstraints::new().with_aux_trace(PermutationCheck(0, 0));
+    let options = ProofOptions::default().with_public_tape();
+
+    // the write counter is appended to the auxiliary registers defined by custom constraints
+    let program = assembly::compile("begin push.3 dup write push.5 write mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2]);
+    let (outputs, proof) = execute_with_constraints(&program, &inputs, 1, &custom, &options);
+    assert_eq!(vec![6], outputs);
+    assert_eq!(2, proof.get_aux_at_z1().len());
+    assert_eq!(2, proof.tape().unwrap().values.len());
+
+    let result = verify_with_constraints(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &custom);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn forged_permutation_argument_fail() {
+    // the prover commits to a product column whose final value is forced to 1 even though the
+    // 1st and the 3rd registers are not permutations of each other; the column is committed to
+    // consistently, and thus, passes Merkle checks, but violates the grand product constraint
+    let forged = CustomConstraints::new().with_aux_trace(ForgedPermutationCheck(PermutationCheck(0, 2)));
+    let options = ProofOptions::default();
+
+    let program = assembly::compile("begin swap noop dup end").unwrap();
+    let inputs = ProgramInputs::from_public(&[3, 5]);
+    let (outputs, proof) = execute_with_constraints(&program, &inputs, 2, &forged, &options);
+    assert!(proof.aux_root().is_some());
+
+    // the verifier evaluates the actual constraint at the out-of-domain point, and thus, the
+    // composition of the committed values is not of low degree
+    let custom = CustomConstraints::new().with_aux_trace(PermutationCheck(0, 2));
+    let result = verify_with_constraints(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &custom);
+    let expected = "verification of low-degree proof failed: evaluations did not match column value at depth 0";
+    assert_eq!(Err(String::from(expected)), result);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Auxiliary trace which checks that values of user stack registers `.0` and `.1` at all steps
+/// but the last one are permutations of each other; the check is a grand product argument:
+/// p_0 = 1 and p_{i+1} = p_i * (a_i + r) / (b_i + r) for a random r, and p_{n-1} = 1.
+struct PermutationCheck(usize, usize);
+
+impl AuxTrace for PermutationCheck {
+    fn width(&self) -> usize {
+        return 1;
+    }
+
+    fn num_challenges(&self) -> usize {
+        return 1;
+    }
+
+    fn build_registers(&self, trace: &[TraceState], challenges: &[u128]) -> Vec<Vec<u128>> {
+        let r = challenges[0];
+        let mut product = vec![field::ONE; trace.len()];
+        for i in 0..(trace.len() - 1) {
+            let a = field::add(trace[i].user_stack()[self.0], r);
+            let b = field::add(trace[i].user_stack()[self.1], r);
+            product[i + 1] = field::div(field::mul(product[i], a), b);
+        }
+        return vec![product];
+    }
+
+    fn constraint_degrees(&self) -> Vec<usize> {
+        return vec![2];
+    }
+
+    fn evaluate(&self, frame: &AuxFrame, challenges: &[u128], _x: u128, result: &mut [u128]) {
+        let r = challenges[0];
+        let a = field::add(frame.current.user_stack()[self.0], r);
+        let b = field::add(frame.current.user_stack()[self.1], r);
+        result[0] = field::sub(field::mul(frame.aux_next[0], b), field::mul(frame.aux_current[0], a));
+    }
+
+    fn boundary_values(&self, _challenges: &[u128]) -> (Vec<u128>, Vec<u128>) {
+        return (vec![field::ONE], vec![field::ONE]);
+    }
+}
+
+/// Auxiliary trace which builds the product column of the wrapped permutation check, but forces
+/// the final value of the column to satisfy the boundary constraint, and claims that all of its
+/// transition constraints are satisfied; this is used to simulate a dishonest prover.
+struct ForgedPermutationCheck(PermutationCheck);
+
+impl AuxTrace for ForgedPermutationCheck {
+    fn width(&self) -> usize {
+        return self.0.width();
+    }
+
+    fn num_challenges(&self) -> usize {
+        return self.0.num_challenges();
+    }
+
+    fn build_registers(&self, trace: &[TraceState], challenges: &[u128]) -> Vec<Vec<u128>> {
+        let mut registers = self.0.build_registers(trace, challenges);
+        let last_step = trace.len() - 1;
+        registers[0][last_step] = field::ONE;
+        return registers;
+    }
+
+    fn constraint_degrees(&self) -> Vec<usize> {
+        return self.0.constraint_degrees();
+    }
+
+    fn evaluate(&self, _frame: &AuxFrame, _challenges: &[u128], _x: u128, result: &mut [u128]) {
+        result[0] = field::ZERO;
+    }
+
+    fn boundary_values(&self, challenges: &[u128]) -> (Vec<u128>, Vec<u128>) {
+        return self.0.boundary_values(challenges);
+    }
+}
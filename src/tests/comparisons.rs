@@ -1,7 +1,7 @@
-use crate::{ ProofOptions, math::field };
+use crate::{ ProofOptions, math::field, assembly, debug };
 use super::{
     build_program, OpCode,
-    super::{ execute, verify, ProgramInputs }
+    super::{ execute, execute_with_outputs, verify, verify_with_outputs, pack_bytes, ProgramInputs }
 };
 
 #[test]
@@ -27,6 +27,24 @@ fn eq_operations() {
     assert_eq!(Ok(true), result);
 }
 
+#[test]
+fn iszero_operation() {
+    let program = assembly::compile("begin iszero swap iszero end").unwrap();
+    let options = ProofOptions::default();
+
+    // zero and non-zero values
+    for &(a, b) in [(0, 5), (5, 0), (0, 0), (field::neg(1), 1)].iter() {
+        let inputs = ProgramInputs::from_public(&[a, b]);
+        let expected_result = vec![(b == 0) as u128, (a == 0) as u128];
+
+        let (outputs, proof) = execute(&program, &inputs, 2, &options);
+        assert_eq!(expected_result, outputs);
+
+        let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+    }
+}
+
 #[test]
 fn cmp_operation() {
 
@@ -104,6 +122,89 @@ fn binacc_operation() {
     assert_eq!(Ok(true), result);
 }
 
+#[test]
+fn bits_operation() {
+    let program = assembly::compile("begin bits.8 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[0b1011_0101]);
+    let num_outputs = 8;
+
+    // the value is decomposed into bits with the least significant bit at the top of the stack
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![1, 0, 1, 0, 1, 1, 0, 1], outputs);
+
+    // the bits recompose to the original value
+    let value = outputs.iter().rev().fold(0, |acc, &bit| acc * 2 + bit);
+    assert_eq!(0b1011_0101, value);
+
+    let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn bits_operation_out_of_range() {
+    let program = assembly::compile("begin bits.8 end").unwrap();
+
+    // 256 cannot be represented with 8 bits, and thus, recomposition fails
+    let inputs = ProgramInputs::from_public(&[256]);
+    let violation = debug::check_execution(&program, &inputs).unwrap_err();
+    assert_eq!(OpCode::AssertEq, violation.op_code);
+    assert!(violation.message.starts_with("ASSERTEQ failed"));
+}
+
+#[test]
+fn bytes_operation() {
+    let program = assembly::compile("begin bytes.3 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[0x12a4f0]);
+    let num_outputs = 3;
+
+    // the value is decomposed into bytes with the least significant byte at the top of the stack
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![0xf0, 0xa4, 0x12], outputs);
+
+    let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn bytes_operation_out_of_range() {
+    let program = assembly::compile("begin bytes.2 end").unwrap();
+
+    // 2^16 cannot be represented with 2 bytes, and thus, recomposition fails
+    let inputs = ProgramInputs::from_public(&[0x10000]);
+    let violation = debug::check_execution(&program, &inputs).unwrap_err();
+    assert_eq!(OpCode::AssertEq, violation.op_code);
+    assert!(violation.message.starts_with("ASSERTEQ failed"));
+}
+
+#[test]
+fn unpack_packed_bytes() {
+    // 20 bytes are packed into a length prefix followed by 2 elements of 15 and 5 bytes
+    let bytes: Vec<u8> = (0..20).map(|i| (i * 37 + 11) as u8).collect();
+    let inputs = ProgramInputs::from_public(&pack_bytes(&bytes));
+
+    // unpack both elements so that the first byte ends up at the top of the stack, and then
+    // write all bytes to the output tape
+    let program = assembly::compile("
+    begin
+        drop swap bytes.5 movup.5 bytes.15
+        repeat.20
+            write
+        end
+    end").unwrap();
+
+    let options = ProofOptions::default().with_public_tape();
+    let (outputs, proof) = execute_with_outputs(&program, &inputs, 1, &options);
+    let unpacked: Vec<u8> = outputs.tape().iter().map(|&byte| byte as u8).collect();
+    assert_eq!(bytes, unpacked);
+
+    let result = verify_with_outputs(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_inputs_for_cmp(a: u128, b: u128, size: usize) -> (Vec<u128>, Vec<u128>) {
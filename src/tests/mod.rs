@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 use crate::{
-    ProofOptions, Program, ProgramInputs, OpCode, OpHint,
+    ProofOptions, ProverContext, ProverCheckpoint, ProverError, StarkProof, ProgramOutputs, VerifierCost, VerifierSession, VerifierStatus, VerifyError, VerifyPolicy, DEFAULT_MAX_PROOF_SIZE, ProofError, VerificationKey, OptionsError, ExecutionError, PROOF_HEADER_SIZE,
+    Constraint, CustomConstraints, TraceState, Program, ProgramInputs, OpCode, OpHint,
+    crypto::{ HashFunction, hash },
     blocks::{ ProgramBlock, Span, Group },
     math::field, utils::hasher
 };
 
+mod auxiliary;
 mod branches;
 mod comparisons;
+mod stdlib;
 
 #[test]
 fn execute_verify() {
@@ -44,14 +48,23 @@ fn execute_verify_fail() {
     let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
     assert_eq!(outputs, [3]);
 
-    // wrong inputs
+    // wrong inputs: inputs are absorbed into the transcript, and thus, the seed does not match
     let result = super::verify(program.hash(), &[1, 1], &outputs, &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
+    let err_msg = format!("seed proof-of-work verification failed");
     assert_eq!(Err(err_msg), result);
 
     // wrong outputs
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &[5], &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
+    let err_msg = format!("seed proof-of-work verification failed");
+    assert_eq!(Err(err_msg), result);
+
+    // non-canonical encodings of correct inputs and outputs are rejected
+    let result = super::verify(program.hash(), &[field::MODULUS + 1, 0], &outputs, &proof);
+    let err_msg = format!("public input 0 is not a valid field element: {}", field::MODULUS + 1);
+    assert_eq!(Err(err_msg), result);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &[field::MODULUS + 3], &proof);
+    let err_msg = format!("output 0 is not a valid field element: {}", field::MODULUS + 3);
     assert_eq!(Err(err_msg), result);
 
     // wrong program hash
@@ -62,6 +75,746 @@ fn execute_verify_fail() {
     assert_eq!(Err(err_msg), result);
 }
 
+#[test]
+fn verify_hidden_program() {
+    // both programs output 8, but only the first one is executed by the prover
+    let program1 = crate::assembly::compile("begin push.3 push.5 add end").unwrap();
+    let program2 = crate::assembly::compile("begin push.5 push.3 add end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::none();
+    let (outputs, proof) = super::execute(&program1, &inputs, 1, &options);
+    assert_eq!(vec![8], outputs);
+
+    // the verifier receives only the proof and the hash of the program
+    let proof_bytes = bincode::serialize(&proof).unwrap();
+    let program_hash = *program1.hash();
+    drop(program1);
+
+    let proof = bincode::deserialize::<StarkProof>(&proof_bytes).unwrap();
+    assert_eq!(Ok(true), super::verify(&program_hash, &[], &outputs, &proof));
+
+    // the proof does not attest to execution of a different program with the same outputs
+    let result = super::verify(program2.hash(), &[], &outputs, &proof);
+    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
+    assert_eq!(Err(err_msg), result);
+}
+
+#[test]
+fn verify_from_reader() {
+    let program = crate::assembly::compile("begin push.3 push.5 add push.7 mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 3, &options);
+    let proof_bytes = bincode::serialize(&proof).unwrap();
+
+    // the proof is read in small chunks
+    let reader = ChunkedReader(std::io::Cursor::new(proof_bytes.clone()));
+    let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &VerifyPolicy::default());
+    assert_eq!(Ok(outputs), result);
+
+    // the proof is not valid for the specified inputs
+    let reader = std::io::Cursor::new(proof_bytes.clone());
+    let result = super::verify_from_reader(program.hash(), &[1, 3], reader, &VerifyPolicy::default());
+    let err = String::from("seed proof-of-work verification failed");
+    assert_eq!(Err(ProofError::InvalidProof(err)), result);
+
+    // proofs which do not meet the policy are rejected
+    let security_level = options.security_level(false);
+    let policy = VerifyPolicy { min_security_level: security_level + 1, max_proof_size: None, ..VerifyPolicy::default() };
+    let reader = std::io::Cursor::new(proof_bytes.clone());
+    let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &policy);
+    assert_eq!(Err(ProofError::InsufficientSecurity { found: security_level, required: security_level + 1 }), result);
+
+    let policy = VerifyPolicy { min_security_level: 0, max_proof_size: Some(1024), ..VerifyPolicy::default() };
+    let reader = std::io::Cursor::new(proof_bytes.clone());
+    let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &policy);
+    assert_eq!(Err(ProofError::ProofTooLarge { limit: 1024 }), result);
+
+    let policy = VerifyPolicy { require_input_commitment: true, ..VerifyPolicy::default() };
+    let reader = std::io::Cursor::new(proof_bytes.clone());
+    let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &policy);
+    let err = String::from("proof does not commit to public inputs");
+    assert_eq!(Err(ProofError::InvalidProof(err)), result);
+
+    // by default, the size of the proof is limited: a valid header followed by no input
+    // commitment and an endless stream of outputs is rejected once the limit is reached
+    assert_eq!(Some(DEFAULT_MAX_PROOF_SIZE), VerifyPolicy::default().max_proof_size);
+    let mut prefix = proof_bytes[..PROOF_HEADER_SIZE].to_vec();
+    prefix.push(0);
+    prefix.extend_from_slice(&u64::MAX.to_le_bytes());
+    let reader = std::io::Read::chain(std::io::Cursor::new(prefix), std::io::repeat(0));
+    let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &VerifyPolicy::default());
+    assert_eq!(Err(ProofError::ProofTooLarge { limit: DEFAULT_MAX_PROOF_SIZE }), result);
+
+    // decoded trace info and options are range-checked before the proof is verified
+    let mut malformed = proof_bytes.clone();
+    malformed[34] = 200; // domain depth
+    let reader = std::io::Cursor::new(malformed);
+    let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &VerifyPolicy::default());
+    assert!(matches!(result, Err(ProofError::MalformedProof(_))));
+}
+
+#[test]
+fn verify_with_key() {
+    let program = crate::assembly::compile("begin push.3 push.5 add push.7 mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let options = ProofOptions::default();
+    let (outputs, proof, vk) = super::execute_with_key(&program, &inputs, 3, &options);
+    assert_eq!(program.hash(), vk.program_hash());
+    assert_eq!(field::MODULUS, vk.modulus());
+
+    // the key can be sent to the verifier separately from the proof
+    let vk_bytes = bincode::serialize(&vk).unwrap();
+    let vk = bincode::deserialize::<VerificationKey>(&vk_bytes).unwrap();
+    assert_eq!(Ok(outputs), super::verify_with_key(&vk, &[1, 2], &proof));
+
+    // the proof is not valid for a key of a different program
+    let program2 = crate::assembly::compile("begin push.3 push.5 add push.8 mul end").unwrap();
+    let vk2 = VerificationKey::new(program2.hash(), &options);
+    assert!(super::verify_with_key(&vk2, &[1, 2], &proof).is_err());
+
+    // or for a key which specifies different options
+    let options2 = ProofOptions::default().with_transcript_fn(hash::rescue);
+    let vk3 = VerificationKey::new(program.hash(), &options2);
+    let err = String::from("proof was generated with options which do not match the verification key");
+    assert_eq!(Err(err), super::verify_with_key(&vk3, &[1, 2], &proof));
+}
+
+#[test]
+fn prove_from_trace() {
+    let program = crate::assembly::compile("begin push.3 push.5 add push.7 mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 3, &options);
+
+    // proof generated from a precomputed trace is the same as the one generated by execute()
+    let trace = crate::debug::trace_execution(&program, &inputs);
+    let trace_proof = super::prove_from_trace(program.hash(), trace.clone(), &[1, 2], 3, &options).unwrap();
+    assert_eq!(bincode::serialize(&proof).unwrap(), bincode::serialize(&trace_proof).unwrap());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[1, 2], &outputs, &trace_proof));
+
+    // trace of a different program is rejected
+    let program2 = crate::assembly::compile("begin push.3 push.5 add push.8 mul end").unwrap();
+    let result = super::prove_from_trace(program2.hash(), trace.clone(), &[1, 2], 3, &options);
+    assert!(matches!(result, Err(ProverError::InvalidTrace(_))));
+
+    // trace which does not start with the public inputs is rejected
+    let result = super::prove_from_trace(program.hash(), trace.clone(), &[1, 3], 3, &options);
+    assert!(matches!(result, Err(ProverError::InvalidTrace(_))));
+
+    // tampered trace is rejected: change the top of the stack at step 5
+    let mut bytes = trace.to_bytes();
+    let register = TraceState::compute_decoder_width(trace.ctx_depth(), trace.loop_depth());
+    let offset = crate::debug::TRACE_HEADER_SIZE + (register * trace.length() + 5) * 16;
+    bytes[offset] ^= 1;
+    let tampered = crate::debug::ExecutionTrace::from_bytes(&bytes).unwrap();
+    let result = super::prove_from_trace(program.hash(), tampered, &[1, 2], 3, &options);
+    assert!(matches!(result, Err(ProverError::InvalidTrace(_))));
+
+    // requesting more outputs than the stack can provide is an error rather than a panic
+    let result = super::prove_from_trace(program.hash(), trace.clone(), &[1, 2], crate::MAX_OUTPUTS + 1, &options);
+    assert!(matches!(result, Err(ProverError::TooManyOutputs { .. })));
+}
+
+#[test]
+fn verify_from_truncated_reader() {
+    let program = crate::assembly::compile("begin push.3 push.5 add push.7 mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let (_, proof) = super::execute(&program, &inputs, 3, &ProofOptions::default());
+    let proof_bytes = bincode::serialize(&proof).unwrap();
+
+    // streams truncated at any point are rejected
+    for &length in [0, 1, 100, proof_bytes.len() / 2, proof_bytes.len() - 1].iter() {
+        let reader = ChunkedReader(std::io::Cursor::new(proof_bytes[..length].to_vec()));
+        let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &VerifyPolicy::default());
+        assert_eq!(Err(ProofError::UnexpectedEof), result);
+    }
+
+    // so are malformed streams
+    let mut malformed = proof_bytes.clone();
+    malformed[0] = 0xFF;
+    let reader = std::io::Cursor::new(malformed);
+    let result = super::verify_from_reader(program.hash(), &[1, 2], reader, &VerifyPolicy::default());
+    assert!(result.is_err());
+}
+
+#[test]
+fn verify_expecting() {
+    let program = crate::assembly::compile("begin push.3 push.5 add push.7 mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 3, &options);
+    assert_eq!(vec![56, 1, 2], outputs);
+
+    let result = super::verify_expecting(program.hash(), inputs.get_public_inputs(), &[56, 1, 2], &proof);
+    assert_eq!(Ok(()), result);
+
+    // the proof is valid, but attests to different outputs
+    let result = super::verify_expecting(program.hash(), inputs.get_public_inputs(), &[56, 3, 2], &proof);
+    assert_eq!(Err(VerifyError::OutputMismatch { index: 1, expected: 3, found: 1 }), result);
+
+    let result = super::verify_expecting(program.hash(), inputs.get_public_inputs(), &[56, 1], &proof);
+    assert_eq!(Err(VerifyError::OutputCountMismatch { expected: 2, found: 3 }), result);
+
+    // the proof is not valid for the specified inputs
+    let result = super::verify_expecting(program.hash(), &[1, 3], &[56, 1, 2], &proof);
+    let err = String::from("seed proof-of-work verification failed");
+    assert_eq!(Err(VerifyError::InvalidProof(err)), result);
+}
+
+#[test]
+fn verify_batch_same_program() {
+    let program = crate::assembly::compile("begin push.3 add push.7 mul end").unwrap();
+    let options = ProofOptions::default();
+
+    let mut batch = Vec::new();
+    for i in 0..4 {
+        let inputs = vec![i];
+        let (outputs, proof) = super::execute(&program, &ProgramInputs::from_public(&inputs), 1, &options);
+        assert_eq!(vec![field::mul(i + 3, 7)], outputs);
+        batch.push((inputs, outputs, proof));
+    }
+
+    let items: Vec<(&[u128], &[u128], &StarkProof)> = batch.iter()
+        .map(|(inputs, outputs, proof)| (&inputs[..], &outputs[..], proof))
+        .collect();
+    assert_eq!(Ok(()), super::verify_batch_same_program(program.hash(), &items));
+
+    // outputs of the third item are tampered with
+    let tampered = vec![42];
+    let mut items = items;
+    items[2].1 = &tampered;
+    match super::verify_batch_same_program(program.hash(), &items) {
+        Err((index, VerifyError::InvalidProof(_))) => assert_eq!(2, index),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn verify_incrementally() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+    let num_outputs = 1;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+
+    // both valid and invalid outputs should produce the same result as one-shot verification
+    for outputs in [outputs.clone(), vec![4]].iter() {
+        let expected = super::verify(program.hash(), inputs.get_public_inputs(), outputs, &proof);
+
+        let mut session = VerifierSession::new(program.hash(), inputs.get_public_inputs(), outputs, &proof);
+        let mut num_steps = 0;
+        let result = loop {
+            num_steps += 1;
+            match session.step(1) {
+                VerifierStatus::InProgress => continue,
+                VerifierStatus::Done(result) => break result,
+            }
+        };
+
+        assert_eq!(expected, result);
+        if expected.is_ok() {
+            // every query is composed in a separate step, and every FRI layer is verified in
+            // a separate step
+            assert!(num_steps > options.num_queries() + proof.degree_proof().layers.len());
+        }
+
+        // calling step() after completion returns the same result
+        assert_eq!(VerifierStatus::Done(expected), session.step(1));
+    }
+}
+
+#[test]
+fn derive_challenges() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+
+    // challenges are the same for proofs generated in separate runs
+    let (_, proof1) = super::execute(&program, &inputs, 1, &options);
+    let (_, proof2) = super::execute(&program, &inputs, 1, &options);
+    let challenges = proof1.derive_challenges(inputs.get_public_inputs()).unwrap();
+    assert_eq!(challenges, proof2.derive_challenges(inputs.get_public_inputs()).unwrap());
+
+    assert_eq!(options.num_queries(), challenges.query_positions.len());
+    assert_eq!(proof1.degree_proof().layers.len(), challenges.fri_alphas.len());
+
+    // challenges are squeezed from the transcript selected by the options
+    let options2 = options.clone().with_transcript_fn(hash::rescue);
+    let (_, proof3) = super::execute(&program, &inputs, 1, &options2);
+    let challenges3 = proof3.derive_challenges(inputs.get_public_inputs()).unwrap();
+    assert_ne!(challenges.constraints, challenges3.constraints);
+    assert_ne!(challenges.z, challenges3.z);
+    assert_ne!(challenges.composition, challenges3.composition);
+    assert_ne!(challenges.fri_alphas, challenges3.fri_alphas);
+
+    // query positions cannot be derived for different inputs
+    let err = proof1.derive_challenges(&[1, 3]).unwrap_err();
+    assert_eq!("seed proof-of-work verification failed", err);
+}
+
+#[test]
+fn execute_verify_transcript_fn() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+    let num_outputs = 1;
+
+    for &(transcript_fn, other_id) in [(hash::rescue as HashFunction, 2u8), (hash::poseidon, 0), (hash::blake3, 1)].iter() {
+        let options = ProofOptions::default().with_transcript_fn(transcript_fn);
+        let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+        assert_eq!(outputs, [3]);
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+
+        // transcript hash function is the last field of the proof header; replacing it with
+        // a different function should make the proof fail verification
+        let mut proof_bytes = bincode::serialize(&proof).unwrap();
+        proof_bytes[PROOF_HEADER_SIZE - 1] = other_id;
+        let proof = bincode::deserialize::<StarkProof>(&proof_bytes).unwrap();
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn verifier_work_estimate() {
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 0]);
+
+    // trace length = 32
+    let mut instructions = vec![OpCode::Begin];
+    instructions.resize(15, OpCode::Noop);
+    let program = build_program(instructions, &[]);
+    let (_, proof) = super::execute(&program, &inputs, 1, &options);
+    let short_cost = proof.verifier_work_estimate().unwrap();
+    assert_eq!(VerifierCost { hash_invocations: 1453, field_ops: 43900 }, short_cost);
+
+    // trace length = 512
+    let mut instructions = vec![OpCode::Begin];
+    instructions.resize(255, OpCode::Noop);
+    let program = build_program(instructions, &[]);
+    let (_, proof) = super::execute(&program, &inputs, 1, &options);
+    let long_cost = proof.verifier_work_estimate().unwrap();
+    assert_eq!(VerifierCost { hash_invocations: 3055, field_ops: 49300 }, long_cost);
+
+    // a 16x longer trace adds only a few Merkle path levels and FRI layers
+    assert!(long_cost.hash_invocations < short_cost.hash_invocations * 3);
+    assert!(long_cost.field_ops < short_cost.field_ops * 2);
+}
+
+#[test]
+fn execute_with_remainder_degree() {
+    let program = crate::assembly::compile("begin push.3 push.5 add push.7 mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+
+    // the same program can be proven with different FRI remainder degrees
+    let mut num_layers = Vec::new();
+    for &degree in [7, 255].iter() {
+        let options = ProofOptions::default().with_fri_max_remainder_degree(degree).unwrap();
+        let (outputs, proof) = super::execute(&program, &inputs, 3, &options);
+        assert_eq!(degree, proof.options().fri_max_remainder_degree());
+        num_layers.push(proof.degree_proof().layers.len());
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+    }
+    assert!(num_layers[0] > num_layers[1]);
+
+    // but the degree cannot be too large
+    let result = ProofOptions::default().with_fri_max_remainder_degree(1 << 20);
+    let err = OptionsError::InvalidRemainderDegree { degree: 1 << 20, max_degree: 1023 };
+    assert_eq!(Some(err), result.err());
+}
+
+#[test]
+fn execute_with_context() {
+    let program1 = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+    let program2 = build_program(vec![
+        OpCode::Begin, OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Mul,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Add,   OpCode::Swap, OpCode::Dup2, OpCode::Drop,
+        OpCode::Mul,   OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[2, 1]);
+
+    let mut context = ProverContext::new();
+    for program in [program1, program2].iter() {
+        let (outputs1, proof1) = super::execute(program, &inputs, 1, &options);
+        let (outputs2, proof2) = super::execute_with_context(program, &inputs, 1, &options, &mut context);
+
+        assert_eq!(outputs1, outputs2);
+        assert_eq!(bincode::serialize(&proof1).unwrap(), bincode::serialize(&proof2).unwrap());
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs2, &proof2);
+        assert_eq!(Ok(true), result);
+    }
+
+    // both programs have the same trace length, so the LDE domain is built only once
+    assert_eq!(1, context.domain_count());
+}
+
+#[test]
+fn execute_from_checkpoint() {
+    let program = crate::assembly::compile("begin repeat.16 dup mul push.3 add end write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2]);
+    let options = ProofOptions::default().with_public_tape();
+
+    let (outputs1, proof1) = super::execute(&program, &inputs, 1, &options);
+
+    // the checkpoint is serialized and de-serialized to mimic resuming in a different process
+    let checkpoint = super::execute_to_checkpoint(&program, &inputs, 1, &options).unwrap();
+    let checkpoint_bytes = bincode::serialize(&checkpoint).unwrap();
+    drop(checkpoint);
+
+    let checkpoint = bincode::deserialize::<ProverCheckpoint>(&checkpoint_bytes).unwrap();
+    let (outputs2, proof2) = super::resume_from_checkpoint(&checkpoint, &options).unwrap();
+
+    assert_eq!(outputs1, outputs2);
+    assert_eq!(bincode::serialize(&proof1).unwrap(), bincode::serialize(&proof2).unwrap());
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs2, &proof2);
+    assert_eq!(Ok(true), result);
+
+    // the proof cannot be completed with options which differ from the checkpoint options
+    let other = ProofOptions::new(32, 48, 0, crate::crypto::hash::sha3);
+    let result = super::resume_from_checkpoint(&checkpoint, &other).map(|_| ());
+    assert_eq!(Err(String::from("proof options do not match the options with which the checkpoint was created")),
+        result.map_err(|err| err.to_string()));
+}
+
+#[test]
+fn execute_composed_programs() {
+    let a = crate::assembly::compile("begin push.3 mul push.2 add end").unwrap();
+    let b = crate::assembly::compile("begin swap dup mul add push.4 eq end").unwrap();
+    let inputs = ProgramInputs::from_public(&[5, 7]);
+
+    // running the composed program is the same as feeding the final stack of a into b
+    let intermediate = super::simulate(&a, &inputs, 2);
+    assert_eq!(vec![17, 7], intermediate);
+    let expected = super::simulate(&b, &ProgramInputs::from_public(&intermediate), 2);
+
+    let program = a.then(&b);
+    assert_ne!(a.hash(), program.hash());
+    assert_ne!(b.hash(), program.hash());
+
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 2, &options);
+    assert_eq!(expected, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // the first program ends with a control flow block rather than a span
+    let a = crate::assembly::compile("begin push.1 if.true push.3 mul else push.4 mul end end").unwrap();
+    let intermediate = super::simulate(&a, &inputs, 2);
+    let expected = super::simulate(&b, &ProgramInputs::from_public(&intermediate), 2);
+    assert_eq!(expected, super::simulate(&a.then(&b), &inputs, 2));
+
+    // b consumes more values than a leaves on the stack
+    let b = crate::assembly::compile("begin add add end").unwrap();
+    let violation = crate::debug::check_execution(&a.then(&b), &inputs).unwrap_err();
+    assert!(violation.message.starts_with("stack underflow"));
+}
+
+#[test]
+fn execute_with_threads() {
+    let program = crate::assembly::compile("begin repeat.64 dup mul push.3 add end end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2]);
+    let options = ProofOptions::default();
+
+    // proofs generated with different numbers of threads must be identical
+    let (outputs1, proof1) = super::execute_with_context(&program, &inputs, 1, &options, &mut ProverContext::new());
+    let (outputs8, proof8) = super::execute_with_context(&program, &inputs, 1, &options, &mut ProverContext::with_threads(8));
+
+    assert_eq!(outputs1, outputs8);
+    assert_eq!(bincode::serialize(&proof1).unwrap(), bincode::serialize(&proof8).unwrap());
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs8, &proof8);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn execute_with_auto_threads() {
+    let program = crate::assembly::compile("begin repeat.64 dup mul push.3 add end end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2]);
+    let options = ProofOptions::default();
+
+    // a tiny program is proven in a single thread
+    let trace_length = crate::debug::trace_execution(&program, &inputs).length();
+    assert!(trace_length < crate::MIN_PARALLEL_TRACE_LENGTH);
+    let mut context = ProverContext::auto_threads(trace_length);
+    assert_eq!(1, context.num_threads());
+
+    // long traces are proven in multiple threads, if threads are available
+    let num_threads = ProverContext::auto_threads(1 << 20).num_threads();
+    assert!(num_threads.is_power_of_two());
+    assert_eq!(1, ProverContext::auto_threads(crate::MIN_PARALLEL_TRACE_LENGTH / 2).num_threads());
+
+    // proofs do not depend on the number of threads chosen
+    let (outputs1, proof1) = super::execute_with_context(&program, &inputs, 1, &options, &mut context);
+    let (outputs2, proof2) = super::execute_with_context(&program, &inputs, 1, &options, &mut ProverContext::with_threads(num_threads));
+    assert_eq!(outputs1, outputs2);
+    assert_eq!(bincode::serialize(&proof1).unwrap(), bincode::serialize(&proof2).unwrap());
+}
+
+#[test]
+fn export_composition_coefficients() {
+    let program = crate::assembly::compile("begin repeat.20 dup mul push.3 add end end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2]);
+    let coefficients = super::composition_coefficients(&program, &inputs, 1, &ProofOptions::default());
+
+    let trace_length = coefficients.len() / 8;
+    let degree = crate::math::polynom::degree_of(&coefficients);
+    assert!(degree <= super::max_constraint_degree(trace_length));
+}
+
+#[test]
+fn constraint_mul_count() {
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[2]);
+
+    // longer programs require more multiplications
+    let mut previous = 0;
+    for &n in [16, 64, 256].iter() {
+        let source = format!("begin repeat.{} dup mul push.3 add end end", n);
+        let program = crate::assembly::compile(&source).unwrap();
+        let count = super::constraint_mul_count(&program, &inputs, 1, &options);
+        assert!(count > previous);
+        previous = count;
+    }
+}
+
+#[test]
+fn estimate_proving_time() {
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[2]);
+
+    // longer programs take longer to prove
+    let mut previous = std::time::Duration::from_secs(0);
+    for &n in [16, 64, 256].iter() {
+        let source = format!("begin repeat.{} dup mul push.3 add end end", n);
+        let program = crate::assembly::compile(&source).unwrap();
+        let estimate = super::estimate_proving_time(&program, &inputs, &options);
+        assert!(estimate > previous);
+        previous = estimate;
+    }
+}
+
+#[test]
+fn execute_hash_free_program() {
+    // RESCR flag is zero at all trace steps of a program without hash operations, but hash round
+    // constraints still contribute to the constraint polynomial between trace steps; the proof
+    // must still be valid
+    let program = crate::assembly::compile("begin push.3 push.5 add push.7 mul dup mul end").unwrap();
+    let inputs = ProgramInputs::none();
+    let options = ProofOptions::default();
+
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![3136], outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn execute_with_output_tape() {
+    // the main result is left on the stack, while three metadata values are written to the tape
+    let program = crate::assembly::compile("
+    begin
+        push.3 push.5 add dup write
+        push.7 write
+        dup mul push.11 write
+    end").unwrap();
+    let inputs = ProgramInputs::none();
+
+    let options = ProofOptions::default().with_public_tape();
+    let (outputs, proof) = super::execute_with_outputs(&program, &inputs, 1, &options);
+    assert_eq!(&[64], outputs.stack());
+    assert_eq!(&[8, 7, 11], outputs.tape());
+
+    let result = super::verify_with_outputs(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // the proof does not attest to a different tape
+    let other = ProgramOutputs::new(&[64], &[8, 7, 12]);
+    let result = super::verify_with_outputs(program.hash(), inputs.get_public_inputs(), &other, &proof);
+    assert_eq!(Err(String::from("output tape does not match the tape attested to by the proof")), result);
+
+    // when the tape is not public, values are still returned, but are not bound into the proof
+    let (outputs, proof) = super::execute_with_outputs(&program, &inputs, 1, &ProofOptions::default());
+    assert_eq!(&[8, 7, 11], outputs.tape());
+    assert!(proof.tape().is_none());
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), outputs.stack(), &proof);
+    assert_eq!(Ok(true), result);
+    let result = super::verify_with_outputs(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Err(String::from("output tape was not made public in the proof")), result);
+}
+
+#[test]
+fn execute_with_input_commitment() {
+    let program = crate::assembly::compile("begin add push.3 mul end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let options = ProofOptions::default().with_input_commitment();
+
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(Some(&inputs.commitment()), proof.inputs_hash());
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // changing one of the inputs while keeping the commitment in the proof causes rejection
+    let result = super::verify(program.hash(), &[1, 3], &outputs, &proof);
+    assert_eq!(Err(String::from("public inputs do not match the input commitment in the proof")), result);
+    assert!(super::check_input_commitment(&[1, 2], &proof));
+    assert!(!super::check_input_commitment(&[1, 3], &proof));
+
+    // the commitment to empty inputs is the hash of an empty sequence
+    let program = crate::assembly::compile("begin push.1 push.2 add end").unwrap();
+    let (outputs, proof) = super::execute(&program, &ProgramInputs::none(), 1, &options);
+    assert_eq!(Some(&super::commit_to_inputs(&[])), proof.inputs_hash());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &outputs, &proof));
+    let result = super::verify(program.hash(), &[0], &outputs, &proof);
+    assert_eq!(Err(String::from("public inputs do not match the input commitment in the proof")), result);
+
+    // by default, proofs do not commit to inputs
+    let (_, proof) = super::execute(&program, &ProgramInputs::none(), 1, &ProofOptions::default());
+    assert_eq!(None, proof.inputs_hash());
+}
+
+#[test]
+fn execute_with_memory_budget() {
+    let program = crate::assembly::compile("begin repeat.64 dup mul push.3 add end end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2]);
+
+    // a tiny budget results in an error rather than a failed allocation
+    let options = ProofOptions::default().with_max_memory(1024);
+    let estimated = match super::try_execute(&program, &inputs, 1, &options) {
+        Err(ProverError::MemoryBudgetExceeded { estimated, budget }) => {
+            assert_eq!(1024, budget);
+            estimated
+        },
+        Ok(_) => panic!("proof should not have been generated"),
+        Err(err) => panic!("unexpected error: {}", err),
+    };
+    assert!(estimated > 1024 * 1024);
+
+    // a budget which matches the estimate is sufficient
+    let options = ProofOptions::default().with_max_memory(estimated);
+    let (outputs, proof) = super::try_execute(&program, &inputs, 1, &options).unwrap();
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn try_execute_too_many_outputs() {
+    let program = crate::assembly::compile("begin push.3 add end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1]);
+
+    let result = super::try_execute(&program, &inputs, crate::MAX_OUTPUTS + 1, &ProofOptions::default());
+    assert_eq!(Some(ProverError::TooManyOutputs { requested: crate::MAX_OUTPUTS + 1, max: crate::MAX_OUTPUTS }), result.err());
+}
+
+#[test]
+#[should_panic(expected = "cannot produce more than 8 outputs, but requested 9")]
+fn execute_too_many_outputs() {
+    let program = crate::assembly::compile("begin push.3 add end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1]);
+    super::execute(&program, &inputs, crate::MAX_OUTPUTS + 1, &ProofOptions::default());
+}
+
+#[test]
+fn execute_with_step_limit() {
+    // the loop never exits because every iteration leaves 1 at the top of the stack
+    let program = crate::assembly::compile("begin push.1 while.true push.1 end end").unwrap();
+    let inputs = ProgramInputs::none();
+
+    let options = ProofOptions::default().with_execution_step_limit(4096);
+    let result = super::try_execute(&program, &inputs, 1, &options);
+    assert!(matches!(result, Err(ProverError::ExecutionAborted(ExecutionError::StepLimitExceeded { limit: 4096 }))));
+
+    // a program which terminates within the limit is proven as usual
+    let program = crate::assembly::compile("begin repeat.64 dup mul push.3 add end end").unwrap();
+    let inputs = ProgramInputs::from_public(&[2]);
+    let (outputs, proof) = super::try_execute(&program, &inputs, 1, &options).unwrap();
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+#[should_panic(expected = "program execution exceeded the limit of 4096 steps")]
+fn estimate_proving_time_with_step_limit() {
+    let program = crate::assembly::compile("begin push.1 while.true push.1 end end").unwrap();
+    let options = ProofOptions::default().with_execution_step_limit(4096);
+    super::estimate_proving_time(&program, &ProgramInputs::none(), &options);
+}
+
+#[test]
+fn execute_with_custom_constraints() {
+    let custom = CustomConstraints::new().with(ConstantRegister(7));
+    let options = ProofOptions::default();
+
+    // the program does not touch the bottom of the stack, and thus, satisfies the constraint
+    let program = crate::assembly::compile("begin swap neg roll.4 inv swap.2 end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let (outputs, proof) = super::execute_with_constraints(&program, &inputs, 8, &custom, &options);
+    assert_eq!(8, outputs[7]);
+
+    let result = super::verify_with_constraints(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &custom);
+    assert_eq!(Ok(true), result);
+
+    // the proof is bound to the custom constraints
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert!(result.is_err());
+}
+
+#[test]
+fn simulate_execute() {
+    let sources = [
+        "begin push.3 push.5 add push.7 mul end",
+        "begin read.a read.a dup.2 mul swap drop add end",
+        "begin read.a if.true push.3 add else push.5 mul end end",
+        "begin push.1 while.true dup mul push.0 end end",
+        "begin clk push.10 gt.8 end",
+    ];
+
+    let options = ProofOptions::default();
+    for &source in sources.iter() {
+        let program = crate::assembly::compile(source).unwrap();
+        for secret in [[0, 1], [1, 0], [1, 7]].iter() {
+            let inputs = ProgramInputs::new(&[2, 9], &secret[..], &[]);
+            let (outputs, _) = super::execute(&program, &inputs, 4, &options);
+            assert_eq!(outputs, super::simulate(&program, &inputs, 4), "outputs diverged for {}", source);
+        }
+    }
+}
+
 #[test]
 fn stack_manipulation() {
     let program = build_program(vec![
@@ -86,6 +839,45 @@ fn stack_manipulation() {
     assert_eq!(Ok(true), result);
 }
 
+#[test]
+fn move_operations() {
+    let program = crate::assembly::compile("begin movup.3 end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let options = ProofOptions::default();
+
+    let (outputs, proof) = super::execute(&program, &inputs, 8, &options);
+    assert_eq!(outputs, [4, 1, 2, 3, 5, 6, 7, 8]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // all moves work when the stack is just deep enough
+    for n in 0..8 {
+        let values: Vec<u128> = (1..(n as u128 + 2)).collect();
+        let inputs = ProgramInputs::from_public(&values);
+
+        let mut expected = values.clone();
+        let value = expected.remove(n);
+        expected.insert(0, value);
+        let program = crate::assembly::compile(&format!("begin movup.{} end", n)).unwrap();
+        assert_eq!(expected, super::simulate(&program, &inputs, n + 1), "movup.{} failed", n);
+
+        let mut expected = values.clone();
+        let value = expected.remove(0);
+        expected.insert(n, value);
+        let program = crate::assembly::compile(&format!("begin movdn.{} end", n)).unwrap();
+        assert_eq!(expected, super::simulate(&program, &inputs, n + 1), "movdn.{} failed", n);
+    }
+}
+
+#[test]
+#[should_panic(expected = "stack underflow")]
+fn move_operations_panic() {
+    let program = crate::assembly::compile("begin movup.3 end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2, 3]);
+    super::simulate(&program, &inputs, 1);
+}
+
 #[test]
 fn conditional_operations() {
     // CHOOSE
@@ -268,6 +1060,148 @@ fn hash_operations() {
     assert_eq!(Ok(true), result);
 }
 
+#[test]
+fn hash_sequence_operations() {
+    let options = ProofOptions::default();
+    let num_outputs = 2;
+
+    // 10-element sequence, and edge cases of an empty sequence and of a sequence which exactly
+    // fills a single block
+    let sequences: [&[u128]; 3] = [&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], &[], &[1, 2]];
+    for &values in sequences.iter() {
+        let source = format!("begin hashseq.{} end", values.len());
+        let program = crate::assembly::compile(&source).unwrap();
+
+        let mut expected_hash = hasher::hash(values);
+        expected_hash.reverse();
+
+        let inputs = ProgramInputs::new(&[], values, &[]);
+        let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+        assert_eq!(expected_hash, outputs);
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+    }
+
+    // hashes of sequences which differ only in padding are different
+    assert_ne!(hasher::hash(&[1, 2, 3]), hasher::hash(&[1, 2, 3, 0]));
+}
+
+#[test]
+fn hash_sequence_across_executions() {
+    let options = ProofOptions::default();
+    let values: Vec<u128> = (1..12).collect();
+
+    // the first execution absorbs the first 6 values and exports the state of the hash function
+    let program1 = crate::assembly::compile("begin hashseq.start.11.6 end").unwrap();
+    let inputs1 = ProgramInputs::new(&[], &values[..6], &[]);
+    let (state, proof1) = super::execute(&program1, &inputs1, crate::HASH_STATE_WIDTH, &options);
+    let result = super::verify(program1.hash(), inputs1.get_public_inputs(), &state, &proof1);
+    assert_eq!(Ok(true), result);
+
+    // the second execution imports the state and absorbs the remaining values
+    let program2 = crate::assembly::compile("begin hashseq.resume.5 end").unwrap();
+    let inputs2 = ProgramInputs::new(&state, &values[6..], &[]);
+    let (outputs, proof2) = super::execute(&program2, &inputs2, 2, &options);
+    let result = super::verify(program2.hash(), inputs2.get_public_inputs(), &outputs, &proof2);
+    assert_eq!(Ok(true), result);
+
+    // the result is the same as the hash of the entire sequence
+    let mut expected_hash = hasher::hash(&values);
+    expected_hash.reverse();
+    assert_eq!(expected_hash, outputs);
+
+    // the state can also be exported after the entire sequence has been absorbed
+    let program1 = crate::assembly::compile("begin hashseq.start.6.6 end").unwrap();
+    let program2 = crate::assembly::compile("begin hashseq.resume.0 end").unwrap();
+    let state = super::simulate(&program1, &ProgramInputs::new(&[], &values[..6], &[]), crate::HASH_STATE_WIDTH);
+    let outputs = super::simulate(&program2, &ProgramInputs::from_public(&state), 2);
+    let mut expected_hash = hasher::hash(&values[..6]);
+    expected_hash.reverse();
+    assert_eq!(expected_hash, outputs);
+}
+
+#[test]
+fn hash_stack_values() {
+    let options = ProofOptions::default();
+    let num_outputs = 2;
+
+    // hash.n hashes up to 4 values with a single permutation, same as hasher::digest()
+    let program = crate::assembly::compile("begin push.1 push.2 push.3 push.4 hash.4 end").unwrap();
+    let mut expected_hash = hasher::digest(&[1, 2, 3, 4]);
+    expected_hash.reverse();
+
+    let inputs = ProgramInputs::none();
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected_hash, outputs);
+    assert_eq!(Ok(true), super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof));
+
+    // hashchain.n absorbs values 2 at a time starting from the top of the stack, same as
+    // hasher::hash(); this covers both even and odd number of values
+    for &n in [1, 4, 5, 6, 16].iter() {
+        let pushes: Vec<String> = (1..=n).map(|i| format!("push.{}", i)).collect();
+        let source = format!("begin {} hashchain.{} end", pushes.join(" "), n);
+        let program = crate::assembly::compile(&source).unwrap();
+
+        let values: Vec<u128> = (1..=n).rev().collect();
+        let mut expected_hash = hasher::hash(&values);
+        expected_hash.reverse();
+
+        let inputs = ProgramInputs::none();
+        let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+        assert_eq!(expected_hash, outputs);
+        assert_eq!(Ok(true), super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof));
+    }
+
+    // hashing more than 16 values is not supported
+    let err = crate::assembly::compile("begin hashchain.17 end").unwrap_err();
+    assert_eq!("malformed instruction hashchain: parameter 17 is invalid; value must be between 1 and 16", err.message());
+}
+
+#[test]
+fn hash_cycle_length() {
+    // the programs differ only by an extra hash which is executed right after the first one
+    let program1 = crate::assembly::compile("begin push.1 push.2 hash.2 clk end").unwrap();
+    let program2 = crate::assembly::compile("begin push.1 push.2 hash.2 hash.2 clk end").unwrap();
+
+    let inputs = ProgramInputs::none();
+    let clk1 = super::simulate(&program1, &inputs, 1)[0];
+    let clk2 = super::simulate(&program2, &inputs, 1)[0];
+
+    // the extra hash advances the step counter by exactly one hash cycle
+    assert_eq!(crate::HASH_CYCLE_LENGTH as u128, clk2 - clk1);
+
+    // clk value includes the CLK operation itself; thus, in the second program the extra hash
+    // starts at the step at which CLK is executed in the first program
+    let step = (clk1 - 1) as usize;
+    assert_eq!(crate::HASH_CYCLE_LENGTH, crate::assembly::hash_step_cost(2, step));
+}
+
+#[test]
+fn clk_operation() {
+    // clk is read at two points separated by 3 NOOPs
+    let program = crate::assembly::compile("begin clk noop noop noop clk end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::none();
+    let num_outputs = 2;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(4, field::sub(outputs[0], outputs[1]));
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // clk values are determined by program structure; executing the program again yields
+    // the same values
+    let (outputs2, _) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, outputs2);
+
+    // a proof with an incorrect clk value should not verify
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &[outputs[0] + 1, outputs[1]], &proof);
+    assert!(result.is_err());
+}
+
 #[test]
 fn read_operations() {
     let program = build_program(vec![
@@ -314,6 +1248,31 @@ fn assert_operations() {
 
 // HELPER FUNCTIONS
 // ================================================================================================
+
+/// Custom constraint which requires the value of the specified user stack register to remain
+/// the same throughout program execution.
+struct ConstantRegister(usize);
+
+impl Constraint for ConstantRegister {
+    fn degree(&self) -> usize {
+        return 1;
+    }
+
+    fn evaluate(&self, current: &TraceState, next: &TraceState, _x: u128) -> u128 {
+        return field::sub(next.user_stack()[self.0], current.user_stack()[self.0]);
+    }
+}
+
+/// Reader which returns at most 7 bytes per read from the underlying reader.
+struct ChunkedReader<R: std::io::Read>(R);
+
+impl <R: std::io::Read> std::io::Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = std::cmp::min(buf.len(), 7);
+        return self.0.read(&mut buf[..length]);
+    }
+}
+
 fn build_program(instructions: Vec<OpCode>, push_values: &[u128]) -> Program {
 
     // build hint map for PUSh operations
@@ -0,0 +1,148 @@
+use crate::{ ProofOptions, OpCode, ProgramInputs, assembly::{ self, stdlib }, debug, utils::hasher };
+use super::super::{ execute, simulate, verify };
+
+#[test]
+fn assert_range_gadget() {
+    let program = assembly::compile("begin exec.assert_range.16 push.1 end").unwrap();
+
+    let inputs = ProgramInputs::from_public(&[0xFFFF]);
+    assert_eq!(vec![1], simulate(&program, &inputs, 1));
+
+    // 2^16 does not fit into 16 bits
+    let inputs = ProgramInputs::from_public(&[0x10000]);
+    let violation = debug::check_execution(&program, &inputs).unwrap_err();
+    assert_eq!(OpCode::Assert, violation.op_code);
+}
+
+#[test]
+fn select_gadget() {
+    let program = assembly::compile("begin exec.select end").unwrap();
+
+    for &(condition, a, b) in [(1, 3, 5), (0, 3, 5), (1, 7, 7), (0, 0, 9)].iter() {
+        let expected = if condition == 1 { a } else { b };
+        let inputs = ProgramInputs::from_public(&[condition, a, b]);
+        assert_eq!(vec![expected], simulate(&program, &inputs, 1));
+    }
+
+    // selection is proven via CHOOSE constraints
+    let options = ProofOptions::default();
+    for &condition in [0, 1].iter() {
+        let inputs = ProgramInputs::from_public(&[condition, 3, 5]);
+        let (outputs, proof) = execute(&program, &inputs, 1, &options);
+        assert_eq!(vec![if condition == 1 { 3 } else { 5 }], outputs);
+        assert_eq!(Ok(true), verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof));
+    }
+
+    // the condition must be a binary value
+    let inputs = ProgramInputs::from_public(&[2, 3, 5]);
+    assert!(debug::check_execution(&program, &inputs).is_err());
+}
+
+#[test]
+fn max_gadget() {
+    let program = assembly::compile("begin exec.max.32 end").unwrap();
+    let options = ProofOptions::default();
+
+    for &(a, b) in [(3, 5), (5, 3), (7, 7), (0, 0xFFFFFFFF)].iter() {
+        let inputs = ProgramInputs::from_public(&[a, b]);
+        let (outputs, proof) = execute(&program, &inputs, 1, &options);
+        assert_eq!(vec![u128::max(a, b)], outputs);
+        assert_eq!(Ok(true), verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof));
+    }
+}
+
+#[test]
+fn assert_hash_gadget() {
+    for &n in [1, 2, 3, 4].iter() {
+        let program = assembly::compile(&format!("begin exec.assert_hash.{} push.1 end", n)).unwrap();
+        let values: Vec<u128> = (0..n).map(|i| i * 3 + 1).collect();
+
+        // values are hashed with a single invocation of the hash function starting from the
+        // deepest value
+        let digest = hasher::digest(&values.iter().rev().cloned().collect::<Vec<u128>>());
+        let mut public_inputs = values.clone();
+        public_inputs.push(digest[0]);
+        public_inputs.push(digest[1]);
+
+        let inputs = ProgramInputs::from_public(&public_inputs);
+        assert_eq!(vec![1], simulate(&program, &inputs, 1));
+
+        // a different digest is rejected
+        public_inputs[n as usize] = public_inputs[n as usize] + 1;
+        let inputs = ProgramInputs::from_public(&public_inputs);
+        assert!(debug::check_execution(&program, &inputs).is_err());
+    }
+}
+
+#[test]
+fn merkle_verify_gadget() {
+    let leaves: Vec<[u128; 2]> = (0..8).map(|i| [i * 2 + 1, i * 2 + 2]).collect();
+    let program = assembly::compile("begin read.a read.ab exec.merkle_verify.4 push.1 end").unwrap();
+
+    for index in 0..leaves.len() {
+        let (root, path) = build_merkle_path(&leaves, index);
+        let inputs = build_merkle_inputs(root, leaves[index], &path, index);
+        assert_eq!(vec![1], simulate(&program, &inputs, 1));
+
+        // the path does not resolve to the root for a different leaf
+        let inputs = build_merkle_inputs(root, [17, 18], &path, index);
+        assert!(debug::check_execution(&program, &inputs).is_err());
+    }
+}
+
+#[test]
+fn exec_errors() {
+    let err = assembly::compile("begin exec.sqrt end").unwrap_err();
+    assert_eq!("malformed instruction exec: gadget 'sqrt' does not exist", err.message());
+
+    let err = assembly::compile("begin push.1 exec.max end").unwrap_err();
+    assert_eq!("malformed instruction exec: parameter is missing", err.message());
+    assert_eq!(2, err.step());
+
+    let err = assembly::compile("begin exec.select.2 end").unwrap_err();
+    assert_eq!("malformed instruction exec: too many parameters provided", err.message());
+
+    // errors of the gadget's instructions are reported for the exec instruction
+    let err = assembly::compile("begin exec.assert_range.200 end").unwrap_err();
+    assert_eq!("exec.assert_range.200", err.operation());
+    assert!(err.message().starts_with("malformed instruction exec: malformed instruction rc"));
+}
+
+#[test]
+fn gadget_source_matches_exec() {
+    let source = format!("begin {} {} {} end", stdlib::max(16), stdlib::assert_range(8), stdlib::select());
+    let expected = assembly::compile(&source).unwrap();
+    let program = assembly::compile("begin exec.max.16 exec.assert_range.8 exec.select end").unwrap();
+    assert_eq!(expected.hash(), program.hash());
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Builds a Merkle tree from the specified leaves, and returns the root of the tree together
+/// with the authentication path for the leaf at the specified index.
+fn build_merkle_path(leaves: &[[u128; 2]], mut index: usize) -> ([u128; 2], Vec<[u128; 2]>) {
+    let mut path = Vec::new();
+    let mut nodes = leaves.to_vec();
+    while nodes.len() > 1 {
+        path.push(nodes[index ^ 1]);
+        nodes = nodes.chunks(2).map(|pair| {
+            let digest = hasher::digest(&[pair[0][0], pair[0][1], pair[1][0], pair[1][1]]);
+            [digest[0], digest[1]]
+        }).collect();
+        index = index >> 1;
+    }
+    return (nodes[0], path);
+}
+
+/// Places the leaf index and the leaf onto input tapes followed by the nodes of the path;
+/// the root of the tree is provided via public inputs.
+fn build_merkle_inputs(root: [u128; 2], leaf: [u128; 2], path: &[[u128; 2]], index: usize) -> ProgramInputs {
+    let mut tape_a = vec![index as u128, leaf[0]];
+    let mut tape_b = vec![leaf[1]];
+    for node in path.iter() {
+        tape_a.push(node[0]);
+        tape_b.push(node[1]);
+    }
+    return ProgramInputs::new(&root, &tape_a, &tape_b);
+}
@@ -1,5 +1,6 @@
 use crate::{
-    math::field,
+    math::{ field, polynom },
+    crypto::hash,
     HASH_STATE_WIDTH as STATE_WIDTH,
     HASH_STATE_RATE as STATE_RATE,
     HASH_DIGEST_SIZE as DIGEST_SIZE,
@@ -25,6 +26,26 @@ pub fn digest(values: &[u128]) -> Vec<u128> {
     return state[..DIGEST_SIZE].to_vec();
 }
 
+/// Computes a hash of an arbitrary-length sequence of values. The hash is computed by chaining
+/// invocations of `digest()`: the chain is initialized with the length of the sequence, and
+/// every invocation absorbs the previous result together with the next 2 values of the sequence
+/// (the last block is padded with ZEROs). An empty sequence is hashed as a single padded block.
+pub fn hash(values: &[u128]) -> Vec<u128> {
+    let mut result = vec![values.len() as u128, field::ZERO];
+
+    let num_blocks = std::cmp::max(1, (values.len() + 1) / 2);
+    for i in 0..num_blocks {
+        let mut block = [field::ZERO; STATE_RATE];
+        block[..DIGEST_SIZE].copy_from_slice(&result);
+
+        let chunk = &values[(i * 2)..std::cmp::min(values.len(), i * 2 + 2)];
+        block[DIGEST_SIZE..(DIGEST_SIZE + chunk.len())].copy_from_slice(chunk);
+        result = digest(&block);
+    }
+
+    return result;
+}
+
 pub fn apply_round(state: &mut [u128], step: usize) {
     
     let ark_idx = step % CYCLE_LENGTH;
@@ -88,6 +109,103 @@ pub fn apply_inv_mds(state: &mut[u128]) {
     state.copy_from_slice(&result);
 }
 
+// ROUND CONSTANT REPRESENTATIONS
+// ================================================================================================
+
+/// Evaluates polynomials of round constants over a cycle of the evaluation domain extended by
+/// `extension_factor`; every polynomial must have CYCLE_LENGTH coefficients (i.e. be interpolated
+/// from one cycle of constants such as a row of ARK). The cycle is shifted by `cycle_offset`,
+/// which is the offset of the evaluation domain raised to the power trace_length / CYCLE_LENGTH
+/// (ONE for an unshifted domain).
+pub fn ark_to_evaluations(polys: &[Vec<u128>], extension_factor: usize, cycle_offset: u128) -> Vec<Vec<u128>> {
+    assert!(extension_factor.is_power_of_two(),
+        "extension factor must be a power of 2, but was {}", extension_factor);
+
+    let cycle_length = CYCLE_LENGTH * extension_factor;
+    return polys.iter().map(|poly| {
+        assert!(poly.len() == CYCLE_LENGTH,
+            "expected constant polynomial to have {} coefficients, but it had {}", CYCLE_LENGTH, poly.len());
+        let mut evaluations = poly.clone();
+        polynom::scale_argument(&mut evaluations, cycle_offset);
+        evaluations.resize(cycle_length, field::ZERO);
+        polynom::eval_fft(&mut evaluations, true);
+        evaluations
+    }).collect();
+}
+
+/// Interpolates evaluations of round constants over a shifted cycle of the extended evaluation
+/// domain back into polynomials of CYCLE_LENGTH coefficients; this is the inverse of
+/// ark_to_evaluations() for the same `cycle_offset`. Panics if some evaluations are not of a
+/// polynomial of degree smaller than CYCLE_LENGTH.
+pub fn ark_to_polys(evaluations: &[Vec<u128>], cycle_offset: u128) -> Vec<Vec<u128>> {
+    let inv_offset = field::inv(cycle_offset);
+    return evaluations.iter().enumerate().map(|(i, evaluations)| {
+        assert!(evaluations.len().is_power_of_two() && evaluations.len() >= CYCLE_LENGTH,
+            "expected a power of 2 number of evaluations no smaller than {}, but received {}", CYCLE_LENGTH, evaluations.len());
+        let mut poly = evaluations.clone();
+        polynom::interpolate_fft(&mut poly, true);
+        assert!(poly[CYCLE_LENGTH..].iter().all(|&c| c == field::ZERO),
+            "evaluations {} are not of a polynomial of degree smaller than {}", i, CYCLE_LENGTH);
+        poly.truncate(CYCLE_LENGTH);
+        polynom::scale_argument(&mut poly, inv_offset);
+        poly
+    }).collect();
+}
+
+// CONSTANT VERIFICATION
+// ================================================================================================
+
+/// Returns true if Rescue constants used by the VM match the expected values. Round constants
+/// and the MDS matrix are compared against the constants of the standard Rescue instance in
+/// `crypto::hash`, while the inverse MDS matrix and the inverse S-box exponent are checked
+/// against their definitions. Any change to these constants changes all digests (and thus,
+/// hashes of all programs).
+pub fn verify_constants() -> bool {
+    return check_constants(&ARK, &MDS, &INV_MDS);
+}
+
+fn check_constants(ark: &[[u128; CYCLE_LENGTH]], mds: &[u128], inv_mds: &[u128]) -> bool {
+    // row 2k of ARK holds element k of the constants for the first CYCLE_LENGTH rounds of the
+    // standard instance, and row 2k + 1 holds element k of the constants for the next ones
+    if ark.len() != STATE_WIDTH * 2 {
+        return false;
+    }
+    for (i, row) in ark.iter().enumerate() {
+        for (j, &constant) in row.iter().enumerate() {
+            let round = (i % 2) * CYCLE_LENGTH + j;
+            if constant != hash::ARK[round * STATE_WIDTH + i / 2] {
+                return false;
+            }
+        }
+    }
+    if mds[..] != hash::MDS[..] {
+        return false;
+    }
+
+    // MDS * INV_MDS must be the identity matrix
+    for i in 0..STATE_WIDTH {
+        for j in 0..STATE_WIDTH {
+            let mut value = field::ZERO;
+            for k in 0..STATE_WIDTH {
+                value = field::add(value, field::mul(mds[i * STATE_WIDTH + k], inv_mds[k * STATE_WIDTH + j]));
+            }
+            let expected = if i == j { field::ONE } else { field::ZERO };
+            if value != expected {
+                return false;
+            }
+        }
+    }
+
+    // the inverse S-box must undo the S-box
+    for &x in [2, 7, field::MODULUS - 2].iter() {
+        if field::exp(field::exp(x, ALPHA), INV_ALPHA) != x {
+            return false;
+        }
+    }
+
+    return true;
+}
+
 // 128-BIT RESCUE CONSTANTS
 // ================================================================================================
 
@@ -125,4 +243,31 @@ pub const ARK: [[u128; CYCLE_LENGTH]; STATE_WIDTH * 2] = [
     [179443458614881887600494128053111694648, 108165142884901978856319583750672324489,  97063282200318501142854934314343169049, 261286087759526359216271155361018330507,  67833038363599207475373040930824843019,  56878992720628535103195481580617360771, 198852036109370286966576164360266278255, 174521831193496100673067735908873646985, 251654188127562510403516067236333482372,  48056343894932757577046683797067209079, 306942787541210815164178987028698818659, 156642260202818413362503062578539720517, 251616653853928459967283575542057535293, 188741644029927191719040650968720800409, 281428110117091114144446350524650424481,  64627937813848943279040280988334503406],
     [289278996656706117461857789813498821934, 274604860873273636237081114376077113475, 126000924558481152083098962591383883438, 129877116445533126989528570413807277693, 172066229584406173063202914726937339958, 298530663250990395227144225232608384365,  16989575615175240495557720305287640349, 102835474498154050313290986853294842906, 297928660776980173370496618733852490961,  96037481352786813748421760769380383926,   2818165229115014774032882127170013258, 293027053537479076557105009345927645442, 249369722351358137898587699909312963803, 300544292992993952360719000252205715076, 323117003802246814764810890058143344905, 243579355010018669877160932197352017974],
     [339223760157195739332845857285008200423, 208632865147351209340449219082125897333,  96675618862527967726114378655626650641, 162892536327655189685235410342890574896, 196910153233132861881308509456401645140, 281841826874183647567546019531929972702, 155276073049009029667373106803046514344, 152642017050116048509158960350000858013, 286456894851095755022390967246767421000, 215531716255970146473658338852472046173, 324452408864695917006896030536225525119, 314094406162389098987684450322979120529, 114910730596486251472791631840513265074,  81795345404219176616297063519210464031,  22603524397731600512825466576357638930,  63900149356112496372337283043133097338],
-];
\ No newline at end of file
+];
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::math::field;
+    use super::{ ARK, MDS, INV_MDS };
+
+    #[test]
+    fn verify_constants() {
+        assert!(super::verify_constants());
+
+        // changing any of the constants is detected
+        let mut ark = ARK;
+        ark[3][5] = field::add(ark[3][5], field::ONE);
+        assert!(!super::check_constants(&ark, &MDS, &INV_MDS));
+
+        let mut mds = MDS;
+        mds[7] = field::add(mds[7], field::ONE);
+        assert!(!super::check_constants(&ARK, &mds, &INV_MDS));
+
+        let mut inv_mds = INV_MDS;
+        inv_mds[7] = field::add(inv_mds[7], field::ONE);
+        assert!(!super::check_constants(&ARK, &MDS, &inv_mds));
+    }
+}